@@ -1,3 +1,8 @@
 pub mod config;
 pub mod cors;
+pub mod csp;
+pub mod maintenance;
+pub mod metrics;
+pub mod request_id;
 pub mod template;
+pub mod version;
@@ -5,6 +5,128 @@ use base64::Engine;
 use jsonwebtoken::{DecodingKey, EncodingKey};
 use wildmatch::WildMatch;
 
+fn _default_max_artifact_bytes() -> u64 {
+    40 * 1024 * 1024
+}
+
+fn _default_max_artifact_request_bytes() -> u64 {
+    128 * 1024 * 1024
+}
+
+fn _default_max_image_bytes() -> u64 {
+    2 * 1024 * 1024
+}
+
+fn _default_max_image_request_bytes() -> u64 {
+    16 * 1024 * 1024
+}
+
+fn _default_max_dat_bytes() -> u64 {
+    64 * 1024 * 1024
+}
+
+fn _default_max_images_per_game() -> u32 {
+    20
+}
+
+/// Maximum sizes (in bytes) of uploads. Requests can upload multiple files,
+/// so each file and the whole request have their own limit.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct UploadLimitsConfig {
+    /// Maximum size of each core release artifact.
+    #[serde(default = "_default_max_artifact_bytes")]
+    pub max_artifact_bytes: u64,
+    /// Maximum size of a request uploading core release artifacts.
+    #[serde(default = "_default_max_artifact_request_bytes")]
+    pub max_artifact_request_bytes: u64,
+
+    /// Maximum size of each game image.
+    #[serde(default = "_default_max_image_bytes")]
+    pub max_image_bytes: u64,
+    /// Maximum size of a request uploading game images.
+    #[serde(default = "_default_max_image_request_bytes")]
+    pub max_image_request_bytes: u64,
+
+    /// Maximum size of a DAT file imported into a system.
+    #[serde(default = "_default_max_dat_bytes")]
+    pub max_dat_bytes: u64,
+
+    /// Maximum number of images a single game can have.
+    #[serde(default = "_default_max_images_per_game")]
+    pub max_images_per_game: u32,
+}
+
+impl Default for UploadLimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_artifact_bytes: _default_max_artifact_bytes(),
+            max_artifact_request_bytes: _default_max_artifact_request_bytes(),
+            max_image_bytes: _default_max_image_bytes(),
+            max_image_request_bytes: _default_max_image_request_bytes(),
+            max_dat_bytes: _default_max_dat_bytes(),
+            max_images_per_game: _default_max_images_per_game(),
+        }
+    }
+}
+
+fn _default_max_games_offset() -> i64 {
+    10_000
+}
+
+fn _default_max_release_date_skew_secs() -> i64 {
+    24 * 60 * 60
+}
+
+fn _default_csp_header() -> Option<String> {
+    Some("default-src 'self'; img-src * data:; script-src 'self' 'unsafe-inline'".to_string())
+}
+
+fn _default_password_reset_path() -> String {
+    "reset-password".to_string()
+}
+
+fn _default_allow_all() -> Vec<String> {
+    vec!["*".to_string()]
+}
+
+/// File extensions and MIME types accepted for core release artifacts. Use `*`
+/// and `?` as wildcards. Extensions are matched without the leading dot and
+/// case insensitively.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ArtifactTypesConfig {
+    #[serde(default = "_default_allow_all")]
+    pub extensions: Vec<String>,
+    #[serde(default = "_default_allow_all")]
+    pub mime_types: Vec<String>,
+}
+
+impl Default for ArtifactTypesConfig {
+    fn default() -> Self {
+        Self {
+            extensions: _default_allow_all(),
+            mime_types: _default_allow_all(),
+        }
+    }
+}
+
+impl ArtifactTypesConfig {
+    pub(crate) fn is_extension_allowed(&self, filename: &str) -> bool {
+        let extension = std::path::Path::new(filename)
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        self.extensions
+            .iter()
+            .any(|e| WildMatch::new(&e.to_lowercase()).matches(&extension))
+    }
+
+    pub(crate) fn is_mime_type_allowed(&self, mime_type: &str) -> bool {
+        self.mime_types
+            .iter()
+            .any(|m| WildMatch::new(m).matches(mime_type))
+    }
+}
+
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct RetronomiconConfig {
     pub base_url: String,
@@ -13,9 +135,35 @@ pub struct RetronomiconConfig {
 
     bypass_email_validation: Vec<String>,
 
+    /// The largest offset (page times limit) allowed when listing games.
+    #[serde(default = "_default_max_games_offset")]
+    pub max_games_offset: i64,
+
+    /// How far in the future (in seconds) the release date of a core release
+    /// can be, to allow for clock differences with the uploader.
+    #[serde(default = "_default_max_release_date_skew_secs")]
+    pub max_release_date_skew_secs: i64,
+
+    /// The `Content-Security-Policy` header of the responses that are not
+    /// from the API. An empty policy disables the header.
+    #[serde(default = "_default_csp_header")]
+    pub csp_header: Option<String>,
+
+    /// The page of the frontend where users set a new password, relative to
+    /// `base_url`. Password reset emails link to it with a `token` query
+    /// parameter.
+    #[serde(default = "_default_password_reset_path")]
+    pub password_reset_path: String,
+
     template_dir: String,
 
     pub smtp: SmtpConfig,
+
+    #[serde(default)]
+    pub upload_limits: UploadLimitsConfig,
+
+    #[serde(default)]
+    pub artifact_types: ArtifactTypesConfig,
 }
 
 impl RetronomiconConfig {
@@ -24,6 +172,11 @@ impl RetronomiconConfig {
         TemplateResolver::new(&self.template_dir)
     }
 
+    /// The URL of the frontend page where users set a new password.
+    pub(crate) fn password_reset_url(&self) -> Result<url::Url, url::ParseError> {
+        url::Url::parse(&self.base_url).and_then(|u| u.join(&self.password_reset_path))
+    }
+
     pub(crate) fn bypass_email_validation(&self, email: &str) -> bool {
         self.bypass_email_validation
             .iter()
@@ -59,3 +212,12 @@ impl DbPepper {
         Self(secret)
     }
 }
+
+/// When the server started, to report its uptime.
+pub struct StartTime(pub std::time::Instant);
+
+impl StartTime {
+    pub fn now() -> Self {
+        Self(std::time::Instant::now())
+    }
+}
@@ -52,6 +52,10 @@ impl Fairing for Cors {
             "POST, GET, OPTIONS",
         ));
         response.set_header(Header::new("Access-Control-Allow-Headers", "*"));
+        response.set_header(Header::new(
+            "Access-Control-Expose-Headers",
+            "X-Request-Id, X-Api-Version, X-Api-Min-Version",
+        ));
         response.set_header(Header::new("Access-Control-Allow-Credentials", "true"));
     }
 }
@@ -0,0 +1,46 @@
+use crate::fairings::config::RetronomiconConfig;
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::Header;
+use rocket::{Request, Response};
+
+/// Add security headers to every response. Static files of the frontend get
+/// the `Content-Security-Policy` header from the configuration, and the API
+/// responses cannot be sniffed or embedded in a frame.
+pub struct CspFairing;
+
+impl CspFairing {
+    /// Whether the response is a static file of the frontend. Rocket names
+    /// the routes of a `FileServer` after it.
+    fn is_static(request: &Request<'_>) -> bool {
+        request
+            .route()
+            .and_then(|route| route.name.as_deref())
+            .is_some_and(|name| name.starts_with("FileServer"))
+    }
+}
+
+#[rocket::async_trait]
+impl Fairing for CspFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Add security headers to responses",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        if !Self::is_static(request) {
+            response.set_header(Header::new("X-Content-Type-Options", "nosniff"));
+            response.set_header(Header::new("X-Frame-Options", "DENY"));
+            return;
+        }
+
+        let csp = request
+            .rocket()
+            .state::<RetronomiconConfig>()
+            .and_then(|config| config.csp_header.clone());
+        if let Some(csp) = csp.filter(|csp| !csp.is_empty()) {
+            response.set_header(Header::new("Content-Security-Policy", csp));
+        }
+    }
+}
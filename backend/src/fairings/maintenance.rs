@@ -0,0 +1,60 @@
+use retronomicon_db::{models, DbConnection, RetronomiconDbPool};
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::tokio;
+use rocket::{Orbit, Rocket};
+use std::time::Duration;
+
+/// How often the maintenance tasks run.
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Clear the email validation tokens older than
+/// [`models::VALIDATION_TOKEN_VALIDITY_SECS`]. Returns the number of tokens
+/// cleared.
+pub async fn expire_tokens(db: &mut DbConnection) -> Result<usize, String> {
+    let older_than = chrono::Utc::now().naive_utc()
+        - chrono::Duration::seconds(models::VALIDATION_TOKEN_VALIDITY_SECS);
+    models::UserPassword::expire_old_tokens(db, older_than)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Run periodic maintenance tasks on the database in the background, once
+/// the server started.
+pub struct Maintenance;
+
+#[rocket::async_trait]
+impl Fairing for Maintenance {
+    fn info(&self) -> Info {
+        Info {
+            name: "Periodic database maintenance",
+            kind: Kind::Liftoff,
+        }
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        let Some(pool) = rocket.state::<RetronomiconDbPool>() else {
+            rocket::error!("No database pool, maintenance tasks will not run.");
+            return;
+        };
+        let pool = pool.detached();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(MAINTENANCE_INTERVAL);
+            loop {
+                interval.tick().await;
+
+                let mut db = match pool.connect().await {
+                    Ok(db) => db,
+                    Err(e) => {
+                        rocket::error!("Maintenance could not connect to the database: {e}");
+                        continue;
+                    }
+                };
+                match expire_tokens(&mut db).await {
+                    Ok(count) => rocket::info!("Expired {count} validation tokens."),
+                    Err(e) => rocket::error!("Could not expire validation tokens: {e}"),
+                }
+            }
+        });
+    }
+}
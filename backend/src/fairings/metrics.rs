@@ -0,0 +1,92 @@
+use retronomicon_db::{ssl_pool, RetronomiconDbPool};
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::{Build, Rocket};
+use rocket_prometheus::prometheus;
+use rocket_prometheus::prometheus::core::{Collector, Desc};
+use rocket_prometheus::prometheus::proto::MetricFamily;
+use rocket_prometheus::prometheus::{IntGauge, Registry};
+
+/// Reads the usage of the database pool when the metrics are scraped.
+struct DbPoolCollector {
+    pool: ssl_pool::Pool,
+    size: IntGauge,
+    in_use: IntGauge,
+    waiters: IntGauge,
+}
+
+impl DbPoolCollector {
+    fn new(pool: ssl_pool::Pool) -> prometheus::Result<Self> {
+        Ok(Self {
+            pool,
+            size: IntGauge::new(
+                "retronomicon_db_pool_size",
+                "Maximum number of database connections open at the same time.",
+            )?,
+            in_use: IntGauge::new(
+                "retronomicon_db_pool_in_use",
+                "Number of database connections currently open.",
+            )?,
+            waiters: IntGauge::new(
+                "retronomicon_db_pool_waiters",
+                "Number of requests waiting for a database connection.",
+            )?,
+        })
+    }
+
+    fn gauges(&self) -> [&IntGauge; 3] {
+        [&self.size, &self.in_use, &self.waiters]
+    }
+}
+
+impl Collector for DbPoolCollector {
+    fn desc(&self) -> Vec<&Desc> {
+        self.gauges().into_iter().flat_map(|g| g.desc()).collect()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        self.size.set(self.pool.size() as i64);
+        self.in_use.set(self.pool.in_use() as i64);
+        self.waiters.set(self.pool.waiters() as i64);
+        self.gauges()
+            .into_iter()
+            .flat_map(|g| g.collect())
+            .collect()
+    }
+}
+
+/// Register gauges of the database pool usage in a Prometheus registry. This
+/// must be attached after the database pool.
+pub struct DbPoolMetrics(Registry);
+
+impl DbPoolMetrics {
+    pub fn new(registry: &Registry) -> Self {
+        Self(registry.clone())
+    }
+}
+
+#[rocket::async_trait]
+impl Fairing for DbPoolMetrics {
+    fn info(&self) -> Info {
+        Info {
+            name: "Database pool metrics",
+            kind: Kind::Ignite,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> rocket::fairing::Result {
+        let Some(pool) = rocket.state::<RetronomiconDbPool>() else {
+            rocket::error!("No database pool, cannot register its metrics.");
+            return Err(rocket);
+        };
+
+        let registered = DbPoolCollector::new(pool.detached())
+            .and_then(|collector| self.0.register(Box::new(collector)));
+        match registered {
+            Ok(()) => Ok(rocket),
+            Err(e) => {
+                rocket::error!("Could not register the database pool metrics: {e}");
+                Err(rocket)
+            }
+        }
+    }
+}
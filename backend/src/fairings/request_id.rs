@@ -0,0 +1,82 @@
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::Header;
+use rocket::request::{FromRequest, Outcome};
+use rocket::{Data, Request, Response};
+use rocket_okapi::OpenApiFromRequest;
+use std::convert::Infallible;
+
+/// The header carrying the request ID, in requests and responses.
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// Maximum length of a request ID sent by a client. Longer IDs are replaced
+/// by a generated one.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// The ID of the current request. Either sent by the client in the
+/// `X-Request-Id` header, or generated by the server.
+#[derive(Clone, Debug, PartialEq, Eq, OpenApiFromRequest)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    fn generate() -> Self {
+        Self(hex::encode(rand::random::<[u8; 16]>()))
+    }
+
+    /// Only accept IDs that can be echoed back and logged safely.
+    fn from_header(value: &str) -> Option<Self> {
+        let valid = !value.is_empty()
+            && value.len() <= MAX_REQUEST_ID_LEN
+            && value.bytes().all(|b| b.is_ascii_graphic());
+        valid.then(|| Self(value.to_string()))
+    }
+
+    /// The ID of a request. Generates one if the fairing did not run.
+    pub fn of<'r>(request: &'r Request<'_>) -> &'r RequestId {
+        request.local_cache(Self::generate)
+    }
+}
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RequestId {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(RequestId::of(request).clone())
+    }
+}
+
+/// Assign an ID to every request, log it along with the request and echo it
+/// back in the `X-Request-Id` response header.
+pub struct RequestIdFairing;
+
+#[rocket::async_trait]
+impl Fairing for RequestIdFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request ID",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut Data<'_>) {
+        let id = request
+            .headers()
+            .get_one(REQUEST_ID_HEADER)
+            .and_then(RequestId::from_header)
+            .unwrap_or_else(RequestId::generate);
+        let id = request.local_cache(move || id);
+
+        rocket::info_!("Request ID: {id}");
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let id = RequestId::of(request);
+        response.set_header(Header::new(REQUEST_ID_HEADER, id.0.clone()));
+    }
+}
@@ -16,4 +16,14 @@ impl TemplateResolver {
         let path = self.root.join("email-verification.hbs");
         std::fs::read_to_string(path).expect("Failed to read email verification template")
     }
+
+    pub fn email_login(&self) -> String {
+        let path = self.root.join("email-login.hbs");
+        std::fs::read_to_string(path).expect("Failed to read email login template")
+    }
+
+    pub fn password_reset(&self) -> String {
+        let path = self.root.join("password-reset.hbs");
+        std::fs::read_to_string(path).expect("Failed to read password reset template")
+    }
 }
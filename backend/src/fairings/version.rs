@@ -0,0 +1,24 @@
+use retronomicon_dto as dto;
+use retronomicon_dto::client::{API_MIN_VERSION_HEADER, API_VERSION_HEADER};
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::Header;
+use rocket::{Request, Response};
+
+/// Add the API version of the server, and the minimum version of the client
+/// it supports, to every response.
+pub struct VersionFairing;
+
+#[rocket::async_trait]
+impl Fairing for VersionFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Add API version headers to responses",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, _request: &'r Request<'_>, response: &mut Response<'r>) {
+        response.set_header(Header::new(API_VERSION_HEADER, dto::VERSION));
+        response.set_header(Header::new(API_MIN_VERSION_HEADER, dto::API_MIN_VERSION));
+    }
+}
@@ -1,3 +1,4 @@
 pub mod emailer;
+pub mod idempotency;
 pub mod storage;
 pub mod users;
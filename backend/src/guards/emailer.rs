@@ -1,4 +1,5 @@
 use crate::fairings::config::RetronomiconConfig;
+use crate::fairings::template::TemplateResolver;
 use lettre::message::header::ContentType;
 use lettre::transport::smtp::authentication::Credentials;
 use lettre::{Message, SmtpTransport, Transport};
@@ -28,7 +29,7 @@ pub struct SmtpConfig {
 
 pub struct EmailGuard {
     config: SmtpConfig,
-    template: String,
+    templates: TemplateResolver,
 }
 
 #[rocket::async_trait]
@@ -41,23 +42,55 @@ impl<'r> FromRequest<'r> for EmailGuard {
             None => return Outcome::Error((Status::InternalServerError, "No config".to_string())),
         };
         let smtp_config = config.smtp.clone();
-
-        let template = config.templates().email_verification();
+        let templates = config.templates();
 
         Outcome::Success(Self {
             config: smtp_config,
-            template,
+            templates,
         })
     }
 }
 
 impl EmailGuard {
     pub fn send_email_verification(&self, email: &str, url: &str) -> Result<(), (Status, String)> {
+        self.send(
+            email,
+            url,
+            "Retronomicon Email Verification",
+            &self.templates.email_verification(),
+        )
+    }
+
+    pub fn send_email_login(&self, email: &str, url: &str) -> Result<(), (Status, String)> {
+        self.send(
+            email,
+            url,
+            "Retronomicon Login",
+            &self.templates.email_login(),
+        )
+    }
+
+    pub fn send_password_reset(&self, email: &str, url: &str) -> Result<(), (Status, String)> {
+        self.send(
+            email,
+            url,
+            "Retronomicon Password Reset",
+            &self.templates.password_reset(),
+        )
+    }
+
+    fn send(
+        &self,
+        email: &str,
+        url: &str,
+        subject: &str,
+        template: &str,
+    ) -> Result<(), (Status, String)> {
         let server_url = match self.config.server.as_ref() {
             Some(api_key) => api_key,
             None => {
                 rocket::warn!("No SMTP server set, not sending email");
-                rocket::warn!("Url to validate email: {}", url);
+                rocket::warn!("Url to send ({}): {}", subject, url);
                 return Ok(());
             }
         };
@@ -65,7 +98,7 @@ impl EmailGuard {
         let hbar = handlebars::Handlebars::new();
         let text = hbar
             .render_template(
-                &self.template,
+                template,
                 &json!({
                     "email": email,
                     "url": url,
@@ -89,7 +122,7 @@ impl EmailGuard {
         let email = Message::builder()
             .from(from)
             .to(to)
-            .subject("Retronomicon Email Verification")
+            .subject(subject)
             .header(ContentType::TEXT_PLAIN)
             .body(text)
             .unwrap();
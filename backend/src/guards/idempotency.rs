@@ -0,0 +1,27 @@
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome};
+use rocket::Request;
+use rocket_okapi::OpenApiFromRequest;
+use std::convert::Infallible;
+
+/// The header a client sets to make a create request safe to retry.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// The `Idempotency-Key` header of a request, for routes to take as an
+/// `Option`. When set, creating a resource that already exists with the same
+/// content returns the existing one instead of a conflict. The key itself is
+/// not stored; the content of the resource is what identifies a retry.
+#[derive(Clone, Debug, PartialEq, Eq, OpenApiFromRequest)]
+pub struct IdempotencyKey(pub String);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IdempotencyKey {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match request.headers().get_one(IDEMPOTENCY_KEY_HEADER) {
+            Some(key) if !key.is_empty() => Outcome::Success(Self(key.to_string())),
+            _ => Outcome::Forward(Status::BadRequest),
+        }
+    }
+}
@@ -143,6 +143,21 @@ impl Storage {
         Ok(url)
     }
 
+    /// Check that the cores bucket is reachable with the configured
+    /// credentials, using a HEAD request on the bucket itself.
+    pub async fn ping(&self) -> Result<(), String> {
+        let bucket = self
+            .bucket(self.config.cores_bucket.as_str(), false)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        match bucket.head_object("/").await {
+            Ok((_, 200)) => Ok(()),
+            Ok((_, code)) => Err(format!("Storage returned status {}", code)),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
     pub async fn upload_core(
         &self,
         filename: &str,
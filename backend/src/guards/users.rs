@@ -1,7 +1,7 @@
 use crate::fairings::config::{JwtKeys, RetronomiconConfig};
 use jsonwebtoken::{DecodingKey, EncodingKey};
-use retronomicon_db::models::{User, UserTeam};
-use retronomicon_db::Db;
+use retronomicon_db::models::{User, UserAuthProvider, UserPassword, UserTeam};
+use retronomicon_db::{Db, RetronomiconDbPool};
 use retronomicon_dto as dto;
 use rocket::http::{Cookie, CookieJar, Status};
 use rocket::outcome::{IntoOutcome, Outcome};
@@ -11,7 +11,8 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::ops::{Deref, DerefMut};
 
-/// A user that is part of the root team.
+/// A member of the root team. Viewers of the root team and users who were
+/// only invited to it are not.
 #[derive(Debug, Clone, Serialize, Deserialize, OpenApiFromRequest)]
 pub struct RootUserGuard {
     pub id: i32,
@@ -27,17 +28,23 @@ impl<'r> request::FromRequest<'r> for RootUserGuard {
             .state::<RetronomiconConfig>()
             .expect("No retronomicon config");
 
-        let mut db = request
-            .guard::<Db>()
-            .await
-            .expect("Database connection guard");
         let user = match request.guard::<UserGuard>().await {
             Outcome::Success(user) => user,
             Outcome::Forward(_) => return Outcome::Forward(Status::Unauthorized),
             Outcome::Error(e) => return Outcome::Error(e),
         };
 
-        let result = UserTeam::user_is_in_team(&mut db, user.id, config.root_team_id)
+        // The route may already hold a connection of the pool, so don't use
+        // the `Db` guard here.
+        let Some(pool) = request.rocket().state::<RetronomiconDbPool>() else {
+            return Outcome::Error((Status::InternalServerError, "No database pool".into()));
+        };
+        let mut db = match pool.connect_for_guard().await {
+            Ok(db) => db,
+            Err(e) => return Outcome::Error((Status::ServiceUnavailable, e.to_string())),
+        };
+
+        let result = UserTeam::user_is_member_of_team(&mut db, user.id, config.root_team_id)
             .await
             .map_err(|e| e.to_string());
         match result {
@@ -236,11 +243,18 @@ impl UserGuard {
 
     /// Create a new user or select an existing one. This should only be used
     /// from an OAuth provider.
+    ///
+    /// If `current` is set (the user is already logged in), the provider is
+    /// linked to the current user instead. If a user already exists with the
+    /// same email, the provider is linked to it as the provider verified the
+    /// email.
     pub async fn login_from_auth(
         db: &mut Db,
+        current: Option<&UserGuard>,
         username: Option<String>,
         email: &str,
         auth_provider: String,
+        external_id: &str,
         avatar_url: Option<String>,
     ) -> Result<(bool, User, Self), (Status, String)> {
         // Set username to None if it doesn't validate.
@@ -254,29 +268,80 @@ impl UserGuard {
                 .ok()
         });
 
-        let maybe_user = User::from_auth(db, email, &auth_provider)
+        let maybe_link = UserAuthProvider::find(db, &auth_provider, external_id)
             .await
             .map_err(|e| (Status::Unauthorized, e.to_string()))?;
 
-        if let Some(u) = maybe_user {
-            return Ok((false, u.clone(), Self::from_model(u)));
+        if let Some(link) = maybe_link {
+            if current.is_some_and(|c| c.id != link.user_id) {
+                return Err((
+                    Status::Conflict,
+                    "This account is already linked to another user".to_string(),
+                ));
+            }
+
+            let user = User::from_id(db, link.user_id)
+                .await
+                .map_err(|e| (Status::Unauthorized, e.to_string()))?;
+            return Ok((false, user.clone(), Self::from_model(user)));
         }
 
-        let user = User::create(
-            db,
-            username.as_deref(),
-            None,
-            avatar_url.as_deref(),
-            email,
-            Some(&auth_provider),
-            None,
-            json!({}),
-            json!({}),
-        )
-        .await
-        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+        let existing = match current {
+            Some(current) => Some(current.clone().into_model(db).await?),
+            None => {
+                let maybe_user = User::find_by_email(db, email)
+                    .await
+                    .map_err(|e| (Status::Unauthorized, e.to_string()))?;
+
+                // An unvalidated password could have been set by anyone who knew
+                // the email. Remove it now that the provider verified the email.
+                if let Some(user) = &maybe_user {
+                    let user_password = UserPassword::from_user(db, user)
+                        .await
+                        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+                    if let Some(p) = user_password.filter(|p| p.validation_token.is_some()) {
+                        p.delete(db)
+                            .await
+                            .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+                    }
+                }
+                maybe_user
+            }
+        };
 
-        Ok((true, user.clone(), Self::from_model(user)))
+        let (created, user) = match existing {
+            Some(user) => (false, user),
+            None => {
+                // Find a free username, adding a numeric suffix if the one from the
+                // provider is already taken.
+                let username = match username {
+                    Some(u) => User::available_username(db, &u)
+                        .await
+                        .map_err(|e| (Status::InternalServerError, e.to_string()))?,
+                    None => None,
+                };
+                let user = User::create(
+                    db,
+                    username.as_deref(),
+                    None,
+                    avatar_url.as_deref(),
+                    email,
+                    Some(&auth_provider),
+                    None,
+                    json!({}),
+                    json!({}),
+                )
+                .await
+                .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+                (true, user)
+            }
+        };
+
+        UserAuthProvider::link(db, &user, &auth_provider, external_id)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+        Ok((created, user.clone(), Self::from_model(user)))
     }
 
     pub async fn login_from_password(
@@ -296,6 +361,11 @@ impl UserGuard {
         db: &mut Db,
         form: dto::user::UserUpdate<'_>,
     ) -> Result<(), (Status, String)> {
+        if let Some(display_name) = form.display_name {
+            dto::user::validate_display_name(display_name)
+                .map_err(|e| (Status::BadRequest, e.to_string()))?;
+        }
+
         let user = self.clone().into_model(db).await?;
         user.update(db, form)
             .await
@@ -317,7 +387,16 @@ impl UserGuard {
         .map(|token| token.claims)
     }
 
-    pub fn create_jwt(mut self, key: &EncodingKey) -> Result<String, jsonwebtoken::errors::Error> {
+    pub fn create_jwt(self, key: &EncodingKey) -> Result<String, jsonwebtoken::errors::Error> {
+        self.create_jwt_with_expiry(key).map(|(token, _)| token)
+    }
+
+    /// Same as [`Self::create_jwt`], but also returns when the token expires,
+    /// in seconds since UNIX EPOCH.
+    pub fn create_jwt_with_expiry(
+        mut self,
+        key: &EncodingKey,
+    ) -> Result<(String, i64), jsonwebtoken::errors::Error> {
         let expiration = default_expiration_();
         self.set_expiry(expiration);
 
@@ -326,6 +405,7 @@ impl UserGuard {
             &self,
             key,
         )
+        .map(|token| (token, expiration))
     }
 
     pub fn remove_cookie(&self, cookies: &CookieJar) {
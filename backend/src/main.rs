@@ -1,5 +1,6 @@
-use crate::fairings::config::{DbPepper, JwtKeys, RetronomiconConfig};
+use crate::fairings::config::{DbPepper, JwtKeys, RetronomiconConfig, StartTime};
 use crate::routes::v1;
+use crate::utils::markdown::ReleaseNotesCache;
 use clap::Parser;
 use retronomicon_db::{run_migrations, RetronomiconDbPool};
 use rocket::fairing::AdHoc;
@@ -46,6 +47,15 @@ async fn rocket() -> _ {
             .unwrap(),
     );
 
+    // The size of the database pool can also be set from the environment.
+    let figment = match env::var("DATABASE_POOL_SIZE") {
+        Ok(size) => figment.merge((
+            "databases.retronomicon_db.pool_size",
+            size.parse::<usize>().expect("Invalid DATABASE_POOL_SIZE."),
+        )),
+        Err(_) => figment,
+    };
+
     let secret_key = figment
         .find_value("secret_key")
         .expect("No secret key.")
@@ -113,12 +123,19 @@ async fn rocket() -> _ {
             ),
         )
         .attach(RetronomiconDbPool::init())
+        .attach(fairings::metrics::DbPoolMetrics::new(prometheus.registry()))
         .attach(prometheus)
         .attach(OAuth2::<routes::auth::GitHubUserInfo>::fairing("github"))
         .attach(OAuth2::<routes::auth::GoogleUserInfo>::fairing("google"))
         .attach(OAuth2::<routes::auth::PatreonUserInfo>::fairing("patreon"))
+        .attach(fairings::request_id::RequestIdFairing)
         .attach(fairings::cors::Cors)
+        .attach(fairings::csp::CspFairing)
+        .attach(fairings::version::VersionFairing)
+        .attach(fairings::maintenance::Maintenance)
         .manage(JwtKeys::from_base64(&jwt_secret_b64))
         .manage(DbPepper::from_base64(&db_pepper))
+        .manage(StartTime::now())
+        .manage(ReleaseNotesCache::default())
         .attach(AdHoc::config::<RetronomiconConfig>())
 }
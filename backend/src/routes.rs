@@ -3,6 +3,7 @@ pub mod v1;
 use rocket::{routes, Route};
 
 pub mod auth;
+pub mod error;
 
 pub fn routes() -> Vec<Route> {
     routes![
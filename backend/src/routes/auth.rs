@@ -1,6 +1,8 @@
 use crate::fairings::config::RetronomiconConfig;
 use crate::guards::users::UserGuard;
-use retronomicon_db::{models, Db};
+use crate::routes::error::ApiError;
+use retronomicon_db::scoped_futures::ScopedFutureExt;
+use retronomicon_db::{models, transaction, Db};
 use rocket::http::hyper::header::{ACCEPT, AUTHORIZATION, USER_AGENT};
 use rocket::http::{CookieJar, Status};
 use rocket::response::Redirect;
@@ -13,7 +15,7 @@ async fn maybe_add_to_root(
     db: &mut Db,
     config: &RetronomiconConfig,
     model: &models::User,
-) -> Result<(), (Status, String)> {
+) -> Result<(), ApiError> {
     if config.should_add_to_root(&model.email) {
         if let Err(error) = model
             .join_team(db, config.root_team_id, models::UserTeamRole::Owner)
@@ -26,17 +28,38 @@ async fn maybe_add_to_root(
     Ok(())
 }
 
+/// Login (or link the provider if the user is already logged in) using the
+/// information returned by an OAuth provider. The provider's account is
+/// identified by `external_id`, which must not change for the same account.
+/// The email is only used to find an existing user the first time.
+#[allow(clippy::too_many_arguments)]
 async fn login_(
     mut db: Db,
     cookies: &CookieJar<'_>,
     config: &State<RetronomiconConfig>,
+    current: Option<UserGuard>,
     username: Option<String>,
     email: &str,
     auth_provider: &str,
-) -> Result<Redirect, (Status, String)> {
-    let (_created, model, user_guard) =
-        UserGuard::login_from_auth(&mut db, username, email, auth_provider.to_string(), None)
-            .await?;
+    external_id: &str,
+) -> Result<Redirect, ApiError> {
+    // Creating the user and linking the provider must succeed together.
+    let (_created, model, user_guard) = transaction(&mut db, |db| {
+        async move {
+            Ok(UserGuard::login_from_auth(
+                db,
+                current.as_ref(),
+                username,
+                email,
+                auth_provider.to_string(),
+                external_id,
+                None,
+            )
+            .await?)
+        }
+        .scope_boxed()
+    })
+    .await?;
 
     maybe_add_to_root(&mut db, config, &model).await?;
     user_guard.update_cookie(cookies);
@@ -45,6 +68,27 @@ async fn login_(
     Ok(Redirect::to(base_url))
 }
 
+/// Validate an email token and log the user in, setting the auth cookie.
+/// This is shared between the email link and the email login API.
+pub(crate) async fn login_from_validation_token(
+    db: &mut Db,
+    cookies: &CookieJar<'_>,
+    config: &RetronomiconConfig,
+    email: &str,
+    token: &str,
+) -> Result<UserGuard, ApiError> {
+    let (user, _) = models::UserPassword::consume_validation_token(db, email, token)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((Status::NotFound, "Invalid token".to_string()))?;
+
+    maybe_add_to_root(db, config, &user).await?;
+
+    let user_guard = UserGuard::from_model(user);
+    user_guard.update_cookie(cookies);
+    Ok(user_guard)
+}
+
 #[get("/auth/verify?<email>&<token>")]
 pub async fn login_token_callback(
     mut db: Db,
@@ -52,23 +96,8 @@ pub async fn login_token_callback(
     config: &State<RetronomiconConfig>,
     email: String,
     token: String,
-) -> Result<Redirect, (Status, String)> {
-    let (user, user_password) =
-        models::UserPassword::from_validation_token(&mut db, &email, &token)
-            .await
-            .map_err(|e| (Status::InternalServerError, e.to_string()))?
-            .ok_or((Status::NotFound, "Invalid token".to_string()))?;
-
-    // At this point we know we have the right token, user and user_password entry.
-    user_password
-        .validated(&mut db)
-        .await
-        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
-
-    maybe_add_to_root(&mut db, config, &user).await?;
-
-    let user_guard = UserGuard::from_model(user);
-    user_guard.update_cookie(cookies);
+) -> Result<Redirect, ApiError> {
+    login_from_validation_token(&mut db, cookies, config, &email, &token).await?;
 
     let base_url = config.base_url.clone();
     Ok(Redirect::to(base_url))
@@ -77,6 +106,7 @@ pub async fn login_token_callback(
 /// User information to be retrieved from the GitHub API.
 #[derive(serde::Deserialize)]
 pub struct GitHubUserInfo {
+    id: i64,
     login: String,
     email: String,
 }
@@ -84,10 +114,11 @@ pub struct GitHubUserInfo {
 #[get("/auth/github")]
 pub async fn github_callback(
     db: Db,
+    current: Option<UserGuard>,
     token: TokenResponse<GitHubUserInfo>,
     cookies: &CookieJar<'_>,
     config: &State<RetronomiconConfig>,
-) -> Result<Redirect, (Status, String)> {
+) -> Result<Redirect, ApiError> {
     let json: Value = reqwest::Client::builder()
         .build()
         .map_err(|e| (Status::InternalServerError, e.to_string()))?
@@ -109,9 +140,11 @@ pub async fn github_callback(
         db,
         cookies,
         config,
+        current,
         Some(user_info.login),
         &user_info.email,
         "github",
+        &user_info.id.to_string(),
     )
     .await
 }
@@ -119,6 +152,9 @@ pub async fn github_callback(
 /// User information to be retrieved from the Google People API.
 #[derive(serde::Deserialize)]
 pub struct GoogleUserInfo {
+    #[serde(rename = "resourceName")]
+    resource_name: String,
+
     #[allow(unused)]
     names: Vec<Value>,
 
@@ -129,10 +165,11 @@ pub struct GoogleUserInfo {
 #[get("/auth/google")]
 pub async fn google_callback(
     db: Db,
+    current: Option<UserGuard>,
     token: TokenResponse<GoogleUserInfo>,
     cookies: &CookieJar<'_>,
     frontend_config: &State<RetronomiconConfig>,
-) -> Result<Redirect, (Status, String)> {
+) -> Result<Redirect, ApiError> {
     let json: Value = reqwest::Client::builder()
         .build()
         .map_err(|e| (Status::InternalServerError, e.to_string()))?
@@ -152,17 +189,29 @@ pub async fn google_callback(
         .and_then(|e| e.as_str());
 
     if let Some(email) = email {
-        login_(db, cookies, frontend_config, None, email, "google").await
+        login_(
+            db,
+            cookies,
+            frontend_config,
+            current,
+            None,
+            email,
+            "google",
+            &user_info.resource_name,
+        )
+        .await
     } else {
         Err((
             Status::InternalServerError,
             "Failed to get email".to_string(),
-        ))
+        )
+            .into())
     }
 }
 
 #[derive(serde::Deserialize)]
 pub struct PatreonUserInfoData {
+    id: String,
     attributes: BTreeMap<String, Value>,
 }
 
@@ -176,10 +225,11 @@ pub struct PatreonUserInfo {
 #[get("/auth/patreon")]
 pub async fn patreon_callback(
     db: Db,
+    current: Option<UserGuard>,
     token: TokenResponse<PatreonUserInfo>,
     cookies: &CookieJar<'_>,
     frontend_config: &State<RetronomiconConfig>,
-) -> Result<Redirect, (Status, String)> {
+) -> Result<Redirect, ApiError> {
     let json: Value = reqwest::Client::builder()
         .build()
         .map_err(|e| (Status::InternalServerError, e.to_string()))?
@@ -197,7 +247,8 @@ pub async fn patreon_callback(
         return Err((
             Status::InternalServerError,
             format!("failed to get email: {:#?}", err),
-        ));
+        )
+            .into());
     }
     let data = match user_info.data {
         Some(data) => data,
@@ -205,7 +256,8 @@ pub async fn patreon_callback(
             return Err((
                 Status::InternalServerError,
                 "Failed to get email".to_string(),
-            ));
+            )
+                .into());
         }
     };
     let email = match data.attributes.get("email") {
@@ -215,13 +267,24 @@ pub async fn patreon_callback(
                 return Err((
                     Status::InternalServerError,
                     "Invalid email type".to_string(),
-                ));
+                )
+                    .into());
             }
         },
         None => {
-            return Err((Status::InternalServerError, "no email".to_string()));
+            return Err((Status::InternalServerError, "no email".to_string()).into());
         }
     };
 
-    login_(db, cookies, frontend_config, None, email, "patreon").await
+    login_(
+        db,
+        cookies,
+        frontend_config,
+        current,
+        None,
+        email,
+        "patreon",
+        &data.id,
+    )
+    .await
 }
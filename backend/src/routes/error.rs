@@ -0,0 +1,118 @@
+use crate::fairings::request_id::RequestId;
+use retronomicon_db::{DbError, TransactionError};
+use retronomicon_dto as dto;
+use retronomicon_dto::types::IdOrSlugError;
+use rocket::http::Status;
+use rocket::response::Responder;
+use rocket::serde::json::Json;
+use rocket::Request;
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::okapi::openapi3::Responses;
+use rocket_okapi::response::OpenApiResponderInner;
+
+/// The message sent to clients instead of the actual message of a server
+/// error.
+const INTERNAL_ERROR_MESSAGE: &str = "Internal server error";
+
+/// An error returned by a route handler.
+///
+/// Client errors (4xx) are sent with their message. The message of server
+/// errors (5xx) may leak internals (e.g. SQL or constraint names), so it is
+/// only logged along with the request ID, and clients get a generic message.
+#[derive(Debug)]
+pub struct ApiError {
+    status: Status,
+    message: String,
+}
+
+impl ApiError {
+    pub fn new(status: Status, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    /// The message that can be sent to clients, i.e. a generic message for
+    /// server errors.
+    pub fn public_message(&self) -> &str {
+        if self.status.class().is_server_error() {
+            INTERNAL_ERROR_MESSAGE
+        } else {
+            &self.message
+        }
+    }
+}
+
+impl From<(Status, String)> for ApiError {
+    fn from((status, message): (Status, String)) -> Self {
+        Self::new(status, message)
+    }
+}
+
+impl From<TransactionError> for ApiError {
+    fn from(TransactionError(status, message): TransactionError) -> Self {
+        Self::new(status, message)
+    }
+}
+
+impl From<DbError> for ApiError {
+    fn from(value: DbError) -> Self {
+        TransactionError::from(value).into()
+    }
+}
+
+impl From<IdOrSlugError> for ApiError {
+    fn from(value: IdOrSlugError) -> Self {
+        Self::new(Status::BadRequest, value.to_string())
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(self, request: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let Self { status, message } = self;
+        if status.class().is_server_error() {
+            rocket::error_!("[{}] {status}: {message}", RequestId::of(request));
+            (status, INTERNAL_ERROR_MESSAGE.to_string()).respond_to(request)
+        } else {
+            (status, message).respond_to(request)
+        }
+    }
+}
+
+impl OpenApiResponderInner for ApiError {
+    fn responses(gen: &mut OpenApiGenerator) -> rocket_okapi::Result<Responses> {
+        String::responses(gen)
+    }
+}
+
+/// An error returned by the password login route. A login with a password
+/// that must be reset first is a 403 with a JSON body, so clients can send
+/// the user to the reset page. Other errors are sent as [`ApiError`].
+#[derive(Debug, Responder)]
+pub enum LoginError {
+    #[response(status = 403)]
+    PasswordResetRequired(Json<dto::auth::PasswordResetRequiredResponse>),
+    Api(ApiError),
+}
+
+impl From<ApiError> for LoginError {
+    fn from(value: ApiError) -> Self {
+        Self::Api(value)
+    }
+}
+
+impl From<(Status, String)> for LoginError {
+    fn from(value: (Status, String)) -> Self {
+        Self::Api(value.into())
+    }
+}
+
+impl OpenApiResponderInner for LoginError {
+    fn responses(gen: &mut OpenApiGenerator) -> rocket_okapi::Result<Responses> {
+        let mut responses = ApiError::responses(gen)?;
+        let schema = gen.json_schema::<dto::auth::PasswordResetRequiredResponse>();
+        rocket_okapi::util::add_schema_response(&mut responses, 403, "application/json", schema)?;
+        Ok(responses)
+    }
+}
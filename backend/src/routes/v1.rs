@@ -1,10 +1,15 @@
 use rocket_okapi::openapi_get_routes;
 
+pub mod admin;
+pub mod artifacts;
 pub mod auth;
 pub mod cores;
 pub mod games;
+pub mod health;
 pub mod me;
 pub mod platforms;
+pub mod releases;
+pub mod search;
 pub mod systems;
 pub mod tags;
 pub mod teams;
@@ -12,43 +17,81 @@ pub mod users;
 
 pub fn routes() -> Vec<rocket::Route> {
     openapi_get_routes![
+        admin::admin_cores_merge,
+        admin::admin_cores_releases_verify,
+        admin::admin_maintenance_backfill_sha1,
+        admin::admin_maintenance_expire_tokens,
+        admin::admin_users_require_password_reset,
+        artifacts::artifacts_by_sha256,
+        artifacts::artifacts_details,
+        auth::email_login_confirm,
+        auth::email_login_request,
+        auth::forgot,
         auth::github_login,
         auth::google_login,
         auth::patreon_login,
         auth::login,
         auth::logout,
+        auth::reset,
         auth::signup,
+        cores::cores_artifact_filenames,
         cores::cores_create,
         cores::cores_details,
         cores::cores_list,
+        cores::cores_recently_released,
+        cores::cores_search,
+        cores::releases::cores_changelog,
+        cores::releases::cores_changelog_markdown,
+        cores::releases::cores_releases_artifacts_attach,
+        cores::releases::cores_releases_artifacts_details,
         cores::releases::cores_releases_artifacts_download,
         cores::releases::cores_releases_artifacts_download_filename,
+        cores::releases::cores_releases_artifacts_download_zip,
         cores::releases::cores_releases_artifacts_list,
         cores::releases::cores_releases_artifacts_upload,
+        cores::releases::cores_releases_artifacts_verify,
         cores::releases::cores_releases_create,
+        cores::releases::cores_releases_full,
         cores::releases::cores_releases_list,
+        cores::releases::cores_releases_notes_html,
         games::games_add_artifact,
         games::games_create,
         games::games_details,
         games::games_images,
+        games::games_images_set_primary,
         games::games_images_upload,
         games::games_list,
+        games::games_list_extended,
+        games::games_merge,
         games::games_update,
+        health::health_detailed,
         me::me,
+        me::me_change_password,
+        me::me_providers,
+        me::me_providers_unlink,
         me::me_token,
         me::me_update,
         platforms::platforms_create,
         platforms::platforms_details,
         platforms::platforms_list,
         platforms::platforms_update,
+        releases::releases_list,
+        search::resolve,
+        search::search,
         systems::systems_create,
         systems::systems_details,
+        systems::systems_games_dat,
+        systems::systems_games_dat_json,
+        systems::systems_import_dat,
         systems::systems_list,
         tags::tags,
+        tags::tags_cores,
         tags::tags_create,
         tags::tags_delete,
+        tags::tags_games,
         teams::invite,
         teams::invite_accept,
+        teams::invite_bulk,
         teams::teams,
         teams::teams_create,
         teams::teams_delete,
@@ -57,6 +100,7 @@ pub fn routes() -> Vec<rocket::Route> {
         users::check_username,
         users::users,
         users::users_details,
+        users::users_search,
         users::users_update,
     ]
 }
@@ -0,0 +1,212 @@
+use crate::fairings::maintenance;
+use crate::guards;
+use crate::routes::error::ApiError;
+use crate::routes::v1::cores::releases::artifact_data;
+use crate::utils;
+use retronomicon_db::models;
+use retronomicon_db::types::{found_or_404_named, FetchModel};
+use retronomicon_db::Db;
+use retronomicon_dto as dto;
+use rocket::http::Status;
+use rocket::post;
+use rocket::serde::json::Json;
+use rocket_okapi::openapi;
+use sha2::Digest;
+
+/// Expire the email validation tokens that were not used in time. This also
+/// runs periodically in the background.
+#[openapi(tag = "Admin", ignore = "db")]
+#[post("/admin/maintenance/expire-tokens")]
+pub async fn admin_maintenance_expire_tokens(
+    mut db: Db,
+    _root_user: guards::users::RootUserGuard,
+) -> Result<Json<dto::auth::ExpireTokensResponse>, ApiError> {
+    maintenance::expire_tokens(&mut db)
+        .await
+        .map(|expired| Json(dto::auth::ExpireTokensResponse { expired }))
+        .map_err(|e| (Status::InternalServerError, e).into())
+}
+
+/// The number of artifacts to backfill in a single request, by default and
+/// at most.
+const BACKFILL_SHA1_DEFAULT_LIMIT: i64 = 20;
+const BACKFILL_SHA1_MAX_LIMIT: i64 = 100;
+
+/// Download an artifact and store its SHA1, making sure the content matches
+/// its size and MD5. Returns whether the SHA1 was stored.
+async fn backfill_sha1(db: &mut Db, artifact: &mut models::Artifact) -> bool {
+    let Some(url) = artifact.download_url.clone() else {
+        return false;
+    };
+
+    let data = match utils::http::download(&url, utils::http::MAX_DOWNLOAD_BYTES).await {
+        Ok(data) => data,
+        Err(e) => {
+            rocket::warn!("Could not download artifact {}: {}", artifact.id, e);
+            return false;
+        }
+    };
+
+    if data.len() != artifact.size as usize
+        || (!artifact.md5.is_empty() && md5::compute(&data).0 != artifact.md5.as_slice())
+    {
+        rocket::warn!(
+            "Downloaded artifact {} does not match its checksums",
+            artifact.id
+        );
+        return false;
+    }
+
+    let sha1 = sha1::Sha1::digest(&data).to_vec();
+    match artifact.set_sha1(db, sha1).await {
+        Ok(()) => true,
+        Err(e) => {
+            rocket::error!("Could not update SHA1 of artifact {}: {}", artifact.id, e);
+            false
+        }
+    }
+}
+
+/// Compute the SHA1 of the artifacts created before it was recorded, by
+/// downloading them from their download URL. Goes through the artifacts with
+/// an id greater than `after`, `limit` at a time (20 by default, at most
+/// 100). Call again with the returned `last_id` until it is missing.
+#[openapi(tag = "Admin", ignore = "db")]
+#[post("/admin/maintenance/backfill-sha1?<after>&<limit>")]
+pub async fn admin_maintenance_backfill_sha1(
+    mut db: Db,
+    _root_user: guards::users::RootUserGuard,
+    after: Option<u32>,
+    limit: Option<i64>,
+) -> Result<Json<dto::artifact::ArtifactBackfillResponse>, ApiError> {
+    let limit = limit.unwrap_or(BACKFILL_SHA1_DEFAULT_LIMIT);
+    if !(1..=BACKFILL_SHA1_MAX_LIMIT).contains(&limit) {
+        return Err((
+            Status::BadRequest,
+            format!("Limit must be between 1 and {BACKFILL_SHA1_MAX_LIMIT}"),
+        )
+            .into());
+    }
+
+    let artifacts = models::Artifact::list_missing_sha1(&mut db, after.unwrap_or(0) as i32, limit)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    let last_id = artifacts.last().map(|a| a.id);
+
+    let mut updated = 0;
+    let mut failed = Vec::new();
+    for mut artifact in artifacts {
+        if backfill_sha1(&mut db, &mut artifact).await {
+            updated += 1;
+        } else {
+            failed.push(artifact.id);
+        }
+    }
+
+    Ok(Json(dto::artifact::ArtifactBackfillResponse {
+        updated,
+        failed,
+        last_id,
+    }))
+}
+
+/// Verify the integrity of all the artifacts of a release, by computing the
+/// SHA256 checksum of their data and comparing it to the stored checksum.
+/// Useful after moving artifacts between storage backends. Artifacts whose
+/// data cannot be read are reported as invalid, with the error.
+#[openapi(tag = "Admin", ignore = "db")]
+#[post("/admin/cores/<core_id>/releases/<release_id>/verify")]
+pub async fn admin_cores_releases_verify(
+    mut db: Db,
+    _root_user: guards::users::RootUserGuard,
+    core_id: dto::types::IdOrSlug<'_>,
+    release_id: u32,
+) -> Result<Json<dto::artifact::ReleaseVerifyResponse>, ApiError> {
+    let core = models::Core::from_id_or_slug(&mut db, core_id).await?;
+    let release = found_or_404_named(
+        "Release",
+        models::CoreRelease::from_core_and_id(&mut db, core.id, release_id as i32).await,
+    )?;
+
+    let artifacts = models::Artifact::list_for_releases(&mut db, &[release.id])
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    let mut items = Vec::with_capacity(artifacts.len());
+    for (_, artifact) in artifacts {
+        let (actual, error) = match artifact_data(&mut db, &artifact).await {
+            Ok(data) => (Some(sha2::Sha256::digest(&data).to_vec()), None),
+            Err(e) => {
+                rocket::error!("Could not read artifact {}: {e:?}", artifact.id);
+                (None, Some(e.public_message().to_string()))
+            }
+        };
+
+        items.push(dto::artifact::ReleaseArtifactVerifyItem {
+            id: artifact.id,
+            filename: artifact.filename,
+            valid: actual.as_ref() == Some(&artifact.sha256),
+            expected: artifact.sha256.into(),
+            actual: actual.map(Into::into),
+            error,
+        });
+    }
+
+    Ok(Json(dto::artifact::ReleaseVerifyResponse {
+        artifacts: items,
+    }))
+}
+
+/// Merge a duplicate core into another one of the same system. The releases
+/// and tags of the source core are moved to the target, then the source is
+/// deleted and its slug redirects to the target. The slug stays reserved and
+/// cannot be used by a new core.
+#[openapi(tag = "Admin", ignore = "db")]
+#[post("/admin/cores/merge", format = "application/json", data = "<form>")]
+pub async fn admin_cores_merge(
+    mut db: Db,
+    root_user: guards::users::RootUserGuard,
+    form: Json<dto::cores::CoreMergeRequest<'_>>,
+) -> Result<Json<dto::cores::CoreMergeResponse>, ApiError> {
+    let dto::cores::CoreMergeRequest { source, target } = form.into_inner();
+    let source = models::Core::from_id_or_slug(&mut db, source).await?;
+    let target = models::Core::from_id_or_slug(&mut db, target).await?;
+    if source.id == target.id {
+        return Err((
+            Status::BadRequest,
+            "Cannot merge a core into itself.".to_string(),
+        )
+            .into());
+    }
+
+    let (moved_releases, moved_tags) =
+        models::Core::merge(&mut db, source.id, target.id, root_user.id).await?;
+
+    Ok(Json(dto::cores::CoreMergeResponse {
+        moved_releases: moved_releases as i64,
+        moved_tags: moved_tags as i64,
+    }))
+}
+
+/// Require a user to reset their password. Logging in with the current
+/// password fails until it is reset.
+#[openapi(tag = "Admin", ignore = "db")]
+#[post("/admin/users/<user_id>/require-password-reset")]
+pub async fn admin_users_require_password_reset(
+    mut db: Db,
+    _root_user: guards::users::RootUserGuard,
+    user_id: dto::user::UserIdOrUsername<'_>,
+) -> Result<Json<dto::Ok>, ApiError> {
+    let user = models::User::from_userid(&mut db, user_id)
+        .await
+        .map_err(|e| (Status::NotFound, e.to_string()))?;
+
+    let updated = models::UserPassword::require_reset(&mut db, user.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    if !updated {
+        return Err((Status::NotFound, "User has no password".to_string()).into());
+    }
+
+    Ok(Json(dto::Ok))
+}
@@ -0,0 +1,135 @@
+use crate::guards;
+use crate::routes::error::ApiError;
+use crate::utils::acls;
+use retronomicon_db::models;
+use retronomicon_db::types::{found_or_404_named, FetchModel};
+use retronomicon_db::Db;
+use retronomicon_dto as dto;
+use rocket::get;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket_okapi::openapi;
+use std::str::FromStr;
+
+/// The URL to download an artifact, either its external URL or the download
+/// route of a non-yanked core release it belongs to.
+pub(crate) async fn public_download_url(
+    db: &mut Db,
+    artifact: &models::Artifact,
+) -> Result<Option<String>, ApiError> {
+    if let Some(url) = &artifact.download_url {
+        return Ok(Some(url.clone()));
+    }
+
+    Ok(artifact
+        .public_core_release(db)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .map(|(release, core)| release_download_url(&core, &release, artifact)))
+}
+
+/// The URL to download an artifact that is not publicly visible, e.g. one
+/// that is only part of yanked releases, if the user can read the private
+/// resources of the team owning its core.
+async fn private_download_url(
+    db: &mut Db,
+    user: Option<guards::users::AuthenticatedUserGuard>,
+    artifact: &models::Artifact,
+) -> Result<Option<String>, ApiError> {
+    let Some(user) = user else {
+        return Ok(None);
+    };
+    let Some((release, core)) = artifact
+        .latest_core_release(db, true)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+    else {
+        return Ok(None);
+    };
+
+    let user = user.into_model(db).await?;
+    let role = user
+        .role_in(db, core.owner_team_id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    if !role.is_some_and(|role| acls::can_read_private_core_resources(&user, &core, &role)) {
+        return Ok(None);
+    }
+
+    Ok(Some(release_download_url(&core, &release, artifact)))
+}
+
+fn release_download_url(
+    core: &models::Core,
+    release: &models::CoreRelease,
+    artifact: &models::Artifact,
+) -> String {
+    rocket::uri!(
+        "/api/v1/",
+        crate::routes::v1::cores::releases::cores_releases_artifacts_download(
+            core.slug.as_str(),
+            release.id as u32,
+            artifact.id as u32
+        )
+    )
+    .to_string()
+}
+
+/// Check whether an artifact with this SHA256 checksum exists, for example
+/// to skip uploading a file the server already has. Returns 404 if none does,
+/// or if it is not publicly visible (see [`artifacts_details`]). This also
+/// answers `HEAD` requests, without a body.
+#[openapi(tag = "Artifacts", ignore = "db")]
+#[get("/artifacts/by-sha256/<sha256>")]
+pub async fn artifacts_by_sha256(
+    mut db: Db,
+    sha256: &str,
+) -> Result<Json<dto::artifact::ArtifactExistsResponse>, ApiError> {
+    let sha256 = dto::encodings::HexString::from_str(sha256)
+        .map_err(|e| (Status::BadRequest, e.to_string()))?;
+
+    let artifact = found_or_404_named(
+        "Artifact",
+        models::Artifact::from_sha256(&mut db, &sha256).await,
+    )?;
+
+    let download_url = public_download_url(&mut db, &artifact)
+        .await?
+        .ok_or((Status::NotFound, "Artifact not found".to_string()))?;
+
+    Ok(Json(dto::artifact::ArtifactExistsResponse {
+        id: artifact.id,
+        download_url,
+    }))
+}
+
+/// Get the details of an artifact. Only artifacts that have an external
+/// download URL or that are part of a non-yanked core release are visible,
+/// except to members of the core's team (including viewers).
+#[openapi(tag = "Artifacts", ignore = "db")]
+#[get("/artifacts/<artifact_id>")]
+pub async fn artifacts_details(
+    mut db: Db,
+    user: Option<guards::users::AuthenticatedUserGuard>,
+    artifact_id: u32,
+) -> Result<Json<dto::artifact::ArtifactDetails>, ApiError> {
+    let artifact = models::Artifact::fetch_or_404(&mut db, artifact_id as i32).await?;
+
+    let download_url = match public_download_url(&mut db, &artifact).await? {
+        Some(url) => url,
+        None => private_download_url(&mut db, user, &artifact)
+            .await?
+            .ok_or((Status::NotFound, "Artifact not found".to_string()))?,
+    };
+
+    let r#ref = artifact.clone().into();
+
+    Ok(Json(dto::artifact::ArtifactDetails {
+        id: artifact.id,
+        filename: artifact.filename,
+        mime_type: artifact.mime_type,
+        created_at: artifact.created_at.timestamp(),
+        download_url,
+        r#ref,
+    }))
+}
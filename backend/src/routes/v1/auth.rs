@@ -1,9 +1,11 @@
-use crate::fairings::config::{DbPepper, RetronomiconConfig};
+use crate::fairings::config::{DbPepper, JwtKeys, RetronomiconConfig};
 use crate::guards::emailer::EmailGuard;
 use crate::guards::users::UserGuard;
 use crate::routes::auth::{GitHubUserInfo, GoogleUserInfo, PatreonUserInfo};
-use retronomicon_db::models::{User, UserPassword};
-use retronomicon_db::Db;
+use crate::routes::error::{ApiError, LoginError};
+use retronomicon_db::models::{validate_password, PasswordNeedsReset, User, UserPassword};
+use retronomicon_db::scoped_futures::ScopedFutureExt;
+use retronomicon_db::{transaction, Db};
 use retronomicon_dto as dto;
 use rocket::http::{CookieJar, Status};
 use rocket::response::Redirect;
@@ -22,87 +24,114 @@ pub async fn signup(
     cookies: &CookieJar<'_>,
     pepper: &State<DbPepper>,
     config: &State<RetronomiconConfig>,
+    jwt_secret: &State<JwtKeys>,
     emailer: EmailGuard,
-) -> Result<Json<dto::auth::SignupResponse>, (Status, String)> {
+) -> Result<Json<dto::auth::SignupResponse>, ApiError> {
     let form = form.into_inner();
-    let user = User::create(
-        &mut db,
-        form.username,
-        None,
-        None,
-        form.email,
-        None,
-        None,
-        json!({}),
-        json!({}),
-    )
-    .await
-    .map_err(|e| (Status::InternalServerError, e.to_string()))?;
-    // Create the user password.
-    let user_password = UserPassword::create(&mut db, &user, Some(form.password), &pepper.0, true)
-        .await
-        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    validate_password(form.password, form.email, form.username)
+        .map_err(|e| (Status::BadRequest, e.to_string()))?;
 
-    if let Some(token) = user_password.validation_token {
-        // Check if we bypass the validation_token and go straight to login.
-        if config.inner().bypass_email_validation(form.email) {
-            crate::routes::auth::login_token_callback(
+    let password = form.password;
+    let (username, email) = (form.username, form.email);
+    let (user, token) = transaction(&mut db, |db| {
+        async move {
+            let user = User::create(
                 db,
-                cookies,
-                config,
-                form.email.to_string(),
-                token,
+                username,
+                None,
+                None,
+                email,
+                None,
+                None,
+                json!({}),
+                json!({}),
             )
             .await?;
-            return Ok(Json(dto::auth::SignupResponse {
-                email: form.email.to_string(),
-                id: user.id,
-            }));
+            // Create the user password.
+            let user_password = UserPassword::create(db, &user, Some(password), &pepper.0, true)
+                .await
+                .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+            let token = user_password.validation_token.ok_or((
+                Status::InternalServerError,
+                "Validation token not set".to_string(),
+            ))?;
+            Ok((user, token))
         }
+        .scope_boxed()
+    })
+    .await?;
 
-        // Send an email.
-        emailer.send_email_verification(
-            &user.email,
-            url::Url::parse(&format!(
-                "{}{}",
-                config.inner().base_url,
-                uri!(
-                    "/api",
-                    crate::routes::auth::login_token_callback(&user.email, &token)
-                ),
-            ))
-            .map_err(|e| (Status::InternalServerError, e.to_string()))?
-            .as_str(),
-        )?;
-
-        Ok(Json(dto::auth::SignupResponse {
-            email: user.email,
+    // Check if we bypass the validation_token and go straight to login.
+    if config.inner().bypass_email_validation(form.email) {
+        let guard = crate::routes::auth::login_from_validation_token(
+            &mut db, cookies, config, form.email, &token,
+        )
+        .await?;
+        let (token, token_expiry) = guard
+            .create_jwt_with_expiry(&jwt_secret.inner().encoding)
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+        return Ok(Json(dto::auth::SignupResponse {
+            email: form.email.to_string(),
             id: user.id,
-        }))
-    } else {
-        let _ = user_password.delete(&mut db).await;
-        let _ = user.delete_row(&mut db).await;
-        Err((
-            Status::InternalServerError,
-            "Validation token not set".to_string(),
-        ))
+            token: Some(token),
+            token_expiry: Some(token_expiry),
+        }));
     }
+
+    // Send an email.
+    emailer.send_email_verification(
+        &user.email,
+        url::Url::parse(&format!(
+            "{}{}",
+            config.inner().base_url,
+            uri!(
+                "/api",
+                crate::routes::auth::login_token_callback(&user.email, &token)
+            ),
+        ))
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .as_str(),
+    )?;
+
+    Ok(Json(dto::auth::SignupResponse {
+        email: user.email,
+        id: user.id,
+        token: None,
+        token_expiry: None,
+    }))
 }
 
-/// Login with an email and password.
+/// Login with an email and password. If the password must be reset first,
+/// this fails with a 403 and a [`dto::auth::PasswordResetRequiredResponse`]
+/// body.
 #[openapi(tag = "Authentication", ignore = "db")]
 #[post("/login", format = "application/json", data = "<form>")]
 pub async fn login(
     mut db: Db,
     cookies: &CookieJar<'_>,
     pepper: &State<DbPepper>,
+    config: &State<RetronomiconConfig>,
     form: Json<dto::auth::LoginRequest<'_>>,
-) -> Result<Json<dto::Ok>, (Status, String)> {
+) -> Result<Json<dto::Ok>, LoginError> {
     let form = form.into_inner();
 
-    let user = User::from_email(&mut db, form.email, form.password, &pepper.inner().0)
-        .await
-        .map_err(|e| (Status::Unauthorized, e.to_string()))?;
+    let user = match User::from_email(&mut db, form.email, form.password, &pepper.inner().0).await {
+        Ok(user) => user,
+        Err(e) if e.is::<PasswordNeedsReset>() => {
+            let reset_url = config
+                .inner()
+                .password_reset_url()
+                .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+            return Err(LoginError::PasswordResetRequired(Json(
+                dto::auth::PasswordResetRequiredResponse {
+                    error: "password_reset_required".to_string(),
+                    reset_url: reset_url.to_string(),
+                },
+            )));
+        }
+        Err(e) => return Err((Status::Unauthorized, e.to_string()).into()),
+    };
 
     let guard = UserGuard::from_model(user);
     guard.update_cookie(cookies);
@@ -110,6 +139,148 @@ pub async fn login(
     Ok(Json(dto::Ok))
 }
 
+/// Request a login link by email. If an account exists with this email, a link
+/// to login will be sent to it. Unknown emails succeed too, so this cannot be
+/// used to find which emails are registered. Requesting another link for the
+/// same account within 5 minutes fails with a 429.
+#[openapi(tag = "Authentication", ignore = "db", ignore = "emailer")]
+#[post(
+    "/auth/email-login/request",
+    format = "application/json",
+    data = "<form>"
+)]
+pub async fn email_login_request(
+    mut db: Db,
+    form: Json<dto::auth::EmailLoginRequest<'_>>,
+    pepper: &State<DbPepper>,
+    config: &State<RetronomiconConfig>,
+    emailer: EmailGuard,
+) -> Result<Json<dto::Ok>, ApiError> {
+    let form = form.into_inner();
+    let user = match User::find_by_email(&mut db, form.email)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+    {
+        Some(user) => user,
+        None => return Ok(Json(dto::Ok)),
+    };
+
+    let token = UserPassword::create_validation_token(&mut db, &user, &pepper.0)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((
+            Status::TooManyRequests,
+            "A login link was sent recently, please check your email".to_string(),
+        ))?;
+
+    emailer.send_email_login(
+        &user.email,
+        url::Url::parse(&format!(
+            "{}{}",
+            config.inner().base_url,
+            uri!(
+                "/api",
+                crate::routes::auth::login_token_callback(&user.email, &token)
+            ),
+        ))
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .as_str(),
+    )?;
+
+    Ok(Json(dto::Ok))
+}
+
+/// Login using the token received by email. This sets the authentication cookie
+/// and returns a JWT token for the user.
+#[openapi(tag = "Authentication", ignore = "db")]
+#[post(
+    "/auth/email-login/confirm",
+    format = "application/json",
+    data = "<form>"
+)]
+pub async fn email_login_confirm(
+    mut db: Db,
+    cookies: &CookieJar<'_>,
+    config: &State<RetronomiconConfig>,
+    jwt_secret: &State<JwtKeys>,
+    form: Json<dto::auth::EmailLoginConfirmRequest<'_>>,
+) -> Result<Json<dto::auth::TokenResponse>, ApiError> {
+    let form = form.into_inner();
+    let guard = crate::routes::auth::login_from_validation_token(
+        &mut db, cookies, config, form.email, form.token,
+    )
+    .await?;
+
+    guard
+        .create_jwt(&jwt_secret.inner().encoding)
+        .map(|token| Json(dto::auth::TokenResponse { token }))
+        .map_err(|e| (Status::InternalServerError, e.to_string()).into())
+}
+
+/// Request a password reset. If an account exists with this email, a link to
+/// reset its password will be sent to it. This always succeeds, so it cannot be
+/// used to find which emails are registered. Only one email per minute will be
+/// sent for the same account.
+#[openapi(tag = "Authentication", ignore = "db", ignore = "emailer")]
+#[post("/auth/forgot", format = "application/json", data = "<form>")]
+pub async fn forgot(
+    mut db: Db,
+    form: Json<dto::auth::PasswordForgotRequest<'_>>,
+    pepper: &State<DbPepper>,
+    config: &State<RetronomiconConfig>,
+    emailer: EmailGuard,
+) -> Result<Json<dto::Ok>, ApiError> {
+    let form = form.into_inner();
+    let user = match User::find_by_email(&mut db, form.email)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+    {
+        Some(user) => user,
+        None => return Ok(Json(dto::Ok)),
+    };
+
+    let token = UserPassword::create_reset_token(&mut db, &user, &pepper.0)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    if let Some(token) = token {
+        let mut url = config
+            .inner()
+            .password_reset_url()
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+        url.query_pairs_mut().append_pair("token", &token);
+
+        emailer.send_password_reset(&user.email, url.as_str())?;
+    }
+
+    Ok(Json(dto::Ok))
+}
+
+/// Set a new password using a reset token sent by email.
+#[openapi(tag = "Authentication", ignore = "db")]
+#[post("/auth/reset", format = "application/json", data = "<form>")]
+pub async fn reset(
+    mut db: Db,
+    form: Json<dto::auth::PasswordResetRequest<'_>>,
+    pepper: &State<DbPepper>,
+) -> Result<Json<dto::Ok>, ApiError> {
+    let form = form.into_inner();
+    let (user, user_password) = UserPassword::from_reset_token(&mut db, form.token)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((Status::NotFound, "Invalid or expired token".to_string()))?;
+
+    validate_password(form.password, &user.email, user.username.as_deref())
+        .map_err(|e| (Status::BadRequest, e.to_string()))?;
+
+    user_password
+        .reset(&mut db, form.password, &pepper.0)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(dto::Ok))
+}
+
 /// Login using GitHub with OAuth2. This will redirect the user to GitHub's login
 /// page. If the user accepts the request, GitHub will redirect the user back to
 /// the callback URL specified in the OAuth2 configuration.
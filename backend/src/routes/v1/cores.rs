@@ -1,14 +1,17 @@
 use crate::guards;
-use crate::utils::json;
+use crate::routes::error::ApiError;
+use crate::utils::{acls, json};
 use retronomicon_db::models;
-use retronomicon_db::types::FetchModel;
+use retronomicon_db::types::{found_or_404_named, FetchModel};
 use retronomicon_db::Db;
 use retronomicon_dto as dto;
 use rocket::http::Status;
+use rocket::response::Redirect;
 use rocket::serde::json::Json;
-use rocket::{get, post};
+use rocket::{get, post, uri, Either};
 use rocket_okapi::openapi;
 use serde_json::json;
+use std::collections::BTreeMap;
 
 pub mod releases;
 
@@ -20,7 +23,7 @@ pub async fn cores_list(
     filter: dto::cores::CoreListQueryParams<'_>,
     page: Option<i64>,
     limit: Option<i64>,
-) -> Result<Json<dto::Paginated<dto::cores::CoreListItem>>, (Status, String)> {
+) -> Result<Json<dto::Paginated<dto::cores::CoreListItem>>, ApiError> {
     let (page, limit) = dto::params::PagingParams::new(page, limit)
         .validate()
         .map_err(|e| (Status::BadRequest, e))?;
@@ -37,6 +40,8 @@ pub async fn cores_list(
         Some(team) => Some(models::Team::from_id_or_slug(&mut db, team).await?),
         None => None,
     };
+    let sort = dto::params::SortParams::parse_opt(filter.sort.as_deref())
+        .map_err(|e| (Status::BadRequest, e))?;
     let release = filter
         .release_date_ge
         .and_then(|release| chrono::NaiveDateTime::from_timestamp_opt(release, 0));
@@ -49,10 +54,28 @@ pub async fn cores_list(
         system.map(|s| s.id),
         team.map(|t| t.id),
         release,
+        filter.name_contains.as_deref(),
+        filter.q.as_deref(),
+        sort,
     )
     .await
     .map_err(|e| (Status::InternalServerError, e.to_string()))?;
 
+    // Fetch the platforms of all cores of the page at once.
+    let mut platforms = if filter.with_platforms.unwrap_or(false) {
+        let core_ids = items.iter().map(|(c, ..)| c.id).collect::<Vec<_>>();
+        let mut platforms = BTreeMap::<i32, Vec<dto::platforms::PlatformRef>>::new();
+        for (core_id, platform) in models::Core::platforms_for_cores(&mut db, &core_ids)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        {
+            platforms.entry(core_id).or_default().push(platform.into());
+        }
+        Some(platforms)
+    } else {
+        None
+    };
+
     let paginated = dto::Paginated::new(
         page,
         limit,
@@ -61,12 +84,16 @@ pub async fn cores_list(
             .into_iter()
             .map(
                 |(core, system, team, core_release, platform)| dto::cores::CoreListItem {
+                    platforms: platforms
+                        .as_mut()
+                        .map(|p| p.remove(&core.id).unwrap_or_default()),
                     id: core.id,
                     slug: core.slug,
                     name: core.name,
                     owner_team: team.into(),
                     system: system.into(),
                     latest_release: core_release.map(|cr| cr.into_ref(platform)),
+                    updated_at: core.updated_at.timestamp(),
                 },
             )
             .collect(),
@@ -75,22 +102,113 @@ pub async fn cores_list(
     Ok(Json(paginated))
 }
 
+/// Search cores by name. The name does not need to be exact; cores are
+/// ordered by how similar their name is to the query.
+#[openapi(tag = "Cores", ignore = "db")]
+#[get("/cores/search?<q>&<limit>")]
+pub async fn cores_search(
+    mut db: Db,
+    q: &str,
+    limit: Option<i64>,
+) -> Result<Json<Vec<dto::cores::CoreSearchItem>>, ApiError> {
+    let (_, limit) = dto::params::PagingParams::new(None, limit)
+        .validate()
+        .map_err(|e| (Status::BadRequest, e))?;
+
+    let items = models::Core::search_by_name(&mut db, q, limit)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(
+        items
+            .into_iter()
+            .map(
+                |(core, system, team, similarity)| dto::cores::CoreSearchItem {
+                    id: core.id,
+                    slug: core.slug,
+                    name: core.name,
+                    system: system.into(),
+                    owner_team: team.into(),
+                    similarity,
+                },
+            )
+            .collect(),
+    ))
+}
+
+/// List the cores released since a date, with their latest release. Cores
+/// are ordered from the most recently released.
+#[openapi(tag = "Cores", ignore = "db")]
+#[get("/cores/recently-released?<since>&<page>&<limit>")]
+pub async fn cores_recently_released(
+    mut db: Db,
+    since: i64,
+    page: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Json<Vec<dto::cores::CoreListItem>>, ApiError> {
+    let (page, limit) = dto::params::PagingParams::new(page, limit)
+        .validate()
+        .map_err(|e| (Status::BadRequest, e))?;
+    let Some(since) = chrono::NaiveDateTime::from_timestamp_opt(since, 0) else {
+        return Err((Status::BadRequest, "Invalid since timestamp".to_string()).into());
+    };
+
+    let items = models::Core::list_recently_released(&mut db, since, page, limit)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(
+        items
+            .into_iter()
+            .map(
+                |(core, system, team, core_release, platform)| dto::cores::CoreListItem {
+                    id: core.id,
+                    slug: core.slug,
+                    name: core.name,
+                    owner_team: team.into(),
+                    system: system.into(),
+                    latest_release: Some(core_release.into_ref(platform)),
+                    updated_at: core.updated_at.timestamp(),
+                    platforms: None,
+                },
+            )
+            .collect(),
+    ))
+}
+
 #[openapi(tag = "Cores", ignore = "db")]
 #[get("/cores/<core_id>")]
 pub async fn cores_details(
     mut db: Db,
     core_id: dto::types::IdOrSlug<'_>,
-) -> Result<Json<dto::cores::CoreDetailsResponse>, (Status, String)> {
-    let (core, owner_team, system) = match core_id {
+) -> Result<Either<Json<dto::cores::CoreDetailsResponse>, Redirect>, ApiError> {
+    let found = match core_id {
         dto::types::IdOrSlug::Id(id) => models::Core::get_with_owner_and_system(&mut db, id).await,
-        dto::types::IdOrSlug::Slug(slug) => {
-            models::Core::get_by_slug_with_owner_and_system(&mut db, &slug).await
+        dto::types::IdOrSlug::Slug(ref slug) => {
+            models::Core::get_by_slug_with_owner_and_system(&mut db, slug).await
         }
     }
-    .map_err(|e| (Status::InternalServerError, e.to_string()))?
-    .ok_or((Status::NotFound, "Core not found".to_string()))?;
+    .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    let Some((core, owner_team, system)) = found else {
+        // The core might have been merged into another one, in which case
+        // redirect to the slug of the core it was merged into.
+        let (core, _) = models::Core::from_id_or_slug_or_previous(&mut db, core_id).await?;
 
-    Ok(Json(dto::cores::CoreDetailsResponse {
+        return Ok(Either::Right(Redirect::moved(uri!(
+            "/api/v1",
+            cores_details(core.slug.as_str())
+        ))));
+    };
+
+    let releases_by_platform = models::Core::latest_release_per_platform(&mut db, core.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .into_iter()
+        .map(|(release, platform)| release.into_ref(platform))
+        .collect();
+
+    Ok(Either::Left(Json(dto::cores::CoreDetailsResponse {
         id: core.id,
         slug: core.slug,
         name: core.name,
@@ -101,7 +219,36 @@ pub async fn cores_details(
             .map_err(|e| (Status::InternalServerError, e.to_string()))?,
         system: system.into(),
         owner_team: owner_team.into(),
-    }))
+        updated_at: core.updated_at.timestamp(),
+        releases_by_platform,
+    })))
+}
+
+/// List the filenames of all the artifacts of a core, across all its
+/// releases. Only admins of the core's team can list them.
+#[openapi(tag = "Cores", ignore = "db")]
+#[get("/cores/<core_id>/artifact-filenames")]
+pub async fn cores_artifact_filenames(
+    mut db: Db,
+    user: guards::users::AuthenticatedUserGuard,
+    core_id: dto::types::IdOrSlug<'_>,
+) -> Result<Json<Vec<String>>, ApiError> {
+    let core = models::Core::from_id_or_slug(&mut db, core_id).await?;
+
+    let user = user.into_model(&mut db).await?;
+    let role = user
+        .role_in(&mut db, core.owner_team_id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((Status::Forbidden, "Not a member of the team".to_string()))?;
+    if !acls::can_inspect_core_artifacts(&user, &core, &role) {
+        return Err((Status::Forbidden, "Not enough permission".to_string()).into());
+    }
+
+    models::CoreReleaseArtifact::list_all_filenames_for_core(&mut db, core.id)
+        .await
+        .map(Json)
+        .map_err(|e| (Status::InternalServerError, e.to_string()).into())
 }
 
 #[openapi(tag = "Cores", ignore = "db")]
@@ -110,7 +257,7 @@ pub async fn cores_create(
     mut db: Db,
     user: guards::users::AuthenticatedUserGuard,
     form: Json<dto::cores::CoreCreateRequest<'_>>,
-) -> Result<Json<dto::cores::CoreCreateResponse>, (Status, String)> {
+) -> Result<Json<dto::cores::CoreCreateResponse>, ApiError> {
     let dto::cores::CoreCreateRequest {
         slug,
         name,
@@ -120,16 +267,29 @@ pub async fn cores_create(
         system,
         owner_team,
     } = form.into_inner();
+    slug.validate()
+        .map_err(|e| (Status::BadRequest, e.to_string()))?;
+    let slug = slug.as_str();
 
     let system = models::System::from_id_or_slug(&mut db, system).await?;
-    let (_user, team, role) =
-        models::User::get_user_team_and_role(&mut db, user.into(), owner_team)
-            .await
-            .map_err(|e| (Status::InternalServerError, e.to_string()))?
-            .ok_or((Status::NotFound, "Not found".to_string()))?;
+    let (_user, team, role) = found_or_404_named(
+        "Team",
+        models::User::get_user_team_and_role(&mut db, user.into(), owner_team).await,
+    )?;
 
     if !role.can_create_cores() {
-        return Err((Status::Forbidden, "User cannot create cores".to_string()));
+        return Err((Status::Forbidden, "User cannot create cores".to_string()).into());
+    }
+
+    if models::Core::slug_exists(&mut db, slug)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+    {
+        return Err((
+            Status::Conflict,
+            format!("A core with slug '{slug}' already exists."),
+        )
+            .into());
     }
 
     let core = models::Core::create(
@@ -138,7 +298,7 @@ pub async fn cores_create(
         name,
         description,
         json!(links),
-        json!(metadata),
+        metadata.unwrap_or_default().into_json_value(),
         &system,
         &team,
     )
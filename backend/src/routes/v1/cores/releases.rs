@@ -1,21 +1,29 @@
+use crate::fairings::config::RetronomiconConfig;
 use crate::guards;
 use crate::guards::storage::Paths;
+use crate::routes::error::ApiError;
+use crate::utils;
 use crate::utils::acls;
+use crate::utils::markdown::ReleaseNotesCache;
 use retronomicon_db::models;
-use retronomicon_db::types::FetchModel;
-use retronomicon_db::Db;
+use retronomicon_db::scoped_futures::ScopedFutureExt;
+use retronomicon_db::types::{found_or_404_named, FetchModel};
+use retronomicon_db::{transaction, Db, DbConnection, RetronomiconDbPool};
 use retronomicon_dto as dto;
-use rocket::data::ToByteUnit;
+use rocket::futures::Stream;
 use rocket::http::{ContentType, Header, Status};
+use rocket::response::content::RawHtml;
+use rocket::response::stream::ByteStream;
 use rocket::response::Responder;
 use rocket::serde::json::Json;
-use rocket::{get, post, Data, Request, Response};
+use rocket::{get, post, Data, Request, Response, State};
 use rocket_multipart_form_data::{
     MultipartFormData, MultipartFormDataField, MultipartFormDataOptions, Repetition,
 };
 use rocket_okapi::openapi;
 use serde_json::json;
 use sha1::Digest;
+use std::collections::BTreeMap;
 use std::io::Cursor;
 use std::path::PathBuf;
 
@@ -26,7 +34,7 @@ pub async fn cores_releases_list(
     core_id: dto::types::IdOrSlug<'_>,
     paging: dto::params::PagingParams,
     filter: dto::cores::releases::CoreReleaseFilterParams<'_>,
-) -> Result<Json<dto::Paginated<dto::cores::releases::CoreReleaseListItem>>, (Status, String)> {
+) -> Result<Json<dto::Paginated<dto::cores::releases::CoreReleaseListItem>>, ApiError> {
     let (page, limit) = paging.validate().map_err(|e| (Status::BadRequest, e))?;
 
     let (items, total) = models::CoreRelease::list(&mut db, core_id, page, limit, filter)
@@ -39,8 +47,8 @@ pub async fn cores_releases_list(
         total,
         items
             .into_iter()
-            .map(
-                |(release, platform, core, uploader)| dto::cores::releases::CoreReleaseListItem {
+            .map(|(release, platform, core, uploader, system_release)| {
+                dto::cores::releases::CoreReleaseListItem {
                     release: release.into_ref(platform),
                     core: dto::cores::CoreRef {
                         id: core.id,
@@ -48,22 +56,146 @@ pub async fn cores_releases_list(
                         name: core.name,
                     },
                     uploader: uploader.into(),
-                },
-            )
+                    required_system_release: system_release.map(Into::into),
+                }
+            })
             .collect(),
     )))
 }
 
+/// List the releases of a core, most recent first, each with its artifacts.
+/// At most [`dto::cores::releases::MAX_ARTIFACTS_PER_RELEASE`] artifacts are
+/// included per release, to keep responses small.
+#[openapi(tag = "Core Releases", ignore = "db")]
+#[get("/cores/<core_id>/releases/full?<paging..>")]
+pub async fn cores_releases_full(
+    mut db: Db,
+    core_id: dto::types::IdOrSlug<'_>,
+    paging: dto::params::PagingParams,
+) -> Result<Json<dto::Paginated<dto::cores::releases::CoreReleaseFullItem>>, ApiError> {
+    let (page, limit) = paging.validate().map_err(|e| (Status::BadRequest, e))?;
+
+    let core = models::Core::from_id_or_slug(&mut db, core_id).await?;
+    let (releases, total) = models::CoreRelease::list_for_core(&mut db, core.id, page, limit)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    // Fetch the artifacts of all releases at once, and group them by release.
+    let release_ids = releases.iter().map(|(r, _)| r.id).collect::<Vec<_>>();
+    let mut artifacts_by_release = BTreeMap::<i32, Vec<models::Artifact>>::new();
+    for (release_id, artifact) in models::Artifact::list_for_releases(&mut db, &release_ids)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+    {
+        artifacts_by_release
+            .entry(release_id)
+            .or_default()
+            .push(artifact);
+    }
+
+    let items = releases
+        .into_iter()
+        .map(|(release, platform)| {
+            let artifacts = artifacts_by_release.remove(&release.id).unwrap_or_default();
+            let artifacts_count = artifacts.len();
+            let artifacts = artifacts
+                .into_iter()
+                .take(dto::cores::releases::MAX_ARTIFACTS_PER_RELEASE)
+                .map(|artifact| artifact_list_item(&core, &release, artifact))
+                .collect();
+
+            dto::cores::releases::CoreReleaseFullItem {
+                release: release.into_ref(platform),
+                artifacts,
+                artifacts_count,
+            }
+        })
+        .collect();
+
+    Ok(Json(dto::Paginated::new(page, limit, total, items)))
+}
+
+async fn changelog_entries(
+    db: &mut Db,
+    core_id: dto::types::IdOrSlug<'_>,
+    filter: dto::cores::releases::CoreChangelogParams<'_>,
+) -> Result<Vec<dto::cores::releases::CoreChangelogEntry>, ApiError> {
+    let core = models::Core::from_id_or_slug(db, core_id).await?;
+    let releases = models::CoreRelease::changelog(db, core.id, filter.platform)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(releases
+        .into_iter()
+        .map(
+            |(release, platform)| dto::cores::releases::CoreChangelogEntry {
+                version: release.version,
+                date_released: release.date_released.timestamp(),
+                notes: release.notes,
+                platform: platform.into(),
+            },
+        )
+        .collect())
+}
+
+/// The changelog of a core: the notes of its releases that were not yanked,
+/// most recent first. Send `Accept: text/markdown` to get it in Markdown.
+#[openapi(tag = "Core Releases", ignore = "db")]
+#[get("/cores/<core_id>/changelog?<filter..>", format = "json", rank = 1)]
+pub async fn cores_changelog(
+    mut db: Db,
+    core_id: dto::types::IdOrSlug<'_>,
+    filter: dto::cores::releases::CoreChangelogParams<'_>,
+) -> Result<Json<Vec<dto::cores::releases::CoreChangelogEntry>>, ApiError> {
+    changelog_entries(&mut db, core_id, filter).await.map(Json)
+}
+
+/// The changelog of a core, in Markdown.
+#[openapi(tag = "Core Releases", ignore = "db", skip)]
+#[get(
+    "/cores/<core_id>/changelog?<filter..>",
+    format = "text/markdown",
+    rank = 2
+)]
+pub async fn cores_changelog_markdown(
+    mut db: Db,
+    core_id: dto::types::IdOrSlug<'_>,
+    filter: dto::cores::releases::CoreChangelogParams<'_>,
+) -> Result<(ContentType, String), ApiError> {
+    let entries = changelog_entries(&mut db, core_id, filter).await?;
+
+    let mut markdown = String::from("# Changelog\n");
+    for entry in entries {
+        let date = chrono::NaiveDateTime::from_timestamp_opt(entry.date_released, 0)
+            .unwrap_or_default()
+            .format("%Y-%m-%d");
+        markdown.push_str(&format!(
+            "\n## {} ({}) - {date}\n\n{}\n",
+            entry.version,
+            entry.platform.name,
+            entry.notes.trim_end()
+        ));
+    }
+
+    Ok((ContentType::new("text", "markdown"), markdown))
+}
+
 /// Create a release for a core. This does not include any artifacts, which
 /// must be uploaded separately.
+///
+/// With an `Idempotency-Key` header, creating a release that already exists
+/// with the same content returns the existing release instead of a conflict,
+/// so the upload can be retried safely.
 #[openapi(tag = "Core Releases", ignore = "db")]
 #[post("/cores/<core_id>/releases/new", format = "json", data = "<input>")]
 pub async fn cores_releases_create(
     mut db: Db,
     admin: guards::users::AuthenticatedUserGuard,
+    config: &State<RetronomiconConfig>,
+    idempotency_key: Option<guards::idempotency::IdempotencyKey>,
     core_id: dto::types::IdOrSlug<'_>,
     input: Json<dto::cores::releases::CoreReleaseCreateRequest<'_>>,
-) -> Result<Json<dto::cores::releases::CoreReleaseCreateResponse>, (Status, String)> {
+) -> Result<Json<dto::cores::releases::CoreReleaseCreateResponse>, ApiError> {
     let dto::cores::releases::CoreReleaseCreateRequest {
         version,
         notes,
@@ -72,10 +204,11 @@ pub async fn cores_releases_create(
         links,
         metadata,
         platform,
+        required_system_release,
     } = input.into_inner();
 
     if version == "latest" {
-        return Err((Status::BadRequest, "Version cannot be 'latest'".to_string()));
+        return Err((Status::BadRequest, "Version cannot be 'latest'".to_string()).into());
     }
 
     let core = models::Core::from_id_or_slug(&mut db, core_id).await?;
@@ -88,27 +221,103 @@ pub async fn cores_releases_create(
             .ok_or((Status::Unauthorized, "Not logged in".to_string()))?;
 
     if !acls::can_create_core_releases(&user, &team, &role, &core).await {
-        return Err((Status::Forbidden, "Not authorized".to_string()));
+        return Err((Status::Forbidden, "Not authorized".to_string()).into());
     }
 
-    let timestamp = chrono::NaiveDateTime::from_timestamp_opt(
-        date_released.unwrap_or(chrono::Utc::now().timestamp()),
-        0,
-    )
-    .ok_or((Status::BadRequest, "Invalid date_released".to_string()))?;
+    // The required system release must be a release of the core's system.
+    let system_release_id = match required_system_release {
+        Some(release) => Some(
+            found_or_404_named(
+                "System release",
+                models::SystemReleaseRef::find(&mut db, core.system_id, release).await,
+            )?
+            .id,
+        ),
+        None => None,
+    };
+
+    let metadata = metadata.unwrap_or_default().into_json_value();
+    if let Some(schema) = platform
+        .metadata_schema()
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+    {
+        schema.validate(&metadata).map_err(|errors| {
+            (
+                Status::BadRequest,
+                format!("Invalid metadata: {}", errors.join(", ")),
+            )
+        })?;
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let date_given = date_released.is_some();
+    let date_released = date_released.unwrap_or(now);
+    if date_released > now.saturating_add(config.max_release_date_skew_secs) {
+        return Err((
+            Status::BadRequest,
+            "date_released cannot be in the future".to_string(),
+        )
+            .into());
+    }
+    let timestamp = chrono::NaiveDateTime::from_timestamp_opt(date_released, 0)
+        .ok_or((Status::BadRequest, "Invalid date_released".to_string()))?;
+    if timestamp < core.created_at {
+        // Cores are often added after their first releases were made, so
+        // this is allowed.
+        rocket::warn!(
+            "Release {version} of core {} is dated before the core was created.",
+            core.slug
+        );
+    }
+
+    let links = json!(links);
+    if let Some(existing) =
+        models::CoreRelease::find_by_version(&mut db, core.id, platform.id, version)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+    {
+        // Without a date, a retry is dated when it is sent, so only compare
+        // the dates the client chose.
+        let timestamp = if date_given {
+            timestamp
+        } else {
+            existing.date_released
+        };
+        let requested = release_content_hash(
+            version,
+            &notes,
+            timestamp,
+            prerelease,
+            &links,
+            &metadata,
+            system_release_id,
+        );
+        if idempotency_key.is_some() && requested == existing_content_hash(&existing) {
+            return Ok(Json(dto::cores::releases::CoreReleaseCreateResponse {
+                id: existing.id,
+            }));
+        }
+
+        return Err((
+            Status::Conflict,
+            "A release for this core+platform+version already exists".to_string(),
+        )
+            .into());
+    }
 
     // Create the release.
     let release = models::CoreRelease::create(
         &mut db,
         version,
-        notes,
+        &notes,
         timestamp,
         prerelease,
-        json!(links),
-        json!(metadata),
+        links,
+        metadata,
         &user,
         &core,
         &platform,
+        system_release_id,
     )
     .await
     .map_err(|e| (Status::InternalServerError, e.to_string()))?;
@@ -118,21 +327,103 @@ pub async fn cores_releases_create(
     }))
 }
 
-pub struct ArtifactDownload {
-    filename: String,
-    mime_type: String,
-    data: Vec<u8>,
+/// A hash of the content of a release, to tell whether a release being created
+/// is a retry of an existing one.
+fn release_content_hash(
+    version: &str,
+    notes: &str,
+    date_released: chrono::NaiveDateTime,
+    prerelease: bool,
+    links: &serde_json::Value,
+    metadata: &serde_json::Value,
+    system_release_id: Option<i32>,
+) -> Vec<u8> {
+    let content = json!({
+        "version": version,
+        "notes": notes,
+        "date_released": date_released.timestamp(),
+        "prerelease": prerelease,
+        "links": links,
+        "metadata": metadata,
+        "system_release_id": system_release_id,
+    });
+    sha2::Sha256::digest(content.to_string()).to_vec()
+}
+
+fn existing_content_hash(release: &models::CoreRelease) -> Vec<u8> {
+    release_content_hash(
+        &release.version,
+        &release.notes,
+        release.date_released,
+        release.prerelease,
+        &release.links,
+        &release.metadata,
+        release.system_release_id,
+    )
+}
+
+/// The response to an artifact download. Artifacts stored externally are
+/// redirected to, so the CDN can serve them directly.
+pub enum ArtifactDownload {
+    Data {
+        filename: String,
+        mime_type: String,
+        data: Vec<u8>,
+    },
+    Redirect(String),
+}
+
+impl ArtifactDownload {
+    fn new(artifact: models::Artifact, file: Option<models::File>) -> Result<Self, ApiError> {
+        if let Some(url) = artifact.download_url {
+            return Ok(Self::Redirect(url));
+        }
+
+        let models::File { data, .. } =
+            file.ok_or((Status::NotFound, "File not found".to_string()))?;
+        Ok(Self::Data {
+            filename: artifact.filename,
+            mime_type: artifact.mime_type,
+            data,
+        })
+    }
 }
 
 impl<'r> Responder<'r, 'static> for ArtifactDownload {
     fn respond_to(self, _req: &'r Request<'_>) -> rocket::response::Result<'static> {
-        let mut response = Response::build()
-            .sized_body(self.data.len(), Cursor::new(self.data))
-            .ok()?;
-        response.set_header(ContentType::parse_flexible(&self.mime_type).unwrap());
+        let mut response = match self {
+            Self::Data {
+                filename,
+                mime_type,
+                data,
+            } => {
+                let mut response = Response::build()
+                    .sized_body(data.len(), Cursor::new(data))
+                    .ok()?;
+                // Legacy artifacts may have a malformed MIME type stored.
+                response.set_header(
+                    ContentType::parse_flexible(&mime_type).unwrap_or(ContentType::Binary),
+                );
+                response.set_header(Header::new(
+                    "Content-Disposition",
+                    format!("attachment; filename=\"{}\"", filename),
+                ));
+                // Artifacts never change once uploaded.
+                response.set_header(Header::new("Cache-Control", "max-age=86400, immutable"));
+                response
+            }
+            Self::Redirect(url) => {
+                let mut response = Response::build().status(Status::TemporaryRedirect).ok()?;
+                response.set_header(Header::new("Location", url));
+                // The download URL may change, so the redirect itself must be
+                // revalidated.
+                response.set_header(Header::new("Cache-Control", "no-cache"));
+                response
+            }
+        };
         response.set_header(Header::new(
-            "Content-Disposition",
-            format!("attachment; filename=\"{}\"", self.filename),
+            "X-Generated-At",
+            chrono::Utc::now().timestamp().to_string(),
         ));
 
         Ok(response)
@@ -150,18 +441,12 @@ pub async fn cores_releases_artifacts_download(
     core_id: dto::types::IdOrSlug<'_>,
     release_id: u32,
     artifact_id: u32,
-) -> Result<ArtifactDownload, (Status, String)> {
+) -> Result<ArtifactDownload, ApiError> {
     let (artifact, data) = models::Artifact::get_file(&mut db, core_id, release_id, artifact_id)
         .await
         .map_err(|e| (Status::InternalServerError, e.to_string()))?;
 
-    let models::File { data, .. } = data.ok_or((Status::NotFound, "File not found".to_string()))?;
-
-    Ok(ArtifactDownload {
-        filename: artifact.filename,
-        mime_type: artifact.mime_type,
-        data,
-    })
+    ArtifactDownload::new(artifact, data)
 }
 
 /// Download an artifact by its filename.
@@ -175,7 +460,7 @@ pub async fn cores_releases_artifacts_download_filename(
     core_id: dto::types::IdOrSlug<'_>,
     release_id: u32,
     filename: PathBuf,
-) -> Result<ArtifactDownload, (Status, String)> {
+) -> Result<ArtifactDownload, ApiError> {
     let (artifact, data) = models::Artifact::get_fileby_filename(
         &mut db,
         core_id,
@@ -185,36 +470,165 @@ pub async fn cores_releases_artifacts_download_filename(
     .await
     .map_err(|e| (Status::InternalServerError, e.to_string()))?;
 
-    let models::File { data, .. } = data.ok_or((Status::NotFound, "File not found".to_string()))?;
+    ArtifactDownload::new(artifact, data)
+}
+
+/// A zip archive of the artifacts of a release, streamed one artifact at a
+/// time.
+pub struct ArtifactBundle<S> {
+    filename: String,
+    stream: ByteStream<S>,
+}
+
+impl<'r, S> Responder<'r, 'r> for ArtifactBundle<S>
+where
+    S: Stream<Item = Vec<u8>> + Send + 'r,
+{
+    fn respond_to(self, request: &'r Request<'_>) -> rocket::response::Result<'r> {
+        let mut response = self.stream.respond_to(request)?;
+        response.set_header(ContentType::ZIP);
+        response.set_header(Header::new(
+            "Content-Disposition",
+            format!("attachment; filename=\"{}\"", self.filename),
+        ));
+        Ok(response)
+    }
+}
+
+/// Download all the artifacts of a release as a zip archive, named after the
+/// core and the version. Artifacts are read and sent one at a time, each with
+/// its own short-lived connection so a slow client does not hold one for the
+/// whole download. Errors after the download started can only be logged, and
+/// leave the archive truncated.
+#[openapi(tag = "Core Releases", ignore = "db", skip)]
+#[get(
+    "/cores/<core_id>/releases/<release_id>/artifacts/download.zip",
+    rank = 1
+)]
+pub async fn cores_releases_artifacts_download_zip(
+    mut db: Db,
+    pool: &State<RetronomiconDbPool>,
+    core_id: dto::types::IdOrSlug<'_>,
+    release_id: u32,
+) -> Result<ArtifactBundle<impl Stream<Item = Vec<u8>>>, ApiError> {
+    let core = models::Core::from_id_or_slug(&mut db, core_id).await?;
+    let release = found_or_404_named(
+        "Release",
+        models::CoreRelease::from_core_and_id(&mut db, core.id, release_id as i32).await,
+    )?;
+
+    let artifacts = models::Artifact::list_for_releases(&mut db, &[release.id])
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    drop(db);
 
-    Ok(ArtifactDownload {
+    let pool = pool.detached();
+    Ok(ArtifactBundle {
+        filename: format!("{}-{}.zip", core.slug, release.version),
+        stream: ByteStream! {
+            let mut zip = utils::zip::ZipWriter::new();
+            for (_, artifact) in artifacts {
+                let mut db = match pool.connect().await {
+                    Ok(db) => db,
+                    Err(e) => {
+                        rocket::error!("Could not connect to read artifact {}: {e}", artifact.id);
+                        return;
+                    }
+                };
+                let data = artifact_data(&mut db, &artifact).await;
+                drop(db);
+                let data = match data {
+                    Ok(data) => data,
+                    Err(e) => {
+                        rocket::error!("Could not read artifact {}: {e:?}", artifact.id);
+                        return;
+                    }
+                };
+                let header = match zip.start_entry(&artifact.filename, artifact.created_at, &data) {
+                    Ok(header) => header,
+                    Err(e) => {
+                        rocket::error!("Could not add artifact {} to zip: {e}", artifact.id);
+                        return;
+                    }
+                };
+                yield header;
+                yield data;
+            }
+            let directory = match zip.finish() {
+                Ok(directory) => directory,
+                Err(e) => {
+                    rocket::error!("Could not finish zip: {e}");
+                    return;
+                }
+            };
+            yield directory;
+        },
+    })
+}
+
+fn artifact_list_item(
+    core: &models::Core,
+    release: &models::CoreRelease,
+    artifact: models::Artifact,
+) -> dto::artifact::CoreReleaseArtifactListItem {
+    let download_url = artifact.download_url.clone().unwrap_or_else(|| {
+        rocket::uri!(
+            "/api/v1/",
+            cores_releases_artifacts_download(
+                core.slug.as_str(),
+                release.id as u32,
+                artifact.id as u32
+            )
+        )
+        .to_string()
+    });
+
+    let r#ref = artifact.clone().into();
+
+    dto::artifact::CoreReleaseArtifactListItem {
+        id: artifact.id,
         filename: artifact.filename,
+        download_url,
         mime_type: artifact.mime_type,
-        data,
-    })
+        created_at: artifact.created_at.timestamp(),
+        size: artifact.size,
+        r#ref,
+    }
 }
 
 /// Get a release's artifact list, including everything except the data itself.
 #[openapi(tag = "Core Releases", ignore = "db")]
-#[get("/cores/<core_id>/releases/<release_id>/artifacts?<paging..>")]
+#[get("/cores/<core_id>/releases/<release_id>/artifacts?<platform>&<mime_type>&<paging..>")]
 pub async fn cores_releases_artifacts_list(
     mut db: Db,
     core_id: dto::types::IdOrSlug<'_>,
     release_id: u32,
     paging: dto::params::PagingParams,
-) -> Result<Json<dto::Paginated<dto::artifact::CoreReleaseArtifactListItem>>, (Status, String)> {
+    platform: Option<dto::types::IdOrSlug<'_>>,
+    mime_type: Option<&str>,
+) -> Result<Json<dto::Paginated<dto::artifact::CoreReleaseArtifactListItem>>, ApiError> {
     let (page, limit) = paging.validate().map_err(|e| (Status::BadRequest, e))?;
 
-    let release = models::CoreRelease::from_id(&mut db, release_id as i32)
-        .await
-        .map_err(|e| (Status::InternalServerError, e.to_string()))?
-        .ok_or((Status::NotFound, "Release not found".to_string()))?;
-
     let core = models::Core::from_id_or_slug(&mut db, core_id).await?;
+    let release = found_or_404_named(
+        "Release",
+        models::CoreRelease::from_core_and_id(&mut db, core.id, release_id as i32).await,
+    )?;
+    let platform = match platform {
+        Some(platform) => Some(models::Platform::from_id_or_slug(&mut db, platform).await?),
+        None => None,
+    };
 
-    let (items, total) = models::Artifact::list(&mut db, &release, page, limit)
-        .await
-        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    let (items, total) = models::Artifact::list(
+        &mut db,
+        &release,
+        platform.map(|p| p.id),
+        mime_type,
+        page,
+        limit,
+    )
+    .await
+    .map_err(|e| (Status::InternalServerError, e.to_string()))?;
 
     Ok(Json(dto::Paginated::new(
         page,
@@ -222,53 +636,223 @@ pub async fn cores_releases_artifacts_list(
         total,
         items
             .into_iter()
-            .map(|artifact| {
-                let download_url = artifact.download_url.clone().unwrap_or_else(|| {
-                    rocket::uri!(
-                        "/api/v1/",
-                        cores_releases_artifacts_download(
-                            core.slug.as_str(),
-                            release.id as u32,
-                            artifact.id as u32
-                        )
-                    )
-                    .to_string()
-                });
-
-                let r#ref = artifact.clone().into();
-
-                dto::artifact::CoreReleaseArtifactListItem {
-                    id: artifact.id,
-                    filename: artifact.filename,
-                    download_url,
-                    mime_type: artifact.mime_type,
-                    created_at: artifact.created_at.timestamp(),
-                    r#ref,
-                }
-            })
+            .map(|artifact| artifact_list_item(&core, &release, artifact))
             .collect(),
     )))
 }
 
+/// Get the notes of a release rendered from Markdown to sanitized HTML.
+#[openapi(tag = "Core Releases", ignore = "db", ignore = "cache")]
+#[get("/cores/<core_id>/releases/<release_id>/notes.html")]
+pub async fn cores_releases_notes_html(
+    mut db: Db,
+    cache: &State<ReleaseNotesCache>,
+    core_id: dto::types::IdOrSlug<'_>,
+    release_id: u32,
+) -> Result<RawHtml<String>, ApiError> {
+    let core = models::Core::from_id_or_slug(&mut db, core_id).await?;
+
+    let release = found_or_404_named(
+        "Release",
+        models::CoreRelease::from_core_and_id(&mut db, core.id, release_id as i32).await,
+    )?;
+
+    Ok(RawHtml(cache.get_or_render(release.id, &release.notes)))
+}
+
+/// Get a single artifact of a release, including everything except the data
+/// itself. Useful to verify checksums before downloading.
+#[openapi(tag = "Core Releases", ignore = "db")]
+#[get("/cores/<core_id>/releases/<release_id>/artifacts/<artifact_id>")]
+pub async fn cores_releases_artifacts_details(
+    mut db: Db,
+    core_id: dto::types::IdOrSlug<'_>,
+    release_id: u32,
+    artifact_id: u32,
+) -> Result<Json<dto::artifact::CoreReleaseArtifactListItem>, ApiError> {
+    let core = models::Core::from_id_or_slug(&mut db, core_id).await?;
+
+    let release = found_or_404_named(
+        "Release",
+        models::CoreRelease::from_core_and_id(&mut db, core.id, release_id as i32).await,
+    )?;
+
+    let artifact = found_or_404_named(
+        "Artifact",
+        models::Artifact::from_release(&mut db, &release, artifact_id as i32).await,
+    )?;
+
+    Ok(Json(artifact_list_item(&core, &release, artifact)))
+}
+
+/// Get the data of an artifact, either from the database or by downloading
+/// it from its download URL.
+pub(crate) async fn artifact_data(
+    db: &mut DbConnection,
+    artifact: &models::Artifact,
+) -> Result<Vec<u8>, ApiError> {
+    let file = artifact
+        .file(db)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    match (file, &artifact.download_url) {
+        (Some(models::File { data, .. }), _) => Ok(data),
+        (None, Some(url)) => Ok(utils::http::download(url, utils::http::MAX_DOWNLOAD_BYTES)
+            .await
+            .map_err(|e| (Status::BadGateway, e))?),
+        (None, None) => Err((Status::NotFound, "File not found".to_string()).into()),
+    }
+}
+
+/// Verify the integrity of an artifact by computing the SHA256 checksum of
+/// its stored data (from the database or its download URL) and comparing it
+/// to the stored checksum. Only admins of the core's team can verify.
+#[openapi(tag = "Core Releases", ignore = "db")]
+#[post("/cores/<core_id>/releases/<release_id>/artifacts/<artifact_id>/verify")]
+pub async fn cores_releases_artifacts_verify(
+    mut db: Db,
+    user: guards::users::AuthenticatedUserGuard,
+    core_id: dto::types::IdOrSlug<'_>,
+    release_id: u32,
+    artifact_id: u32,
+) -> Result<Json<dto::artifact::ArtifactVerifyResponse>, ApiError> {
+    let core = models::Core::from_id_or_slug(&mut db, core_id).await?;
+
+    let user = user.into_model(&mut db).await?;
+    let role = user
+        .role_in(&mut db, core.owner_team_id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((Status::Forbidden, "Not a member of the team".to_string()))?;
+    if !acls::can_inspect_core_artifacts(&user, &core, &role) {
+        return Err((Status::Forbidden, "Not enough permission".to_string()).into());
+    }
+
+    let release = found_or_404_named(
+        "Release",
+        models::CoreRelease::from_core_and_id(&mut db, core.id, release_id as i32).await,
+    )?;
+
+    let artifact = found_or_404_named(
+        "Artifact",
+        models::Artifact::from_release(&mut db, &release, artifact_id as i32).await,
+    )?;
+
+    let data = artifact_data(&mut db, &artifact).await?;
+    let computed_sha256 = sha2::Sha256::digest(&data).to_vec();
+
+    Ok(Json(dto::artifact::ArtifactVerifyResponse {
+        valid: computed_sha256 == artifact.sha256,
+        stored_sha256: artifact.sha256.into(),
+        computed_sha256: computed_sha256.into(),
+    }))
+}
+
+/// Attach an artifact the server already has to a release, instead of
+/// uploading the same file again. Use [`crate::routes::v1::artifacts::artifacts_by_sha256`]
+/// to find it. Only publicly visible artifacts can be attached, by the users
+/// who can upload artifacts to the release. The artifact is attached for the
+/// release's platform, and keeps its filename, which must not already be used
+/// in the release.
+#[openapi(tag = "Core Releases", ignore = "db")]
+#[post("/cores/<core_id>/releases/<release_id>/artifacts/<artifact_id>/attach")]
+pub async fn cores_releases_artifacts_attach(
+    mut db: Db,
+    user: guards::users::AuthenticatedUserGuard,
+    core_id: dto::types::IdOrSlug<'_>,
+    release_id: u32,
+    artifact_id: u32,
+) -> Result<Json<dto::artifact::ArtifactCreateResponse>, ApiError> {
+    let core = models::Core::from_id_or_slug(&mut db, core_id).await?;
+
+    let (user, team, role) =
+        models::User::get_user_team_and_role(&mut db, user.into(), core.owner_team_id.into())
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+            .ok_or((Status::Unauthorized, "Not logged in".to_string()))?;
+    if !acls::can_create_core_releases(&user, &team, &role, &core).await {
+        return Err((Status::Forbidden, "Not authorized".to_string()).into());
+    }
+
+    let release = found_or_404_named(
+        "Release",
+        models::CoreRelease::from_core_and_id(&mut db, core.id, release_id as i32).await,
+    )?;
+
+    let artifact = models::Artifact::fetch_or_404(&mut db, artifact_id as i32).await?;
+    let download_url = crate::routes::v1::artifacts::public_download_url(&mut db, &artifact)
+        .await?
+        .ok_or((Status::NotFound, "Artifact not found".to_string()))?;
+
+    if !models::CoreReleaseArtifact::is_filename_unique_for_release(
+        &mut db,
+        &release,
+        &artifact.filename,
+    )
+    .await
+    .map_err(|e| (Status::InternalServerError, e.to_string()))?
+    {
+        return Err((
+            Status::Conflict,
+            "Filename already exists for this release".to_string(),
+        )
+            .into());
+    }
+
+    models::CoreReleaseArtifact::create(&mut db, &release, &artifact, release.platform_id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(dto::artifact::ArtifactCreateResponse {
+        id: artifact.id,
+        filename: artifact.filename,
+        url: Some(download_url),
+    }))
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn upload_single_artifact(
     db: &mut Db,
     core: &models::Core,
     release: &models::CoreRelease,
+    platform_id: i32,
     storage: &guards::storage::Storage,
     file_name: &str,
     mime_type: &str,
     file_data: &[u8],
-) -> Result<dto::artifact::ArtifactCreateResponse, (Status, String)> {
-    if file_data.len() > 24.mebibytes().as_u64() as usize {
-        return Err((
-            Status::BadRequest,
-            "File is too large (max 24 MiB)".to_string(),
-        ));
+    on_conflict: dto::artifact::ArtifactConflictMode,
+) -> Result<dto::artifact::ArtifactCreateResponse, ApiError> {
+    // Make sure the filename is unique, renaming the artifact if asked to.
+    // TODO: figure out if we can make this check in the database itself.
+    let mut stored_name = file_name.to_string();
+    let mut n = 1;
+    while !models::CoreReleaseArtifact::is_filename_unique_for_release(db, release, &stored_name)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+    {
+        if on_conflict == dto::artifact::ArtifactConflictMode::Error {
+            return Err((
+                Status::Conflict,
+                "Filename already exists for this release".to_string(),
+            )
+                .into());
+        }
+
+        n += 1;
+        stored_name = models::CoreReleaseArtifact::numbered_filename(file_name, n);
+        if !models::CoreReleaseArtifact::is_filename_conform(db, release, &stored_name)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        {
+            return Err((Status::BadRequest, "Filename is invalid".to_string()).into());
+        }
     }
+    let file_name = stored_name.as_str();
 
     let md5 = md5::compute(file_data).to_vec();
     let sha1 = sha1::Sha1::digest(file_data).to_vec();
     let sha256 = sha2::Sha256::digest(file_data).to_vec();
+    let crc32 = models::artifact::crc32(file_data);
 
     // Upload to storage.
     let download_url = storage
@@ -280,46 +864,66 @@ async fn upload_single_artifact(
         .await
         .map_err(|e| (Status::InternalServerError, e))?;
 
-    let artifact = models::Artifact::create_with_checksum(
-        db,
-        file_name,
-        mime_type,
-        Some(&md5),
-        Some(&sha1),
-        Some(&sha256),
-        Some(&download_url),
-        file_data.len() as i32,
-    )
-    .await
-    .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    // Never leave an artifact that isn't linked to its release.
+    let size = file_data.len() as i32;
+    let artifact = transaction(db, |db| {
+        async move {
+            let artifact = models::Artifact::create_with_checksum(
+                db,
+                file_name,
+                mime_type,
+                Some(&md5),
+                Some(&sha1),
+                Some(&sha256),
+                Some(&crc32),
+                Some(&download_url),
+                size,
+            )
+            .await?;
 
-    models::CoreReleaseArtifact::create(db, release, &artifact)
-        .await
-        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+            models::CoreReleaseArtifact::create(db, release, &artifact, platform_id).await?;
+            Ok(artifact)
+        }
+        .scope_boxed()
+    })
+    .await?;
 
     Ok(dto::artifact::ArtifactCreateResponse {
         id: artifact.id,
-        url: Some(download_url),
+        filename: artifact.filename,
+        url: artifact.download_url,
     })
 }
 
 /// Upload an artifact to a release. This can be done multiple times.
 /// The upload will be refused if the user does not have permission to
-/// upload artifacts to the release's core.
-#[openapi(tag = "Core Releases", ignore = "db", ignore = "storage")]
+/// upload artifacts to the release's core. Artifacts are for the release's
+/// platform, unless another platform is specified. Uploading a file with the
+/// name of an existing artifact of the release fails, unless `on_conflict` is
+/// `rename`, in which case it is stored under a numbered name.
+#[openapi(
+    tag = "Core Releases",
+    ignore = "config",
+    ignore = "db",
+    ignore = "storage"
+)]
 #[post(
-    "/cores/<core_id>/releases/<release_id>/artifacts/new",
+    "/cores/<core_id>/releases/<release_id>/artifacts/new?<platform>&<on_conflict>",
     data = "<file>"
 )]
+#[allow(clippy::too_many_arguments)]
 pub async fn cores_releases_artifacts_upload(
     mut db: Db,
     admin: guards::users::AuthenticatedUserGuard,
+    config: &State<RetronomiconConfig>,
     storage: guards::storage::Storage,
     core_id: dto::types::IdOrSlug<'_>,
     release_id: u32,
+    platform: Option<dto::types::IdOrSlug<'_>>,
+    on_conflict: Option<dto::artifact::ArtifactConflictMode>,
     content_type: &ContentType,
     file: Data<'_>,
-) -> Result<Json<Vec<dto::artifact::ArtifactCreateResponse>>, (Status, String)> {
+) -> Result<Json<Vec<dto::artifact::ArtifactCreateResponse>>, ApiError> {
     // Check the uploader's role.
     let core = models::Core::from_id_or_slug(&mut db, core_id).await?;
 
@@ -330,25 +934,35 @@ pub async fn cores_releases_artifacts_upload(
             .ok_or((Status::Unauthorized, "Not logged in".to_string()))?;
 
     if !acls::can_create_core_releases(&user, &team, &role, &core).await {
-        return Err((Status::Forbidden, "Not authorized".to_string()));
+        return Err((Status::Forbidden, "Not authorized".to_string()).into());
     }
 
-    let release = models::CoreRelease::from_id(&mut db, release_id as i32)
-        .await
-        .map_err(|e| (Status::InternalServerError, e.to_string()))?
-        .ok_or((Status::NotFound, "Release not found".to_string()))?;
+    let release = found_or_404_named(
+        "Release",
+        models::CoreRelease::from_core_and_id(&mut db, core.id, release_id as i32).await,
+    )?;
+
+    let platform_id = match platform {
+        Some(platform) => {
+            models::Platform::from_id_or_slug(&mut db, platform)
+                .await?
+                .id
+        }
+        None => release.platform_id,
+    };
 
     let mut result = Vec::new();
 
+    let max_bytes = config.upload_limits.max_artifact_bytes;
     let mut options = MultipartFormDataOptions::with_multipart_form_data_fields(vec![
         MultipartFormDataField::file("file")
-            .size_limit(24.mebibytes().as_u64())
+            .size_limit(max_bytes)
             .repetition(Repetition::infinite()),
         MultipartFormDataField::file("artifact")
-            .size_limit(24.mebibytes().as_u64())
+            .size_limit(max_bytes)
             .repetition(Repetition::infinite()),
     ]);
-    options.max_data_bytes = 40.mebibytes().as_u64();
+    options.max_data_bytes = config.upload_limits.max_artifact_request_bytes;
     let multipart_form_data = MultipartFormData::parse(content_type, file, options)
         .await
         .map_err(|e| (Status::BadRequest, e.to_string()))?;
@@ -363,38 +977,50 @@ pub async fn cores_releases_artifacts_upload(
                 Status::BadRequest,
                 "Content-Type not specified.".to_string(),
             ))?;
-            let file_data = std::fs::read(&file.path)
-                .map_err(|e| (Status::InternalServerError, e.to_string()))?;
-
-            // Make sure the filename is unique.
-            // TODO: figure out if we can make this check in the database itself.
-            if !models::CoreReleaseArtifact::is_filename_unique_for_release(
-                &mut db, &release, &filename,
-            )
-            .await
-            .map_err(|e| (Status::InternalServerError, e.to_string()))?
-            {
-                return Err((
-                    Status::Conflict,
-                    "Filename already exists for this release".to_string(),
-                ));
-            }
-
+            // The filename becomes part of the storage path, so validate it first.
+            let filename = models::CoreReleaseArtifact::normalize_filename(&filename);
             if !models::CoreReleaseArtifact::is_filename_conform(&mut db, &release, &filename)
                 .await
                 .map_err(|e| (Status::InternalServerError, e.to_string()))?
             {
-                return Err((Status::BadRequest, "Filename is invalid".to_string()));
+                return Err((Status::BadRequest, "Filename is invalid".to_string()).into());
             }
 
+            let allowed = &config.artifact_types;
+            if !allowed.is_extension_allowed(&filename) {
+                return Err((
+                    Status::BadRequest,
+                    format!(
+                        "File extension is not allowed. Allowed extensions: {}",
+                        allowed.extensions.join(", ")
+                    ),
+                )
+                    .into());
+            }
+            if !allowed.is_mime_type_allowed(mimetype.essence_str()) {
+                return Err((
+                    Status::BadRequest,
+                    format!(
+                        "Content-Type is not allowed. Allowed types: {}",
+                        allowed.mime_types.join(", ")
+                    ),
+                )
+                    .into());
+            }
+
+            let file_data = std::fs::read(&file.path)
+                .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
             let artifact = upload_single_artifact(
                 &mut db,
                 &core,
                 &release,
+                platform_id,
                 &storage,
                 &filename,
                 mimetype.as_ref(),
                 &file_data,
+                on_conflict.unwrap_or_default(),
             )
             .await?;
 
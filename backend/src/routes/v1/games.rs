@@ -1,11 +1,13 @@
 use crate::fairings::config::RetronomiconConfig;
 use crate::guards;
+use crate::routes::error::ApiError;
 use crate::utils::acls;
 use image::{GenericImageView, ImageFormat};
 use retronomicon_db::models;
-use retronomicon_db::Db;
+use retronomicon_db::scoped_futures::ScopedFutureExt;
+use retronomicon_db::types::FetchModel;
+use retronomicon_db::{transaction, Db};
 use retronomicon_dto as dto;
-use rocket::data::ToByteUnit;
 use rocket::http::{ContentType, Status};
 use rocket::serde::json::Json;
 use rocket::{get, post, put, Data, State};
@@ -14,7 +16,6 @@ use rocket_multipart_form_data::{
 };
 use rocket_okapi::openapi;
 use serde_json::json;
-use std::collections::BTreeMap;
 
 const MAX_IMAGE_WIDTH: u32 = 4096;
 const MAX_IMAGE_HEIGHT: u32 = 4096;
@@ -25,7 +26,7 @@ pub async fn games_create(
     mut db: Db,
     _root_user: guards::users::RootUserGuard,
     form: Json<dto::games::GameCreateRequest<'_>>,
-) -> Result<Json<dto::games::GameCreateResponse>, (Status, String)> {
+) -> Result<Json<dto::games::GameCreateResponse>, ApiError> {
     let dto::games::GameCreateRequest {
         name,
         short_description,
@@ -35,12 +36,34 @@ pub async fn games_create(
         developer,
         links,
         system,
+        region,
+        languages,
         system_unique_id,
     } = form.into_inner();
-    let system = models::System::get(&mut db, system)
-        .await
-        .map_err(|e| (Status::InternalServerError, e.to_string()))?
-        .ok_or((Status::NotFound, "Not found".to_string()))?;
+    let system = models::System::from_id_or_slug(&mut db, system).await?;
+
+    let system_unique_id = match system_unique_id {
+        Some(id) => {
+            if models::Game::system_unique_id_exists(&mut db, system.id, id)
+                .await
+                .map_err(|e| (Status::InternalServerError, e.to_string()))?
+            {
+                return Err((
+                    Status::Conflict,
+                    format!("A game with unique ID {id} already exists in this system."),
+                )
+                    .into());
+            }
+            id
+        }
+        None => {
+            models::Game::max_system_unique_id(&mut db, system.id)
+                .await
+                .map_err(|e| (Status::InternalServerError, e.to_string()))?
+                .unwrap_or(0)
+                + 1
+        }
+    };
 
     let game = models::Game::create(
         &mut db,
@@ -53,9 +76,10 @@ pub async fn games_create(
         json!(links),
         system.id,
         system_unique_id,
+        region,
+        &languages,
     )
-    .await
-    .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    .await?;
 
     Ok(Json(dto::games::GameCreateResponse { id: game.id }))
 }
@@ -64,12 +88,14 @@ pub async fn games_create(
 #[get("/games?<page>&<limit>&<filter..>", format = "application/json")]
 pub async fn games_list(
     db: Db,
+    config: &State<RetronomiconConfig>,
     filter: dto::games::GameListQueryParams<'_>,
     page: Option<i64>,
     limit: Option<i64>,
-) -> Result<Json<dto::Paginated<dto::games::GameListItemResponse>>, (Status, String)> {
+) -> Result<Json<dto::Paginated<dto::games::GameListItemResponse>>, ApiError> {
     games_list_extended(
         db,
+        config,
         filter,
         page,
         limit,
@@ -87,18 +113,26 @@ pub async fn games_list(
 )]
 pub async fn games_list_extended(
     mut db: Db,
+    config: &State<RetronomiconConfig>,
     filter: dto::games::GameListQueryParams<'_>,
     page: Option<i64>,
     limit: Option<i64>,
     form: Json<dto::games::GameListBody>,
-) -> Result<Json<dto::Paginated<dto::games::GameListItemResponse>>, (Status, String)> {
+) -> Result<Json<dto::Paginated<dto::games::GameListItemResponse>>, ApiError> {
     let (page, limit) = dto::params::PagingParams::new(page, limit)
-        .validate()
+        .validate_max_offset(config.max_games_offset)
         .map_err(|e| (Status::BadRequest, e))?;
 
     let year = filter.year.unwrap_or_default().into();
     let name = filter.name.as_deref();
     let exact_name = filter.exact_name.as_deref();
+    let region = filter.region.as_deref();
+    let sort = dto::params::SortParams::parse_opt(filter.sort.as_deref())
+        .map_err(|e| (Status::BadRequest, e))?;
+    let system = filter
+        .system
+        .map(dto::types::IdOrSlug::resolve)
+        .transpose()?;
 
     let form = form.into_inner();
     let md5 = form
@@ -119,46 +153,65 @@ pub async fn games_list_extended(
         .into_iter()
         .map(|m| m.into())
         .collect();
+    let crc32 = form
+        .crc32
+        .unwrap_or_default()
+        .into_iter()
+        .map(|m| m.into())
+        .collect();
 
-    let (items, total) = models::Game::list(
+    let (items, total, count_is_estimate) = models::Game::list(
         &mut db,
         page,
         limit,
-        filter.system,
+        system,
         year,
         name,
         exact_name,
+        region,
         md5,
         sha1,
         sha256,
+        crc32,
+        form.checksum_match_mode.unwrap_or_default(),
+        sort,
+        filter.count.unwrap_or_default(),
     )
     .await
     .map_err(|e| (Status::InternalServerError, e.to_string()))?;
 
-    let items = items
-        .into_iter()
-        .fold(BTreeMap::new(), |mut acc, (g, s, a)| {
-            let entry = acc
-                .entry(g.id)
-                .or_insert_with(|| dto::games::GameListItemResponse {
-                    id: g.id,
-                    name: g.name,
-                    short_description: g.short_description,
-                    year: g.year,
-                    system_id: s.into(),
-                    system_unique_id: g.system_unique_id,
-                    artifacts: vec![],
-                });
-
-            if let Some(a) = a {
-                entry.artifacts.push(a.into());
-            }
+    Ok(Json(
+        dto::Paginated::new(page, limit, total, group_game_rows(items))
+            .with_estimated_count(count_is_estimate),
+    ))
+}
 
-            acc
-        });
+/// Group the rows of a games list, one per artifact, into list items.
+/// Rows of the same game must be consecutive, and the order is kept.
+pub(crate) fn group_game_rows(
+    rows: Vec<(models::Game, models::System, Option<models::Artifact>)>,
+) -> Vec<dto::games::GameListItemResponse> {
+    let mut games: Vec<dto::games::GameListItemResponse> = Vec::new();
+    for (g, s, a) in rows {
+        if games.last().map(|last| last.id) != Some(g.id) {
+            games.push(dto::games::GameListItemResponse {
+                id: g.id,
+                name: g.name,
+                short_description: g.short_description,
+                year: g.year,
+                system_id: s.into(),
+                system_unique_id: g.system_unique_id,
+                region: g.region,
+                languages: g.languages,
+                artifacts: vec![],
+            });
+        }
 
-    let items = items.into_values().collect::<Vec<_>>();
-    Ok(Json(dto::Paginated::new(page, limit, total, items)))
+        if let (Some(a), Some(entry)) = (a, games.last_mut()) {
+            entry.artifacts.push(a.into());
+        }
+    }
+    games
 }
 
 #[openapi(tag = "Games", ignore = "db")]
@@ -166,8 +219,10 @@ pub async fn games_list_extended(
 pub async fn games_details(
     mut db: Db,
     game_id: u32,
-) -> Result<Json<dto::games::GameDetails>, (Status, String)> {
-    let (game, system) = models::Game::details(&mut db, game_id as i32)
+) -> Result<Json<dto::games::GameDetails>, ApiError> {
+    // Missing and deleted games are not found.
+    models::Game::fetch_or_404(&mut db, game_id as i32).await?;
+    let (game, system, primary_image) = models::Game::details(&mut db, game_id as i32)
         .await
         .map_err(|e| (Status::InternalServerError, e.to_string()))?;
 
@@ -182,6 +237,13 @@ pub async fn games_details(
         links: game.links,
         system: system.into(),
         system_unique_id: game.system_unique_id,
+        region: game.region,
+        languages: game.languages,
+        primary_image: primary_image.map(|i| dto::images::Image {
+            name: i.image_name,
+            url: i.url,
+            mime_type: i.mime_type,
+        }),
     }))
 }
 
@@ -192,7 +254,7 @@ pub async fn games_update(
     _root_user: guards::users::RootUserGuard,
     game_id: u32,
     form: Json<dto::games::GameUpdateRequest<'_>>,
-) -> Result<Json<dto::Ok>, (Status, String)> {
+) -> Result<Json<dto::Ok>, ApiError> {
     models::Game::update(
         &mut db,
         game_id as i32,
@@ -205,6 +267,8 @@ pub async fn games_update(
         form.add_links.clone(),
         form.remove_links.clone(),
         form.system_unique_id,
+        form.region,
+        form.languages.clone(),
     )
     .await
     .map_err(|e| (Status::InternalServerError, e.to_string()))?;
@@ -212,6 +276,33 @@ pub async fn games_update(
     Ok(Json(dto::Ok))
 }
 
+/// Merge a duplicate game into this one.
+#[openapi(tag = "Games", ignore = "db")]
+#[post("/games/<game_id>/merge", format = "application/json", data = "<form>")]
+pub async fn games_merge(
+    mut db: Db,
+    _root_user: guards::users::RootUserGuard,
+    game_id: u32,
+    form: Json<dto::games::GameMergeRequest>,
+) -> Result<Json<dto::Ok>, ApiError> {
+    if form.game_id == game_id as i32 {
+        return Err((
+            Status::BadRequest,
+            "Cannot merge a game into itself.".to_string(),
+        )
+            .into());
+    }
+
+    let keep = models::Game::fetch_or_404(&mut db, game_id as i32).await?;
+    let drop = models::Game::fetch_or_404(&mut db, form.game_id).await?;
+
+    models::Game::merge(&mut db, keep.id, drop.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(dto::Ok))
+}
+
 #[openapi(tag = "Games", ignore = "db")]
 #[post(
     "/games/<game_id>/artifacts",
@@ -223,30 +314,33 @@ pub async fn games_add_artifact(
     _root_user: guards::users::RootUserGuard,
     game_id: u32,
     form: Json<Vec<dto::games::GameAddArtifactRequest<'_>>>,
-) -> Result<Json<dto::Ok>, (Status, String)> {
-    let game = models::Game::get(&mut db, game_id as i32)
-        .await
-        .map_err(|e| (Status::InternalServerError, e.to_string()))?
-        .ok_or((Status::NotFound, "Not found".to_string()))?;
-
-    for a in form.into_inner() {
-        let artifact = models::Artifact::create_with_checksum(
-            &mut db,
-            "",
-            a.mime_type,
-            a.md5.as_ref().map(|s| s.as_slice()),
-            a.sha1.as_ref().map(|s| s.as_slice()),
-            a.sha256.as_ref().map(|s| s.as_slice()),
-            None,
-            a.size,
-        )
-        .await
-        .map_err(|e: _| (Status::InternalServerError, e.to_string()))?;
-
-        models::GameArtifact::create(&mut db, game.id, artifact.id)
-            .await
-            .map_err(|e: _| (Status::InternalServerError, e.to_string()))?;
-    }
+) -> Result<Json<dto::Ok>, ApiError> {
+    let game = models::Game::fetch_or_404(&mut db, game_id as i32).await?;
+
+    // Either all artifacts are added, or none.
+    transaction(&mut db, |db| {
+        async move {
+            for a in form.into_inner() {
+                let artifact = models::Artifact::create_with_checksum(
+                    db,
+                    "",
+                    a.mime_type,
+                    a.md5.as_ref().map(|s| s.as_slice()),
+                    a.sha1.as_ref().map(|s| s.as_slice()),
+                    a.sha256.as_ref().map(|s| s.as_slice()),
+                    a.crc32.as_ref().map(|s| s.as_slice()),
+                    None,
+                    a.size,
+                )
+                .await?;
+
+                models::GameArtifact::create(db, game.id, artifact.id).await?;
+            }
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await?;
 
     Ok(Json(dto::Ok))
 }
@@ -257,7 +351,7 @@ pub async fn games_images(
     mut db: Db,
     game_id: u32,
     paging: dto::params::PagingParams,
-) -> Result<Json<dto::Paginated<dto::images::Image>>, (Status, String)> {
+) -> Result<Json<dto::Paginated<dto::images::Image>>, ApiError> {
     let game_id = game_id as i32;
     let (page, limit) = paging.validate().map_err(|e| (Status::BadRequest, e))?;
     let (items, total) = models::GameImage::list(&mut db, page, limit, game_id)
@@ -284,6 +378,29 @@ pub async fn games_images(
     Ok(Json(images))
 }
 
+/// Set the primary image of a game. The image must already be uploaded to
+/// this game.
+#[openapi(tag = "Games", ignore = "db")]
+#[put("/games/<game_id>/images/<image_name>/set-primary")]
+pub async fn games_images_set_primary(
+    mut db: Db,
+    _root_user: guards::users::RootUserGuard,
+    game_id: u32,
+    image_name: &str,
+) -> Result<Json<dto::Ok>, ApiError> {
+    let game_id = game_id as i32;
+    let image = models::GameImage::find_by_name(&mut db, game_id, image_name)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((Status::NotFound, "Image not found".to_string()))?;
+
+    models::Game::set_primary_image(&mut db, game_id, image.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(dto::Ok))
+}
+
 /// Upload an image to a game. This can be done multiple times (as long
 /// as the filename is unique).
 /// The upload will be refused if the user does not have permission to
@@ -298,7 +415,7 @@ pub async fn games_images_upload(
     game_id: i32,
     content_type: &ContentType,
     file: Data<'_>,
-) -> Result<Json<Vec<dto::images::Image>>, (Status, String)> {
+) -> Result<Json<Vec<dto::images::Image>>, ApiError> {
     // Check the uploader's role.
     let (user, team, role) =
         models::User::get_user_team_and_role(&mut db, admin.into(), config.root_team_id.into())
@@ -307,26 +424,42 @@ pub async fn games_images_upload(
             .ok_or((Status::Unauthorized, "Not logged in".to_string()))?;
 
     if !acls::can_upload_image(&user, &team, &role) {
-        return Err((Status::Forbidden, "Forbidden".to_string()));
+        return Err((Status::Forbidden, "Forbidden".to_string()).into());
     }
 
-    let game = models::Game::get(&mut db, game_id)
-        .await
-        .map_err(|e| (Status::InternalServerError, e.to_string()))?
-        .ok_or((Status::NotFound, "Game not found".to_string()))?;
+    let game = models::Game::fetch_or_404(&mut db, game_id).await?;
 
-    let options = MultipartFormDataOptions::with_multipart_form_data_fields(vec![
+    let max_bytes = config.upload_limits.max_image_bytes;
+    let mut options = MultipartFormDataOptions::with_multipart_form_data_fields(vec![
         MultipartFormDataField::file("file")
-            .size_limit(2.mebibytes().as_u64())
+            .size_limit(max_bytes)
             .repetition(Repetition::infinite()),
         MultipartFormDataField::file("image")
-            .size_limit(2.mebibytes().as_u64())
+            .size_limit(max_bytes)
             .repetition(Repetition::infinite()),
     ]);
+    options.max_data_bytes = config.upload_limits.max_image_request_bytes;
     let multipart_form_data = MultipartFormData::parse(content_type, file, options)
         .await
         .map_err(|e| (Status::BadRequest, e.to_string()))?;
 
+    let max_images = config.upload_limits.max_images_per_game as i64;
+    let existing = models::GameImage::count_for_game(&mut db, game_id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    let uploaded = multipart_form_data
+        .files
+        .values()
+        .map(Vec::len)
+        .sum::<usize>() as i64;
+    if existing + uploaded > max_images {
+        return Err((
+            Status::UnprocessableEntity,
+            format!("LimitExceeded: a game cannot have more than {max_images} images"),
+        )
+            .into());
+    }
+
     let mut result = Vec::new();
     for (_s, files) in multipart_form_data.files.iter() {
         for file in files {
@@ -342,7 +475,7 @@ pub async fn games_images_upload(
                 .await
                 .map_err(|e| (Status::InternalServerError, e.to_string()))?
             {
-                return Err((Status::BadRequest, "Filename is invalid".to_string()));
+                return Err((Status::BadRequest, "Filename is invalid".to_string()).into());
             }
 
             // Try to figure out the image format based on mimetype.
@@ -354,7 +487,8 @@ pub async fn games_images_upload(
                     return Err((
                         Status::BadRequest,
                         format!("Unsupported image format: {}", mimetype),
-                    ))
+                    )
+                        .into())
                 }
             };
 
@@ -372,7 +506,8 @@ pub async fn games_images_upload(
                         "Image is too large ({}x{} > {}x{})",
                         width, height, MAX_IMAGE_WIDTH, MAX_IMAGE_HEIGHT
                     ),
-                ));
+                )
+                    .into());
             }
 
             let path = guards::storage::Paths::path_for_game_image(&game, &filename);
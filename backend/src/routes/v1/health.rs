@@ -0,0 +1,54 @@
+use crate::fairings::config::StartTime;
+use crate::guards;
+use retronomicon_db::Db;
+use retronomicon_dto as dto;
+use rocket::serde::json::Json;
+use rocket::{get, State};
+use rocket_okapi::openapi;
+use std::time::Instant;
+
+fn status_of<T, E: std::fmt::Display>(
+    component: &str,
+    result: Result<T, E>,
+) -> dto::health::ComponentStatus {
+    match result {
+        Ok(_) => dto::health::ComponentStatus::Ok,
+        Err(e) => {
+            rocket::warn!("Health check of {} failed: {}", component, e);
+            dto::health::ComponentStatus::Error
+        }
+    }
+}
+
+/// Check the status of every component the server depends on. This reveals
+/// infrastructure details, so it is restricted to root users.
+#[openapi(
+    tag = "Health",
+    ignore = "db",
+    ignore = "storage",
+    ignore = "start_time"
+)]
+#[get("/health/detailed")]
+pub async fn health_detailed(
+    mut db: Db,
+    _root_user: guards::users::RootUserGuard,
+    storage: guards::storage::Storage,
+    start_time: &State<StartTime>,
+) -> Json<dto::health::HealthDetailedResponse> {
+    let start = Instant::now();
+    let database = status_of("database", retronomicon_db::ping(&mut db).await);
+    let db_latency_ms = start.elapsed().as_secs_f64() * 1000.;
+
+    let start = Instant::now();
+    let storage = status_of("storage", storage.ping().await);
+    let storage_latency_ms = start.elapsed().as_secs_f64() * 1000.;
+
+    Json(dto::health::HealthDetailedResponse {
+        database,
+        storage,
+        db_latency_ms,
+        storage_latency_ms,
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        uptime_seconds: start_time.0.elapsed().as_secs(),
+    })
+}
@@ -1,10 +1,12 @@
-use crate::fairings::config::JwtKeys;
+use crate::fairings::config::{DbPepper, JwtKeys};
 use crate::guards::users::UserGuard;
+use crate::routes::error::ApiError;
+use retronomicon_db::models::{validate_password, User, UserAuthProvider, UserPassword};
 use retronomicon_db::Db;
 use retronomicon_dto as dto;
 use rocket::http::{CookieJar, Status};
 use rocket::serde::json::Json;
-use rocket::{get, post, put, State};
+use rocket::{delete, get, post, put, State};
 use rocket_okapi::openapi;
 
 #[openapi(tag = "Users", ignore = "db")]
@@ -14,9 +16,9 @@ pub async fn me_update(
     cookies: &CookieJar<'_>,
     mut user: UserGuard,
     form: Json<dto::user::UserUpdate<'_>>,
-) -> Result<Json<dto::Ok>, (Status, String)> {
+) -> Result<Json<dto::Ok>, ApiError> {
     if user.username.is_some() && form.username.is_some() {
-        return Err((Status::Forbidden, "Username already set".to_string()));
+        return Err((Status::Forbidden, "Username already set".to_string()).into());
     }
     if let Some(username) = form.username {
         dto::user::Username::new(username).map_err(|e| (Status::BadRequest, e.to_string()))?;
@@ -33,11 +35,137 @@ pub async fn me_update(
     Ok(Json(dto::Ok))
 }
 
+/// Change the password of the current user. This requires the current password,
+/// and cannot be used by users without a password (e.g. OAuth logins).
+#[openapi(tag = "Users", ignore = "db")]
+#[put("/me/password", format = "application/json", data = "<form>")]
+pub async fn me_change_password(
+    mut db: Db,
+    user: UserGuard,
+    pepper: &State<DbPepper>,
+    form: Json<dto::auth::PasswordChangeRequest<'_>>,
+) -> Result<Json<dto::Ok>, ApiError> {
+    let form = form.into_inner();
+    let user = user.into_model(&mut db).await?;
+
+    let has_password = UserPassword::from_user(&mut db, &user)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .is_some_and(|p| !p.password.is_empty());
+    if !has_password {
+        return Err((
+            Status::Forbidden,
+            "User does not have a password".to_string(),
+        )
+            .into());
+    }
+
+    let (user, user_password) =
+        UserPassword::verify_password(&mut db, user, form.current_password, &pepper.0)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+            .ok_or((Status::Unauthorized, "Invalid password".to_string()))?;
+
+    validate_password(form.new_password, &user.email, user.username.as_deref())
+        .map_err(|e| (Status::BadRequest, e.to_string()))?;
+
+    user_password
+        .update_password(&mut db, form.new_password, &pepper.0)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(dto::Ok))
+}
+
 #[openapi(tag = "Users", ignore = "db")]
 #[get("/me")]
-pub async fn me(db: Db, user: UserGuard) -> Result<Json<dto::user::UserDetails>, (Status, String)> {
-    let id = user.id;
-    crate::routes::v1::users::users_details(db, user, id.into()).await
+pub async fn me(mut db: Db, user: UserGuard) -> Result<Json<dto::user::Me>, ApiError> {
+    let (user, teams, pending_invitations) = User::get_me(&mut db, user.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    let teams = teams
+        .into_iter()
+        .map(|(id, name, slug, role)| dto::user::UserTeamRef {
+            team: dto::teams::TeamRef { id, name, slug },
+            role: role.into(),
+        })
+        .collect();
+
+    Ok(Json(dto::user::Me {
+        user: dto::user::UserDetailsInner {
+            id: user.id,
+            username: user.username,
+            display_name: user.display_name,
+            description: user.description,
+            links: user.links,
+            metadata: user.metadata,
+        },
+        teams,
+        pending_invitations,
+    }))
+}
+
+/// List the OAuth providers linked to the current user.
+#[openapi(tag = "Users", ignore = "db")]
+#[get("/me/providers")]
+pub async fn me_providers(
+    mut db: Db,
+    user: UserGuard,
+) -> Result<Json<Vec<dto::auth::LinkedAuthProvider>>, ApiError> {
+    let user = user.into_model(&mut db).await?;
+    let providers = UserAuthProvider::list(&mut db, &user)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(
+        providers
+            .into_iter()
+            .map(|p| dto::auth::LinkedAuthProvider {
+                provider: p.provider,
+                external_id: p.external_id,
+                created_at: p.created_at.timestamp(),
+            })
+            .collect(),
+    ))
+}
+
+/// Unlink an OAuth provider from the current user. The last provider cannot be
+/// unlinked if the user does not have a password, as they could not login anymore.
+#[openapi(tag = "Users", ignore = "db")]
+#[delete("/me/providers/<provider>")]
+pub async fn me_providers_unlink(
+    mut db: Db,
+    user: UserGuard,
+    provider: &str,
+) -> Result<Json<dto::Ok>, ApiError> {
+    let user = user.into_model(&mut db).await?;
+    let providers = UserAuthProvider::list(&mut db, &user)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    if !providers.iter().any(|p| p.provider == provider) {
+        return Err((Status::NotFound, "Provider not linked".to_string()).into());
+    }
+
+    let has_password = UserPassword::from_user(&mut db, &user)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .is_some_and(|p| !p.password.is_empty());
+    let has_other_provider = providers.iter().any(|p| p.provider != provider);
+    if !has_password && !has_other_provider {
+        return Err((
+            Status::Forbidden,
+            "Cannot unlink the last provider of a user without a password".to_string(),
+        )
+            .into());
+    }
+
+    UserAuthProvider::unlink(&mut db, &user, provider)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(dto::Ok))
 }
 
 /// Create a JWT token for the current logged-in user.
@@ -46,8 +174,8 @@ pub async fn me(db: Db, user: UserGuard) -> Result<Json<dto::user::UserDetails>,
 pub async fn me_token(
     user: UserGuard,
     jwt_secret: &State<JwtKeys>,
-) -> Result<Json<dto::auth::TokenResponse>, (Status, String)> {
+) -> Result<Json<dto::auth::TokenResponse>, ApiError> {
     user.create_jwt(&jwt_secret.inner().encoding)
         .map(|token| Json(dto::auth::TokenResponse { token }))
-        .map_err(|e| (Status::Unauthorized, e.to_string()))
+        .map_err(|e| (Status::Unauthorized, e.to_string()).into())
 }
@@ -1,6 +1,8 @@
 use crate::guards;
+use crate::routes::error::ApiError;
 use retronomicon_db::models;
 use retronomicon_db::types::FetchModel;
+use retronomicon_db::utils::merge_json_metadata;
 use retronomicon_db::Db;
 use retronomicon_dto as dto;
 use rocket::http::Status;
@@ -10,17 +12,21 @@ use rocket_okapi::openapi;
 use serde_json::json;
 
 #[openapi(tag = "Platforms", ignore = "db")]
-#[get("/platforms?<paging..>")]
+#[get("/platforms?<page>&<limit>&<filter..>")]
 pub async fn platforms_list(
     mut db: Db,
-    paging: dto::params::PagingParams,
-) -> Result<Json<Vec<dto::platforms::Platform>>, (Status, String)> {
-    let (page, limit) = paging.validate().map_err(|e| (Status::BadRequest, e))?;
-
-    models::Platform::list(&mut db, page, limit)
+    page: Option<i64>,
+    limit: Option<i64>,
+    filter: dto::platforms::PlatformListQueryParams<'_>,
+) -> Result<Json<Vec<dto::platforms::Platform>>, ApiError> {
+    let (page, limit) = dto::params::PagingParams::new(page, limit)
+        .validate()
+        .map_err(|e| (Status::BadRequest, e))?;
+
+    models::Platform::list(&mut db, page, limit, filter.name.as_deref(), filter.system)
         .await
         .map(|p| Json(p.into_iter().map(Into::into).collect()))
-        .map_err(|e| (Status::InternalServerError, e.to_string()))
+        .map_err(|e| (Status::InternalServerError, e.to_string()).into())
 }
 
 #[openapi(tag = "Platforms", ignore = "db")]
@@ -28,11 +34,13 @@ pub async fn platforms_list(
 pub async fn platforms_details(
     mut db: Db,
     platform_id: dto::types::IdOrSlug<'_>,
-) -> Result<Json<dto::platforms::PlatformDetails>, (Status, String)> {
-    let (platform, owner_team) = models::Platform::get_with_owner(&mut db, platform_id)
-        .await
-        .map_err(|e| (Status::InternalServerError, e.to_string()))?
-        .ok_or((Status::NotFound, "User not found".to_string()))?;
+) -> Result<Json<dto::platforms::PlatformDetails>, ApiError> {
+    let platform = models::Platform::from_id_or_slug(&mut db, platform_id).await?;
+    let owner_team = models::Team::fetch_or_404(&mut db, platform.owner_team_id).await?;
+
+    let metadata_schema = platform
+        .metadata_schema()
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
 
     Ok(Json(dto::platforms::PlatformDetails {
         id: platform.id,
@@ -41,7 +49,9 @@ pub async fn platforms_details(
         description: platform.description,
         links: platform.links,
         metadata: platform.metadata,
+        metadata_schema,
         owner_team: owner_team.into(),
+        updated_at: platform.updated_at.timestamp(),
     }))
 }
 
@@ -51,15 +61,20 @@ pub async fn platforms_create(
     mut db: Db,
     user: guards::users::AuthenticatedUserGuard,
     form: Json<dto::platforms::PlatformCreateRequest<'_>>,
-) -> Result<Json<dto::platforms::PlatformCreateResponse>, (Status, String)> {
+) -> Result<Json<dto::platforms::PlatformCreateResponse>, ApiError> {
     let dto::platforms::PlatformCreateRequest {
         slug,
         name,
         description,
         links,
         metadata,
+        metadata_schema,
         owner_team,
+        upsert,
     } = form.into_inner();
+    slug.validate()
+        .map_err(|e| (Status::BadRequest, e.to_string()))?;
+    let slug = slug.as_str();
 
     // Get team.
     let team = models::Team::from_id_or_slug(&mut db, owner_team).await?;
@@ -73,7 +88,19 @@ pub async fn platforms_create(
         .map_err(|e| (Status::InternalServerError, e.to_string()))?
         .ok_or((Status::Forbidden, "Not a member of the team".to_string()))?;
     if role < models::UserTeamRole::Admin {
-        return Err((Status::Forbidden, "Not enough permission".to_string()));
+        return Err((Status::Forbidden, "Not enough permission".to_string()).into());
+    }
+
+    if !upsert
+        && models::Platform::slug_exists(&mut db, slug)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+    {
+        return Err((
+            Status::Conflict,
+            format!("A platform with slug '{slug}' already exists."),
+        )
+            .into());
     }
 
     // Create platform.
@@ -83,15 +110,27 @@ pub async fn platforms_create(
         name,
         description,
         json!(links.unwrap_or_default()),
-        json!(metadata.unwrap_or_default()),
+        metadata.unwrap_or_default().into_json_value(),
+        metadata_schema.map(|s| json!(s)),
         &team,
+        upsert,
     )
     .await
     .map_err(|e| (Status::InternalServerError, e.to_string()))?;
 
+    // Without a new platform, one with the same slug already exists.
+    let (platform, created) = match platform {
+        Some(platform) => (platform, true),
+        None => (
+            models::Platform::fetch_or_404_slug(&mut db, slug).await?,
+            false,
+        ),
+    };
+
     Ok(Json(dto::platforms::PlatformCreateResponse {
         id: platform.id,
         slug: platform.slug,
+        created,
     }))
 }
 
@@ -106,40 +145,55 @@ pub async fn platforms_update(
     user: guards::users::AuthenticatedUserGuard,
     platform_id: dto::types::IdOrSlug<'_>,
     form: Json<dto::platforms::PlatformUpdateRequest<'_>>,
-) -> Result<Json<dto::Ok>, (Status, String)> {
+) -> Result<Json<dto::Ok>, ApiError> {
     let dto::platforms::PlatformUpdateRequest {
         slug,
         name,
         description,
         links,
         metadata,
+        metadata_schema,
         team_id,
+        add_metadata,
+        remove_metadata,
     } = form.into_inner();
 
     let platform = models::Platform::from_id_or_slug(&mut db, platform_id).await?;
 
+    let metadata = if metadata.is_some() {
+        metadata
+    } else if add_metadata.is_some() || remove_metadata.is_some() {
+        let mut metadata = platform.metadata.clone();
+        merge_json_metadata(&mut metadata, add_metadata, remove_metadata);
+        Some(metadata)
+    } else {
+        None
+    };
+
     let user = user.into_model(&mut db).await?;
 
     // Check permissions.
-    let role_in_old_team = user
-        .role_in(&mut db, platform.owner_team_id)
+    let team_ids = [Some(platform.owner_team_id), team_id]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    let roles = models::UserTeam::roles_in_teams(&mut db, user.id, &team_ids)
         .await
-        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    let role_in_old_team = roles
+        .get(&platform.owner_team_id)
         .ok_or((Status::Forbidden, "Not a member of the team".to_string()))?;
-    if role_in_old_team < models::UserTeamRole::Admin {
-        return Err((Status::Forbidden, "Not enough permission".to_string()));
+    if role_in_old_team < &models::UserTeamRole::Admin {
+        return Err((Status::Forbidden, "Not enough permission".to_string()).into());
     }
     if let Some(team_id) = team_id {
-        let role_in_new_team = user
-            .role_in(&mut db, team_id)
-            .await
-            .map_err(|e| (Status::InternalServerError, e.to_string()))?
-            .ok_or((
-                Status::Forbidden,
-                "Not a member of the new team".to_string(),
-            ))?;
-        if role_in_new_team < models::UserTeamRole::Admin {
-            return Err((Status::Forbidden, "Not enough permission".to_string()));
+        let role_in_new_team = roles.get(&team_id).ok_or((
+            Status::Forbidden,
+            "Not a member of the new team".to_string(),
+        ))?;
+        if role_in_new_team < &models::UserTeamRole::Admin {
+            return Err((Status::Forbidden, "Not enough permission".to_string()).into());
         }
     }
 
@@ -153,6 +207,7 @@ pub async fn platforms_update(
         links,
         metadata,
         team_id,
+        metadata_schema.map(|s| json!(s)),
     )
     .await
     .map_err(|e| (Status::InternalServerError, e.to_string()))?;
@@ -0,0 +1,74 @@
+use crate::routes::error::ApiError;
+use retronomicon_db::models;
+use retronomicon_db::types::FetchModel;
+use retronomicon_db::Db;
+use retronomicon_dto as dto;
+use rocket::get;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket_okapi::openapi;
+
+/// List the releases of all cores, most recently released first unless
+/// sorted otherwise.
+#[openapi(tag = "Core Releases", ignore = "db")]
+#[get("/releases?<page>&<limit>&<filter..>")]
+pub async fn releases_list(
+    mut db: Db,
+    filter: dto::cores::releases::ReleaseListQueryParams<'_>,
+    page: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Json<dto::Paginated<dto::cores::releases::CoreReleaseListItem>>, ApiError> {
+    let (page, limit) = dto::params::PagingParams::new(page, limit)
+        .validate()
+        .map_err(|e| (Status::BadRequest, e))?;
+
+    let sort = dto::params::SortParams::parse_opt(filter.sort.as_deref())
+        .map_err(|e| (Status::BadRequest, e))?;
+
+    let platform = match filter.platform {
+        Some(platform) => Some(models::Platform::from_id_or_slug(&mut db, platform).await?),
+        None => None,
+    };
+    let system = match filter.system {
+        Some(system) => Some(models::System::from_id_or_slug(&mut db, system).await?),
+        None => None,
+    };
+    let team = match filter.team {
+        Some(team) => Some(models::Team::from_id_or_slug(&mut db, team).await?),
+        None => None,
+    };
+
+    let (items, total) = models::CoreRelease::list_all(
+        &mut db,
+        page,
+        limit,
+        platform.map(|p| p.id),
+        system.map(|s| s.id),
+        team.map(|t| t.id),
+        filter.core_slug.as_deref(),
+        sort,
+    )
+    .await
+    .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(dto::Paginated::new(
+        page,
+        limit,
+        total,
+        items
+            .into_iter()
+            .map(|(release, platform, core, uploader, system_release)| {
+                dto::cores::releases::CoreReleaseListItem {
+                    release: release.into_ref(platform),
+                    core: dto::cores::CoreRef {
+                        id: core.id,
+                        slug: core.slug,
+                        name: core.name,
+                    },
+                    uploader: uploader.into(),
+                    required_system_release: system_release.map(Into::into),
+                }
+            })
+            .collect(),
+    )))
+}
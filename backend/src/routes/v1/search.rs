@@ -0,0 +1,138 @@
+use crate::routes::error::ApiError;
+use retronomicon_db::models;
+use retronomicon_db::Db;
+use retronomicon_dto as dto;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{get, post};
+use rocket_okapi::openapi;
+
+/// Search cores, games and systems at once. Cores are matched by how similar
+/// their name is to the query (see `/cores/search`), games by a substring of
+/// their name and systems by a substring of their name or slug.
+///
+/// `types` is a comma separated list of `core`, `game` and `system` to only
+/// search some types. By default, all types are searched. Each type returns at
+/// most [`dto::search::SEARCH_LIMIT_PER_TYPE`] results.
+#[openapi(tag = "Search", ignore = "db")]
+#[get("/search?<q>&<types>")]
+pub async fn search(
+    mut db: Db,
+    q: &str,
+    types: Option<&str>,
+) -> Result<Json<dto::search::SearchResults>, ApiError> {
+    use dto::search::{SearchType, SEARCH_LIMIT_PER_TYPE};
+
+    let types = match types {
+        Some(types) => SearchType::parse_list(types).map_err(|e| (Status::BadRequest, e))?,
+        None => SearchType::ALL.to_vec(),
+    };
+
+    let mut results = dto::search::SearchResults {
+        cores: vec![],
+        games: vec![],
+        systems: vec![],
+    };
+
+    if types.contains(&SearchType::Core) {
+        results.cores = models::Core::search_by_name(&mut db, q, SEARCH_LIMIT_PER_TYPE)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+            .into_iter()
+            .map(
+                |(core, system, team, similarity)| dto::cores::CoreSearchItem {
+                    id: core.id,
+                    slug: core.slug,
+                    name: core.name,
+                    system: system.into(),
+                    owner_team: team.into(),
+                    similarity,
+                },
+            )
+            .collect();
+    }
+
+    if types.contains(&SearchType::Game) {
+        results.games = models::Game::search_by_name(&mut db, q, SEARCH_LIMIT_PER_TYPE)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+            .into_iter()
+            .map(|(game, system)| dto::search::GameSearchItem {
+                id: game.id,
+                name: game.name,
+                year: game.year,
+                system: system.into(),
+            })
+            .collect();
+    }
+
+    if types.contains(&SearchType::System) {
+        results.systems =
+            models::System::list_with_team(&mut db, 0, SEARCH_LIMIT_PER_TYPE, Some(q), None, None)
+                .await
+                .map_err(|e| (Status::InternalServerError, e.to_string()))?
+                .into_iter()
+                .map(|(s, t)| dto::systems::SystemListItem {
+                    id: s.id,
+                    slug: s.slug,
+                    manufacturer: s.manufacturer,
+                    name: s.name,
+                    owner_team: t.into(),
+                    updated_at: s.updated_at.timestamp(),
+                })
+                .collect();
+    }
+
+    Ok(Json(results))
+}
+
+/// Resolve slugs of cores, systems and platforms to their ids, in a single
+/// request. Unknown slugs are omitted from the response. At most
+/// [`dto::search::MAX_RESOLVE_SLUGS_PER_TYPE`] slugs of each type can be
+/// resolved at once.
+#[openapi(tag = "Search", ignore = "db")]
+#[post("/resolve", format = "json", data = "<form>")]
+pub async fn resolve(
+    mut db: Db,
+    form: Json<dto::search::ResolveRequest>,
+) -> Result<Json<dto::search::ResolveResponse>, ApiError> {
+    use dto::search::MAX_RESOLVE_SLUGS_PER_TYPE;
+
+    let dto::search::ResolveRequest {
+        cores,
+        systems,
+        platforms,
+    } = form.into_inner();
+    if [&cores, &systems, &platforms]
+        .iter()
+        .any(|slugs| slugs.len() > MAX_RESOLVE_SLUGS_PER_TYPE)
+    {
+        return Err((
+            Status::BadRequest,
+            format!("Cannot resolve more than {MAX_RESOLVE_SLUGS_PER_TYPE} slugs of each type"),
+        )
+            .into());
+    }
+
+    let mut response = dto::search::ResolveResponse::default();
+    if !cores.is_empty() {
+        response.cores = models::Core::ids_by_slugs(&mut db, &cores)
+            .await?
+            .into_iter()
+            .collect();
+    }
+    if !systems.is_empty() {
+        response.systems = models::System::ids_by_slugs(&mut db, &systems)
+            .await?
+            .into_iter()
+            .collect();
+    }
+    if !platforms.is_empty() {
+        response.platforms = models::Platform::ids_by_slugs(&mut db, &platforms)
+            .await?
+            .into_iter()
+            .collect();
+    }
+
+    Ok(Json(response))
+}
@@ -1,26 +1,42 @@
+use crate::fairings::config::RetronomiconConfig;
 use crate::guards;
-use crate::utils::json;
+use crate::routes::error::ApiError;
+use crate::utils::{dat, json};
 use retronomicon_db::models;
+use retronomicon_db::scoped_futures::ScopedFutureExt;
 use retronomicon_db::types::FetchModel;
-use retronomicon_db::Db;
+use retronomicon_db::{transaction, Db};
 use retronomicon_dto as dto;
-use rocket::http::Status;
+use rocket::data::ToByteUnit;
+use rocket::http::{ContentType, Status};
+use rocket::response::stream::TextStream;
 use rocket::serde::json::Json;
-use rocket::{get, post};
+use rocket::{get, post, Data, State};
 use rocket_okapi::openapi;
 use serde_json::json;
 use std::collections::BTreeMap;
 
 #[openapi(tag = "Systems", ignore = "db")]
-#[get("/systems?<paging..>")]
+#[get("/systems?<page>&<limit>&<filter..>")]
 pub async fn systems_list(
     mut db: Db,
-    paging: dto::params::PagingParams,
-) -> Result<Json<Vec<dto::systems::SystemListItem>>, (Status, String)> {
-    let (page, limit) = paging.validate().map_err(|e| (Status::BadRequest, e))?;
-    let system_list = models::System::list_with_team(&mut db, page, limit)
-        .await
-        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    page: Option<i64>,
+    limit: Option<i64>,
+    filter: dto::systems::SystemListQueryParams<'_>,
+) -> Result<Json<Vec<dto::systems::SystemListItem>>, ApiError> {
+    let (page, limit) = dto::params::PagingParams::new(page, limit)
+        .validate()
+        .map_err(|e| (Status::BadRequest, e))?;
+    let system_list = models::System::list_with_team(
+        &mut db,
+        page,
+        limit,
+        filter.name.as_deref(),
+        filter.manufacturer.as_deref(),
+        filter.team,
+    )
+    .await
+    .map_err(|e| (Status::InternalServerError, e.to_string()))?;
 
     Ok(Json(
         system_list
@@ -31,6 +47,7 @@ pub async fn systems_list(
                 manufacturer: s.manufacturer,
                 name: s.name,
                 owner_team: t.into(),
+                updated_at: s.updated_at.timestamp(),
             })
             .collect(),
     ))
@@ -42,7 +59,7 @@ pub async fn systems_create(
     mut db: Db,
     user: guards::users::AuthenticatedUserGuard,
     form: Json<dto::systems::SystemCreateRequest<'_>>,
-) -> Result<Json<dto::systems::SystemCreateResponse>, (Status, String)> {
+) -> Result<Json<dto::systems::SystemCreateResponse>, ApiError> {
     let dto::systems::SystemCreateRequest {
         slug,
         name,
@@ -51,7 +68,11 @@ pub async fn systems_create(
         links,
         metadata,
         owner_team,
+        upsert,
     } = form.into_inner();
+    slug.validate()
+        .map_err(|e| (Status::BadRequest, e.to_string()))?;
+    let slug = slug.as_str();
 
     // Get team.
     let team = models::Team::from_id_or_slug(&mut db, owner_team).await?;
@@ -66,7 +87,19 @@ pub async fn systems_create(
         .ok_or((Status::Forbidden, "Not a member of the team".to_string()))?;
 
     if !role.can_create_systems() {
-        return Err((Status::Forbidden, "Not enough permission".to_string()));
+        return Err((Status::Forbidden, "Not enough permission".to_string()).into());
+    }
+
+    if !upsert
+        && models::System::slug_exists(&mut db, slug)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+    {
+        return Err((
+            Status::Conflict,
+            format!("A system with slug '{slug}' already exists."),
+        )
+            .into());
     }
 
     // Create system.
@@ -77,15 +110,26 @@ pub async fn systems_create(
         description,
         manufacturer,
         json!(links.unwrap_or_else(|| BTreeMap::new())),
-        json!(metadata.unwrap_or_else(|| BTreeMap::new())),
+        metadata.unwrap_or_default().into_json_value(),
         team.id,
+        upsert,
     )
     .await
     .map_err(|e| (Status::InternalServerError, e.to_string()))?;
 
+    // Without a new system, one with the same slug already exists.
+    let (system, created) = match system {
+        Some(system) => (system, true),
+        None => (
+            models::System::fetch_or_404_slug(&mut db, slug).await?,
+            false,
+        ),
+    };
+
     Ok(Json(dto::systems::SystemCreateResponse {
         id: system.id,
         slug: system.slug,
+        created,
     }))
 }
 
@@ -94,9 +138,9 @@ pub async fn systems_create(
 pub async fn systems_details(
     mut db: Db,
     id: dto::types::IdOrSlug<'_>,
-) -> Result<Json<dto::systems::SystemDetails>, (Status, String)> {
+) -> Result<Json<dto::systems::SystemDetails>, ApiError> {
     let system = models::System::from_id_or_slug(&mut db, id).await?;
-    let team = models::Team::from_id(&mut db, system.owner_team_id).await?;
+    let team = models::Team::fetch_or_404(&mut db, system.owner_team_id).await?;
     let links =
         json::links_into_btree_map(system.links).map_err(|e| (Status::InternalServerError, e))?;
     let metadata = json::metadata_into_btree_map(system.metadata)
@@ -111,5 +155,220 @@ pub async fn systems_details(
         links,
         metadata,
         owner_team: team.into(),
+        updated_at: system.updated_at.timestamp(),
     }))
 }
+
+fn decode_checksum(checksum: Option<&str>) -> Result<Option<Vec<u8>>, (Status, String)> {
+    checksum
+        .filter(|c| !c.is_empty())
+        .map(|c| hex::decode(c).map_err(|e| (Status::BadRequest, format!("Invalid checksum: {e}"))))
+        .transpose()
+}
+
+/// Import a Logiqx XML DAT file into a system. Games are matched by their
+/// exact name; missing games are created and ROMs that are not yet linked
+/// to a game (by filename) are added to it.
+#[openapi(tag = "Systems", ignore = "config", ignore = "db")]
+#[post("/systems/<id>/import-dat", data = "<dat>")]
+pub async fn systems_import_dat(
+    mut db: Db,
+    _root_user: guards::users::RootUserGuard,
+    config: &State<RetronomiconConfig>,
+    id: dto::types::IdOrSlug<'_>,
+    dat: Data<'_>,
+) -> Result<Json<dto::systems::SystemDatImportResponse>, ApiError> {
+    let system = models::System::from_id_or_slug(&mut db, id).await?;
+
+    let content = dat
+        .open(config.upload_limits.max_dat_bytes.bytes())
+        .into_bytes()
+        .await
+        .map_err(|e| (Status::BadRequest, e.to_string()))?;
+    if !content.is_complete() {
+        return Err((Status::PayloadTooLarge, "DAT file too large".to_string()).into());
+    }
+    let dat = datary::from_reader(content.as_slice())
+        .map_err(|e| (Status::BadRequest, format!("Invalid DAT file: {e}")))?;
+
+    // Games without an ID in the DAT get one after all known IDs.
+    let mut next_unique_id = models::Game::max_system_unique_id(&mut db, system.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .into_iter()
+        .chain(dat.games.iter().filter_map(|g| g.id.map(|i| i as i32)))
+        .max()
+        .unwrap_or(0);
+
+    // Import the whole DAT in a single transaction, so a failure does not
+    // leave a partially imported system.
+    let response = transaction(&mut db, |db| {
+        async move {
+            let mut response = dto::systems::SystemDatImportResponse {
+                games_created: 0,
+                games_existing: 0,
+                roms_added: 0,
+            };
+
+            for game in dat.games {
+                let (game_model, existing) =
+                    match models::Game::find_by_exact_name(db, system.id, &game.name).await? {
+                        Some(g) => {
+                            response.games_existing += 1;
+                            let artifacts = models::GameArtifact::list_artifacts(db, g.id).await?;
+                            (g, artifacts)
+                        }
+                        None => {
+                            let system_unique_id = match game.id {
+                                Some(id) => id as i32,
+                                None => {
+                                    next_unique_id += 1;
+                                    next_unique_id
+                                }
+                            };
+                            let tags = datary::naming::parse_name_tags(&game.name);
+                            let languages = tags
+                                .languages
+                                .iter()
+                                .map(String::as_str)
+                                .collect::<Vec<_>>();
+                            let g = models::Game::create(
+                                db,
+                                &game.name,
+                                &game.description,
+                                "",
+                                game.year
+                                    .as_ref()
+                                    .and_then(|y| y.parse::<i32>().ok())
+                                    .unwrap_or_default(),
+                                game.manufacturer.as_deref().unwrap_or_default(),
+                                "",
+                                json!({}),
+                                system.id,
+                                system_unique_id,
+                                tags.region.as_deref(),
+                                &languages,
+                            )
+                            .await?;
+                            response.games_created += 1;
+                            (g, Vec::new())
+                        }
+                    };
+
+                for rom in game.roms {
+                    if existing.iter().any(|a| a.filename == rom.name) {
+                        continue;
+                    }
+
+                    let size = i32::try_from(rom.size)
+                        .map_err(|_| (Status::BadRequest, format!("ROM {} too large", rom.name)))?;
+                    let md5 = decode_checksum(rom.md5.as_deref())?;
+                    let sha1 = decode_checksum(rom.sha1.as_deref())?;
+                    let crc32 = decode_checksum(rom.crc.as_deref())?;
+
+                    let artifact = models::Artifact::create_with_checksum(
+                        db,
+                        &rom.name,
+                        "application/octet-stream",
+                        md5.as_deref(),
+                        sha1.as_deref(),
+                        None,
+                        crc32.as_deref(),
+                        None,
+                        size,
+                    )
+                    .await?;
+
+                    models::GameArtifact::create(db, game_model.id, artifact.id).await?;
+                    response.roms_added += 1;
+                }
+            }
+            Ok(response)
+        }
+        .scope_boxed()
+    })
+    .await?;
+
+    Ok(Json(response))
+}
+
+/// Group the rows of [`models::Game::list_with_artifacts`] by game.
+fn dat_games(
+    rows: Vec<(models::Game, Option<models::Artifact>)>,
+) -> Vec<dto::systems::SystemDatGame> {
+    let checksum = |c: Vec<u8>| if c.is_empty() { None } else { Some(c.into()) };
+    let mut games: Vec<dto::systems::SystemDatGame> = Vec::new();
+    for (game, artifact) in rows {
+        if games.last().map(|g| g.id) != Some(game.id) {
+            games.push(dto::systems::SystemDatGame {
+                id: game.id,
+                name: game.name,
+                description: game.description,
+                year: game.year,
+                system_unique_id: game.system_unique_id,
+                roms: Vec::new(),
+            });
+        }
+
+        if let (Some(artifact), Some(g)) = (artifact, games.last_mut()) {
+            g.roms.push(dto::systems::SystemDatRom {
+                name: artifact.filename,
+                size: artifact.size,
+                md5: checksum(artifact.md5),
+                sha1: checksum(artifact.sha1),
+                sha256: checksum(artifact.sha256),
+                crc32: checksum(artifact.crc32),
+            });
+        }
+    }
+
+    games
+}
+
+/// Export all games of a system and their ROMs, in JSON.
+#[openapi(tag = "Systems", ignore = "db")]
+#[get("/systems/<id>/games.dat.json")]
+pub async fn systems_games_dat_json(
+    mut db: Db,
+    id: dto::types::IdOrSlug<'_>,
+) -> Result<Json<Vec<dto::systems::SystemDatGame>>, ApiError> {
+    let system = models::System::from_id_or_slug(&mut db, id).await?;
+    let rows = models::Game::list_with_artifacts(&mut db, system.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(dat_games(rows)))
+}
+
+/// Export all games of a system and their ROMs as a Logiqx XML DAT, which
+/// can be imported back or loaded in a ROM manager. The document is streamed
+/// one game at a time.
+#[openapi(tag = "Systems", ignore = "db", skip)]
+#[get("/systems/<id>/games.dat")]
+pub async fn systems_games_dat(
+    mut db: Db,
+    id: dto::types::IdOrSlug<'_>,
+) -> Result<(ContentType, TextStream![String]), ApiError> {
+    let system = models::System::from_id_or_slug(&mut db, id).await?;
+    let rows = models::Game::list_with_artifacts(&mut db, system.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    let header = dat::header(
+        &system.name,
+        &system.description,
+        &chrono::Utc::now().format("%Y%m%d-%H%M%S").to_string(),
+    );
+    let games = dat_games(rows);
+
+    Ok((
+        ContentType::XML,
+        TextStream! {
+            yield header;
+            for game in games {
+                yield dat::game(&game);
+            }
+            yield dat::FOOTER.to_string();
+        },
+    ))
+}
@@ -1,4 +1,5 @@
 use crate::guards;
+use crate::routes::error::ApiError;
 use retronomicon_db::models;
 use retronomicon_db::types::FetchModel;
 use retronomicon_db::Db;
@@ -14,13 +15,66 @@ use rocket_okapi::openapi;
 pub async fn tags(
     mut db: Db,
     paging: dto::params::PagingParams,
-) -> Result<Json<Vec<dto::tags::Tag>>, (Status, String)> {
+) -> Result<Json<Vec<dto::tags::Tag>>, ApiError> {
     let (page, limit) = paging.validate().map_err(|e| (Status::BadRequest, e))?;
 
     models::Tag::list(&mut db, page, limit)
         .await
         .map(|u| Json(u.into_iter().map(Into::into).collect()))
-        .map_err(|e| (Status::InternalServerError, e.to_string()))
+        .map_err(|e| (Status::InternalServerError, e.to_string()).into())
+}
+
+/// List the cores tagged with a tag.
+#[openapi(tag = "Tags", ignore = "db")]
+#[get("/tags/<tag_id>/cores?<paging..>")]
+pub async fn tags_cores(
+    mut db: Db,
+    tag_id: dto::types::IdOrSlug<'_>,
+    paging: dto::params::PagingParams,
+) -> Result<Json<Vec<dto::cores::CoreListItem>>, ApiError> {
+    let (page, limit) = paging.validate().map_err(|e| (Status::BadRequest, e))?;
+    let tag = models::Tag::from_id_or_slug(&mut db, tag_id).await?;
+
+    let items = models::Core::list_by_tag(&mut db, tag.id, page, limit)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(
+        items
+            .into_iter()
+            .map(
+                |(core, system, team, core_release, platform)| dto::cores::CoreListItem {
+                    id: core.id,
+                    slug: core.slug,
+                    name: core.name,
+                    owner_team: team.into(),
+                    system: system.into(),
+                    latest_release: core_release
+                        .zip(platform)
+                        .map(|(cr, platform)| cr.into_ref(platform)),
+                    updated_at: core.updated_at.timestamp(),
+                    platforms: None,
+                },
+            )
+            .collect(),
+    ))
+}
+
+/// List the games tagged with a tag.
+#[openapi(tag = "Tags", ignore = "db")]
+#[get("/tags/<tag_id>/games?<paging..>")]
+pub async fn tags_games(
+    mut db: Db,
+    tag_id: dto::types::IdOrSlug<'_>,
+    paging: dto::params::PagingParams,
+) -> Result<Json<Vec<dto::games::GameListItemResponse>>, ApiError> {
+    let (page, limit) = paging.validate().map_err(|e| (Status::BadRequest, e))?;
+    let tag = models::Tag::from_id_or_slug(&mut db, tag_id).await?;
+
+    models::Game::list_by_tag(&mut db, tag.id, page, limit)
+        .await
+        .map(|rows| Json(super::games::group_game_rows(rows)))
+        .map_err(|e| (Status::InternalServerError, e.to_string()).into())
 }
 
 /// Create or update a tag.
@@ -30,13 +84,13 @@ pub async fn tags_create(
     mut db: Db,
     _user: guards::users::RootUserGuard,
     tag: Json<dto::tags::TagCreate>,
-) -> Result<Json<dto::Ok>, (Status, String)> {
+) -> Result<Json<dto::Ok>, ApiError> {
     let tag = tag.into_inner();
 
     models::Tag::create(&mut db, tag.slug, tag.description, tag.color)
         .await
         .map(|_| Json(dto::Ok))
-        .map_err(|e| (Status::InternalServerError, e.to_string()))
+        .map_err(|e| (Status::InternalServerError, e.to_string()).into())
 }
 
 /// Get a tag information (including its description).
@@ -46,11 +100,11 @@ pub async fn tags_delete(
     mut db: Db,
     _user: guards::users::RootUserGuard,
     tag_id: dto::types::IdOrSlug<'_>,
-) -> Result<Json<dto::Ok>, (Status, String)> {
+) -> Result<Json<dto::Ok>, ApiError> {
     let tag = models::Tag::from_id_or_slug(&mut db, tag_id).await?;
 
     tag.delete(&mut db)
         .await
         .map(|_| Json(dto::Ok))
-        .map_err(|e| (Status::InternalServerError, e.to_string()))
+        .map_err(|e| (Status::InternalServerError, e.to_string()).into())
 }
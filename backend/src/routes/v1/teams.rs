@@ -1,29 +1,33 @@
 use crate::guards::users::AuthenticatedUserGuard;
+use crate::routes::error::ApiError;
 use crate::utils::{acls, json};
 use retronomicon_db::models;
 use retronomicon_db::models::Team;
-use retronomicon_db::types::FetchModel;
-use retronomicon_db::Db;
+use retronomicon_db::scoped_futures::ScopedFutureExt;
+use retronomicon_db::types::{found_or_404_named, FetchModel};
+use retronomicon_db::utils::{merge_json_links, merge_json_metadata};
+use retronomicon_db::{transaction, Db};
 use retronomicon_dto as dto;
 use retronomicon_dto::types::IdOrSlug;
 use rocket::http::Status;
+use rocket::response::Redirect;
 use rocket::serde::json::Json;
-use rocket::{delete, get, post, put};
+use rocket::{delete, get, post, put, uri, Either};
 use rocket_okapi::openapi;
-use serde_json::{json, Value};
+use serde_json::json;
 
 #[openapi(tag = "Teams", ignore = "db")]
 #[get("/teams?<paging..>")]
 pub async fn teams(
     mut db: Db,
     paging: dto::params::PagingParams,
-) -> Result<Json<Vec<dto::teams::Team>>, (Status, String)> {
+) -> Result<Json<Vec<dto::teams::Team>>, ApiError> {
     let (page, limit) = paging.validate().map_err(|e| (Status::BadRequest, e))?;
 
     Team::list(&mut db, page, limit)
         .await
         .map(|t| Json(t.into_iter().map(Into::into).collect()))
-        .map_err(|e| (Status::InternalServerError, e.to_string()))
+        .map_err(|e| (Status::InternalServerError, e.to_string()).into())
 }
 
 #[openapi(tag = "Teams", ignore = "db")]
@@ -31,8 +35,15 @@ pub async fn teams(
 pub async fn teams_details(
     mut db: Db,
     id: IdOrSlug<'_>,
-) -> Result<Json<dto::teams::TeamDetails>, (Status, String)> {
-    let team = Team::from_id_or_slug(&mut db, id).await?;
+) -> Result<Either<Json<dto::teams::TeamDetails>, Redirect>, ApiError> {
+    let (team, moved) = Team::from_id_or_slug_or_previous(&mut db, id).await?;
+    if moved {
+        return Ok(Either::Right(Redirect::moved(uri!(
+            "/api/v1",
+            teams_details(team.slug.as_str())
+        ))));
+    }
+
     let users = team
         .users_ref(&mut db)
         .await
@@ -43,7 +54,7 @@ pub async fn teams_details(
     let metadata = json::metadata_into_btree_map(team.metadata)
         .map_err(|e| (Status::InternalServerError, e))?;
 
-    Ok(Json(dto::teams::TeamDetails {
+    Ok(Either::Left(Json(dto::teams::TeamDetails {
         team: dto::teams::TeamRef {
             id: team.id,
             slug: team.slug,
@@ -52,8 +63,9 @@ pub async fn teams_details(
         description: team.description,
         links,
         metadata,
+        updated_at: team.updated_at.timestamp(),
         users,
-    }))
+    })))
 }
 
 /// Create a new team, and make the current user its owner.
@@ -63,7 +75,7 @@ pub async fn teams_create(
     mut db: Db,
     owner: AuthenticatedUserGuard,
     form: Json<dto::teams::TeamCreateRequest<'_>>,
-) -> Result<Json<dto::teams::TeamCreateResponse>, (Status, String)> {
+) -> Result<Json<dto::teams::TeamCreateResponse>, ApiError> {
     let db = &mut db;
     let dto::teams::TeamCreateRequest {
         slug,
@@ -71,36 +83,61 @@ pub async fn teams_create(
         description,
         links,
         metadata,
+        upsert,
     } = form.into_inner();
+    slug.validate()
+        .map_err(|e| (Status::BadRequest, e.to_string()))?;
+    let slug = slug.as_str();
 
     // Links must be only a map of strings.
     let links = links.map(|l| json!(l));
     // Metadata must be a map.
-    let metadata = metadata.map(|m| json!(m));
+    let metadata = metadata.map(dto::types::Metadata::into_json_value);
     let user = owner.into_model(db).await?;
 
     if !acls::can_create_team(&user) {
-        return Err((Status::Unauthorized, "Insufficient permissions".to_string()));
+        return Err((Status::Unauthorized, "Insufficient permissions".to_string()).into());
     }
 
-    let team = models::Team::create(
-        db,
-        slug,
-        name,
-        description,
-        links.unwrap_or_else(|| json!({})),
-        metadata.unwrap_or_else(|| json!({})),
-    )
-    .await
-    .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    // Create the team and add its owner together, so a team never exists
+    // without an owner.
+    let (team, created) = transaction(db, |db| {
+        async move {
+            if !upsert && models::Team::slug_exists(db, slug).await? {
+                return Err((
+                    Status::Conflict,
+                    format!("A team with slug '{slug}' already exists."),
+                )
+                    .into());
+            }
 
-    user.join_team(db, team.id, models::UserTeamRole::Owner)
-        .await
-        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+            let Some(team) = models::Team::create(
+                db,
+                slug,
+                name,
+                description,
+                links.unwrap_or_else(|| json!({})),
+                metadata.unwrap_or_else(|| json!({})),
+                upsert,
+            )
+            .await?
+            else {
+                // The team already exists, return it unchanged.
+                return Ok((models::Team::fetch_or_404_slug(db, slug).await?, false));
+            };
+
+            user.join_team(db, team.id, models::UserTeamRole::Owner)
+                .await?;
+            Ok((team, true))
+        }
+        .scope_boxed()
+    })
+    .await?;
 
     Ok(Json(dto::teams::TeamCreateResponse {
         id: team.id,
         slug: team.slug,
+        created,
     }))
 }
 
@@ -112,15 +149,15 @@ pub async fn teams_update(
     owner: AuthenticatedUserGuard,
     team_id: dto::types::IdOrSlug<'_>,
     form: Json<dto::teams::TeamUpdateRequest<'_>>,
-) -> Result<Json<dto::Ok>, (Status, String)> {
+) -> Result<Json<dto::Ok>, ApiError> {
     let db = &mut db;
-    let (user, mut team, role) = models::User::get_user_team_and_role(db, owner.id.into(), team_id)
-        .await
-        .map_err(|e| (Status::InternalServerError, e.to_string()))?
-        .ok_or((Status::NotFound, "Not found".to_string()))?;
+    let (user, team, role) = found_or_404_named(
+        "Team",
+        models::User::get_user_team_and_role(db, owner.id.into(), team_id.resolve()?).await,
+    )?;
 
     if !acls::can_update_team(&user, &team, &role) {
-        return Err((Status::Unauthorized, "Insufficient permissions".to_string()));
+        return Err((Status::Unauthorized, "Insufficient permissions".to_string()).into());
     }
 
     let dto::teams::TeamUpdateRequest {
@@ -131,28 +168,33 @@ pub async fn teams_update(
         metadata,
         add_links,
         remove_links,
+        add_metadata,
+        remove_metadata,
     } = form.into_inner();
 
+    if let Some(slug) = slug {
+        dto::types::validate_slug(slug).map_err(|e| (Status::BadRequest, e.to_string()))?;
+    }
+
     let links = if let Some(links) = links {
         Some(json!(links))
     } else if add_links.is_some() || remove_links.is_some() {
-        let links = team.links.as_object_mut().unwrap();
-        if let Some(add_links) = add_links {
-            for (k, v) in add_links {
-                links[k] = Value::String(v.to_string());
-            }
-        }
-        if let Some(remove_links) = remove_links {
-            for k in remove_links {
-                links.remove(&k.to_string());
-            }
-        }
-        Some(json!(links))
+        let mut links = team.links;
+        merge_json_links(&mut links, add_links, remove_links);
+        Some(links)
     } else {
         None
     };
 
-    let metadata = metadata.map(|m| json!(m));
+    let metadata = if let Some(metadata) = metadata {
+        Some(metadata.into_json_value())
+    } else if add_metadata.is_some() || remove_metadata.is_some() {
+        let mut metadata = team.metadata;
+        merge_json_metadata(&mut metadata, add_metadata, remove_metadata);
+        Some(metadata)
+    } else {
+        None
+    };
 
     models::teams::Team::update(db, team.id, slug, name, description, links, metadata)
         .await
@@ -166,15 +208,26 @@ pub async fn teams_delete(
     mut db: Db,
     admin: AuthenticatedUserGuard,
     team_id: dto::types::IdOrSlug<'_>,
-) -> Result<Json<dto::Ok>, (Status, String)> {
+) -> Result<Json<dto::Ok>, ApiError> {
     let db = &mut db;
-    let (user, team, role) = models::User::get_user_team_and_role(db, admin.into(), team_id)
-        .await
-        .map_err(|e| (Status::InternalServerError, e.to_string()))?
-        .ok_or((Status::NotFound, "Not found".to_string()))?;
+    let (user, team, role) = found_or_404_named(
+        "Team",
+        models::User::get_user_team_and_role(db, admin.into(), team_id.resolve()?).await,
+    )?;
 
     if !acls::can_delete_team(&user, &team, &role) {
-        return Err((Status::Unauthorized, "Insufficient permissions".to_string()));
+        return Err((Status::Unauthorized, "Insufficient permissions".to_string()).into());
+    }
+
+    let (cores, systems, platforms) = models::Team::owned_resources_count(db, team.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    if cores + systems + platforms > 0 {
+        return Err((
+            Status::Conflict,
+            format!("Team still owns {cores} cores, {systems} systems and {platforms} platforms"),
+        )
+            .into());
     }
 
     models::Team::delete(db, team.id)
@@ -190,13 +243,12 @@ pub async fn invite(
     admin: AuthenticatedUserGuard,
     team_id: IdOrSlug<'_>,
     form: Json<dto::teams::TeamInvite<'_>>,
-) -> Result<Json<dto::Ok>, (Status, String)> {
+) -> Result<Json<dto::Ok>, ApiError> {
     let db = &mut db;
-    let (admin_user, team, admin_role) =
-        models::User::get_user_team_and_role(db, admin.id.into(), team_id)
-            .await
-            .map_err(|e| (Status::InternalServerError, e.to_string()))?
-            .ok_or((Status::NotFound, "Not found".to_string()))?;
+    let (admin_user, team, admin_role) = found_or_404_named(
+        "Team",
+        models::User::get_user_team_and_role(db, admin.id.into(), team_id.resolve()?).await,
+    )?;
 
     let dto::teams::TeamInvite { user, role } = form.into_inner();
     let user = models::User::from_userid(db, user)
@@ -205,7 +257,7 @@ pub async fn invite(
     let role = models::UserTeamRole::from(role);
 
     if !acls::can_invite_to_team(&team, &admin_user, &admin_role, &user, &role) {
-        return Err((Status::Unauthorized, "Insufficient permissions".to_string()));
+        return Err((Status::Unauthorized, "Insufficient permissions".to_string()).into());
     }
 
     user.invite_to(db, admin_user.id, team.id, role)
@@ -215,13 +267,66 @@ pub async fn invite(
     Ok(Json(dto::Ok))
 }
 
+/// Invite multiple users to a team. Users that cannot be found or invited
+/// are reported in the result instead of failing the whole request.
+#[openapi(tag = "Teams", ignore = "db")]
+#[post("/teams/<team_id>/invitations/bulk", data = "<form>")]
+pub async fn invite_bulk(
+    mut db: Db,
+    admin: AuthenticatedUserGuard,
+    team_id: IdOrSlug<'_>,
+    form: Json<dto::teams::TeamInviteBulkRequest<'_>>,
+) -> Result<Json<Vec<dto::teams::TeamInviteBulkResult>>, ApiError> {
+    let db = &mut db;
+    let (admin_user, team, admin_role) = found_or_404_named(
+        "Team",
+        models::User::get_user_team_and_role(db, admin.id.into(), team_id.resolve()?).await,
+    )?;
+
+    // Viewer is the lowest role, so if it cannot be invited nothing can.
+    if !acls::can_invite_to_team(
+        &team,
+        &admin_user,
+        &admin_role,
+        &admin_user,
+        &models::UserTeamRole::Viewer,
+    ) {
+        return Err((Status::Unauthorized, "Insufficient permissions".to_string()).into());
+    }
+
+    let mut results = Vec::new();
+    let mut invites = Vec::new();
+    for dto::teams::TeamInvite { user, role } in form.into_inner().invites {
+        let name = user.to_string();
+        let role = models::UserTeamRole::from(role);
+        let status = match models::User::from_userid(db, user).await {
+            Ok(user) => {
+                if acls::can_invite_to_team(&team, &admin_user, &admin_role, &user, &role) {
+                    invites.push((user, role));
+                    dto::teams::TeamInviteStatus::Invited
+                } else {
+                    dto::teams::TeamInviteStatus::Forbidden
+                }
+            }
+            Err(_) => dto::teams::TeamInviteStatus::NotFound,
+        };
+        results.push(dto::teams::TeamInviteBulkResult { user: name, status });
+    }
+
+    team.invite_users(db, admin_user.id, &invites)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(results))
+}
+
 #[openapi(tag = "Teams", ignore = "db")]
 #[post("/teams/<team_id>/invitation/accept")]
 pub async fn invite_accept(
     mut db: Db,
     invited: AuthenticatedUserGuard,
     team_id: IdOrSlug<'_>,
-) -> Result<Json<dto::Ok>, (Status, String)> {
+) -> Result<Json<dto::Ok>, ApiError> {
     let db = &mut db;
     let user = models::User::from_id(db, invited.id)
         .await
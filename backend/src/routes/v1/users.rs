@@ -1,4 +1,5 @@
 use crate::guards;
+use crate::routes::error::ApiError;
 use retronomicon_db::models::User;
 use retronomicon_db::Db;
 use retronomicon_dto as dto;
@@ -14,7 +15,7 @@ use rocket_okapi::openapi;
 pub async fn check_username(
     mut db: Db,
     username: &str,
-) -> Result<Json<dto::user::UserCheckResponse>, (Status, String)> {
+) -> Result<Json<dto::user::UserCheckResponse>, ApiError> {
     let user_id =
         dto::user::Username::new(username).map_err(|e| (Status::BadRequest, e.to_string()))?;
     let exists = User::exists(&mut db, user_id.into())
@@ -34,7 +35,7 @@ pub async fn check_username(
 pub async fn users(
     mut db: Db,
     paging: dto::params::PagingParams,
-) -> Result<Json<dto::Paginated<dto::user::User>>, (Status, String)> {
+) -> Result<Json<dto::Paginated<dto::user::User>>, ApiError> {
     let (page, limit) = paging.validate().map_err(|e| (Status::BadRequest, e))?;
 
     let (items, total) = User::list(&mut db, page, limit)
@@ -49,20 +50,49 @@ pub async fn users(
     )))
 }
 
+/// Search users by the start of their username, e.g. to complete the username
+/// of a team invitation. Only authenticated users can search, so anonymous
+/// clients cannot enumerate usernames. Emails are never returned.
+#[openapi(tag = "Users", ignore = "db")]
+#[get("/users/search?<q>&<limit>")]
+pub async fn users_search(
+    mut db: Db,
+    _user: guards::users::AuthenticatedUserGuard,
+    q: &str,
+    limit: Option<i64>,
+) -> Result<Json<Vec<dto::user::UserRef>>, ApiError> {
+    use dto::user::{USER_SEARCH_LIMIT_DEFAULT, USER_SEARCH_LIMIT_MAX};
+
+    if q.is_empty() {
+        return Err((Status::BadRequest, "Query cannot be empty".to_string()).into());
+    }
+    let limit = limit.unwrap_or(USER_SEARCH_LIMIT_DEFAULT);
+    if !(1..=USER_SEARCH_LIMIT_MAX).contains(&limit) {
+        return Err((
+            Status::BadRequest,
+            format!("Limit must be between 1 and {USER_SEARCH_LIMIT_MAX}"),
+        )
+            .into());
+    }
+
+    let users = User::search_by_username_prefix(&mut db, q, limit).await?;
+    Ok(Json(users.into_iter().map(Into::into).collect()))
+}
+
 #[openapi(tag = "Users", ignore = "db")]
 #[get("/users/<id>")]
 pub async fn users_details(
     mut db: Db,
     user_guard: guards::users::UserGuard,
     id: dto::user::UserIdOrUsername<'_>,
-) -> Result<Json<dto::user::UserDetails>, (Status, String)> {
+) -> Result<Json<dto::user::UserDetails>, ApiError> {
     let (user, teams) = User::get_user_with_teams(&mut db, id)
         .await
         .map_err(|e| (Status::InternalServerError, e.to_string()))?
         .ok_or((Status::NotFound, "User not found".to_string()))?;
 
     if user.username.is_none() && user_guard.id != user.id {
-        return Err((Status::NotFound, "User not found".to_string()));
+        return Err((Status::NotFound, "User not found".to_string()).into());
     }
 
     let teams = teams
@@ -78,6 +108,7 @@ pub async fn users_details(
         user: dto::user::UserDetailsInner {
             id: user.id,
             username: user.username,
+            display_name: user.display_name,
             description: user.description,
             links: user.links,
             metadata: user.metadata,
@@ -93,7 +124,7 @@ pub async fn users_update(
     _root_user: guards::users::RootUserGuard,
     id: dto::user::UserIdOrUsername<'_>,
     form: Json<dto::user::UserUpdate<'_>>,
-) -> Result<Json<dto::Ok>, (Status, String)> {
+) -> Result<Json<dto::Ok>, ApiError> {
     let user = User::from_userid(&mut db, id)
         .await
         .map_err(|e| (Status::NotFound, e.to_string()))?;
@@ -1,4 +1,8 @@
 pub mod acls;
+pub mod dat;
+pub mod http;
+pub mod markdown;
+pub mod zip;
 
 pub mod json {
     use serde_json::Value;
@@ -1,4 +1,5 @@
 use retronomicon_db::models;
+use std::collections::HashMap;
 
 pub fn can_create_team(_user: &models::User) -> bool {
     true
@@ -32,24 +33,57 @@ pub fn can_invite_to_team(
     } else if admin_role == &models::UserTeamRole::Owner {
         true
     } else {
-        admin_role > invited_role
+        // Admins can invite members and viewers, but not other admins.
+        admin_role >= &models::UserTeamRole::Admin && admin_role > invited_role
     }
 }
 
 pub(crate) async fn can_create_core_releases(
+    user: &models::User,
+    team: &models::Team,
+    role: &models::UserTeamRole,
+    core: &models::Core,
+) -> bool {
+    can_create_core_releases_with_roles(user, &HashMap::from([(team.id, *role)]), core)
+}
+
+/// Same as [`can_create_core_releases`], using roles fetched ahead of time
+/// with [`models::UserTeam::roles_in_teams`] when checking multiple cores.
+pub(crate) fn can_create_core_releases_with_roles(
     _user: &models::User,
-    _team: &models::Team,
+    roles: &HashMap<i32, models::UserTeamRole>,
+    core: &models::Core,
+) -> bool {
+    // All members can do releases.
+    roles
+        .get(&core.owner_team_id)
+        .is_some_and(|role| role >= &models::UserTeamRole::Member)
+}
+
+/// Only admins of the core's team can inspect its artifacts, e.g. verify
+/// their integrity or list their filenames.
+pub(crate) fn can_inspect_core_artifacts(
+    _user: &models::User,
+    _core: &models::Core,
     role: &models::UserTeamRole,
+) -> bool {
+    role >= &models::UserTeamRole::Admin
+}
+
+/// All members of the core's team, including viewers, can read its private
+/// resources, e.g. the artifacts of its yanked releases.
+pub(crate) fn can_read_private_core_resources(
+    _user: &models::User,
     _core: &models::Core,
+    role: &models::UserTeamRole,
 ) -> bool {
-    // All members can do releases.
-    role >= &models::UserTeamRole::Member
+    role.can_read_private_resources()
 }
 
 pub(crate) fn can_upload_image(
     _user: &models::User,
     team: &models::Team,
-    _role: &models::UserTeamRole,
+    role: &models::UserTeamRole,
 ) -> bool {
-    team.is_root()
+    team.is_root() && role >= &models::UserTeamRole::Member
 }
@@ -0,0 +1,89 @@
+//! Serialization of a system's games to a Logiqx XML DAT file.
+//!
+//! The DAT is written one game at a time so it can be streamed to the client
+//! instead of building the whole document in memory.
+use retronomicon_dto as dto;
+use std::borrow::Cow;
+use std::fmt::Write;
+
+/// The closing tag of the DAT, to send after the last game.
+pub const FOOTER: &str = "</datafile>\n";
+
+/// Escape a string for use in XML text and attribute values.
+fn escape(s: &str) -> Cow<'_, str> {
+    if !s.contains(['&', '<', '>', '"', '\'']) {
+        return Cow::Borrowed(s);
+    }
+
+    let mut escaped = String::with_capacity(s.len() + 8);
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// The XML declaration and header of the DAT, up to the first game.
+pub fn header(name: &str, description: &str, version: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\"?>\n\
+         <datafile>\n\
+         \t<header>\n\
+         \t\t<name>{}</name>\n\
+         \t\t<description>{}</description>\n\
+         \t\t<version>{}</version>\n\
+         \t\t<author>retronomicon</author>\n\
+         \t</header>\n",
+        escape(name),
+        escape(description),
+        escape(version),
+    )
+}
+
+/// A single `<game>` element, with all its ROMs.
+pub fn game(game: &dto::systems::SystemDatGame) -> String {
+    let mut out = String::new();
+
+    // Writing to a `String` cannot fail.
+    let _ = writeln!(
+        out,
+        "\t<game name=\"{}\" id=\"{}\">",
+        escape(&game.name),
+        game.system_unique_id
+    );
+    let _ = writeln!(
+        out,
+        "\t\t<description>{}</description>",
+        escape(&game.description)
+    );
+    if game.year != 0 {
+        let _ = writeln!(out, "\t\t<year>{}</year>", game.year);
+    }
+    for rom in &game.roms {
+        let _ = write!(
+            out,
+            "\t\t<rom name=\"{}\" size=\"{}\"",
+            escape(&rom.name),
+            rom.size
+        );
+        if let Some(crc32) = &rom.crc32 {
+            let _ = write!(out, " crc=\"{crc32}\"");
+        }
+        if let Some(md5) = &rom.md5 {
+            let _ = write!(out, " md5=\"{md5}\"");
+        }
+        if let Some(sha1) = &rom.sha1 {
+            let _ = write!(out, " sha1=\"{sha1}\"");
+        }
+        out.push_str("/>\n");
+    }
+    out.push_str("\t</game>\n");
+
+    out
+}
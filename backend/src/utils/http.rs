@@ -0,0 +1,50 @@
+//! Downloading of files stored outside of the server, e.g. artifacts that
+//! only have an external download URL.
+//!
+//! All downloads share a single client, and are bounded in time and in size
+//! so a slow or oversized remote file cannot tie up the server.
+use once_cell::sync::Lazy;
+use std::time::Duration;
+
+/// How long to wait for a download to complete, including its body.
+pub const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The largest body a download can have, in bytes. 64 Megabytes, which is
+/// more than any artifact upload can be.
+pub const MAX_DOWNLOAD_BYTES: usize = 64 * 1024 * 1024;
+
+static CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(DOWNLOAD_TIMEOUT)
+        .build()
+        .expect("Could not create the HTTP client")
+});
+
+/// Download the content at `url`. Fails if the server answers with an error
+/// status, or if the content is larger than `max_bytes`.
+pub async fn download(url: &str, max_bytes: usize) -> Result<Vec<u8>, String> {
+    let mut response = CLIENT
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| e.to_string())?;
+
+    let too_large = || format!("Content of {url} is larger than {max_bytes} bytes");
+    if response
+        .content_length()
+        .is_some_and(|len| len > max_bytes as u64)
+    {
+        return Err(too_large());
+    }
+
+    // The content length can be missing or wrong, so count while reading.
+    let mut data = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+        if data.len() + chunk.len() > max_bytes {
+            return Err(too_large());
+        }
+        data.extend_from_slice(&chunk);
+    }
+    Ok(data)
+}
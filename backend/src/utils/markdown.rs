@@ -0,0 +1,78 @@
+use lru::LruCache;
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag};
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+
+const RELEASE_NOTES_CACHE_SIZE: usize = 100;
+
+/// URL schemes that can run code in the browser.
+const UNSAFE_SCHEMES: &[&str] = &["javascript:", "vbscript:", "file:", "data:"];
+
+fn is_url_safe(url: &str) -> bool {
+    let url = url.trim_start().to_lowercase();
+    !UNSAFE_SCHEMES.iter().any(|s| url.starts_with(s))
+}
+
+fn sanitize_url(url: CowStr<'_>) -> CowStr<'_> {
+    if is_url_safe(&url) {
+        url
+    } else {
+        CowStr::Borrowed("")
+    }
+}
+
+/// Render Markdown to HTML that is safe to embed in a page. Raw HTML is
+/// escaped, and links or images with a scheme that can run code are emptied.
+pub fn render_safe(markdown: &str) -> String {
+    let parser = Parser::new_ext(markdown, Options::all()).map(|event| match event {
+        Event::Html(html) | Event::InlineHtml(html) => Event::Text(html),
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: sanitize_url(dest_url),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: sanitize_url(dest_url),
+            title,
+            id,
+        }),
+        event => event,
+    });
+
+    let mut output = String::new();
+    html::push_html(&mut output, parser);
+    output
+}
+
+/// Rendered release notes, keyed by release ID. Release notes cannot be
+/// edited, so entries never need to be invalidated.
+pub struct ReleaseNotesCache(Mutex<LruCache<i32, String>>);
+
+impl Default for ReleaseNotesCache {
+    fn default() -> Self {
+        Self(Mutex::new(LruCache::new(
+            NonZeroUsize::new(RELEASE_NOTES_CACHE_SIZE).unwrap(),
+        )))
+    }
+}
+
+impl ReleaseNotesCache {
+    pub fn get_or_render(&self, release_id: i32, notes: &str) -> String {
+        let mut cache = self.0.lock().unwrap();
+        cache
+            .get_or_insert(release_id, || render_safe(notes))
+            .clone()
+    }
+}
@@ -0,0 +1,135 @@
+//! Writing of zip archives whose entries are stored without compression.
+//!
+//! The archive is written one entry at a time so it can be streamed to the
+//! client while the next entry is being read. Core artifacts are usually
+//! compressed already, so deflating them would cost time for little gain.
+use chrono::{Datelike, NaiveDateTime, Timelike};
+use retronomicon_db::models;
+
+const LOCAL_FILE_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const CENTRAL_DIRECTORY_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+
+/// Version 1.0 of the format, which is enough for stored entries.
+const VERSION: u16 = 10;
+
+/// The general purpose flag for entry names encoded in UTF-8.
+const FLAG_UTF8: u16 = 1 << 11;
+
+struct Entry {
+    name: String,
+    crc32: u32,
+    size: u32,
+    time: u16,
+    date: u16,
+    offset: u32,
+}
+
+/// Builds a zip archive. Send the result of [`ZipWriter::start_entry`]
+/// followed by the data of each entry, then the result of
+/// [`ZipWriter::finish`].
+#[derive(Default)]
+pub struct ZipWriter {
+    entries: Vec<Entry>,
+    offset: u64,
+}
+
+/// The time and date of a timestamp in the MS-DOS format used by zip files,
+/// which cannot represent years before 1980.
+fn dos_time_and_date(timestamp: NaiveDateTime) -> (u16, u16) {
+    let time = (timestamp.hour() << 11) | (timestamp.minute() << 5) | (timestamp.second() / 2);
+    let year = timestamp.year().clamp(1980, 2107) as u32 - 1980;
+    let date = (year << 9) | (timestamp.month() << 5) | timestamp.day();
+    (time as u16, date as u16)
+}
+
+impl ZipWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an entry, returning its header. The data of the entry must be sent
+    /// right after it.
+    pub fn start_entry(
+        &mut self,
+        name: &str,
+        modified: NaiveDateTime,
+        data: &[u8],
+    ) -> Result<Vec<u8>, String> {
+        let offset = u32::try_from(self.offset).map_err(|_| "Archive is too large")?;
+        let size = u32::try_from(data.len()).map_err(|_| format!("{name} is too large"))?;
+        let name_len = u16::try_from(name.len()).map_err(|_| format!("{name} is too long"))?;
+        if self.entries.len() >= u16::MAX as usize {
+            return Err("Too many entries".to_string());
+        }
+
+        let crc32 = models::artifact::crc32(data);
+        let crc32 = u32::from_be_bytes([crc32[0], crc32[1], crc32[2], crc32[3]]);
+        let (time, date) = dos_time_and_date(modified);
+
+        let mut header = Vec::with_capacity(30 + name.len());
+        header.extend_from_slice(&LOCAL_FILE_HEADER_SIGNATURE.to_le_bytes());
+        header.extend_from_slice(&VERSION.to_le_bytes());
+        header.extend_from_slice(&FLAG_UTF8.to_le_bytes());
+        // Stored, without compression.
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(&time.to_le_bytes());
+        header.extend_from_slice(&date.to_le_bytes());
+        header.extend_from_slice(&crc32.to_le_bytes());
+        header.extend_from_slice(&size.to_le_bytes());
+        header.extend_from_slice(&size.to_le_bytes());
+        header.extend_from_slice(&name_len.to_le_bytes());
+        // No extra field.
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(name.as_bytes());
+
+        self.offset += header.len() as u64 + u64::from(size);
+        self.entries.push(Entry {
+            name: name.to_string(),
+            crc32,
+            size,
+            time,
+            date,
+            offset,
+        });
+        Ok(header)
+    }
+
+    /// The central directory of the archive, to send after the last entry.
+    pub fn finish(self) -> Result<Vec<u8>, String> {
+        let directory_offset = u32::try_from(self.offset).map_err(|_| "Archive is too large")?;
+
+        let mut directory = Vec::new();
+        for entry in &self.entries {
+            directory.extend_from_slice(&CENTRAL_DIRECTORY_HEADER_SIGNATURE.to_le_bytes());
+            // Made by and needed to extract.
+            directory.extend_from_slice(&VERSION.to_le_bytes());
+            directory.extend_from_slice(&VERSION.to_le_bytes());
+            directory.extend_from_slice(&FLAG_UTF8.to_le_bytes());
+            directory.extend_from_slice(&0u16.to_le_bytes());
+            directory.extend_from_slice(&entry.time.to_le_bytes());
+            directory.extend_from_slice(&entry.date.to_le_bytes());
+            directory.extend_from_slice(&entry.crc32.to_le_bytes());
+            directory.extend_from_slice(&entry.size.to_le_bytes());
+            directory.extend_from_slice(&entry.size.to_le_bytes());
+            directory.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+            // No extra field, comment, disk number nor attributes.
+            directory.extend_from_slice(&[0; 12]);
+            directory.extend_from_slice(&entry.offset.to_le_bytes());
+            directory.extend_from_slice(entry.name.as_bytes());
+        }
+
+        let directory_size = directory.len() as u32;
+        let count = self.entries.len() as u16;
+        directory.extend_from_slice(&END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+        // A single disk.
+        directory.extend_from_slice(&[0; 4]);
+        directory.extend_from_slice(&count.to_le_bytes());
+        directory.extend_from_slice(&count.to_le_bytes());
+        directory.extend_from_slice(&directory_size.to_le_bytes());
+        directory.extend_from_slice(&directory_offset.to_le_bytes());
+        // No comment.
+        directory.extend_from_slice(&0u16.to_le_bytes());
+        Ok(directory)
+    }
+}
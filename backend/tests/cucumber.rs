@@ -1,10 +1,16 @@
 use crate::ghenkins::UserParam;
 use crate::user::User as CucumberUser;
 use anyhow::{anyhow, Error};
-use backend::fairings::config::{DbPepper, JwtKeys, RetronomiconConfig};
+use backend::fairings::config::{DbPepper, JwtKeys, RetronomiconConfig, StartTime};
+use backend::fairings::csp::CspFairing;
+use backend::fairings::metrics::DbPoolMetrics;
+use backend::fairings::request_id::RequestIdFairing;
+use backend::fairings::version::VersionFairing;
 use backend::routes::v1;
+use backend::utils::markdown::ReleaseNotesCache;
 use backend::{config, routes};
 use cucumber::{writer, World as _};
+use rand::Rng;
 use retronomicon_db as db;
 use retronomicon_dto as dto;
 use rocket::fairing::AdHoc;
@@ -28,8 +34,25 @@ struct World {
     users: BTreeMap<String, Arc<Mutex<CucumberUser>>>,
     teams: BTreeMap<String, dto::teams::TeamCreateResponse>,
 
+    pub dats: BTreeMap<String, String>,
+    pub cores: BTreeMap<String, i32>,
+    pub releases: BTreeMap<(String, String), i32>,
+    pub artifacts: BTreeMap<String, i32>,
     pub games: BTreeMap<String, i32>,
+    pub platforms: BTreeMap<String, i32>,
     pub systems: BTreeMap<String, i32>,
+    pub system_releases: BTreeMap<String, i32>,
+    /// The slugs of the cores, systems and platforms created, by name.
+    pub resource_slugs: BTreeMap<String, String>,
+    pub tags: BTreeMap<String, String>,
+    pub reset_tokens: BTreeMap<String, String>,
+    pub login_tokens: BTreeMap<String, String>,
+    slugs: BTreeMap<String, String>,
+
+    db_url: Url,
+    /// Whether [`World::fail_team_join`] installed its trigger, which must be
+    /// dropped after the scenario.
+    team_join_trigger: bool,
 
     last_result: Option<Result<String, Error>>,
 }
@@ -83,6 +106,350 @@ impl World {
         Ok(self.teams.get(name).expect("Just created team").clone())
     }
 
+    /// Returns a slug unique to this test run for a name used in a scenario. The
+    /// same name will always return the same slug in a scenario.
+    pub fn slug(&mut self, name: &str) -> String {
+        self.slugs
+            .entry(name.to_string())
+            .or_insert_with(|| {
+                let suffix = rand::thread_rng()
+                    .sample_iter(&rand::distributions::Alphanumeric)
+                    .take(8)
+                    .map(char::from)
+                    .collect::<String>()
+                    .to_lowercase();
+                format!("{name}-{suffix}")
+            })
+            .clone()
+    }
+
+    /// Open a direct connection to the database. This is used to inspect values that
+    /// are only sent by email (e.g. tokens).
+    pub async fn db(&self) -> Result<tokio_postgres::Client, Error> {
+        let (client, connection) =
+            tokio_postgres::connect(self.db_url.as_str(), tokio_postgres::NoTls).await?;
+        tokio::spawn(connection);
+        Ok(client)
+    }
+
+    pub async fn reset_token(&self, email: &str) -> Result<Option<String>, Error> {
+        let row = self
+            .db()
+            .await?
+            .query_one(
+                "SELECT reset_token FROM user_passwords \
+                    INNER JOIN users ON users.id = user_passwords.user_id \
+                    WHERE users.email = $1",
+                &[&email],
+            )
+            .await?;
+        Ok(row.get(0))
+    }
+
+    pub async fn validation_token(&self, email: &str) -> Result<Option<String>, Error> {
+        let row = self
+            .db()
+            .await?
+            .query_one(
+                "SELECT validation_token FROM user_passwords \
+                    INNER JOIN users ON users.id = user_passwords.user_id \
+                    WHERE users.email = $1",
+                &[&email],
+            )
+            .await?;
+        Ok(row.get(0))
+    }
+
+    /// Link an OAuth provider to a user, as if they logged in with it.
+    pub async fn link_provider(
+        &self,
+        user_id: i32,
+        provider: &str,
+        external_id: &str,
+    ) -> Result<(), Error> {
+        self.db()
+            .await?
+            .execute(
+                "INSERT INTO user_auth_providers (user_id, provider, external_id) VALUES ($1, $2, $3)",
+                &[&user_id, &provider, &external_id],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Add a user to the root team directly, bypassing the checks of the
+    /// invite routes. If `invited` is set, the user only has a pending
+    /// invitation (from themselves).
+    pub async fn join_root_team(
+        &self,
+        user_id: i32,
+        role: dto::types::UserTeamRole,
+        invited: bool,
+    ) -> Result<(), Error> {
+        let invite_from = invited.then_some(user_id);
+        self.db()
+            .await?
+            .execute(
+                "INSERT INTO user_teams (team_id, user_id, role, invite_from) \
+                    VALUES (1, $1, $2::text::user_team_role, $3)",
+                &[&user_id, &role.to_string(), &invite_from],
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_password(&self, user_id: i32) -> Result<(), Error> {
+        self.db()
+            .await?
+            .execute("DELETE FROM user_passwords WHERE user_id = $1", &[&user_id])
+            .await?;
+        Ok(())
+    }
+
+    pub async fn expire_reset_token(&self, email: &str) -> Result<(), Error> {
+        self.db()
+            .await?
+            .execute(
+                "UPDATE user_passwords SET reset_requested_at = reset_requested_at - INTERVAL '1 day' \
+                    FROM users WHERE users.id = user_passwords.user_id AND users.email = $1",
+                &[&email],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Make the password entry of a user look like it was last updated some
+    /// days ago, so its validation token is old enough to expire.
+    pub async fn age_password(&self, email: &str, days: i32) -> Result<(), Error> {
+        self.db()
+            .await?
+            .execute(
+                "UPDATE user_passwords SET updated_at = updated_at - make_interval(days => $2) \
+                    FROM users WHERE users.id = user_passwords.user_id AND users.email = $1",
+                &[&email, &days],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Tag a core. There is no endpoint for this yet.
+    pub async fn tag_core(&self, tag_slug: &str, core_id: i32) -> Result<(), Error> {
+        self.db()
+            .await?
+            .execute(
+                "INSERT INTO core_tags (core_id, tag_id) SELECT $2, id FROM tags WHERE slug = $1",
+                &[&tag_slug, &core_id],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Tag a game. There is no endpoint for this yet.
+    pub async fn tag_game(&self, tag_slug: &str, game_id: i32) -> Result<(), Error> {
+        self.db()
+            .await?
+            .execute(
+                "INSERT INTO game_tags (game_id, tag_id) SELECT $2, id FROM tags WHERE slug = $1",
+                &[&tag_slug, &game_id],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Store a JSON `null` as the links of a team, as older rows might have.
+    pub async fn set_team_links_null(&self, team_id: i32) -> Result<(), Error> {
+        self.db()
+            .await?
+            .execute(
+                "UPDATE teams SET links = 'null'::jsonb WHERE id = $1",
+                &[&team_id],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Make a team look like it was last updated some days ago.
+    pub async fn age_team(&self, team_id: i32, days: i32) -> Result<(), Error> {
+        self.db()
+            .await?
+            .execute(
+                "UPDATE teams SET updated_at = updated_at - make_interval(days => $2) WHERE id = $1",
+                &[&team_id, &days],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Refresh the statistics Postgres keeps on the games table, which are used
+    /// to estimate its size.
+    pub async fn analyze_games(&self) -> Result<(), Error> {
+        self.db().await?.execute("ANALYZE games", &[]).await?;
+        Ok(())
+    }
+
+    /// Overwrite the stored SHA256 of an artifact, as a corrupted storage would
+    /// make it mismatch its data.
+    pub async fn set_artifact_sha256(&self, artifact_id: i32, sha256: &[u8]) -> Result<(), Error> {
+        self.db()
+            .await?
+            .execute(
+                "UPDATE artifacts SET sha256 = $1 WHERE id = $2",
+                &[&sha256, &artifact_id],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Delete the data of an artifact, from the database and its download URL,
+    /// as a storage that lost it would.
+    pub async fn delete_artifact_data(&self, artifact_id: i32) -> Result<(), Error> {
+        let db = self.db().await?;
+        db.execute("DELETE FROM files WHERE id = $1", &[&artifact_id])
+            .await?;
+        db.execute(
+            "UPDATE artifacts SET download_url = NULL WHERE id = $1",
+            &[&artifact_id],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Rename the cores, games and systems left by previous runs whose name
+    /// contains `term`, so they do not crowd the results of a search. Systems
+    /// are also searched by slug, so their slug is changed too.
+    pub async fn retire_matching(&self, term: &str) -> Result<(), Error> {
+        let db = self.db().await?;
+        for query in [
+            "UPDATE cores SET name = 'retired_' || id WHERE name ILIKE '%' || $1 || '%'",
+            "UPDATE games SET name = 'retired_' || id WHERE name ILIKE '%' || $1 || '%'",
+            "UPDATE systems SET name = 'retired_' || id, slug = 'retired-' || id \
+             WHERE name ILIKE '%' || $1 || '%' OR slug ILIKE '%' || $1 || '%'",
+        ] {
+            db.execute(query, &[&term]).await?;
+        }
+        Ok(())
+    }
+
+    /// Yank a core release. There is no endpoint for this yet.
+    pub async fn yank_release(&self, release_id: i32) -> Result<(), Error> {
+        self.db()
+            .await?
+            .execute(
+                "UPDATE core_releases SET yanked = true WHERE id = $1",
+                &[&release_id],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// The user who deleted a core, if it was deleted.
+    pub async fn core_deleted_by(&self, core_id: i32) -> Result<Option<i32>, Error> {
+        let row = self
+            .db()
+            .await?
+            .query_one("SELECT deleted_by_id FROM cores WHERE id = $1", &[&core_id])
+            .await?;
+        Ok(row.get(0))
+    }
+
+    /// Create a release of a system, uploaded by a member of its owner team.
+    /// There is no endpoint for this yet. Versions are unique across systems,
+    /// so a random suffix is added to the version.
+    pub async fn create_system_release(&self, system_id: i32, version: &str) -> Result<i32, Error> {
+        let version = format!("{version}-{}", rand::thread_rng().gen::<u32>());
+        let row = self
+            .db()
+            .await?
+            .query_one(
+                "INSERT INTO system_releases \
+                    (version, note, date_released, prerelease, yanked, links, metadata, \
+                     uploader_id, system_id) \
+                 SELECT $1, '', now(), false, false, '{}'::jsonb, '{}'::jsonb, \
+                    user_teams.user_id, systems.id \
+                 FROM systems \
+                 JOIN user_teams ON user_teams.team_id = systems.owner_team_id \
+                 WHERE systems.id = $2 \
+                 LIMIT 1 \
+                 RETURNING id",
+                &[&version, &system_id],
+            )
+            .await?;
+        Ok(row.get(0))
+    }
+
+    /// Move the data of an artifact into the database, as legacy artifacts were
+    /// stored, with the given MIME type.
+    pub async fn store_artifact_in_db(
+        &self,
+        artifact_id: i32,
+        data: &[u8],
+        mime_type: &str,
+    ) -> Result<(), Error> {
+        let db = self.db().await?;
+        db.execute(
+            "UPDATE artifacts SET download_url = NULL, mime_type = $1 WHERE id = $2",
+            &[&mime_type, &artifact_id],
+        )
+        .await?;
+        db.execute(
+            "INSERT INTO files (id, data) VALUES ($1, $2)",
+            &[&artifact_id, &data],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Make adding any member to the team with this slug fail in the database,
+    /// to check that the team creation is rolled back. The trigger is dropped
+    /// after the scenario (see [`World::teardown`]).
+    pub async fn fail_team_join(&mut self, slug: &str) -> Result<(), Error> {
+        self.team_join_trigger = true;
+        self.db()
+            .await?
+            .batch_execute(&format!(
+                r#"
+                CREATE OR REPLACE FUNCTION fail_team_join() RETURNS trigger AS $$
+                BEGIN
+                    IF EXISTS (SELECT 1 FROM teams WHERE id = NEW.team_id AND slug = '{slug}') THEN
+                        RAISE EXCEPTION 'Joining team {slug} is not allowed';
+                    END IF;
+                    RETURN NEW;
+                END
+                $$ LANGUAGE plpgsql;
+
+                DROP TRIGGER IF EXISTS fail_team_join ON user_teams;
+                CREATE TRIGGER fail_team_join BEFORE INSERT ON user_teams
+                    FOR EACH ROW EXECUTE FUNCTION fail_team_join();
+                "#
+            ))
+            .await?;
+        Ok(())
+    }
+
+    /// Undo the database changes of a scenario that would leak into the
+    /// following ones.
+    pub async fn teardown(&mut self) -> Result<(), Error> {
+        if std::mem::take(&mut self.team_join_trigger) {
+            self.db()
+                .await?
+                .batch_execute(
+                    "DROP TRIGGER IF EXISTS fail_team_join ON user_teams; \
+                     DROP FUNCTION IF EXISTS fail_team_join();",
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn team_slug_exists(&self, slug: &str) -> Result<bool, Error> {
+        let row = self
+            .db()
+            .await?
+            .query_one("SELECT COUNT(*) FROM teams WHERE slug = $1", &[&slug])
+            .await?;
+        Ok(row.get::<_, i64>(0) > 0)
+    }
+
     async fn new() -> Self {
         // Relative to the root of the crate.
         let figment =
@@ -111,15 +478,28 @@ impl World {
             "Cucumber tests must run with a local database."
         );
 
+        let prometheus = rocket_prometheus::PrometheusMetrics::new();
         let rocket = rocket::custom(figment)
             .mount("/", v1::routes())
+            .mount("/metrics", prometheus.clone())
+            .mount(
+                "/static",
+                rocket::fs::FileServer::from(relative!("tests/static")),
+            )
             .attach(db::RetronomiconDbPool::init())
+            .attach(DbPoolMetrics::new(prometheus.registry()))
+            .attach(prometheus)
+            .attach(RequestIdFairing)
+            .attach(VersionFairing)
+            .attach(CspFairing)
             .attach(OAuth2::<routes::auth::GitHubUserInfo>::fairing("github"))
             .attach(OAuth2::<routes::auth::GoogleUserInfo>::fairing("google"))
             .attach(OAuth2::<routes::auth::PatreonUserInfo>::fairing("patreon"))
             .attach(AdHoc::config::<RetronomiconConfig>())
             .manage(JwtKeys::from_base64(&jwt_secret_b64))
-            .manage(DbPepper::from_base64(&db_pepper));
+            .manage(DbPepper::from_base64(&db_pepper))
+            .manage(StartTime::now())
+            .manage(ReleaseNotesCache::default());
         let client = Arc::new(
             Client::untracked(rocket)
                 .await
@@ -131,8 +511,21 @@ impl World {
             admins: BTreeMap::new(),
             users: BTreeMap::new(),
             teams: BTreeMap::new(),
+            dats: BTreeMap::new(),
+            cores: BTreeMap::new(),
+            releases: BTreeMap::new(),
+            artifacts: BTreeMap::new(),
             games: BTreeMap::new(),
+            platforms: BTreeMap::new(),
             systems: BTreeMap::new(),
+            system_releases: BTreeMap::new(),
+            resource_slugs: BTreeMap::new(),
+            reset_tokens: BTreeMap::new(),
+            login_tokens: BTreeMap::new(),
+            tags: BTreeMap::new(),
+            slugs: BTreeMap::new(),
+            db_url,
+            team_join_trigger: false,
             last_result: None,
         }
     }
@@ -157,6 +550,48 @@ impl World {
         }
     }
 
+    pub fn assert_result_err_status(&mut self, status: u16) {
+        match self.last_result {
+            Some(Err(ref e)) => assert!(
+                e.to_string().contains(&format!("status: {status} ")),
+                "Expected status {status}, got: {e}"
+            ),
+            Some(Ok(ref e)) => panic!("Expected Err, got Ok: {}", e),
+            None => panic!("Expected Err, got nothing"),
+        }
+    }
+
+    pub fn assert_result_err_contains(&mut self, expected: &str) {
+        match self.last_result {
+            Some(Err(ref e)) => assert!(
+                e.to_string().contains(expected),
+                "Expected error with {expected:?}, got: {e}"
+            ),
+            Some(Ok(ref e)) => panic!("Expected Err, got Ok: {}", e),
+            None => panic!("Expected Err, got nothing"),
+        }
+    }
+
+    pub fn assert_result_err_not_contains(&mut self, unexpected: &str) {
+        match self.last_result {
+            Some(Err(ref e)) => assert!(
+                !e.to_string().contains(unexpected),
+                "Expected error without {unexpected:?}, got: {e}"
+            ),
+            Some(Ok(ref e)) => panic!("Expected Err, got Ok: {}", e),
+            None => panic!("Expected Err, got nothing"),
+        }
+    }
+
+    /// Deserialize the last successful result.
+    pub fn last_result<T: serde::de::DeserializeOwned>(&self) -> T {
+        match self.last_result {
+            Some(Ok(ref json)) => serde_json::from_str(json).expect("Invalid last result"),
+            Some(Err(ref e)) => panic!("Expected Ok, got Err: {}", e),
+            None => panic!("Expected Ok, got nothing"),
+        }
+    }
+
     pub fn reset_result(&mut self) {
         self.last_result = None;
     }
@@ -166,6 +601,13 @@ impl World {
 async fn main() {
     World::cucumber()
         .max_concurrent_scenarios(1)
+        .after(|_, _, _, _, world| {
+            Box::pin(async move {
+                if let Some(world) = world {
+                    world.teardown().await.expect("Teardown failed");
+                }
+            })
+        })
         .with_writer(writer::Libtest::or_basic())
         .run_and_exit("tests/features/")
         .await;
@@ -0,0 +1,199 @@
+Feature: Artifacts
+
+  Scenario: Unknown artifacts are not found
+    When anonymous user gets the details of artifact 999999
+    Then an error occured with status 404
+
+  Scenario: Root users can backfill the SHA1 of artifacts
+    When admin R backfills the SHA1 of artifacts
+    Then no error occured
+    When admin R backfills the SHA1 of 1000 artifacts
+    Then an error occured with status 400
+
+  Scenario: Only root users can backfill the SHA1 of artifacts
+    When user A backfills the SHA1 of artifacts
+    Then an error occured
+
+  Scenario: Can look up an artifact by its SHA256
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core C1 on platform P1 at 1700000000
+    And  user A uploads artifact F1 to version 1.0 of core C1
+    And  anonymous user looks up artifact F1 by its SHA256
+    Then artifact F1 was found
+    When anonymous user looks up artifact F2 by its SHA256
+    Then an error occured with status 404
+
+  Scenario: Artifacts that are not public cannot be looked up by their SHA256
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core C1 on platform P1 at 1700000000
+    And  user A uploads artifact F1 to version 1.0 of core C1
+    Given artifact F1 is stored in the database with mime type "application/octet-stream"
+    And   version 1.0 of core C1 is yanked
+    When anonymous user looks up artifact F1 by its SHA256
+    Then an error occured with status 404
+
+  Scenario: Viewers of the core's team can see the details of private artifacts
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core C1 on platform P1 at 1700000000
+    And  user A uploads artifact F1 to version 1.0 of core C1
+    And  user A invites user V to team T1 as viewer
+    And  user V accepts the invitation to team T1
+    Given artifact F1 is stored in the database with mime type "application/octet-stream"
+    And   version 1.0 of core C1 is yanked
+    When user V gets the details of artifact F1
+    Then no error occured
+    When user B gets the details of artifact F1
+    Then an error occured with status 404
+    When anonymous user gets the details of artifact F1
+    Then an error occured with status 404
+
+  Scenario: An existing artifact can be attached to another release
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core C1 on platform P1 at 1700000000
+    And  user A releases version 2.0 of core C1 on platform P1 at 1710000000
+    And  user A uploads artifact F1 to version 1.0 of core C1
+    And  user A attaches artifact F1 to version 2.0 of core C1
+    Then no error occured
+    When user A lists the artifacts of version 2.0 of core C1 for platform P1
+    Then the artifact list is "F1"
+    When user A attaches artifact F1 to version 2.0 of core C1
+    Then an error occured with status 409
+
+  Scenario: Only members of the core's team can attach artifacts
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core C1 on platform P1 at 1700000000
+    And  user A releases version 2.0 of core C1 on platform P1 at 1710000000
+    And  user A uploads artifact F1 to version 1.0 of core C1
+    And  user B attaches artifact F1 to version 2.0 of core C1
+    Then an error occured
+
+  Scenario: Downloading an externally stored artifact redirects to it
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core C1 on platform P1 at 1700000000
+    And  user A uploads artifact F1 to version 1.0 of core C1
+    And  anonymous user downloads artifact F1 of version 1.0 of core C1
+    Then the download is redirected to the artifact URL
+
+  Scenario: Only allowed file extensions can be uploaded as artifacts
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core C1 on platform P1 at 1700000000
+    And  user A uploads artifact F1 as "core.RBF" to version 1.0 of core C1
+    Then no error occured
+    When user A uploads artifact F2 as "notes.txt" to version 1.0 of core C1
+    Then an error occured with status 400
+    When user A uploads artifact F3 as "core" to version 1.0 of core C1
+    Then an error occured with status 400
+
+  Scenario: Artifact filenames cannot escape the release directory
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core C1 on platform P1 at 1700000000
+    And  user A uploads artifact F1 as "../evil.bin" to version 1.0 of core C1
+    Then an error occured with status 400
+    When user A uploads artifact F2 as "con.bin" to version 1.0 of core C1
+    Then an error occured with status 400
+
+  Scenario: Artifacts with a malformed stored mime type can be downloaded
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core C1 on platform P1 at 1700000000
+    And  user A uploads artifact F1 to version 1.0 of core C1
+    Given artifact F1 is stored in the database with mime type "not/a valid type"
+    When anonymous user downloads artifact F1 of version 1.0 of core C1
+    Then the download is served with content type "application/octet-stream"
+
+  Scenario: Release artifacts can be listed by platform
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a platform P2 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core C1 on platform P1 at 1700000000
+    And  user A uploads artifact F1 to version 1.0 of core C1
+    And  user A uploads artifact F2 for platform P2 to version 1.0 of core C1
+    And  user A uploads artifact F3 for platform P2 to version 1.0 of core C1
+    And  anonymous user lists the artifacts of version 1.0 of core C1 for platform P1
+    Then the artifact list is "F1"
+    When anonymous user lists the artifacts of version 1.0 of core C1 for platform P2
+    Then the artifact list is "F2, F3"
+
+  Scenario: Release artifacts can be listed by mime type
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core C1 on platform P1 at 1700000000
+    And  user A uploads artifact F1 to version 1.0 of core C1
+    And  user A uploads artifact F2 to version 1.0 of core C1
+    Given artifact F2 is stored in the database with mime type "application/x-bitstream"
+    When anonymous user lists the artifacts of version 1.0 of core C1 of type "APPLICATION/X-BITSTREAM"
+    Then the artifact list is "F2"
+    When anonymous user lists the artifacts of version 1.0 of core C1 of type "application/%"
+    Then the artifact list is ""
+
+  Scenario: The full release list nests the artifacts of each release
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core C1 on platform P1 at 1700000000
+    And  user A releases version 2.0 of core C1 on platform P1 at 1710000000
+    And  user A releases version 3.0 of core C1 on platform P1 at 1720000000
+    And  user A uploads artifact F1 to version 1.0 of core C1
+    And  user A uploads artifact F2 to version 1.0 of core C1
+    And  user A uploads artifact F3 to version 2.0 of core C1
+    And  anonymous user lists the full releases of core C1
+    Then the full release list is "3.0: ; 2.0: F3; 1.0: F1, F2"
+
+  Scenario: All the artifacts of a release can be downloaded as a zip
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core C1 on platform P1 at 1700000000
+    And  user A uploads artifact F1 as "core.rbf" to version 1.0 of core C1
+    And  user A uploads artifact F2 as "core.bin" to version 1.0 of core C1
+    And  anonymous user downloads all the artifacts of version 1.0 of core C1
+    Then the zip contains "core.bin, core.rbf"
+
+  Scenario: Uploading artifacts with the same filename fails by default
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core C1 on platform P1 at 1700000000
+    And  user A uploads artifacts "F1, F2" as "core.bin" to version 1.0 of core C1 with conflict mode error
+    Then an error occured with status 409
+
+  Scenario: Artifacts with the same filename can be renamed on upload
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core C1 on platform P1 at 1700000000
+    And  user A uploads artifacts "F1, F2, F3" as "core.bin" to version 1.0 of core C1 with conflict mode rename
+    Then the uploaded artifacts are named "core.bin, core (2).bin, core (3).bin"
+
+  Scenario: Team admins can list the artifact filenames of a core
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core C1 on platform P1 at 1700000000
+    And  user A releases version 2.0 of core C1 on platform P1 at 1710000000
+    And  user A uploads artifact F2 to version 1.0 of core C1
+    And  user A uploads artifact F1 to version 1.0 of core C1
+    And  user A uploads artifact F3 as "F1.bin" to version 2.0 of core C1
+    And  user A lists the artifact filenames of core C1
+    Then the artifact filenames are "F1.bin, F2.bin"
+    When user B lists the artifact filenames of core C1
+    Then an error occured with status 403
@@ -0,0 +1,35 @@
+Feature: Auth Providers
+
+  Scenario: User can list their linked providers
+    Given user A
+      And user A has linked provider github
+      And user A has linked provider google
+    Then user A will have linked providers "github, google"
+
+  Scenario: User can unlink a provider
+    Given user A
+      And user A has linked provider github
+      And user A has linked provider google
+    When user A unlinks provider github
+    Then no error occured
+     And user A will have linked providers "google"
+
+  Scenario: User with a password can unlink their last provider
+    Given user A
+      And user A has linked provider github
+    When user A unlinks provider github
+    Then no error occured
+     And user A will have linked providers ""
+
+  Scenario: User without a password cannot unlink their last provider
+    Given user A
+      And user A has linked provider github
+      And user A has no password
+    When user A unlinks provider github
+    Then an error occured with status 403
+     And user A will have linked providers "github"
+
+  Scenario: Cannot unlink a provider that is not linked
+    Given user A
+    When user A unlinks provider github
+    Then an error occured with status 404
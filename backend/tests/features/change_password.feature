@@ -0,0 +1,18 @@
+Feature: Change Password
+
+  Scenario: User can change their password
+    Given user A
+    When user A changes their password to "new-Password-1234"
+    Then no error occured
+     And user A can login with password "new-Password-1234"
+     And user A cannot login with their old password
+
+  Scenario: Cannot change password with an incorrect current password
+    Given user A
+    When user A changes their password from "wrong-Password-1234" to "new-Password-1234"
+    Then an error occured
+     And user A can login with their old password
+
+  Scenario: Anonymous user cannot change password
+    When anonymous user changes their password from "old-Password-1234" to "new-Password-1234"
+    Then an error occured
@@ -0,0 +1,265 @@
+Feature: Cores
+
+  Scenario: Core details show the latest release of each platform
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a platform P2 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core C1 on platform P1 at 1700000000
+    And  user A releases version 2.0 of core C1 on platform P1 at 1710000000
+    And  user A releases version 1.5 of core C1 on platform P2 at 1705000000
+    Then the latest releases of core C1 are 'P1 2.0, P2 1.5'
+
+  Scenario: Core release metadata must follow the platform's metadata schema
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 with metadata schema '{"required": ["storage"], "properties": {"storage": "string", "fps": "integer"}}' created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core C1 on platform P1 with metadata '{"fps": 60}'
+    Then an error occured with status 400
+    When user A releases version 1.0 of core C1 on platform P1 with metadata '{"storage": "sd", "fps": 59.94}'
+    Then an error occured with status 400
+    When user A releases version 1.0 of core C1 on platform P1 with metadata '{"storage": "sd", "fps": 60}'
+    Then the latest releases of core C1 are 'P1 1.0'
+
+  Scenario: Can get a single artifact of a core release
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core C1 on platform P1 at 1700000000
+    And  user A releases version 2.0 of core C1 on platform P1 at 1710000000
+    And  user A uploads artifact F1 to version 1.0 of core C1
+    And  user A uploads artifact F2 to version 2.0 of core C1
+    And  anonymous user gets artifact F1 of version 1.0 of core C1
+    Then the artifact matches artifact F1 in the list of version 1.0 of core C1
+    When anonymous user gets artifact F2 of version 1.0 of core C1
+    Then an error occured with status 404
+
+  Scenario: Team admins can verify the integrity of an artifact
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core C1 on platform P1 at 1700000000
+    And  user A uploads artifact F1 to version 1.0 of core C1
+    And  user A verifies artifact F1 of version 1.0 of core C1
+    Then the artifact is valid
+    Given the stored checksum of artifact F1 is corrupted
+    When user A verifies artifact F1 of version 1.0 of core C1
+    Then the artifact is not valid
+    When user B verifies artifact F1 of version 1.0 of core C1
+    Then an error occured with status 403
+
+  Scenario: Root admins can verify all the artifacts of a release
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core C1 on platform P1 at 1700000000
+    And  user A uploads artifact F1 to version 1.0 of core C1
+    And  user A uploads artifact F2 to version 1.0 of core C1
+    Given the stored checksum of artifact F1 is corrupted
+    When admin R verifies all artifacts of version 1.0 of core C1
+    Then artifact F1 is invalid in the verification
+    And  artifact F2 is valid in the verification
+    Given the data of artifact F2 is lost
+    When admin R verifies all artifacts of version 1.0 of core C1
+    Then artifact F1 is invalid in the verification
+    And  artifact F2 is unreadable in the verification
+    When user A verifies all artifacts of version 1.0 of core C1
+    Then an error occured
+
+  Scenario: Cores can be filtered by a part of their name
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core Spectrumzx on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core Spectrumzx on platform P1 at 1700000000
+    And  anonymous user lists cores with a name containing "ECTRUMZ"
+    Then core Spectrumzx is in the list
+    When anonymous user lists cores with a name containing "SPEC%ZX"
+    Then core Spectrumzx is not in the list
+
+  Scenario: Cores can be searched by a part of their name or slug
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core Spectrumzx on system S1 created by user A owned by team T1
+    And   a core Colecovision on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core Spectrumzx on platform P1 at 1700000000
+    And  user A releases version 1.0 of core Colecovision on platform P1 at 1700000000
+    And  anonymous user lists cores matching "trumz"
+    Then core Spectrumzx is in the list
+    And  core Colecovision is not in the list
+    When anonymous user lists cores matching "spec%zx"
+    Then core Spectrumzx is not in the list
+
+  Scenario: Cores can be searched with an approximate name
+    Given a system S1 created by user A owned by team T1
+    And   a core Spectrumzx on system S1 created by user A owned by team T1
+    And   a core Colecovision on system S1 created by user A owned by team T1
+    When anonymous user searches cores for the name of core Spectrumzx with "zx" replaced by "sx"
+    Then core Spectrumzx is in the search results
+    And  core Colecovision is not in the search results
+
+  Scenario: Release notes are rendered to sanitized HTML
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core C1 on platform P1 with notes "# Changes\n\n* **Faster** <script>alert(1)</script> [click](javascript:alert(1))"
+    And  anonymous user gets the notes of version 1.0 of core C1 as HTML
+    Then the HTML contains "<h1>Changes</h1>"
+    And  the HTML contains "<strong>Faster</strong>"
+    And  the HTML contains "&lt;script&gt;"
+    And  the HTML does not contain "<script>"
+    And  the HTML does not contain "javascript:"
+
+  Scenario: Releases of all cores are listed most recent first
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    And   a core C2 on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core C1 on platform P1 at 1700000000
+    And  user A releases version 1.0 of core C2 on platform P1 at 1705000000
+    And  user A releases version 2.0 of core C1 on platform P1 at 1710000000
+    And  anonymous user lists the releases of system S1
+    Then the release list is "C1 2.0, C2 1.0, C1 1.0"
+
+  Scenario: Releases cannot be dated in the future
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core C1 on platform P1 dated 48 hours from now
+    Then an error occured with status 400
+    When user A releases version 1.0 of core C1 on platform P1 at 253402300799
+    Then an error occured with status 400
+    When user A releases version 1.0 of core C1 on platform P1 dated 1 hours from now
+    And  anonymous user lists the releases of system S1
+    Then the release list is "C1 1.0"
+    When user A releases version 1.1 of core C1 on platform P1 dated -24 hours from now
+    And  anonymous user lists the releases of system S1
+    Then the release list is "C1 1.0, C1 1.1"
+
+  Scenario: Releases can require a release of their core's system
+    Given a system S1 created by user A owned by team T1
+    And   a system S2 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    And   a system release R1 of system S1
+    And   a system release R2 of system S2
+    When user A releases version 1.0 of core C1 on platform P1 at 1700000000
+    And  user A releases version 2.0 of core C1 on platform P1 requiring system release R1
+    And  anonymous user lists the releases of system S1
+    Then the required system releases are "2.0: R1, 1.0: none"
+    When user A releases version 3.0 of core C1 on platform P1 requiring system release R2
+    Then an error occured with status 404
+
+  Scenario: Cores and releases can be sorted by name and creation date
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core Beta on system S1 created by user A owned by team T1
+    And   a core Alpha on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core Alpha on platform P1 at 1710000000
+    And  user A releases version 1.0 of core Beta on platform P1 at 1700000000
+    And  anonymous user lists the cores of system S1 sorted by "name"
+    Then the core list is "Alpha, Beta"
+    When anonymous user lists the cores of system S1 sorted by "-created"
+    Then the core list is "Alpha, Beta"
+    When anonymous user lists the cores of system S1 sorted by "created"
+    Then the core list is "Beta, Alpha"
+    When anonymous user lists the releases of system S1 sorted by "-name"
+    Then the release list is "Beta 1.0, Alpha 1.0"
+    When anonymous user lists the releases of system S1 sorted by "created"
+    Then the release list is "Alpha 1.0, Beta 1.0"
+    When anonymous user lists the cores of system S1 sorted by "-id"
+    Then an error occured with status 400
+
+  Scenario: Releases of all cores can be filtered by core slug prefix
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core Alpha on system S1 created by user A owned by team T1
+    And   a core Beta on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core Alpha on platform P1 at 1700000000
+    And  user A releases version 1.0 of core Beta on platform P1 at 1710000000
+    And  anonymous user lists the releases of system S1 with core slug "slug-alph"
+    Then the release list is "Alpha 1.0"
+    When anonymous user lists the releases of system S1 with core slug "slug-a%"
+    Then the release list is ""
+
+  Scenario: Cores can be listed with the platforms they have releases for
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a platform P2 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    And   a core C2 on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core C1 on platform P1 at 1700000000
+    And  user A releases version 1.1 of core C1 on platform P1 at 1701000000
+    And  user A releases version 1.0 of core C1 on platform P2 at 1702000000
+    And  user A releases version 1.0 of core C2 on platform P2 at 1703000000
+    And  user A lists the cores of system S1 with their platforms
+    Then core C1 in the list has platforms "P1, P2"
+    And  core C2 in the list has platforms "P2"
+
+  Scenario: The changelog of a core lists its releases, most recent first
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a platform P2 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core C1 on platform P1 at 1700000000
+    And  user A releases version 1.1 of core C1 on platform P2 at 1701000000
+    And  user A releases version 2.0 of core C1 on platform P1 at 1710000000
+    And  anonymous user gets the changelog of core C1
+    Then the changelog is "2.0, 1.1, 1.0"
+    When anonymous user gets the changelog of core C1 for platform P1
+    Then the changelog is "2.0, 1.0"
+    When anonymous user gets the changelog of core C1 in Markdown
+    Then the Markdown contains "## 1.0 ("
+    And  the Markdown contains ") - 2023-11-14"
+
+  Scenario: Cores released recently can be listed, most recent first
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    And   a core C2 on system S1 created by user A owned by team T1
+    And   a core C3 on system S1 created by user A owned by team T1
+    When user A releases version 1.0 of core C1 on platform P1 dated -48 hours from now
+    And  user A releases version 1.1 of core C1 on platform P1 dated -1 hours from now
+    And  user A releases version 1.0 of core C2 on platform P1 dated -24 hours from now
+    And  user A releases version 1.0 of core C3 on platform P1 dated -200 hours from now
+    And  anonymous user lists the cores released in the last 72 hours
+    Then the recently released cores are "C1 1.1, C2 1.0"
+
+  Scenario: Retrying the creation of a release returns the existing one
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    When user A retries releasing version 1.0 of core C1 on platform P1 with notes "Initial release"
+    Then no error occured
+    When user A retries releasing version 1.0 of core C1 on platform P1 with notes "Initial release"
+    Then the release is version 1.0 of core C1
+    When user A retries releasing version 1.0 of core C1 on platform P1 with notes "Other notes"
+    Then an error occured with status 409
+    When user A releases version 1.0 of core C1 on platform P1 at 1700000000
+    Then an error occured with status 409
+
+  Scenario: Root admins can merge duplicate cores
+    Given a system S1 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    And   a core C2 on system S1 created by user A owned by team T1
+    And   a tag Arcade created by admin R
+    And   core C2 is tagged Arcade
+    When user A releases version 1.0 of core C1 on platform P1 at 1700000000
+    And  user A releases version 2.0 of core C2 on platform P1 at 1710000000
+    And  user A merges core C2 into core C1
+    Then an error occured
+    When admin R merges core C2 into core C1
+    Then 1 release and 1 tag were moved
+    And  the latest releases of core C1 are 'P1 2.0'
+    And  core C2 was deleted by admin R
+    And  core C2 redirects to core C1
+    When admin R merges core C2 into core C1
+    Then an error occured with status 404
+
+  Scenario: Cores of different systems cannot be merged
+    Given a system S1 created by user A owned by team T1
+    And   a system S2 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    And   a core C2 on system S2 created by user A owned by team T1
+    When admin R merges core C2 into core C1
+    Then an error occured with status 400
@@ -0,0 +1,29 @@
+Feature: DAT import and export roundtrip
+
+  Scenario: Importing a DAT requires root team
+    Given a system S1 created by user U1 owned by team T1
+    And a DAT D1 with 2 games
+    When user U1 imports DAT D1 into system S1
+    Then an error occured
+
+  Scenario: Games imported from a DAT are exported with the same ROMs
+    Given a system S1 created by user U1 owned by team T1
+    And a DAT D1 with 3 games
+    When admin A1 imports DAT D1 into system S1
+    Then no error occured
+    And the games DAT of system S1 matches DAT D1
+
+  Scenario: Importing the same DAT twice does not duplicate games
+    Given a system S1 created by user U1 owned by team T1
+    And a DAT D1 with 3 games
+    When admin A1 imports DAT D1 into system S1
+    And admin A1 imports DAT D1 into system S1
+    Then no error occured
+    And the games DAT of system S1 matches DAT D1
+
+  Scenario: Games imported from a DAT are exported as an XML DAT
+    Given a system S1 created by user U1 owned by team T1
+    And a DAT D1 with 3 games
+    When admin A1 imports DAT D1 into system S1
+    Then no error occured
+    And the XML games DAT of system S1 matches DAT D1
@@ -0,0 +1,59 @@
+Feature: Email Login
+
+  Scenario: User can login using a link sent by email
+    Given user A
+    When user A requests a login link by email
+     And user A logs in with the token received by email
+    Then no error occured
+     And user A can login with their old password
+
+  Scenario: Cannot login by email with an invalid token
+    Given user A
+    When user A requests a login link by email
+     And user A logs in with the email token "invalid-token"
+    Then an error occured
+
+  Scenario: An email login token can only be used once
+    Given user A
+    When user A requests a login link by email
+     And user A logs in with the token received by email
+    Then no error occured
+    When user A logs in again with the same token
+    Then an error occured with status 404
+
+  Scenario: An expired email login token is refused
+    Given user A
+    When user A requests a login link by email
+     And the email token of user A was sent 2 days ago
+     And user A logs in with the token received by email
+    Then an error occured with status 404
+
+  Scenario: Email login requests are rate limited
+    Given user A
+    When user A requests a login link by email
+    Then no error occured
+    When user A requests a login link by email
+    Then an error occured with status 429
+
+  Scenario: A new login link can be requested once the cooldown is over
+    Given user A
+    When user A requests a login link by email
+     And the email token of user A was sent 2 days ago
+     And user A requests a login link by email
+    Then no error occured
+
+  Scenario: Old email tokens are expired
+    Given user A
+    And   user B
+    When user A requests a login link by email
+     And user B requests a login link by email
+     And the email token of user A was sent 2 days ago
+     And admin R expires old tokens
+    Then no error occured
+     And user A has no email token
+     And user B has an email token
+
+  Scenario: Only root users can expire old tokens
+    Given user A
+    When user A expires old tokens
+    Then an error occured
@@ -5,8 +5,141 @@ Feature: Games
     When user U1 creates a game G1 on system S1
     Then an error occured
 
+  Scenario: Viewers of the root team cannot create games
+    Given a system S1 created by user U1 owned by team T1
+    And   user V1 is a viewer of the root team
+    When user V1 creates a game G1 on system S1
+    Then an error occured
+
+  Scenario: Users only invited to the root team cannot create games
+    Given a system S1 created by user U1 owned by team T1
+    And   user V1 is invited to the root team as owner
+    When user V1 creates a game G1 on system S1
+    Then an error occured
+
+  Scenario: Members of the root team can create games
+    Given a system S1 created by user U1 owned by team T1
+    And   user M1 is a member of the root team
+    When user M1 creates a game G1 on system S1
+    Then no error occured
+
   Scenario: Can add a game to a system
     Given a system S1 created by user U1 owned by team T1
     When admin A1 creates a game G1 on system S1
     Then no error occured
     And game G1 exists on system S1
+
+  Scenario: Game unique IDs are unique within a system
+    Given a system S1 created by user U1 owned by team T1
+    And a system S2 created by user U1 owned by team T1
+    When admin A1 creates a game G1 with unique id 12 on system S1
+    Then game G1 has unique id 12
+    When admin A1 creates a game G2 with unique id 12 on system S1
+    Then an error occured with status 409
+    When admin A1 creates a game G3 with unique id 12 on system S2
+    Then game G3 has unique id 12
+    When admin A1 creates a game G4 without unique id on system S1
+    Then game G4 has unique id 13
+
+  Scenario: Can search games by exact name and checksums together
+    Given a system S1 created by user U1 owned by team T1
+    And a DAT D1 with 2 games
+    When admin A1 imports DAT D1 into system S1
+    Then searching system S1 for game 0 of DAT D1 with the md5 of game 0 finds 1 game
+    And  searching system S1 for game 0 of DAT D1 with the md5 of game 1 finds 0 games
+    And  searching system S1 for game 1 of DAT D1 with the md5 of game 1 finds 1 game
+
+  Scenario: Games can be sorted by name and creation date
+    Given a system S1 created by user U1 owned by team T1
+    When admin A1 creates a game Beta on system S1
+    And  admin A1 creates a game Alpha on system S1
+    And  admin A1 creates a game Gamma on system S1
+    And  anonymous user lists the games of system S1 sorted by "name"
+    Then the game list is "Alpha, Beta, Gamma"
+    When anonymous user lists the games of system S1 sorted by "-name"
+    Then the game list is "Gamma, Beta, Alpha"
+    When anonymous user lists the games of system S1 sorted by "created"
+    Then the game list is "Beta, Alpha, Gamma"
+    When anonymous user lists the games of system S1 sorted by "-created"
+    Then the game list is "Gamma, Alpha, Beta"
+    When anonymous user lists the games of system S1 sorted by "year"
+    Then an error occured with status 400
+
+  Scenario: A malformed system filter is rejected
+    Given a system S1 created by user U1 owned by team T1
+    When admin A1 creates a game Alpha on system S1
+    And  anonymous user lists the games of system ""
+    Then an error occured with status 400
+    When anonymous user lists the games of system "0"
+    Then an error occured with status 400
+
+  Scenario: Checksum filters can match any or all of the checksums
+    Given a system S1 created by user U1 owned by team T1
+    And a DAT D1 with 2 games
+    When admin A1 imports DAT D1 into system S1
+    Then searching system S1 for any of the md5 of game 0 and the sha1 of game 1 of DAT D1 finds "0, 1"
+    And  searching system S1 for all of the md5 of game 0 and the sha1 of game 1 of DAT D1 finds ""
+    And  searching system S1 for all of the md5 of game 0 and the sha1 of game 0 of DAT D1 finds "0"
+
+  Scenario: Games can be found by a single kind of checksum
+    Given a system S1 created by user U1 owned by team T1
+    And a DAT D1 with 3 games
+    When admin A1 imports DAT D1 into system S1
+    Then searching system S1 for the sha1 of game 2 of DAT D1 finds "2"
+    And  searching system S1 for the sha1 of game 0 of DAT D1 finds "0"
+    And  searching system S1 for the md5 of game 1 of DAT D1 finds "1"
+
+  Scenario: Games can be found by the CRC32 of their ROMs
+    Given a system S1 created by user U1 owned by team T1
+    And a DAT D1 with 2 games
+    When admin A1 imports DAT D1 into system S1
+    Then searching system S1 for the crc32 of game 1 of DAT D1 finds "1"
+
+  Scenario: Duplicate games can be merged
+    Given a system S1 created by user U1 owned by team T1
+    When admin A1 creates a game G1 on system S1
+    And  admin A1 creates a game G2 on system S1
+    And  admin A1 adds 1 artifact to game G1
+    And  admin A1 adds 2 artifacts to game G2
+    And  admin A1 merges game G2 into game G1
+    Then game G1 of system S1 lists 3 artifacts
+    And  game G2 does not exist
+
+  Scenario: Merging games requires root team
+    Given a system S1 created by user U1 owned by team T1
+    When admin A1 creates a game G1 on system S1
+    And  admin A1 creates a game G2 on system S1
+    And  user U1 merges game G2 into game G1
+    Then an error occured
+
+  Scenario: Games can be filtered by region
+    Given a system S1 created by user U1 owned by team T1
+    When admin A1 creates a game G1 released in "USA, Europe" in "En,Fr" on system S1
+    And  admin A1 creates a game G2 released in "Japan" in "Ja" on system S1
+    Then game G1 was released in "USA, Europe" in "En,Fr"
+    When anonymous user lists the games of system S1 in region "usa"
+    Then the game list is "G1"
+    When anonymous user lists the games of system S1 in region "Japan"
+    Then the game list is "G2"
+    When anonymous user lists the games of system S1 in region "us_"
+    Then the game list is ""
+
+  Scenario: Listing games rejects pages past the maximum offset
+    When anonymous user lists games at page 100 with limit 100
+    Then no error occured
+    When anonymous user lists games at page 101 with limit 100
+    Then an error occured with status 400
+
+  Scenario: Listing games rejects pages that overflow the offset
+    When anonymous user lists games at page 9223372036854775807 with limit 100
+    Then an error occured with status 400
+
+  Scenario: The total of unfiltered games can be estimated
+    Given a system S1 created by user U1 owned by team T1
+    When admin A1 creates a game G1 on system S1
+    And  admin A1 creates a game G2 on system S1
+    Given the games table was analyzed
+    When anonymous user lists games with count estimate
+    Then the games total is an estimate within 50% of the exact total
+    When anonymous user lists the games of system S1 with count estimate
+    Then the games total is exactly 2
@@ -11,3 +11,34 @@ Feature: Game Images
     Given game G1
     When user U1 uploads image I1 to game G1
     Then an error occured
+
+  Scenario: Viewers of the root team cannot upload game images
+    Given game G1
+    And   user V1 is a viewer of the root team
+    When user V1 uploads image I1 to game G1
+    Then an error occured with status 403
+
+  Scenario: Admin can set the primary image of a game
+    Given game G1
+    When admin A1 uploads image I1 to game G1
+     And admin A1 uploads image I2 to game G1
+     And admin A1 sets image I2 as the primary image of game G1
+    Then anonymous user sees image I2 as the primary image of game G1
+
+  Scenario: Only admin can set the primary image of a game
+    Given game G1
+    When admin A1 uploads image I1 to game G1
+     And user U1 sets image I1 as the primary image of game G1
+    Then an error occured
+
+  Scenario: Can list the images of a game
+    Given game G1
+    When admin A1 uploads image I1 to game G1
+     And admin A1 uploads image I2 to game G1
+    Then anonymous user sees the images "I1, I2" of game G1
+
+  Scenario: A game cannot have more than 20 images
+    Given game G1
+    And   admin A1 uploaded 20 images to game G1
+    When admin A1 uploads image I21 to game G1
+    Then an error occured with status 422
@@ -0,0 +1,41 @@
+Feature: Health
+
+  Scenario: Root users can check the health of every component
+    When admin R checks the detailed health of the server
+    Then all components are healthy
+
+  Scenario: Only root users can check the detailed health
+    When user A checks the detailed health of the server
+    Then an error occured
+    When anonymous user checks the detailed health of the server
+    Then an error occured
+
+  Scenario: The usage of the database pool is exported in the metrics
+    When anonymous user fetches the metrics
+    Then the metrics contain "retronomicon_db_pool_size"
+    And  the metrics contain "retronomicon_db_pool_in_use"
+    And  the metrics contain "retronomicon_db_pool_waiters"
+
+  Scenario: The request ID is echoed back in the response
+    When anonymous user sends a request with the request ID "test-request-1"
+    Then the response has the request ID "test-request-1"
+    When anonymous user sends a request with the request ID "not valid"
+    Then the response has a generated request ID
+    When anonymous user sends a request without a request ID
+    Then the response has a generated request ID
+
+  Scenario: The API version is sent in every response
+    When anonymous user reads the "X-Api-Version" header of a response
+    Then the header is the API version
+    When anonymous user reads the "X-Api-Min-Version" header of a response
+    Then the header is the minimum API version
+
+  Scenario: Security headers are sent in API and static responses
+    When anonymous user reads the "X-Content-Type-Options" header of the response to "/platforms"
+    Then the header is "nosniff"
+    When anonymous user reads the "X-Frame-Options" header of the response to "/platforms"
+    Then the header is "DENY"
+    When anonymous user reads the "Content-Security-Policy" header of the response to "/platforms"
+    Then an error occured
+    When anonymous user reads the "Content-Security-Policy" header of the response to "/static/index.html"
+    Then the header is "default-src 'self'; img-src * data:; script-src 'self' 'unsafe-inline'"
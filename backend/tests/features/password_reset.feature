@@ -0,0 +1,56 @@
+Feature: Password Reset
+
+  Scenario: User can reset their password
+    Given user A
+    When user A requests a password reset
+     And user A resets their password to "new-Password-1234"
+    Then no error occured
+     And user A can login with password "new-Password-1234"
+     And user A cannot login with their old password
+
+  Scenario: Requesting a reset for an unknown email succeeds
+    When anonymous user requests a password reset for "unknown@example.com"
+    Then no error occured
+
+  Scenario: Cannot reset a password with an invalid token
+    Given user A
+    When anonymous user resets a password with token "invalid-token"
+    Then an error occured
+
+  Scenario: Cannot reset a password with an expired token
+    Given user A
+    When user A requests a password reset
+     And the password reset token of user A expired
+     And user A resets their password to "new-Password-1234"
+    Then an error occured
+
+  Scenario: Password reset requests are rate limited
+    Given user A
+    When user A requests a password reset
+     And user A requests a password reset
+    Then the password reset token of user A did not change
+
+  Scenario: Cannot reset a password to a weak password
+    Given user A
+    When user A requests a password reset
+     And user A resets their password to "aaaaaaaa"
+    Then an error occured
+     And user A can login with their old password
+
+  Scenario: Root admins can require a user to reset their password
+    Given user A
+    When admin R requires user A to reset their password
+     And user A logs in with their old password
+    Then the error contains "password_reset_required"
+     And the error contains "/reset-password"
+     And an error occured with status 403
+    When user A requests a password reset
+     And user A resets their password to "new-Password-1234"
+    Then no error occured
+     And user A can login with password "new-Password-1234"
+
+  Scenario: Only root admins can require a password reset
+    Given user A
+    When user B requires user A to reset their password
+    Then an error occured
+     And user A can login with their old password
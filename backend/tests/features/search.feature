@@ -0,0 +1,19 @@
+Feature: Search
+
+  Scenario: A search matches cores, games and systems
+    Given no core, game or system from a previous run matches "omnisearchable"
+    And   a system Omnisearchable created by user A owned by team T1
+    And   a core Omnisearchable on system Omnisearchable created by user A owned by team T1
+    When admin A1 creates a game Omnisearchable on system Omnisearchable
+    And  anonymous user searches everything for "omnisearchable"
+    Then the search results contain core Omnisearchable, game Omnisearchable and system Omnisearchable
+    When anonymous user searches everything for "omni%able"
+    Then the search results contain no games
+
+  Scenario: Slugs of cores, systems and platforms can be resolved at once
+    Given a system S1 created by user A owned by team T1
+    And   a system S2 created by user A owned by team T1
+    And   a platform P1 created by user A owned by team T1
+    And   a core C1 on system S1 created by user A owned by team T1
+    When anonymous user resolves the cores "C1, unknown-core", systems "S1, S2, unknown-system" and platforms "unknown-platform"
+    Then the resolved cores are "C1", systems "S1, S2" and platforms ""
@@ -0,0 +1,39 @@
+Feature: Systems
+
+  Scenario: Systems can be filtered by name, manufacturer and team
+    Given a system Genesis made by Sega created by user A owned by team T1
+    And   a system Saturn made by Sega created by user A owned by team T1
+    And   a system Famicom made by Nintendo created by user A owned by team T1
+    And   a system Dreamcast made by Sega created by user B owned by team T2
+    When anonymous user lists the systems of team T1 made by "sega"
+    Then the system list is "Genesis, Saturn"
+    When anonymous user lists the systems of team T2 made by "SEGA"
+    Then the system list is "Dreamcast"
+    When anonymous user lists the systems of team T1 named "fami"
+    Then the system list is "Famicom"
+    When anonymous user lists the systems of team T1 made by "Atari"
+    Then the system list is ""
+    When anonymous user lists the systems of team T1 made by "s_ga"
+    Then the system list is ""
+    When anonymous user lists the systems of team T1 named "fam%"
+    Then the system list is ""
+
+  Scenario: Platforms can be filtered by name
+    Given a platform Pocket created by user A owned by team T1
+    And   a platform PocketPro created by user A owned by team T1
+    And   a platform Mister created by user A owned by team T1
+    When anonymous user lists the platforms named "pocket"
+    Then the platform list is "Pocket, PocketPro"
+    When anonymous user lists the platforms named "STER"
+    Then the platform list is "Mister"
+    When anonymous user lists the platforms named "p_cket"
+    Then the platform list is ""
+
+  Scenario: Platforms can only be updated by admins of their team
+    Given a platform P1 created by user A owned by team T1
+    When user A invites user B to team T1 as admin
+    And  user B renames platform P1 to "Invited"
+    Then an error occured with status 403
+    When user B accepts the invitation to team T1
+    And  user B renames platform P1 to "Accepted"
+    Then no error occured
@@ -0,0 +1,26 @@
+Feature: Tags
+
+  Scenario: Cores can be listed by tag
+    Given a system S1 created by user U1 owned by team T1
+    And   a core C1 on system S1 created by user U1 owned by team T1
+    And   a core C2 on system S1 created by user U1 owned by team T1
+    And   a core C3 on system S1 created by user U1 owned by team T1
+    And   a tag Arcade created by admin A1
+    And   a tag Handheld created by admin A1
+    And   core C1 is tagged Arcade
+    And   core C3 is tagged Arcade
+    And   core C2 is tagged Handheld
+    When anonymous user lists the cores tagged Arcade
+    Then the tagged core list is "C1, C3"
+    When anonymous user lists the cores tagged Handheld
+    Then the tagged core list is "C2"
+
+  Scenario: Games can be listed by tag
+    Given a system S1 created by user U1 owned by team T1
+    And   a tag Puzzle created by admin A1
+    When admin A1 creates a game G1 on system S1
+    And  admin A1 creates a game G2 on system S1
+    Then no error occured
+    Given game G2 is tagged Puzzle
+    When anonymous user lists the games tagged Puzzle
+    Then the tagged game list is "G2"
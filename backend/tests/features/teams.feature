@@ -14,3 +14,129 @@ Feature: Team
     Given team T1 is owned by user A
     When user B invites user C to team T1 as member
     Then an error occured
+
+  Scenario: Can invite a user to a team as viewer
+    Given team C is owned by user A
+    When user A invites user B to team C as viewer
+    And  user B accepts the invitation to team C
+    Then team C will have user B as viewer
+
+  Scenario: A viewer cannot invite users to a team
+    Given team C is owned by user A
+    When user A invites user B to team C as viewer
+    And  user B accepts the invitation to team C
+    Then user B will not be able to invite user D to team C as viewer
+
+  Scenario: A member cannot invite users to a team
+    Given team C is owned by user A
+    When user A invites user B to team C as member
+    And  user B accepts the invitation to team C
+    Then user B will not be able to invite user D to team C as viewer
+
+  Scenario: An admin can only invite users with a lower role
+    Given team C is owned by user A
+    When user A invites user B to team C as admin
+    And  user B accepts the invitation to team C
+    Then user B will not be able to invite user D to team C as admin
+    When user B invites user D to team C as member
+    And  user D accepts the invitation to team C
+    Then team C will have user D as member
+
+  Scenario: Can invite multiple users to a team at once
+    Given team C is owned by user A
+    When user A bulk invites 'user B, unknown nobody, user D' to team C as member
+    Then the bulk invite statuses are 'invited, not_found, invited'
+    When user B accepts the invitation to team C
+    And  user D accepts the invitation to team C
+    Then team C will have user B as member
+    And  team C will have user D as member
+
+  Scenario: Bulk invites report users the inviter cannot invite
+    Given team C is owned by user A
+    When user A invites user B to team C as admin
+    And  user B accepts the invitation to team C
+    And  user B bulk invites 'user D, user E' to team C as admin
+    Then the bulk invite statuses are 'forbidden, forbidden'
+
+  Scenario: Cannot create a team with an existing slug
+    Given user A
+    When user A creates a team T1 with slug duplicate
+    Then no error occured
+    When user A creates a team T2 with slug duplicate
+    Then an error occured with status 409
+
+  Scenario: Creating a team with upsert returns the existing team
+    Given user A
+    When user A upserts a team T1 with slug upserted
+    Then the team with slug upserted was created
+    When user A upserts a team T2 with slug upserted
+    Then the team with slug upserted was reused
+    When user A creates a team T3 with slug upserted
+    Then an error occured with status 409
+
+  Scenario: Cannot create a team with an invalid slug
+    Given user A
+    When user A creates a team T1 with slug Invalid
+    Then an error occured with status 400
+
+  Scenario: Cannot create a team with a slug that is too long
+    Given user A
+    When user A creates a team T1 with slug aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa
+    Then an error occured with status 400
+
+  Scenario: Cannot create a team with a slug the database refuses
+    Given user A
+    When user A creates a team T1 with slug 1-team
+    Then an error occured with status 400
+    When user A creates a team T2 with slug my--team
+    Then an error occured with status 400
+
+  Scenario: Team creation is rolled back if the owner cannot join it
+    Given user A
+    And   joining a team with slug broken fails
+    When user A creates a team T1 with slug broken
+    Then an error occured with status 500
+    And  no team with slug broken exists
+
+  Scenario: Can replace the metadata of a team
+    Given team T1 is owned by user A
+    When user A sets metadata of team T1 to '{"a": 1, "b": "two"}'
+    Then no error occured
+    And team T1 owned by user A has metadata '{"a": 1, "b": "two"}'
+
+  Scenario: Can add and remove metadata keys of a team
+    Given team T1 is owned by user A
+    When user A sets metadata of team T1 to '{"a": 1, "b": "two"}'
+    And user A adds metadata c with value '{"nested": true}' to team T1
+    And user A adds metadata b with value '2' to team T1
+    And user A removes metadata a from team T1
+    Then no error occured
+    And team T1 owned by user A has metadata '{"b": 2, "c": {"nested": true}}'
+
+  Scenario: Can add links to a team whose links are null
+    Given team T1 is owned by user A
+    And team T1 owned by user A has null links
+    When user A adds link homepage to 'https://example.com' to team T1
+    Then no error occured
+    And team T1 owned by user A has links '{"homepage": "https://example.com"}'
+
+  Scenario: Renamed teams can be found by their old slug
+    Given team T1 is owned by user A
+    When user A renames team T1 to renamed
+    Then no error occured
+    And team T1 owned by user A redirects from its original slug to renamed
+
+  Scenario: Can delete a team that owns nothing
+    Given team T1 is owned by user A
+    When user A deletes team T1
+    Then no error occured
+
+  Scenario: Cannot delete a team that still owns a system
+    Given a system S1 created by user A owned by team T1
+    When user A deletes team T1
+    Then an error occured with status 409
+
+  Scenario: Updating a team bumps its update time
+    Given team T1 owned by user A was last updated 2 days ago
+    When user A sets metadata of team T1 to '{"a": 1}'
+    Then team T1 owned by user A was updated in the last hour
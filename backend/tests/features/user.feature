@@ -4,3 +4,64 @@ Feature: User
     Given user A is not authenticated
     When user A gets their details
     Then no error occured
+
+  Scenario: Cannot signup with a weak password
+    When anonymous user signs up with password "short"
+    Then an error occured
+
+  Scenario: Cannot signup with an empty password
+    When anonymous user signs up with password ""
+    Then an error occured
+
+  Scenario: User can set their display name
+    Given user A
+    When user A sets their display name to "Alice The Great"
+    Then no error occured
+    And user A has display name "Alice The Great"
+
+  Scenario: Cannot set a display name that is too long
+    Given user A
+    When user A sets their display name to "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"
+    Then an error occured with status 400
+
+  Scenario: Cannot set a blank display name
+    Given user A
+    When user A sets their display name to "   "
+    Then an error occured with status 400
+
+  Scenario: User can replace their metadata
+    Given user A
+    When user A sets their metadata to '{"a": 1, "b": "two"}'
+    Then no error occured
+    And user A has metadata '{"a": 1, "b": "two"}'
+
+  Scenario: User can add and remove metadata keys
+    Given user A
+    When user A sets their metadata to '{"a": 1, "b": "two"}'
+    And user A adds metadata c with value '[1, 2]'
+    And user A removes metadata a
+    Then no error occured
+    And user A has metadata '{"b": "two", "c": [1, 2]}'
+
+  Scenario: The current user details include pending team invitations
+    Given team T1 is owned by user A
+    When user A invites user B to team T1 as member
+    Then user B has 1 pending invitation
+    When user B accepts the invitation to team T1
+    Then user B has 0 pending invitations
+    And user B is a member of team T1 owned by user A
+
+  Scenario: Signing up returns a token when email validation is bypassed
+    When anonymous user signs up with password "Correct-Horse-Battery-42"
+    Then the signup returned a token expiring in 7 days
+
+  Scenario: Users can be searched by the start of their username
+    When user A searches users by the first 10 letters of the username of user B
+    Then the user search results include user B
+    When anonymous user searches users by the first 10 letters of the username of user B
+    Then an error occured
+
+  Scenario: Database errors are not sent to the client
+    When user A searches users with a NUL character
+    Then the error does not contain "invalid byte sequence"
+    And  an error occured with status 500
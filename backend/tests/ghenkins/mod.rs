@@ -1,10 +1,12 @@
+use crate::user::CoreReleaseOptions;
 use crate::World;
 use cucumber::{given, then, when};
 use retronomicon_dto as dto;
+use std::collections::BTreeMap;
 use std::str::FromStr;
 
 #[derive(Debug, cucumber::Parameter)]
-#[param(name = "team_role", regex = "(owner|admin|member)")]
+#[param(name = "team_role", regex = "(owner|admin|member|viewer)")]
 struct TeamRole(pub dto::types::UserTeamRole);
 
 impl FromStr for TeamRole {
@@ -119,6 +121,18 @@ async fn team_has_user_role(w: &mut World, team: String, user: UserParam, role:
     assert_eq!(user_role, role);
 }
 
+#[given(expr = "{user} is a {team_role} of the root team")]
+async fn user_in_root_team(w: &mut World, user: UserParam, role: TeamRole) {
+    let user_id = w.auth_user(&user).await.unwrap().lock().await.id();
+    w.join_root_team(user_id, role.0, false).await.unwrap();
+}
+
+#[given(expr = "{user} is invited to the root team as {team_role}")]
+async fn user_invited_to_root_team(w: &mut World, user: UserParam, role: TeamRole) {
+    let user_id = w.auth_user(&user).await.unwrap().lock().await.id();
+    w.join_root_team(user_id, role.0, true).await.unwrap();
+}
+
 #[when(expr = "{user} accepts the invitation to team {word}")]
 async fn user_accepts_invitation(w: &mut World, user: UserParam, team: String) {
     w.assert_result_ok();
@@ -161,94 +175,2934 @@ async fn system_owned(w: &mut World, system: String, user: UserParam, team: Stri
     let s = user
         .lock()
         .await
-        .create_system(team.id, &system)
+        .create_system(team.id, &system, "cucumber-manufacturer")
         .await
         .unwrap();
 
     w.systems.insert(system.clone(), s.id);
+    w.resource_slugs.insert(system, s.slug);
 }
 
-#[when(expr = "{user} creates a game {word} on system {word}")]
-async fn game_create(w: &mut World, user: UserParam, game: String, system: String) {
-    w.assert_result_ok();
+#[given(expr = "a system {word} made by {word} created by {user} owned by team {word}")]
+async fn system_owned_by_manufacturer(
+    w: &mut World,
+    system: String,
+    manufacturer: String,
+    user: UserParam,
+    team: String,
+) {
+    let team = w.team(&user, &team).await.unwrap();
+    let user = w.auth_user(&user).await.unwrap();
+
+    let s = user
+        .lock()
+        .await
+        .create_system(team.id, &system, &manufacturer)
+        .await
+        .unwrap();
+
+    w.systems.insert(system.clone(), s.id);
+}
+
+#[when(expr = "{user} lists the systems of team {word} named {string}")]
+async fn systems_list_by_name(w: &mut World, user: UserParam, team: String, name: String) {
+    let team_id = w.teams.get(&team).unwrap().id;
+    let user = w.user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .systems_list(team_id, Some(&name), None)
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} lists the systems of team {word} made by {string}")]
+async fn systems_list_by_manufacturer(
+    w: &mut World,
+    user: UserParam,
+    team: String,
+    manufacturer: String,
+) {
+    let team_id = w.teams.get(&team).unwrap().id;
+    let user = w.user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .systems_list(team_id, None, Some(&manufacturer))
+        .await;
+    w.record_result(result);
+}
+
+#[then(expr = "the system list is {string}")]
+async fn system_list_is(w: &mut World, expected: String) {
+    let list: Vec<dto::systems::SystemListItem> = w.last_result();
+    let mut systems = list
+        .iter()
+        .map(|s| {
+            let (system, _) = w
+                .systems
+                .iter()
+                .find(|(_, id)| **id == s.id)
+                .expect("Unknown system");
+            system.clone()
+        })
+        .collect::<Vec<_>>();
+    systems.sort();
+
+    assert_eq!(systems.join(", "), expected);
+}
+
+#[when(expr = "{user} lists the platforms named {string}")]
+async fn platforms_list_by_name(w: &mut World, user: UserParam, name: String) {
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.platforms_list(&name).await;
+    w.record_result(result);
+}
+
+#[then(expr = "the platform list is {string}")]
+async fn platform_list_is(w: &mut World, expected: String) {
+    let list: Vec<dto::platforms::Platform> = w.last_result();
+    // Platforms are not scoped to a team, so ignore those from other scenarios.
+    let mut platforms = list
+        .iter()
+        .filter_map(|p| {
+            w.platforms
+                .iter()
+                .find(|(_, id)| **id == p.id)
+                .map(|(platform, _)| platform.clone())
+        })
+        .collect::<Vec<_>>();
+    platforms.sort();
 
+    assert_eq!(platforms.join(", "), expected);
+}
+
+#[given(expr = "a platform {word} created by {user} owned by team {word}")]
+async fn platform_owned(w: &mut World, platform: String, user: UserParam, team: String) {
+    let team = w.team(&user, &team).await.unwrap();
     let user = w.auth_user(&user).await.unwrap();
-    let system_id = *w.systems.get(&system).unwrap();
 
-    let result = user.lock().await.create_game(system_id, &game).await;
-    if let Ok(g) = &result {
-        w.games.insert(game.clone(), g.id);
-    }
+    let p = user
+        .lock()
+        .await
+        .create_platform(team.id, &platform, None)
+        .await
+        .unwrap();
+
+    w.platforms.insert(platform.clone(), p.id);
+    w.resource_slugs.insert(platform, p.slug);
+}
+
+#[when(expr = "{user} renames platform {word} to {string}")]
+async fn platform_renamed(w: &mut World, user: UserParam, platform: String, name: String) {
+    let platform_id = *w.platforms.get(&platform).unwrap();
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.rename_platform(platform_id, &name).await;
     w.record_result(result);
 }
 
-#[then(expr = "game {word} exists on system {word}")]
-async fn game_exists(w: &mut World, game: String, system: String) {
-    w.assert_result_ok();
+#[given(
+    expr = "a platform {word} with metadata schema {string} created by {user} owned by team {word}"
+)]
+async fn platform_owned_with_schema(
+    w: &mut World,
+    platform: String,
+    schema: String,
+    user: UserParam,
+    team: String,
+) {
+    let team = w.team(&user, &team).await.unwrap();
+    let user = w.auth_user(&user).await.unwrap();
+    let schema = serde_json::from_str(&schema).unwrap();
 
-    let user = w.user(&UserParam::Anonymous).await.unwrap();
-    let game_id = *w.games.get(&game).unwrap();
+    let p = user
+        .lock()
+        .await
+        .create_platform(team.id, &platform, Some(schema))
+        .await
+        .unwrap();
+
+    w.platforms.insert(platform, p.id);
+}
+
+#[given(expr = "a core {word} on system {word} created by {user} owned by team {word}")]
+async fn core_owned(w: &mut World, core: String, system: String, user: UserParam, team: String) {
+    let team = w.team(&user, &team).await.unwrap();
     let system_id = *w.systems.get(&system).unwrap();
-    let result = user.lock().await.get_game_by_id(game_id).await.unwrap();
+    let user = w.auth_user(&user).await.unwrap();
 
-    assert_eq!(result.system.id, system_id);
+    let c = user
+        .lock()
+        .await
+        .create_core(team.id, system_id, &core)
+        .await
+        .unwrap();
+
+    w.cores.insert(core.clone(), c.id);
+    w.resource_slugs.insert(core, c.slug);
 }
 
-/// Create a game on a default system.
-#[given(expr = "game {word}")]
-async fn given_a_game(w: &mut World, game: String) {
-    given_a_user_auth(w, UserParam::Admin("default".to_string())).await;
-    given_team_owned(
-        w,
-        "default".to_string(),
-        UserParam::Admin("default".to_string()),
-    )
-    .await;
+/// Split a comma separated list of names, mapping the names of created
+/// resources to their slugs. Other names are used as slugs as is.
+fn resource_slugs(w: &World, names: &str) -> Vec<String> {
+    names
+        .split(',')
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .map(|n| {
+            w.resource_slugs
+                .get(n)
+                .cloned()
+                .unwrap_or_else(|| n.to_string())
+        })
+        .collect()
+}
 
-    system_owned(
-        w,
-        "default".to_string(),
-        UserParam::Admin("default".to_string()),
-        "default".to_string(),
-    )
-    .await;
-    game_create(
-        w,
-        UserParam::Admin("default".to_string()),
-        game,
-        "default".to_string(),
-    )
-    .await;
-    w.assert_result_ok();
+/// The names of the resources with these ids, sorted and comma separated.
+fn resource_names<'a>(
+    ids: &BTreeMap<String, i32>,
+    values: impl Iterator<Item = &'a i32>,
+) -> String {
+    let mut names = values
+        .map(|v| {
+            ids.iter()
+                .find(|(_, id)| **id == *v)
+                .map(|(name, _)| name.clone())
+                .expect("Unknown resource")
+        })
+        .collect::<Vec<_>>();
+    names.sort();
+    names.join(", ")
 }
 
-#[when(expr = "{user} uploads image {word} to game {word}")]
-async fn game_upload_image(w: &mut World, user: UserParam, image: String, game: String) {
+#[when(expr = "{user} resolves the cores {string}, systems {string} and platforms {string}")]
+async fn resolve_slugs(
+    w: &mut World,
+    user: UserParam,
+    cores: String,
+    systems: String,
+    platforms: String,
+) {
+    let request = dto::search::ResolveRequest {
+        cores: resource_slugs(w, &cores),
+        systems: resource_slugs(w, &systems),
+        platforms: resource_slugs(w, &platforms),
+    };
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.resolve(&request).await;
+    w.record_result(result);
+}
+
+#[then(expr = "the resolved cores are {string}, systems {string} and platforms {string}")]
+async fn resolved_slugs_are(w: &mut World, cores: String, systems: String, platforms: String) {
+    let response: dto::search::ResolveResponse = w.last_result();
+    assert_eq!(resource_names(&w.cores, response.cores.values()), cores);
+    assert_eq!(
+        resource_names(&w.systems, response.systems.values()),
+        systems
+    );
+    assert_eq!(
+        resource_names(&w.platforms, response.platforms.values()),
+        platforms
+    );
+}
+
+#[given(expr = "a tag {word} created by {user}")]
+async fn tag_created(w: &mut World, tag: String, user: UserParam) {
     let user = w.auth_user(&user).await.unwrap();
+    let slug = user.lock().await.create_tag(&tag).await.unwrap();
+    w.tags.insert(tag, slug);
+}
+
+#[given(expr = "core {word} is tagged {word}")]
+async fn core_tagged(w: &mut World, core: String, tag: String) {
+    let core_id = *w.cores.get(&core).unwrap();
+    let slug = w.tags.get(&tag).unwrap().clone();
+    w.tag_core(&slug, core_id).await.unwrap();
+}
+
+#[given(expr = "game {word} is tagged {word}")]
+async fn game_tagged(w: &mut World, game: String, tag: String) {
     let game_id = *w.games.get(&game).unwrap();
+    let slug = w.tags.get(&tag).unwrap().clone();
+    w.tag_game(&slug, game_id).await.unwrap();
+}
 
-    let result = user.lock().await.upload_image(game_id, &image).await;
+#[when(expr = "{user} lists the cores tagged {word}")]
+async fn tags_cores(w: &mut World, user: UserParam, tag: String) {
+    let slug = w.tags.get(&tag).unwrap().clone();
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.tags_cores(&slug).await;
     w.record_result(result);
 }
 
-#[then(expr = "{user} can see image {word} of game {word}")]
-async fn then_user_can_see(w: &mut World, user: UserParam, image: String, game: String) {
-    w.assert_result_ok();
+#[when(expr = "{user} lists the games tagged {word}")]
+async fn tags_games(w: &mut World, user: UserParam, tag: String) {
+    let slug = w.tags.get(&tag).unwrap().clone();
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.tags_games(&slug).await;
+    w.record_result(result);
+}
+
+#[then(expr = "the tagged core list is {string}")]
+async fn tagged_core_list_is(w: &mut World, expected: String) {
+    let list: Vec<dto::cores::CoreListItem> = w.last_result();
+    let mut cores = list
+        .iter()
+        .map(|c| {
+            let (core, _) = w
+                .cores
+                .iter()
+                .find(|(_, id)| **id == c.id)
+                .expect("Unknown core");
+            core.clone()
+        })
+        .collect::<Vec<_>>();
+    cores.sort();
+
+    assert_eq!(cores.join(", "), expected);
+}
+
+#[then(expr = "the tagged game list is {string}")]
+async fn tagged_game_list_is(w: &mut World, expected: String) {
+    let list: Vec<dto::games::GameListItemResponse> = w.last_result();
+    let mut games = list
+        .iter()
+        .map(|g| {
+            let (game, _) = w
+                .games
+                .iter()
+                .find(|(_, id)| **id == g.id)
+                .expect("Unknown game");
+            game.clone()
+        })
+        .collect::<Vec<_>>();
+    games.sort();
+
+    assert_eq!(games.join(", "), expected);
+}
 
+#[when(expr = "{user} lists cores with a name containing {string}")]
+async fn cores_list_by_name(w: &mut World, user: UserParam, name: String) {
     let user = w.user(&user).await.unwrap();
-    let game_id = *w.games.get(&game).unwrap();
-    let result = user.lock().await.get_game_images(game_id).await.unwrap();
+    let result = user.lock().await.cores_list_by_name(&name).await;
+    w.record_result(result);
+}
 
-    let i = result
-        .items
+#[when(expr = "{user} lists cores matching {string}")]
+async fn cores_list_matching(w: &mut World, user: UserParam, q: String) {
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.cores_list_matching(&q).await;
+    w.record_result(result);
+}
+
+#[then(expr = "core {word} is in the list")]
+async fn core_in_list(w: &mut World, core: String) {
+    let result: dto::Paginated<dto::cores::CoreListItem> = w.last_result();
+    let core_id = *w.cores.get(&core).unwrap();
+    assert!(result.items.iter().any(|c| c.id == core_id));
+}
+
+#[then(expr = "core {word} is not in the list")]
+async fn core_not_in_list(w: &mut World, core: String) {
+    let result: dto::Paginated<dto::cores::CoreListItem> = w.last_result();
+    let core_id = *w.cores.get(&core).unwrap();
+    assert!(!result.items.iter().any(|c| c.id == core_id));
+}
+
+#[when(expr = "{user} searches cores for {string}")]
+async fn cores_search(w: &mut World, user: UserParam, q: String) {
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.search_cores(&q).await;
+    w.record_result(result);
+}
+
+/// Search for the name of a core with a typo. Names of cores created by other
+/// scenarios differ in their random suffix, so they rank lower.
+#[when(
+    expr = "{user} searches cores for the name of core {word} with {string} replaced by {string}"
+)]
+async fn cores_search_misspelled(
+    w: &mut World,
+    user: UserParam,
+    core: String,
+    from: String,
+    to: String,
+) {
+    let core_id = *w.cores.get(&core).unwrap();
+    let user = w.user(&user).await.unwrap();
+    let mut user = user.lock().await;
+    let name = user.core_details(core_id).await.unwrap().name;
+    assert!(name.contains(&from), "{from:?} is not in {name:?}");
+
+    let result = user.search_cores(&name.replacen(&from, &to, 1)).await;
+    w.record_result(result);
+}
+
+#[then(expr = "core {word} is in the search results")]
+async fn core_in_search_results(w: &mut World, core: String) {
+    let result: Vec<dto::cores::CoreSearchItem> = w.last_result();
+    let core_id = *w.cores.get(&core).unwrap();
+    assert!(result.iter().any(|c| c.id == core_id));
+}
+
+#[when(expr = "{user} lists the cores released in the last {int} hours")]
+async fn cores_recently_released(w: &mut World, user: UserParam, hours: i64) {
+    let user = w.user(&user).await.unwrap();
+    let since = chrono::Utc::now().timestamp() - hours * 60 * 60;
+    let result = user.lock().await.cores_recently_released(since).await;
+    w.record_result(result);
+}
+
+#[then(expr = "the recently released cores are {string}")]
+async fn cores_recently_released_are(w: &mut World, expected: String) {
+    let list: Vec<dto::cores::CoreListItem> = w.last_result();
+    // Other scenarios might have released cores too, only keep ours.
+    let cores = list
         .iter()
-        .find(|i| i.name == format!("{image}.png"))
-        .expect("Image not in the list of images.");
+        .filter_map(|c| {
+            let (core, _) = w.cores.iter().find(|(_, id)| **id == c.id)?;
+            let version = &c.latest_release.as_ref()?.version;
+            Some(format!("{core} {version}"))
+        })
+        .collect::<Vec<_>>();
 
-    // Download the image.
-    reqwest::get(&i.url)
+    assert_eq!(cores.join(", "), expected);
+}
+
+#[given(expr = "no core, game or system from a previous run matches {string}")]
+async fn previous_runs_retired(w: &mut World, term: String) {
+    w.retire_matching(&term).await.unwrap();
+}
+
+#[when(expr = "{user} searches everything for {string}")]
+async fn search_everything(w: &mut World, user: UserParam, q: String) {
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.search(&q).await;
+    w.record_result(result);
+}
+
+#[then(expr = "the search results contain core {word}, game {word} and system {word}")]
+async fn search_results_contain(w: &mut World, core: String, game: String, system: String) {
+    let result: dto::search::SearchResults = w.last_result();
+    let core_id = *w.cores.get(&core).unwrap();
+    let game_id = *w.games.get(&game).unwrap();
+    let system_id = *w.systems.get(&system).unwrap();
+    assert!(result.cores.iter().any(|c| c.id == core_id));
+    assert!(result.games.iter().any(|g| g.id == game_id));
+    assert!(result.systems.iter().any(|s| s.id == system_id));
+}
+
+#[then(expr = "the search results contain no games")]
+async fn search_results_contain_no_games(w: &mut World) {
+    let result: dto::search::SearchResults = w.last_result();
+    assert!(result.games.is_empty());
+}
+
+#[then(expr = "core {word} is not in the search results")]
+async fn core_not_in_search_results(w: &mut World, core: String) {
+    let result: Vec<dto::cores::CoreSearchItem> = w.last_result();
+    let core_id = *w.cores.get(&core).unwrap();
+    assert!(!result.iter().any(|c| c.id == core_id));
+}
+
+#[when(expr = "{user} releases version {word} of core {word} on platform {word} at {int}")]
+async fn core_release(
+    w: &mut World,
+    user: UserParam,
+    version: String,
+    core: String,
+    platform: String,
+    date_released: i64,
+) {
+    let core_id = *w.cores.get(&core).unwrap();
+    let platform_id = *w.platforms.get(&platform).unwrap();
+    let user = w.auth_user(&user).await.unwrap();
+
+    let result = user
+        .lock()
         .await
-        .expect("Could not get a response")
-        .error_for_status()
-        .expect("Could not download the image");
+        .create_core_release(
+            core_id,
+            platform_id,
+            &version,
+            CoreReleaseOptions {
+                date_released: Some(date_released),
+                ..Default::default()
+            },
+        )
+        .await;
+    if let Ok(release) = &result {
+        w.releases.insert((core, version), release.id);
+    }
+    w.record_result(result);
+}
+
+#[when(
+    expr = "{user} releases version {word} of core {word} on platform {word} dated {int} hours from now"
+)]
+async fn core_release_dated_from_now(
+    w: &mut World,
+    user: UserParam,
+    version: String,
+    core: String,
+    platform: String,
+    hours: i64,
+) {
+    let core_id = *w.cores.get(&core).unwrap();
+    let platform_id = *w.platforms.get(&platform).unwrap();
+    let user = w.auth_user(&user).await.unwrap();
+    let date_released = chrono::Utc::now().timestamp() + hours * 60 * 60;
+
+    let result = user
+        .lock()
+        .await
+        .create_core_release(
+            core_id,
+            platform_id,
+            &version,
+            CoreReleaseOptions {
+                date_released: Some(date_released),
+                ..Default::default()
+            },
+        )
+        .await;
+    if let Ok(release) = &result {
+        w.releases.insert((core, version), release.id);
+    }
+    w.record_result(result);
+}
+
+#[when(
+    expr = "{user} retries releasing version {word} of core {word} on platform {word} with notes {string}"
+)]
+async fn core_release_retried(
+    w: &mut World,
+    user: UserParam,
+    version: String,
+    core: String,
+    platform: String,
+    notes: String,
+) {
+    let core_id = *w.cores.get(&core).unwrap();
+    let platform_id = *w.platforms.get(&platform).unwrap();
+    let user = w.auth_user(&user).await.unwrap();
+
+    let result = user
+        .lock()
+        .await
+        .create_core_release(
+            core_id,
+            platform_id,
+            &version,
+            CoreReleaseOptions {
+                notes: &notes,
+                idempotency_key: Some("test"),
+                ..Default::default()
+            },
+        )
+        .await;
+    if let Ok(release) = &result {
+        w.releases.entry((core, version)).or_insert(release.id);
+    }
+    w.record_result(result);
+}
+
+#[then(expr = "the release is version {word} of core {word}")]
+async fn core_release_is(w: &mut World, version: String, core: String) {
+    let release: dto::cores::releases::CoreReleaseCreateResponse = w.last_result();
+    assert_eq!(Some(&release.id), w.releases.get(&(core, version)));
+}
+
+#[when(
+    expr = "{user} releases version {word} of core {word} on platform {word} with metadata {string}"
+)]
+async fn core_release_with_metadata(
+    w: &mut World,
+    user: UserParam,
+    version: String,
+    core: String,
+    platform: String,
+    metadata: String,
+) {
+    let core_id = *w.cores.get(&core).unwrap();
+    let platform_id = *w.platforms.get(&platform).unwrap();
+    let user = w.auth_user(&user).await.unwrap();
+    let metadata = serde_json::from_str(&metadata).unwrap();
+
+    let result = user
+        .lock()
+        .await
+        .create_core_release(
+            core_id,
+            platform_id,
+            &version,
+            CoreReleaseOptions {
+                date_released: Some(1700000000),
+                metadata: Some(metadata),
+                ..Default::default()
+            },
+        )
+        .await;
+    if let Ok(release) = &result {
+        w.releases.insert((core, version), release.id);
+    }
+    w.record_result(result);
+}
+
+#[when(
+    expr = "{user} releases version {word} of core {word} on platform {word} with notes {string}"
+)]
+async fn core_release_with_notes(
+    w: &mut World,
+    user: UserParam,
+    version: String,
+    core: String,
+    platform: String,
+    notes: String,
+) {
+    let core_id = *w.cores.get(&core).unwrap();
+    let platform_id = *w.platforms.get(&platform).unwrap();
+    let user = w.auth_user(&user).await.unwrap();
+    // Gherkin strings cannot contain newlines.
+    let notes = notes.replace("\\n", "\n");
+
+    let result = user
+        .lock()
+        .await
+        .create_core_release(
+            core_id,
+            platform_id,
+            &version,
+            CoreReleaseOptions {
+                date_released: Some(1700000000),
+                notes: &notes,
+                ..Default::default()
+            },
+        )
+        .await;
+    if let Ok(release) = &result {
+        w.releases.insert((core, version), release.id);
+    }
+    w.record_result(result);
+}
+
+#[given(expr = "a system release {word} of system {word}")]
+async fn system_release(w: &mut World, name: String, system: String) {
+    let system_id = *w.systems.get(&system).unwrap();
+    let id = w.create_system_release(system_id, &name).await.unwrap();
+    w.system_releases.insert(name, id);
+}
+
+#[when(
+    expr = "{user} releases version {word} of core {word} on platform {word} requiring system release {word}"
+)]
+async fn core_release_requiring_system_release(
+    w: &mut World,
+    user: UserParam,
+    version: String,
+    core: String,
+    platform: String,
+    system_release: String,
+) {
+    let core_id = *w.cores.get(&core).unwrap();
+    let platform_id = *w.platforms.get(&platform).unwrap();
+    let system_release_id = *w.system_releases.get(&system_release).unwrap();
+    let user = w.auth_user(&user).await.unwrap();
+
+    let result = user
+        .lock()
+        .await
+        .create_core_release(
+            core_id,
+            platform_id,
+            &version,
+            CoreReleaseOptions {
+                date_released: Some(1700000000),
+                required_system_release: Some(system_release_id),
+                ..Default::default()
+            },
+        )
+        .await;
+    if let Ok(release) = &result {
+        w.releases.insert((core, version), release.id);
+    }
+    w.record_result(result);
+}
+
+#[when(expr = "{user} gets the notes of version {word} of core {word} as HTML")]
+async fn core_release_notes_html(w: &mut World, user: UserParam, version: String, core: String) {
+    let core_id = *w.cores.get(&core).unwrap();
+    let release_id = *w.releases.get(&(core, version)).unwrap();
+    let user = w.user(&user).await.unwrap();
+
+    let result = user
+        .lock()
+        .await
+        .core_release_notes_html(core_id, release_id)
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} fetches the metrics")]
+async fn metrics_fetch(w: &mut World, user: UserParam) {
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.metrics().await;
+    w.record_result(result);
+}
+
+#[then(expr = "the metrics contain {string}")]
+async fn metrics_contain(w: &mut World, expected: String) {
+    let metrics: String = w.last_result();
+    assert!(
+        metrics.contains(&expected),
+        "{metrics:?} does not contain {expected:?}"
+    );
+}
+
+#[when(expr = "{user} sends a request with the request ID {string}")]
+async fn request_id_send(w: &mut World, user: UserParam, request_id: String) {
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.request_id(Some(&request_id)).await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} sends a request without a request ID")]
+async fn request_id_send_none(w: &mut World, user: UserParam) {
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.request_id(None).await;
+    w.record_result(result);
+}
+
+#[then(expr = "the response has the request ID {string}")]
+async fn request_id_is(w: &mut World, expected: String) {
+    let request_id: String = w.last_result();
+    assert_eq!(request_id, expected);
+}
+
+#[then(expr = "the response has a generated request ID")]
+async fn request_id_generated(w: &mut World) {
+    let request_id: String = w.last_result();
+    assert_eq!(request_id.len(), 32, "{request_id:?} is not a generated ID");
+    assert!(request_id.chars().all(|c| c.is_ascii_hexdigit()));
+}
+
+#[when(expr = "{user} reads the {string} header of a response")]
+async fn response_header(w: &mut World, user: UserParam, name: String) {
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.response_header(&name).await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} reads the {string} header of the response to {string}")]
+async fn response_header_of(w: &mut World, user: UserParam, name: String, path: String) {
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.response_header_of(&path, &name).await;
+    w.record_result(result);
+}
+
+#[then(expr = "the header is {string}")]
+async fn header_is(w: &mut World, expected: String) {
+    let header: String = w.last_result();
+    assert_eq!(header, expected);
+}
+
+#[then(expr = "the header is the API version")]
+async fn header_is_api_version(w: &mut World) {
+    let header: String = w.last_result();
+    assert_eq!(header, dto::VERSION);
+}
+
+#[then(expr = "the header is the minimum API version")]
+async fn header_is_api_min_version(w: &mut World) {
+    let header: String = w.last_result();
+    assert_eq!(header, dto::API_MIN_VERSION);
+}
+
+#[then(expr = "the HTML contains {string}")]
+async fn html_contains(w: &mut World, expected: String) {
+    let html: String = w.last_result();
+    assert!(
+        html.contains(&expected),
+        "{html:?} does not contain {expected:?}"
+    );
+}
+
+#[then(expr = "the HTML does not contain {string}")]
+async fn html_does_not_contain(w: &mut World, unexpected: String) {
+    let html: String = w.last_result();
+    assert!(
+        !html.contains(&unexpected),
+        "{html:?} contains {unexpected:?}"
+    );
+}
+
+#[when(expr = "{user} gets the changelog of core {word}")]
+async fn core_changelog(w: &mut World, user: UserParam, core: String) {
+    let core_id = *w.cores.get(&core).unwrap();
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.core_changelog(core_id, None).await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} gets the changelog of core {word} for platform {word}")]
+async fn core_changelog_for_platform(
+    w: &mut World,
+    user: UserParam,
+    core: String,
+    platform: String,
+) {
+    let core_id = *w.cores.get(&core).unwrap();
+    let platform_id = *w.platforms.get(&platform).unwrap();
+    let user = w.user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .core_changelog(core_id, Some(platform_id))
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} gets the changelog of core {word} in Markdown")]
+async fn core_changelog_markdown(w: &mut World, user: UserParam, core: String) {
+    let core_id = *w.cores.get(&core).unwrap();
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.core_changelog_markdown(core_id).await;
+    w.record_result(result);
+}
+
+#[then(expr = "the changelog is {string}")]
+async fn changelog_is(w: &mut World, expected: String) {
+    let changelog: Vec<dto::cores::releases::CoreChangelogEntry> = w.last_result();
+    let versions = changelog
+        .iter()
+        .map(|e| e.version.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(versions.join(", "), expected);
+}
+
+#[then(expr = "the Markdown contains {string}")]
+async fn markdown_contains(w: &mut World, expected: String) {
+    let markdown: String = w.last_result();
+    assert!(
+        markdown.contains(&expected),
+        "{markdown:?} does not contain {expected:?}"
+    );
+}
+
+/// The content of an artifact, unique to the scenario.
+fn artifact_data(w: &mut World, artifact: &str) -> Vec<u8> {
+    format!("{} data", w.slug(artifact)).into_bytes()
+}
+
+#[when(expr = "{user} uploads artifact {word} to version {word} of core {word}")]
+async fn core_release_upload_artifact(
+    w: &mut World,
+    user: UserParam,
+    artifact: String,
+    version: String,
+    core: String,
+) {
+    let core_id = *w.cores.get(&core).unwrap();
+    let release_id = *w.releases.get(&(core, version)).unwrap();
+    let data = artifact_data(w, &artifact);
+    let user = w.auth_user(&user).await.unwrap();
+
+    let result = user
+        .lock()
+        .await
+        .upload_core_artifact(core_id, release_id, None, &format!("{artifact}.bin"), data)
+        .await;
+    if let Ok(created) = &result {
+        w.artifacts.insert(artifact, created[0].id);
+    }
+    w.record_result(result);
+}
+
+#[when(expr = "{user} attaches artifact {word} to version {word} of core {word}")]
+async fn core_release_attach_artifact(
+    w: &mut World,
+    user: UserParam,
+    artifact: String,
+    version: String,
+    core: String,
+) {
+    let core_id = *w.cores.get(&core).unwrap();
+    let release_id = *w.releases.get(&(core, version)).unwrap();
+    let artifact_id = *w.artifacts.get(&artifact).unwrap();
+    let user = w.auth_user(&user).await.unwrap();
+
+    let result = user
+        .lock()
+        .await
+        .attach_core_artifact(core_id, release_id, artifact_id)
+        .await;
+    w.record_result(result);
+}
+
+#[given(expr = "version {word} of core {word} is yanked")]
+async fn core_release_yanked(w: &mut World, version: String, core: String) {
+    let release_id = *w.releases.get(&(core, version)).unwrap();
+    w.yank_release(release_id).await.unwrap();
+}
+
+#[when(expr = "{user} uploads artifact {word} as {string} to version {word} of core {word}")]
+async fn core_release_upload_artifact_as(
+    w: &mut World,
+    user: UserParam,
+    artifact: String,
+    filename: String,
+    version: String,
+    core: String,
+) {
+    let core_id = *w.cores.get(&core).unwrap();
+    let release_id = *w.releases.get(&(core, version)).unwrap();
+    let data = artifact_data(w, &artifact);
+    let user = w.auth_user(&user).await.unwrap();
+
+    let result = user
+        .lock()
+        .await
+        .upload_core_artifact(core_id, release_id, None, &filename, data)
+        .await;
+    if let Ok(created) = &result {
+        w.artifacts.insert(artifact, created[0].id);
+    }
+    w.record_result(result);
+}
+
+#[when(
+    expr = "{user} uploads artifacts {string} as {string} to version {word} of core {word} with conflict mode {word}"
+)]
+async fn core_release_upload_artifacts_same_name(
+    w: &mut World,
+    user: UserParam,
+    artifacts: String,
+    filename: String,
+    version: String,
+    core: String,
+    on_conflict: String,
+) {
+    let core_id = *w.cores.get(&core).unwrap();
+    let release_id = *w.releases.get(&(core, version)).unwrap();
+    let files = artifacts
+        .split(',')
+        .map(|artifact| (filename.as_str(), artifact_data(w, artifact.trim())))
+        .collect();
+    let user = w.auth_user(&user).await.unwrap();
+
+    let result = user
+        .lock()
+        .await
+        .upload_core_artifacts(core_id, release_id, &on_conflict, files)
+        .await;
+    w.record_result(result);
+}
+
+#[then(expr = "the uploaded artifacts are named {string}")]
+async fn core_release_uploaded_artifacts_named(w: &mut World, expected: String) {
+    let result: Vec<dto::artifact::ArtifactCreateResponse> = w.last_result();
+    let names = result
+        .iter()
+        .map(|a| a.filename.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names.join(", "), expected);
+}
+
+#[when(expr = "{user} lists the artifact filenames of core {word}")]
+async fn core_artifact_filenames(w: &mut World, user: UserParam, core: String) {
+    let core_id = *w.cores.get(&core).unwrap();
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user.lock().await.core_artifact_filenames(core_id).await;
+    w.record_result(result);
+}
+
+#[then(expr = "the artifact filenames are {string}")]
+async fn core_artifact_filenames_are(w: &mut World, expected: String) {
+    let filenames: Vec<String> = w.last_result();
+    assert_eq!(filenames.join(", "), expected);
+}
+
+#[when(expr = "{user} downloads all the artifacts of version {word} of core {word}")]
+async fn core_release_download_zip(w: &mut World, user: UserParam, version: String, core: String) {
+    let core_id = *w.cores.get(&core).unwrap();
+    let release_id = *w.releases.get(&(core, version)).unwrap();
+    let user = w.user(&user).await.unwrap();
+
+    let result = user
+        .lock()
+        .await
+        .download_core_release_zip(core_id, release_id)
+        .await;
+    w.record_result(result);
+}
+
+#[then(expr = "the zip contains {string}")]
+async fn zip_contains(w: &mut World, expected: String) {
+    let mut names: Vec<String> = w.last_result();
+    names.sort();
+    assert_eq!(names.join(", "), expected);
+}
+
+#[when(
+    expr = "{user} uploads artifact {word} for platform {word} to version {word} of core {word}"
+)]
+async fn core_release_upload_artifact_for_platform(
+    w: &mut World,
+    user: UserParam,
+    artifact: String,
+    platform: String,
+    version: String,
+    core: String,
+) {
+    let core_id = *w.cores.get(&core).unwrap();
+    let release_id = *w.releases.get(&(core, version)).unwrap();
+    let platform_id = *w.platforms.get(&platform).unwrap();
+    let data = artifact_data(w, &artifact);
+    let user = w.auth_user(&user).await.unwrap();
+
+    let result = user
+        .lock()
+        .await
+        .upload_core_artifact(
+            core_id,
+            release_id,
+            Some(platform_id),
+            &format!("{artifact}.bin"),
+            data,
+        )
+        .await;
+    if let Ok(created) = &result {
+        w.artifacts.insert(artifact, created[0].id);
+    }
+    w.record_result(result);
+}
+
+#[when(expr = "{user} lists the artifacts of version {word} of core {word} for platform {word}")]
+async fn core_release_list_artifacts_for_platform(
+    w: &mut World,
+    user: UserParam,
+    version: String,
+    core: String,
+    platform: String,
+) {
+    let core_id = *w.cores.get(&core).unwrap();
+    let release_id = *w.releases.get(&(core, version)).unwrap();
+    let platform_id = *w.platforms.get(&platform).unwrap();
+    let user = w.user(&user).await.unwrap();
+
+    let result = user
+        .lock()
+        .await
+        .core_release_artifacts(core_id, release_id, Some(platform_id), None)
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} lists the artifacts of version {word} of core {word} of type {string}")]
+async fn core_release_list_artifacts_of_type(
+    w: &mut World,
+    user: UserParam,
+    version: String,
+    core: String,
+    mime_type: String,
+) {
+    let core_id = *w.cores.get(&core).unwrap();
+    let release_id = *w.releases.get(&(core, version)).unwrap();
+    let user = w.user(&user).await.unwrap();
+
+    let result = user
+        .lock()
+        .await
+        .core_release_artifacts(core_id, release_id, None, Some(&mime_type))
+        .await;
+    w.record_result(result);
+}
+
+#[then(expr = "the artifact list is {string}")]
+async fn artifact_list_is(w: &mut World, expected: String) {
+    let list: dto::Paginated<dto::artifact::CoreReleaseArtifactListItem> = w.last_result();
+    let mut artifacts = list
+        .items
+        .iter()
+        .map(|a| {
+            let (artifact, _) = w
+                .artifacts
+                .iter()
+                .find(|(_, id)| **id == a.id)
+                .expect("Unknown artifact");
+            artifact.clone()
+        })
+        .collect::<Vec<_>>();
+    artifacts.sort();
+
+    assert_eq!(artifacts.join(", "), expected);
+}
+
+#[when(expr = "{user} lists the full releases of core {word}")]
+async fn core_releases_full(w: &mut World, user: UserParam, core: String) {
+    let core_id = *w.cores.get(&core).unwrap();
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.core_releases_full(core_id).await;
+    w.record_result(result);
+}
+
+/// Releases are separated by `;`, and their artifacts listed after a `:`.
+#[then(expr = "the full release list is {string}")]
+async fn full_release_list_is(w: &mut World, expected: String) {
+    let list: dto::Paginated<dto::cores::releases::CoreReleaseFullItem> = w.last_result();
+    let releases = list
+        .items
+        .iter()
+        .map(|r| {
+            assert_eq!(r.artifacts.len(), r.artifacts_count);
+            let mut artifacts = r
+                .artifacts
+                .iter()
+                .map(|a| {
+                    let (artifact, _) = w
+                        .artifacts
+                        .iter()
+                        .find(|(_, id)| **id == a.id)
+                        .expect("Unknown artifact");
+                    artifact.clone()
+                })
+                .collect::<Vec<_>>();
+            artifacts.sort();
+            format!("{}: {}", r.release.version, artifacts.join(", "))
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(releases.join("; "), expected);
+}
+
+#[when(expr = "{user} gets artifact {word} of version {word} of core {word}")]
+async fn core_release_get_artifact(
+    w: &mut World,
+    user: UserParam,
+    artifact: String,
+    version: String,
+    core: String,
+) {
+    let core_id = *w.cores.get(&core).unwrap();
+    let release_id = *w.releases.get(&(core, version)).unwrap();
+    let artifact_id = *w.artifacts.get(&artifact).unwrap();
+    let user = w.user(&user).await.unwrap();
+
+    let result = user
+        .lock()
+        .await
+        .core_release_artifact(core_id, release_id, artifact_id)
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} verifies artifact {word} of version {word} of core {word}")]
+async fn core_release_verify_artifact(
+    w: &mut World,
+    user: UserParam,
+    artifact: String,
+    version: String,
+    core: String,
+) {
+    let core_id = *w.cores.get(&core).unwrap();
+    let release_id = *w.releases.get(&(core, version)).unwrap();
+    let artifact_id = *w.artifacts.get(&artifact).unwrap();
+    let user = w.auth_user(&user).await.unwrap();
+
+    let result = user
+        .lock()
+        .await
+        .verify_core_release_artifact(core_id, release_id, artifact_id)
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} looks up artifact {word} by its SHA256")]
+async fn artifact_by_sha256(w: &mut World, user: UserParam, artifact: String) {
+    use sha2::Digest;
+
+    let sha256 = sha2::Sha256::digest(artifact_data(w, &artifact)).to_vec();
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.artifact_by_sha256(sha256.into()).await;
+    w.record_result(result);
+}
+
+#[then(expr = "artifact {word} was found")]
+async fn artifact_was_found(w: &mut World, artifact: String) {
+    let result: dto::artifact::ArtifactExistsResponse = w.last_result();
+    assert_eq!(Some(&result.id), w.artifacts.get(&artifact));
+    assert!(!result.download_url.is_empty());
+}
+
+#[when(expr = "{user} downloads artifact {word} of version {word} of core {word}")]
+async fn core_release_download_artifact(
+    w: &mut World,
+    user: UserParam,
+    artifact: String,
+    version: String,
+    core: String,
+) {
+    let core_id = *w.cores.get(&core).unwrap();
+    let release_id = *w.releases.get(&(core, version)).unwrap();
+    let artifact_id = *w.artifacts.get(&artifact).unwrap();
+    let user = w.user(&user).await.unwrap();
+
+    let result = user
+        .lock()
+        .await
+        .download_core_release_artifact(core_id, release_id, artifact_id)
+        .await;
+    w.record_result(result);
+}
+
+#[then(expr = "the download is redirected to the artifact URL")]
+async fn download_is_redirected(w: &mut World) {
+    let result: crate::user::DownloadResponse = w.last_result();
+    assert_eq!(result.status, 307);
+    assert!(result.location.is_some());
+    assert_eq!(result.cache_control.as_deref(), Some("no-cache"));
+    assert!(result.generated_at.is_some());
+}
+
+#[then(expr = "the download is served with content type {string}")]
+async fn download_is_served_with_content_type(w: &mut World, content_type: String) {
+    let result: crate::user::DownloadResponse = w.last_result();
+    assert_eq!(result.status, 200);
+    assert_eq!(result.content_type.as_deref(), Some(content_type.as_str()));
+}
+
+#[given(expr = "artifact {word} is stored in the database with mime type {string}")]
+async fn artifact_stored_in_db(w: &mut World, artifact: String, mime_type: String) {
+    let artifact_id = *w.artifacts.get(&artifact).unwrap();
+    let data = artifact_data(w, &artifact);
+    w.store_artifact_in_db(artifact_id, &data, &mime_type)
+        .await
+        .unwrap();
+}
+
+#[given(expr = "the stored checksum of artifact {word} is corrupted")]
+async fn artifact_checksum_corrupted(w: &mut World, artifact: String) {
+    let artifact_id = *w.artifacts.get(&artifact).unwrap();
+    w.set_artifact_sha256(artifact_id, &[0; 32]).await.unwrap();
+}
+
+#[given(expr = "the data of artifact {word} is lost")]
+async fn artifact_data_lost(w: &mut World, artifact: String) {
+    let artifact_id = *w.artifacts.get(&artifact).unwrap();
+    w.delete_artifact_data(artifact_id).await.unwrap();
+}
+
+#[then(expr = "the artifact is valid")]
+async fn artifact_is_valid(w: &mut World) {
+    let result: dto::artifact::ArtifactVerifyResponse = w.last_result();
+    assert!(result.valid);
+    assert_eq!(result.stored_sha256, result.computed_sha256);
+}
+
+#[then(expr = "the artifact is not valid")]
+async fn artifact_is_not_valid(w: &mut World) {
+    let result: dto::artifact::ArtifactVerifyResponse = w.last_result();
+    assert!(!result.valid);
+    assert_eq!(&*result.stored_sha256, &[0; 32]);
+}
+
+#[when(expr = "{user} verifies all artifacts of version {word} of core {word}")]
+async fn core_release_verify(w: &mut World, user: UserParam, version: String, core: String) {
+    let core_id = *w.cores.get(&core).unwrap();
+    let release_id = *w.releases.get(&(core, version)).unwrap();
+    let user = w.auth_user(&user).await.unwrap();
+
+    let result = user
+        .lock()
+        .await
+        .verify_core_release(core_id, release_id)
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} merges core {word} into core {word}")]
+async fn cores_merge(w: &mut World, user: UserParam, source: String, target: String) {
+    let source_id = *w.cores.get(&source).unwrap();
+    let target_id = *w.cores.get(&target).unwrap();
+    let user = w.auth_user(&user).await.unwrap();
+
+    let result = user.lock().await.merge_cores(source_id, target_id).await;
+    w.record_result(result);
+}
+
+#[then(expr = "core {word} was deleted by {user}")]
+async fn core_deleted_by(w: &mut World, core: String, user: UserParam) {
+    let core_id = *w.cores.get(&core).unwrap();
+    let user_id = w.user(&user).await.unwrap().lock().await.id();
+    assert_eq!(w.core_deleted_by(core_id).await.unwrap(), Some(user_id));
+}
+
+#[then(expr = "core {word} redirects to core {word}")]
+async fn core_redirects(w: &mut World, core: String, target: String) {
+    let slug = w.resource_slugs.get(&core).unwrap().clone();
+    let target_slug = w.resource_slugs.get(&target).unwrap().clone();
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let location = user.lock().await.core_redirect(&slug).await.unwrap();
+    assert_eq!(location, Some(format!("/api/v1/cores/{target_slug}")));
+    assert_eq!(
+        user.lock().await.core_redirect(&target_slug).await.unwrap(),
+        None
+    );
+}
+
+#[then(expr = "{int} release(s) and {int} tag(s) were moved")]
+async fn cores_merged(w: &mut World, releases: i64, tags: i64) {
+    let result: dto::cores::CoreMergeResponse = w.last_result();
+    assert_eq!(result.moved_releases, releases);
+    assert_eq!(result.moved_tags, tags);
+}
+
+#[then(expr = "artifact {word} is {word} in the verification")]
+async fn artifact_verified(w: &mut World, artifact: String, validity: String) {
+    let result: dto::artifact::ReleaseVerifyResponse = w.last_result();
+    let artifact_id = *w.artifacts.get(&artifact).unwrap();
+    let item = result
+        .artifacts
+        .iter()
+        .find(|a| a.id == artifact_id)
+        .expect("Artifact not in the verification");
+
+    match validity.as_str() {
+        "valid" => {
+            assert!(item.valid);
+            assert_eq!(Some(&item.expected), item.actual.as_ref());
+        }
+        "invalid" => {
+            assert!(!item.valid);
+            assert_ne!(Some(&item.expected), item.actual.as_ref());
+        }
+        "unreadable" => {
+            assert!(!item.valid);
+            assert!(item.actual.is_none());
+            assert!(item.error.is_some());
+        }
+        _ => panic!("Unknown validity {validity:?}"),
+    }
+}
+
+#[then(expr = "the artifact matches artifact {word} in the list of version {word} of core {word}")]
+async fn core_release_artifact_matches_list(
+    w: &mut World,
+    artifact: String,
+    version: String,
+    core: String,
+) {
+    let actual: dto::artifact::CoreReleaseArtifactListItem = w.last_result();
+
+    let core_id = *w.cores.get(&core).unwrap();
+    let release_id = *w.releases.get(&(core, version)).unwrap();
+    let artifact_id = *w.artifacts.get(&artifact).unwrap();
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let list = user
+        .lock()
+        .await
+        .core_release_artifacts(core_id, release_id, None, None)
+        .await
+        .unwrap();
+    let expected = list
+        .items
+        .into_iter()
+        .find(|a| a.id == artifact_id)
+        .expect("Artifact not in the list of artifacts.");
+
+    assert_eq!(actual.filename, format!("{artifact}.bin"));
+    let data = artifact_data(w, &artifact);
+    assert_eq!(actual.size as usize, data.len());
+    assert_eq!(
+        actual.r#ref.crc32.as_deref(),
+        Some(&retronomicon_db::models::artifact::crc32(&data))
+    );
+    assert_eq!(
+        serde_json::to_value(actual).unwrap(),
+        serde_json::to_value(expected).unwrap()
+    );
+}
+
+#[then(expr = "the latest releases of core {word} are {string}")]
+async fn core_latest_releases(w: &mut World, core: String, expected: String) {
+    w.assert_result_ok();
+
+    let core_id = *w.cores.get(&core).unwrap();
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let details = user.lock().await.core_details(core_id).await.unwrap();
+
+    let mut releases = details
+        .releases_by_platform
+        .iter()
+        .map(|r| {
+            let (platform, _) = w
+                .platforms
+                .iter()
+                .find(|(_, id)| **id == r.platform.id)
+                .expect("Unknown platform");
+            format!("{platform} {}", r.version)
+        })
+        .collect::<Vec<_>>();
+    releases.sort();
+
+    assert_eq!(releases.join(", "), expected);
+}
+
+#[when(expr = "{user} lists the releases of system {word}")]
+async fn releases_list_for_system(w: &mut World, user: UserParam, system: String) {
+    let system_id = *w.systems.get(&system).unwrap();
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.releases_for_system(system_id, None).await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} lists the releases of system {word} sorted by {string}")]
+async fn releases_list_for_system_sorted(
+    w: &mut World,
+    user: UserParam,
+    system: String,
+    sort: String,
+) {
+    let system_id = *w.systems.get(&system).unwrap();
+    let user = w.user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .releases_for_system(system_id, Some(&sort))
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} lists the releases of system {word} with core slug {string}")]
+async fn releases_list_for_system_with_core_slug(
+    w: &mut World,
+    user: UserParam,
+    system: String,
+    core_slug: String,
+) {
+    let system_id = *w.systems.get(&system).unwrap();
+    let user = w.user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .releases_for_system_with_core_slug(system_id, &core_slug)
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} lists the cores of system {word} sorted by {string}")]
+async fn cores_list_for_system_sorted(
+    w: &mut World,
+    user: UserParam,
+    system: String,
+    sort: String,
+) {
+    let system_id = *w.systems.get(&system).unwrap();
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.cores_for_system(system_id, &sort).await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} lists the cores of system {word} with their platforms")]
+async fn cores_list_for_system_with_platforms(w: &mut World, user: UserParam, system: String) {
+    let system_id = *w.systems.get(&system).unwrap();
+    let user = w.user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .cores_for_system_with_platforms(system_id)
+        .await;
+    w.record_result(result);
+}
+
+#[then(expr = "core {word} in the list has platforms {string}")]
+async fn core_in_list_has_platforms(w: &mut World, core: String, expected: String) {
+    let list: dto::Paginated<dto::cores::CoreListItem> = w.last_result();
+    let core_id = *w.cores.get(&core).unwrap();
+    let item = list
+        .items
+        .iter()
+        .find(|c| c.id == core_id)
+        .expect("Core not in the list");
+
+    let mut platforms = item
+        .platforms
+        .as_ref()
+        .expect("Platforms not in the list")
+        .iter()
+        .map(|p| {
+            let (platform, _) = w
+                .platforms
+                .iter()
+                .find(|(_, id)| **id == p.id)
+                .expect("Unknown platform");
+            platform.clone()
+        })
+        .collect::<Vec<_>>();
+    platforms.sort();
+
+    assert_eq!(platforms.join(", "), expected);
+}
+
+#[then(expr = "the core list is {string}")]
+async fn core_list_is(w: &mut World, expected: String) {
+    let list: dto::Paginated<dto::cores::CoreListItem> = w.last_result();
+    let cores = list
+        .items
+        .iter()
+        .map(|c| {
+            let (core, _) = w
+                .cores
+                .iter()
+                .find(|(_, id)| **id == c.id)
+                .expect("Unknown core");
+            core.clone()
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(cores.join(", "), expected);
+}
+
+#[then(expr = "the release list is {string}")]
+async fn release_list_is(w: &mut World, expected: String) {
+    let list: dto::Paginated<dto::cores::releases::CoreReleaseListItem> = w.last_result();
+    let releases = list
+        .items
+        .iter()
+        .map(|r| {
+            let (core, _) = w
+                .cores
+                .iter()
+                .find(|(_, id)| **id == r.core.id)
+                .expect("Unknown core");
+            format!("{core} {}", r.release.version)
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(releases.join(", "), expected);
+}
+
+#[then(expr = "the required system releases are {string}")]
+async fn release_list_required_system_releases(w: &mut World, expected: String) {
+    let list: dto::Paginated<dto::cores::releases::CoreReleaseListItem> = w.last_result();
+    let releases = list
+        .items
+        .iter()
+        .map(|r| {
+            let required = match &r.required_system_release {
+                Some(required) => w
+                    .system_releases
+                    .iter()
+                    .find(|(_, id)| **id == required.id)
+                    .map(|(name, _)| name.as_str())
+                    .expect("Unknown system release"),
+                None => "none",
+            };
+            format!("{}: {required}", r.release.version)
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(releases.join(", "), expected);
+}
+
+#[when(expr = "{user} lists games at page {int} with limit {int}")]
+async fn games_list_page(w: &mut World, user: UserParam, page: i64, limit: i64) {
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.games_page(page, limit).await;
+    w.record_result(result);
+}
+
+#[given(expr = "the games table was analyzed")]
+async fn games_analyzed(w: &mut World) {
+    w.analyze_games().await.unwrap();
+}
+
+#[when(expr = "{user} lists games with count {word}")]
+async fn games_list_counted(w: &mut World, user: UserParam, count: String) {
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.games_counted(None, &count).await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} lists the games of system {word} with count {word}")]
+async fn games_list_system_counted(w: &mut World, user: UserParam, system: String, count: String) {
+    let system_id = *w.systems.get(&system).unwrap();
+    let user = w.user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .games_counted(Some(system_id), &count)
+        .await;
+    w.record_result(result);
+}
+
+#[then(expr = "the games total is an estimate within {int}% of the exact total")]
+async fn games_total_estimate(w: &mut World, percent: i64) {
+    let estimated: dto::Paginated<dto::games::GameListItemResponse> = w.last_result();
+    assert!(estimated.count_is_estimate);
+
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let exact = user
+        .lock()
+        .await
+        .games_counted(None, "exact")
+        .await
+        .unwrap();
+    assert!(!exact.count_is_estimate);
+    assert!(
+        (estimated.total - exact.total).abs() * 100 <= exact.total * percent,
+        "Estimated {} games, counted {}",
+        estimated.total,
+        exact.total
+    );
+}
+
+#[then(expr = "the games total is exactly {int}")]
+async fn games_total_exact(w: &mut World, total: i64) {
+    let games: dto::Paginated<dto::games::GameListItemResponse> = w.last_result();
+    assert!(!games.count_is_estimate);
+    assert_eq!(games.total, total);
+}
+
+#[when(expr = "{user} creates a game {word} on system {word}")]
+async fn game_create(w: &mut World, user: UserParam, game: String, system: String) {
+    w.assert_result_ok();
+
+    let user = w.auth_user(&user).await.unwrap();
+    let system_id = *w.systems.get(&system).unwrap();
+
+    let result = user.lock().await.create_game(system_id, &game).await;
+    if let Ok(g) = &result {
+        w.games.insert(game.clone(), g.id);
+    }
+    w.record_result(result);
+}
+
+#[when(expr = "{user} creates a game {word} with unique id {int} on system {word}")]
+async fn game_create_with_unique_id(
+    w: &mut World,
+    user: UserParam,
+    game: String,
+    unique_id: i32,
+    system: String,
+) {
+    let user = w.auth_user(&user).await.unwrap();
+    let system_id = *w.systems.get(&system).unwrap();
+
+    let result = user
+        .lock()
+        .await
+        .create_game_with_unique_id(system_id, &game, Some(unique_id))
+        .await;
+    if let Ok(g) = &result {
+        w.games.insert(game.clone(), g.id);
+    }
+    w.record_result(result);
+}
+
+#[when(expr = "{user} creates a game {word} without unique id on system {word}")]
+async fn game_create_without_unique_id(
+    w: &mut World,
+    user: UserParam,
+    game: String,
+    system: String,
+) {
+    let user = w.auth_user(&user).await.unwrap();
+    let system_id = *w.systems.get(&system).unwrap();
+
+    let result = user
+        .lock()
+        .await
+        .create_game_with_unique_id(system_id, &game, None)
+        .await;
+    if let Ok(g) = &result {
+        w.games.insert(game.clone(), g.id);
+    }
+    w.record_result(result);
+}
+
+#[when(expr = "{user} creates a game {word} released in {string} in {string} on system {word}")]
+async fn game_create_in_region(
+    w: &mut World,
+    user: UserParam,
+    game: String,
+    region: String,
+    languages: String,
+    system: String,
+) {
+    let user = w.auth_user(&user).await.unwrap();
+    let system_id = *w.systems.get(&system).unwrap();
+    let languages = languages.split(',').map(str::trim).collect::<Vec<_>>();
+
+    let result = user
+        .lock()
+        .await
+        .create_game_in_region(system_id, &game, &region, &languages)
+        .await;
+    if let Ok(g) = &result {
+        w.games.insert(game.clone(), g.id);
+    }
+    w.record_result(result);
+}
+
+#[then(expr = "game {word} was released in {string} in {string}")]
+async fn game_has_region(w: &mut World, game: String, region: String, languages: String) {
+    w.assert_result_ok();
+
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let game_id = *w.games.get(&game).unwrap();
+    let result = user.lock().await.get_game_by_id(game_id).await.unwrap();
+
+    assert_eq!(result.region.as_deref(), Some(region.as_str()));
+    assert_eq!(result.languages.join(","), languages);
+}
+
+#[when(expr = "{user} lists the games of system {word} in region {string}")]
+async fn games_list_for_system_in_region(
+    w: &mut World,
+    user: UserParam,
+    system: String,
+    region: String,
+) {
+    let system_id = *w.systems.get(&system).unwrap();
+    let user = w.user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .games_for_system_in_region(system_id, &region)
+        .await;
+    w.record_result(result);
+}
+
+#[then(expr = "game {word} has unique id {int}")]
+async fn game_has_unique_id(w: &mut World, game: String, unique_id: i32) {
+    w.assert_result_ok();
+
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let game_id = *w.games.get(&game).unwrap();
+    let result = user.lock().await.get_game_by_id(game_id).await.unwrap();
+
+    assert_eq!(result.system_unique_id, unique_id);
+}
+
+#[when(expr = "{user} adds {int} artifact(s) to game {word}")]
+async fn game_add_artifacts(w: &mut World, user: UserParam, count: usize, game: String) {
+    let user = w.auth_user(&user).await.unwrap();
+    let game_id = *w.games.get(&game).unwrap();
+    for _ in 0..count {
+        let result = user.lock().await.add_game_artifact(game_id).await;
+        w.record_result(result);
+    }
+}
+
+#[when(expr = "{user} merges game {word} into game {word}")]
+async fn games_merge(w: &mut World, user: UserParam, drop: String, keep: String) {
+    let user = w.auth_user(&user).await.unwrap();
+    let keep_id = *w.games.get(&keep).unwrap();
+    let drop_id = *w.games.get(&drop).unwrap();
+    let result = user.lock().await.merge_games(keep_id, drop_id).await;
+    w.record_result(result);
+}
+
+#[then(expr = "game {word} does not exist")]
+async fn game_does_not_exist(w: &mut World, game: String) {
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let game_id = *w.games.get(&game).unwrap();
+    let result = user.lock().await.get_game_by_id(game_id).await;
+    w.record_result(result);
+    w.assert_result_err_status(404);
+    w.reset_result();
+}
+
+#[then(expr = "game {word} of system {word} lists {int} artifact(s)")]
+async fn game_lists_artifacts(w: &mut World, game: String, system: String, count: usize) {
+    w.assert_result_ok();
+
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let game_id = *w.games.get(&game).unwrap();
+    let system_id = *w.systems.get(&system).unwrap();
+    let games = user
+        .lock()
+        .await
+        .games_for_system(system_id, "name")
+        .await
+        .unwrap();
+    let game = games
+        .items
+        .iter()
+        .find(|g| g.id == game_id)
+        .expect("Game not listed");
+
+    assert_eq!(game.artifacts.len(), count);
+}
+
+#[then(expr = "game {word} exists on system {word}")]
+async fn game_exists(w: &mut World, game: String, system: String) {
+    w.assert_result_ok();
+
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let game_id = *w.games.get(&game).unwrap();
+    let system_id = *w.systems.get(&system).unwrap();
+    let result = user.lock().await.get_game_by_id(game_id).await.unwrap();
+
+    assert_eq!(result.system.id, system_id);
+}
+
+#[when(expr = "{user} lists the games of system {word} sorted by {string}")]
+async fn games_list_for_system_sorted(
+    w: &mut World,
+    user: UserParam,
+    system: String,
+    sort: String,
+) {
+    let system_id = *w.systems.get(&system).unwrap();
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.games_for_system(system_id, &sort).await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} lists the games of system {string}")]
+async fn games_list_for_raw_system(w: &mut World, user: UserParam, system: String) {
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.games_for_raw_system(&system).await;
+    w.record_result(result);
+}
+
+#[then(expr = "the game list is {string}")]
+async fn game_list_is(w: &mut World, expected: String) {
+    let list: dto::Paginated<dto::games::GameListItemResponse> = w.last_result();
+    let games = list
+        .items
+        .iter()
+        .map(|g| g.name.as_str())
+        .collect::<Vec<_>>();
+
+    assert_eq!(games.join(", "), expected);
+}
+
+/// Create a game on a default system.
+#[given(expr = "game {word}")]
+async fn given_a_game(w: &mut World, game: String) {
+    given_a_user_auth(w, UserParam::Admin("default".to_string())).await;
+    given_team_owned(
+        w,
+        "default".to_string(),
+        UserParam::Admin("default".to_string()),
+    )
+    .await;
+
+    system_owned(
+        w,
+        "default".to_string(),
+        UserParam::Admin("default".to_string()),
+        "default".to_string(),
+    )
+    .await;
+    game_create(
+        w,
+        UserParam::Admin("default".to_string()),
+        game,
+        "default".to_string(),
+    )
+    .await;
+    w.assert_result_ok();
+}
+
+#[when(expr = "{user} uploads image {word} to game {word}")]
+async fn game_upload_image(w: &mut World, user: UserParam, image: String, game: String) {
+    let user = w.auth_user(&user).await.unwrap();
+    let game_id = *w.games.get(&game).unwrap();
+
+    let result = user.lock().await.upload_image(game_id, &image).await;
+    w.record_result(result);
+}
+
+#[given(expr = "{user} uploaded {int} images to game {word}")]
+async fn game_upload_images(w: &mut World, user: UserParam, count: usize, game: String) {
+    let user = w.auth_user(&user).await.unwrap();
+    let game_id = *w.games.get(&game).unwrap();
+
+    let mut user = user.lock().await;
+    for i in 0..count {
+        user.upload_image(game_id, &format!("Many{i}"))
+            .await
+            .unwrap();
+    }
+}
+
+#[then(expr = "{user} can see image {word} of game {word}")]
+async fn then_user_can_see(w: &mut World, user: UserParam, image: String, game: String) {
+    w.assert_result_ok();
+
+    let user = w.user(&user).await.unwrap();
+    let game_id = *w.games.get(&game).unwrap();
+    let result = user.lock().await.get_game_images(game_id).await.unwrap();
+
+    let i = result
+        .items
+        .iter()
+        .find(|i| i.name == format!("{image}.png"))
+        .expect("Image not in the list of images.");
+
+    // Download the image.
+    reqwest::get(&i.url)
+        .await
+        .expect("Could not get a response")
+        .error_for_status()
+        .expect("Could not download the image");
+}
+
+#[then(expr = "{user} sees the images {string} of game {word}")]
+async fn then_user_sees_images(w: &mut World, user: UserParam, images: String, game: String) {
+    let user = w.user(&user).await.unwrap();
+    let game_id = *w.games.get(&game).unwrap();
+    let result = user.lock().await.get_game_images(game_id).await.unwrap();
+
+    let mut names = result
+        .items
+        .iter()
+        .map(|i| {
+            assert_eq!(i.mime_type, "image/png");
+            assert!(!i.url.is_empty());
+            i.name.as_str()
+        })
+        .collect::<Vec<_>>();
+    names.sort();
+
+    let mut expected = images
+        .split(',')
+        .map(|i| format!("{}.png", i.trim()))
+        .collect::<Vec<_>>();
+    expected.sort();
+    assert_eq!(names, expected);
+}
+
+#[when(expr = "{user} sets image {word} as the primary image of game {word}")]
+async fn game_set_primary_image(w: &mut World, user: UserParam, image: String, game: String) {
+    let user = w.auth_user(&user).await.unwrap();
+    let game_id = *w.games.get(&game).unwrap();
+
+    let result = user
+        .lock()
+        .await
+        .set_game_primary_image(game_id, &format!("{image}.png"))
+        .await;
+    w.record_result(result);
+}
+
+#[then(expr = "{user} sees image {word} as the primary image of game {word}")]
+async fn then_user_sees_primary_image(w: &mut World, user: UserParam, image: String, game: String) {
+    w.assert_result_ok();
+
+    let user = w.user(&user).await.unwrap();
+    let game_id = *w.games.get(&game).unwrap();
+    let game = user.lock().await.get_game_by_id(game_id).await.unwrap();
+
+    let primary = game.primary_image.expect("Game has no primary image.");
+    assert_eq!(primary.name, format!("{image}.png"));
+}
+
+#[when(expr = "{user} requests a password reset")]
+async fn user_requests_password_reset(w: &mut World, user: UserParam) {
+    let user = w.user(&user).await.unwrap();
+    let email = user.lock().await.email().to_string();
+    let result = user.lock().await.forgot_password(&email).await;
+    w.record_result(result);
+
+    // Keep the first token around to make sure it does not change.
+    if let Some(token) = w.reset_token(&email).await.unwrap() {
+        w.reset_tokens.entry(email).or_insert(token);
+    }
+}
+
+#[when(expr = "anonymous user requests a password reset for {string}")]
+async fn anonymous_requests_password_reset(w: &mut World, email: String) {
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let result = user.lock().await.forgot_password(&email).await;
+    w.record_result(result);
+}
+
+#[when(expr = "the password reset token of {user} expired")]
+async fn password_reset_token_expired(w: &mut World, user: UserParam) {
+    let user = w.user(&user).await.unwrap();
+    let email = user.lock().await.email().to_string();
+    w.expire_reset_token(&email).await.unwrap();
+}
+
+#[when(expr = "{user} resets their password to {string}")]
+async fn user_resets_password(w: &mut World, user: UserParam, password: String) {
+    w.assert_result_ok();
+
+    let user = w.user(&user).await.unwrap();
+    let email = user.lock().await.email().to_string();
+    let token = w
+        .reset_token(&email)
+        .await
+        .unwrap()
+        .expect("No reset token for user");
+    let result = user.lock().await.reset_password(&token, &password).await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} resets a password with token {string}")]
+async fn user_resets_password_with_token(w: &mut World, user: UserParam, token: String) {
+    let user = w.user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .reset_password(&token, "some-new-password")
+        .await;
+    w.record_result(result);
+}
+
+#[then(expr = "{user} can login with password {string}")]
+async fn user_can_login(w: &mut World, user: UserParam, password: String) {
+    w.assert_result_ok();
+
+    let user = w.user(&user).await.unwrap();
+    user.lock().await.login(&password).await.unwrap();
+}
+
+#[then(expr = "{user} cannot login with their old password")]
+async fn user_cannot_login_old_password(w: &mut World, user: UserParam) {
+    let user = w.user(&user).await.unwrap();
+    let mut user = user.lock().await;
+    let password = user.initial_password();
+    assert!(user.login(&password).await.is_err());
+}
+
+#[then(expr = "the password reset token of {user} did not change")]
+async fn password_reset_token_unchanged(w: &mut World, user: UserParam) {
+    w.assert_result_ok();
+
+    let user = w.user(&user).await.unwrap();
+    let email = user.lock().await.email().to_string();
+    let token = w.reset_token(&email).await.unwrap();
+    assert_eq!(token.as_ref(), w.reset_tokens.get(&email));
+}
+
+#[when(expr = "{user} requires {user} to reset their password")]
+async fn user_requires_password_reset(w: &mut World, admin: UserParam, user: UserParam) {
+    let user = w.user(&user).await.unwrap();
+    let user_id = user.lock().await.id();
+    let admin = w.auth_user(&admin).await.unwrap();
+    let result = admin.lock().await.require_password_reset(user_id).await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} logs in with their old password")]
+async fn user_logs_in_old_password(w: &mut World, user: UserParam) {
+    let user = w.user(&user).await.unwrap();
+    let mut user = user.lock().await;
+    let password = user.initial_password();
+    let result = user.login(&password).await;
+    w.record_result(result);
+}
+
+#[then(expr = "{user} can login with their old password")]
+async fn user_can_login_old_password(w: &mut World, user: UserParam) {
+    let user = w.user(&user).await.unwrap();
+    let mut user = user.lock().await;
+    let password = user.initial_password();
+    user.login(&password).await.unwrap();
+}
+
+#[when(expr = "anonymous user signs up with password {string}")]
+async fn anonymous_signs_up(w: &mut World, password: String) {
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let result = user.lock().await.signup(&password).await;
+    w.record_result(result);
+}
+
+#[then(expr = "the signup returned a token expiring in {int} days")]
+async fn signup_returned_token(w: &mut World, days: i64) {
+    let response: dto::auth::SignupResponse = w.last_result();
+    assert!(response.token.is_some_and(|t| !t.is_empty()));
+
+    let expected = chrono::Utc::now().timestamp() + days * 24 * 60 * 60;
+    let expiry = response.token_expiry.expect("No token expiry");
+    assert!((expiry - expected).abs() < 60, "Unexpected expiry {expiry}");
+}
+
+#[when(expr = "{user} requests a login link by email")]
+async fn user_requests_email_login(w: &mut World, user: UserParam) {
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.email_login_request().await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} logs in with the token received by email")]
+async fn user_confirms_email_login(w: &mut World, user: UserParam) {
+    w.assert_result_ok();
+
+    let user = w.user(&user).await.unwrap();
+    let email = user.lock().await.email().to_string();
+    let token = w
+        .validation_token(&email)
+        .await
+        .unwrap()
+        .expect("No validation token for user");
+    w.login_tokens.insert(email, token.clone());
+    let result = user.lock().await.email_login_confirm(&token).await;
+    w.record_result(result);
+}
+
+#[when(expr = "the email token of {user} was sent {int} days ago")]
+async fn email_token_aged(w: &mut World, user: UserParam, days: i32) {
+    let user = w.user(&user).await.unwrap();
+    let email = user.lock().await.email().to_string();
+    w.age_password(&email, days).await.unwrap();
+}
+
+#[when(expr = "{user} expires old tokens")]
+async fn user_expires_tokens(w: &mut World, user: UserParam) {
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user.lock().await.expire_tokens().await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} backfills the SHA1 of artifacts")]
+async fn user_backfills_sha1(w: &mut World, user: UserParam) {
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user.lock().await.backfill_sha1(None).await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} backfills the SHA1 of {int} artifacts")]
+async fn user_backfills_sha1_limit(w: &mut World, user: UserParam, limit: i64) {
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user.lock().await.backfill_sha1(Some(limit)).await;
+    w.record_result(result);
+}
+
+#[then(expr = "{user} has no email token")]
+async fn user_has_no_email_token(w: &mut World, user: UserParam) {
+    let user = w.user(&user).await.unwrap();
+    let email = user.lock().await.email().to_string();
+    assert_eq!(w.validation_token(&email).await.unwrap(), None);
+}
+
+#[then(expr = "{user} has an email token")]
+async fn user_has_email_token(w: &mut World, user: UserParam) {
+    let user = w.user(&user).await.unwrap();
+    let email = user.lock().await.email().to_string();
+    assert!(w.validation_token(&email).await.unwrap().is_some());
+}
+
+#[when(expr = "{user} logs in again with the same token")]
+async fn user_replays_email_login(w: &mut World, user: UserParam) {
+    let user = w.user(&user).await.unwrap();
+    let email = user.lock().await.email().to_string();
+    let token = w.login_tokens.get(&email).expect("No token used").clone();
+    let result = user.lock().await.email_login_confirm(&token).await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} logs in with the email token {string}")]
+async fn user_confirms_email_login_with_token(w: &mut World, user: UserParam, token: String) {
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.email_login_confirm(&token).await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} changes their password to {string}")]
+async fn user_changes_password(w: &mut World, user: UserParam, password: String) {
+    let user = w.user(&user).await.unwrap();
+    let mut user = user.lock().await;
+    let current_password = user.initial_password();
+    let result = user.change_password(&current_password, &password).await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} changes their password from {string} to {string}")]
+async fn user_changes_password_from(
+    w: &mut World,
+    user: UserParam,
+    current_password: String,
+    password: String,
+) {
+    let user = w.user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .change_password(&current_password, &password)
+        .await;
+    w.record_result(result);
+}
+
+#[then(expr = "the error contains {string}")]
+async fn error_contains(w: &mut World, expected: String) {
+    w.assert_result_err_contains(&expected);
+}
+
+#[then(expr = "the error does not contain {string}")]
+async fn error_does_not_contain(w: &mut World, unexpected: String) {
+    w.assert_result_err_not_contains(&unexpected);
+}
+
+#[then(expr = "an error occured with status {int}")]
+async fn an_error_occured_with_status(w: &mut World, status: u16) {
+    w.assert_result_err_status(status);
+    w.reset_result();
+}
+
+#[when(expr = "{user} creates a team {word} with slug {word}")]
+async fn user_creates_team_with_slug(w: &mut World, user: UserParam, team: String, slug: String) {
+    let slug = w.slug(&slug);
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .create_team_with_slug(&team, &slug, false)
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} upserts a team {word} with slug {word}")]
+async fn user_upserts_team_with_slug(w: &mut World, user: UserParam, team: String, slug: String) {
+    let slug = w.slug(&slug);
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .create_team_with_slug(&team, &slug, true)
+        .await;
+    w.record_result(result);
+}
+
+#[then(expr = "the team with slug {word} was {word}")]
+async fn team_created_or_existing(w: &mut World, slug: String, outcome: String) {
+    let response: dto::teams::TeamCreateResponse = w.last_result();
+    assert_eq!(response.slug, w.slug(&slug));
+    match outcome.as_str() {
+        "created" => assert!(response.created),
+        "reused" => assert!(!response.created),
+        _ => panic!("Unknown outcome {outcome:?}"),
+    }
+}
+
+#[when(expr = "{user} deletes team {word}")]
+async fn user_deletes_team(w: &mut World, user: UserParam, team: String) {
+    let team_id = w.teams.get(&team).unwrap().id;
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user.lock().await.delete_team(team_id).await;
+    w.record_result(result);
+}
+
+#[given(expr = "joining a team with slug {word} fails")]
+async fn joining_team_fails(w: &mut World, slug: String) {
+    let slug = w.slug(&slug);
+    w.fail_team_join(&slug).await.unwrap();
+}
+
+#[then(expr = "no team with slug {word} exists")]
+async fn no_team_with_slug(w: &mut World, slug: String) {
+    let slug = w.slug(&slug);
+    assert!(!w.team_slug_exists(&slug).await.unwrap());
+}
+
+#[when(expr = "{user} checks the detailed health of the server")]
+async fn user_checks_health(w: &mut World, user: UserParam) {
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.health_detailed().await;
+    w.record_result(result);
+}
+
+#[then(expr = "all components are healthy")]
+async fn all_components_healthy(w: &mut World) {
+    let result: dto::health::HealthDetailedResponse = w.last_result();
+    assert_eq!(result.database, dto::health::ComponentStatus::Ok);
+    assert_eq!(result.storage, dto::health::ComponentStatus::Ok);
+    assert!(!result.version.is_empty());
+}
+
+#[given(expr = "{user} has linked provider {word}")]
+async fn user_has_linked_provider(w: &mut World, user: UserParam, provider: String) {
+    let user = w.user(&user).await.unwrap();
+    let id = user.lock().await.id();
+    // Providers identify accounts by an opaque id, never by email.
+    w.link_provider(id, &provider, &format!("{provider}-account-{id}"))
+        .await
+        .unwrap();
+}
+
+#[given(expr = "{user} has no password")]
+async fn user_has_no_password(w: &mut World, user: UserParam) {
+    let user = w.user(&user).await.unwrap();
+    let id = user.lock().await.id();
+    w.delete_password(id).await.unwrap();
+}
+
+#[when(expr = "{user} unlinks provider {word}")]
+async fn user_unlinks_provider(w: &mut World, user: UserParam, provider: String) {
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.unlink_provider(&provider).await;
+    w.record_result(result);
+}
+
+#[then(expr = "{user} will have linked providers {string}")]
+async fn user_has_providers(w: &mut World, user: UserParam, providers: String) {
+    w.assert_result_ok();
+
+    let user = w.user(&user).await.unwrap();
+    let mut actual = user
+        .lock()
+        .await
+        .providers()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|p| p.provider)
+        .collect::<Vec<_>>();
+    actual.sort();
+    let mut expected = providers
+        .split(',')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(String::from)
+        .collect::<Vec<_>>();
+    expected.sort();
+    assert_eq!(actual, expected);
+}
+
+#[given(expr = "a DAT {word} with {int} games")]
+async fn given_a_dat(w: &mut World, dat: String, count: usize) {
+    let name = w.slug(&dat);
+    w.dats.insert(dat, crate::user::create_dat(&name, count));
+}
+
+#[when(expr = "{user} imports DAT {word} into system {word}")]
+async fn user_imports_dat(w: &mut World, user: UserParam, dat: String, system: String) {
+    let user = w.auth_user(&user).await.unwrap();
+    let system_id = *w.systems.get(&system).unwrap();
+    let dat = w.dats.get(&dat).unwrap().clone();
+
+    let result = user.lock().await.import_dat(system_id, &dat).await;
+    w.record_result(result);
+}
+
+#[then(
+    expr = "searching system {word} for game {int} of DAT {word} with the md5 of game {int} finds {int} game(s)"
+)]
+async fn search_games_by_name_and_md5(
+    w: &mut World,
+    system: String,
+    name_game: usize,
+    dat: String,
+    md5_game: usize,
+    count: usize,
+) {
+    w.assert_result_ok();
+
+    let system_id = *w.systems.get(&system).unwrap();
+    let dat = datary::from_reader(w.dats.get(&dat).unwrap().as_bytes()).unwrap();
+    let name = dat.games[name_game].name.clone();
+    let md5 = dat.games[md5_game]
+        .roms
+        .iter()
+        .map(|r| r.md5.as_deref().unwrap().parse().unwrap())
+        .collect();
+
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let games = user
+        .lock()
+        .await
+        .search_games(system_id, &name, md5)
+        .await
+        .unwrap();
+
+    assert_eq!(games.items.len(), count);
+    assert!(games.items.iter().all(|g| g.name == name));
+}
+
+#[then(
+    expr = "searching system {word} for any of the md5 of game {int} and the sha1 of game {int} of DAT {word} finds {string}"
+)]
+async fn search_games_by_any_checksum(
+    w: &mut World,
+    system: String,
+    md5_game: usize,
+    sha1_game: usize,
+    dat: String,
+    expected: String,
+) {
+    search_games_by_checksums(
+        w,
+        system,
+        md5_game,
+        sha1_game,
+        dat,
+        dto::games::ChecksumMatchMode::Any,
+        expected,
+    )
+    .await;
+}
+
+#[then(
+    expr = "searching system {word} for all of the md5 of game {int} and the sha1 of game {int} of DAT {word} finds {string}"
+)]
+async fn search_games_by_all_checksums(
+    w: &mut World,
+    system: String,
+    md5_game: usize,
+    sha1_game: usize,
+    dat: String,
+    expected: String,
+) {
+    search_games_by_checksums(
+        w,
+        system,
+        md5_game,
+        sha1_game,
+        dat,
+        dto::games::ChecksumMatchMode::All,
+        expected,
+    )
+    .await;
+}
+
+#[then(expr = "searching system {word} for the {word} of game {int} of DAT {word} finds {string}")]
+async fn search_games_by_checksum(
+    w: &mut World,
+    system: String,
+    kind: String,
+    game: usize,
+    dat: String,
+    expected: String,
+) {
+    w.assert_result_ok();
+
+    let system_id = *w.systems.get(&system).unwrap();
+    let dat = datary::from_reader(w.dats.get(&dat).unwrap().as_bytes()).unwrap();
+    let rom = &dat.games[game].roms[0];
+    let checksum = match kind.as_str() {
+        "md5" => rom.md5.as_deref(),
+        "sha1" => rom.sha1.as_deref(),
+        "crc32" => rom.crc.as_deref(),
+        _ => panic!("Unknown checksum kind {kind}"),
+    };
+    let checksums = vec![checksum.unwrap().parse().unwrap()];
+    let body = match kind.as_str() {
+        "md5" => dto::games::GameListBody {
+            md5: Some(checksums),
+            ..Default::default()
+        },
+        "sha1" => dto::games::GameListBody {
+            sha1: Some(checksums),
+            ..Default::default()
+        },
+        _ => dto::games::GameListBody {
+            crc32: Some(checksums),
+            ..Default::default()
+        },
+    };
+
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let games = user
+        .lock()
+        .await
+        .search_games_by_body(system_id, &body)
+        .await
+        .unwrap();
+
+    let found = games
+        .items
+        .iter()
+        .map(|g| {
+            dat.games
+                .iter()
+                .position(|d| d.name == g.name)
+                .expect("Unknown game")
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(found.join(", "), expected);
+}
+
+/// Search games by the md5 of a game and the sha1 of another one, and check the
+/// indices (in the DAT) of the games found.
+async fn search_games_by_checksums(
+    w: &mut World,
+    system: String,
+    md5_game: usize,
+    sha1_game: usize,
+    dat: String,
+    mode: dto::games::ChecksumMatchMode,
+    expected: String,
+) {
+    w.assert_result_ok();
+
+    let system_id = *w.systems.get(&system).unwrap();
+    let dat = datary::from_reader(w.dats.get(&dat).unwrap().as_bytes()).unwrap();
+    let md5 = vec![dat.games[md5_game].roms[0]
+        .md5
+        .as_deref()
+        .unwrap()
+        .parse()
+        .unwrap()];
+    let sha1 = vec![dat.games[sha1_game].roms[0]
+        .sha1
+        .as_deref()
+        .unwrap()
+        .parse()
+        .unwrap()];
+
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let games = user
+        .lock()
+        .await
+        .search_games_by_checksums(system_id, md5, sha1, mode)
+        .await
+        .unwrap();
+
+    let mut found = games
+        .items
+        .iter()
+        .map(|g| {
+            dat.games
+                .iter()
+                .position(|d| d.name == g.name)
+                .expect("Unknown game")
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+    found.sort();
+    assert_eq!(found.join(", "), expected);
+}
+
+/// The games of a DAT and their ROMs, sorted, for comparing DATs.
+fn dat_roms(dat: datary::Datafile) -> Vec<(String, Vec<DatRom>)> {
+    let mut games = dat
+        .games
+        .into_iter()
+        .map(|g| {
+            let roms = g
+                .roms
+                .into_iter()
+                .map(|r| (r.name, r.size as i32, r.crc, r.md5, r.sha1))
+                .collect::<Vec<_>>();
+            (g.name, roms)
+        })
+        .collect::<Vec<_>>();
+    games.sort();
+    games
+}
+
+type DatRom = (String, i32, Option<String>, Option<String>, Option<String>);
+
+#[then(expr = "the games DAT of system {word} matches DAT {word}")]
+async fn games_dat_matches(w: &mut World, system: String, dat: String) {
+    w.assert_result_ok();
+
+    let system_id = *w.systems.get(&system).unwrap();
+    let dat = datary::from_reader(w.dats.get(&dat).unwrap().as_bytes()).unwrap();
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let actual = user.lock().await.games_dat_json(system_id).await.unwrap();
+
+    let expected = dat_roms(dat);
+
+    let mut actual = actual
+        .into_iter()
+        .map(|g| {
+            let roms = g
+                .roms
+                .into_iter()
+                .map(|r| {
+                    (
+                        r.name,
+                        r.size,
+                        r.crc32.map(|c| c.to_string()),
+                        r.md5.map(|c| c.to_string()),
+                        r.sha1.map(|c| c.to_string()),
+                    )
+                })
+                .collect::<Vec<_>>();
+            (g.name, roms)
+        })
+        .collect::<Vec<_>>();
+    actual.sort();
+
+    assert_eq!(actual, expected);
+}
+
+#[then(expr = "the XML games DAT of system {word} matches DAT {word}")]
+async fn games_xml_dat_matches(w: &mut World, system: String, dat: String) {
+    w.assert_result_ok();
+
+    let system_id = *w.systems.get(&system).unwrap();
+    let dat = datary::from_reader(w.dats.get(&dat).unwrap().as_bytes()).unwrap();
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let exported = user.lock().await.games_dat(system_id).await.unwrap();
+    let exported = datary::from_reader(exported.as_bytes()).unwrap();
+
+    assert_eq!(dat_roms(exported), dat_roms(dat));
+}
+
+#[when(expr = "{user} sets their display name to {string}")]
+async fn user_sets_display_name(w: &mut World, user: UserParam, display_name: String) {
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .set_info(dto::user::UserUpdate {
+            display_name: Some(&display_name),
+            ..Default::default()
+        })
+        .await;
+    w.record_result(result);
+}
+
+#[then(expr = "{user} has display name {string}")]
+async fn user_has_display_name(w: &mut World, user: UserParam, display_name: String) {
+    w.assert_result_ok();
+
+    let user = w.user(&user).await.unwrap();
+    let details = user.lock().await.whoami().await.unwrap();
+    assert_eq!(
+        details.user.display_name.as_deref(),
+        Some(display_name.as_str())
+    );
+}
+
+#[when(expr = "{user} sets their metadata to {string}")]
+async fn user_sets_metadata(w: &mut World, user: UserParam, metadata: String) {
+    let user = w.auth_user(&user).await.unwrap();
+    let metadata: dto::types::Metadata = serde_json::from_str(&metadata).unwrap();
+    let result = user
+        .lock()
+        .await
+        .set_info(dto::user::UserUpdate {
+            metadata: Some(metadata),
+            ..Default::default()
+        })
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} adds metadata {word} with value {string}")]
+async fn user_adds_metadata(w: &mut World, user: UserParam, key: String, value: String) {
+    let user = w.auth_user(&user).await.unwrap();
+    let value: serde_json::Value = serde_json::from_str(&value).unwrap();
+    let result = user
+        .lock()
+        .await
+        .set_info(dto::user::UserUpdate {
+            add_metadata: Some(BTreeMap::from([(key.as_str(), value)]).into()),
+            ..Default::default()
+        })
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} removes metadata {word}")]
+async fn user_removes_metadata(w: &mut World, user: UserParam, key: String) {
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .set_info(dto::user::UserUpdate {
+            remove_metadata: Some(vec![key.as_str()]),
+            ..Default::default()
+        })
+        .await;
+    w.record_result(result);
+}
+
+#[then(expr = "{user} has metadata {string}")]
+async fn user_has_metadata(w: &mut World, user: UserParam, metadata: String) {
+    w.assert_result_ok();
+
+    let user = w.user(&user).await.unwrap();
+    let details = user.lock().await.whoami().await.unwrap();
+    let expected: serde_json::Value = serde_json::from_str(&metadata).unwrap();
+    assert_eq!(details.user.metadata, expected);
+}
+
+#[when(expr = "{user} sets metadata of team {word} to {string}")]
+async fn team_set_metadata(w: &mut World, user: UserParam, team: String, metadata: String) {
+    let team = w.team(&user, &team).await.unwrap();
+    let user = w.auth_user(&user).await.unwrap();
+    let metadata: dto::types::Metadata = serde_json::from_str(&metadata).unwrap();
+    let result = user
+        .lock()
+        .await
+        .update_team(
+            team.id,
+            &dto::teams::TeamUpdateRequest {
+                metadata: Some(metadata),
+                ..Default::default()
+            },
+        )
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} adds metadata {word} with value {string} to team {word}")]
+async fn team_add_metadata(
+    w: &mut World,
+    user: UserParam,
+    key: String,
+    value: String,
+    team: String,
+) {
+    let team = w.team(&user, &team).await.unwrap();
+    let user = w.auth_user(&user).await.unwrap();
+    let value: serde_json::Value = serde_json::from_str(&value).unwrap();
+    let result = user
+        .lock()
+        .await
+        .update_team(
+            team.id,
+            &dto::teams::TeamUpdateRequest {
+                add_metadata: Some(BTreeMap::from([(key.as_str(), value)]).into()),
+                ..Default::default()
+            },
+        )
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} removes metadata {word} from team {word}")]
+async fn team_remove_metadata(w: &mut World, user: UserParam, key: String, team: String) {
+    let team = w.team(&user, &team).await.unwrap();
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .update_team(
+            team.id,
+            &dto::teams::TeamUpdateRequest {
+                remove_metadata: Some(vec![key.as_str()]),
+                ..Default::default()
+            },
+        )
+        .await;
+    w.record_result(result);
+}
+
+#[then(expr = "team {word} owned by {user} has metadata {string}")]
+async fn team_has_metadata(w: &mut World, team: String, user: UserParam, metadata: String) {
+    w.assert_result_ok();
+
+    let team = w.team(&user, &team).await.unwrap();
+    let user = w.user(&user).await.unwrap();
+    let details = user.lock().await.team_details(team.id).await.unwrap();
+    let expected: std::collections::BTreeMap<String, serde_json::Value> =
+        serde_json::from_str(&metadata).unwrap();
+    assert_eq!(details.metadata, expected);
+}
+
+#[given(expr = "team {word} owned by {user} was last updated {int} days ago")]
+async fn team_aged(w: &mut World, team: String, user: UserParam, days: i32) {
+    let team = w.team(&user, &team).await.unwrap();
+    w.age_team(team.id, days).await.unwrap();
+}
+
+#[then(expr = "team {word} owned by {user} was updated in the last hour")]
+async fn team_recently_updated(w: &mut World, team: String, user: UserParam) {
+    w.assert_result_ok();
+
+    let team = w.team(&user, &team).await.unwrap();
+    let user = w.user(&user).await.unwrap();
+    let details = user.lock().await.team_details(team.id).await.unwrap();
+    assert!(details.updated_at > chrono::Utc::now().timestamp() - 60 * 60);
+}
+
+#[when(expr = "{user} gets the details of artifact {word}")]
+async fn user_gets_artifact(w: &mut World, user: UserParam, artifact: String) {
+    let artifact_id = match w.artifacts.get(&artifact) {
+        Some(id) => *id,
+        None => artifact.parse().unwrap(),
+    };
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.artifact_details(artifact_id).await;
+    w.record_result(result);
+}
+
+#[given(expr = "team {word} owned by {user} has null links")]
+async fn team_null_links(w: &mut World, team: String, user: UserParam) {
+    let team = w.team(&user, &team).await.unwrap();
+    w.set_team_links_null(team.id).await.unwrap();
+}
+
+#[when(expr = "{user} adds link {word} to {string} to team {word}")]
+async fn team_add_link(w: &mut World, user: UserParam, name: String, url: String, team: String) {
+    let team = w.team(&user, &team).await.unwrap();
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .update_team(
+            team.id,
+            &dto::teams::TeamUpdateRequest {
+                add_links: Some([(name.as_str(), url.as_str())].into()),
+                ..Default::default()
+            },
+        )
+        .await;
+    w.record_result(result);
+}
+
+#[then(expr = "team {word} owned by {user} has links {string}")]
+async fn team_has_links(w: &mut World, team: String, user: UserParam, links: String) {
+    w.assert_result_ok();
+
+    let team = w.team(&user, &team).await.unwrap();
+    let user = w.user(&user).await.unwrap();
+    let details = user.lock().await.team_details(team.id).await.unwrap();
+    let expected: std::collections::BTreeMap<String, String> =
+        serde_json::from_str(&links).unwrap();
+    assert_eq!(details.links, expected);
+}
+
+#[when(expr = "{user} searches users by the first {int} letters of the username of {user}")]
+async fn users_search_prefix(w: &mut World, user: UserParam, letters: usize, target: UserParam) {
+    let target = w.auth_user(&target).await.unwrap();
+    let username = target.lock().await.whoami().await.unwrap().user.username;
+    let prefix = username
+        .expect("User has no username")
+        .chars()
+        .take(letters)
+        .collect::<String>()
+        .to_uppercase();
+
+    // Searching requires a username, so authenticate users other than the
+    // anonymous one.
+    let user = if matches!(user, UserParam::Anonymous) {
+        w.user(&user).await
+    } else {
+        w.auth_user(&user).await
+    }
+    .unwrap();
+    let result = user.lock().await.users_search(&prefix).await;
+    w.record_result(result);
+}
+
+/// Postgres rejects strings with a NUL character, so this fails in the
+/// database.
+#[when(expr = "{user} searches users with a NUL character")]
+async fn users_search_nul(w: &mut World, user: UserParam) {
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user.lock().await.users_search("abc\0def").await;
+    w.record_result(result);
+}
+
+#[then(expr = "the user search results include {user}")]
+async fn users_search_include(w: &mut World, user: UserParam) {
+    let results: Vec<dto::user::UserRef> = w.last_result();
+    let id = w.user(&user).await.unwrap().lock().await.id();
+    assert!(
+        results.iter().any(|u| u.id == id),
+        "{results:?} does not include user {id}"
+    );
+}
+
+/// Invite a comma separated list of users. Entries are either a user (e.g.
+/// `user B`) or `unknown <name>` for a username that does not exist.
+#[when(expr = "{user} bulk invites {string} to team {word} as {team_role}")]
+async fn user_bulk_invites(
+    w: &mut World,
+    inviter: UserParam,
+    invitees: String,
+    team: String,
+    role: TeamRole,
+) {
+    let mut users = Vec::new();
+    for invitee in invitees.split(',').map(str::trim) {
+        let user = if let Some(name) = invitee.strip_prefix("unknown ") {
+            dto::user::UserIdOrUsername::from_str(&w.slug(name)).unwrap()
+        } else {
+            let invitee = UserParam::from_str(invitee).unwrap();
+            let id = w.auth_user(&invitee).await.unwrap().lock().await.id();
+            dto::user::UserIdOrUsername::Id(id)
+        };
+        users.push(user);
+    }
+
+    let team_id = w.team(&inviter, &team).await.unwrap().id;
+    let inviter = w.auth_user(&inviter).await.unwrap();
+    let result = inviter
+        .lock()
+        .await
+        .invite_bulk_to_team(
+            team_id,
+            users
+                .into_iter()
+                .map(|user| dto::teams::TeamInvite { user, role: role.0 })
+                .collect(),
+        )
+        .await;
+    w.record_result(result);
+}
+
+#[then(expr = "the bulk invite statuses are {string}")]
+async fn bulk_invite_statuses(w: &mut World, statuses: String) {
+    let results: Vec<dto::teams::TeamInviteBulkResult> = w.last_result();
+    let actual = results
+        .iter()
+        .map(|r| serde_json::to_value(r.status).unwrap())
+        .map(|v| v.as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    let expected = statuses
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(actual, expected);
+}
+
+#[when(expr = "{user} renames team {word} to {word}")]
+async fn team_rename(w: &mut World, user: UserParam, team: String, new_slug: String) {
+    let team = w.team(&user, &team).await.unwrap();
+    let new_slug = w.slug(&new_slug);
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .update_team(
+            team.id,
+            &dto::teams::TeamUpdateRequest {
+                slug: Some(&new_slug),
+                ..Default::default()
+            },
+        )
+        .await;
+    w.record_result(result);
+}
+
+#[then(expr = "team {word} owned by {user} redirects from its original slug to {word}")]
+async fn team_redirects(w: &mut World, team: String, user: UserParam, new_slug: String) {
+    w.assert_result_ok();
+
+    let team = w.team(&user, &team).await.unwrap();
+    let new_slug = w.slug(&new_slug);
+    let user = w.user(&user).await.unwrap();
+    let location = user.lock().await.team_redirect(&team.slug).await.unwrap();
+    assert_eq!(location, Some(format!("/api/v1/teams/{new_slug}")));
+
+    let details = user.lock().await.team_details(team.id).await.unwrap();
+    assert_eq!(details.team.slug, new_slug);
+    assert_eq!(
+        user.lock().await.team_redirect(&new_slug).await.unwrap(),
+        None
+    );
+}
+
+#[then(expr = "{user} has {int} pending invitation(s)")]
+async fn user_pending_invitations(w: &mut World, user: UserParam, count: i64) {
+    w.assert_result_ok();
+
+    let user = w.auth_user(&user).await.unwrap();
+    let me = user.lock().await.whoami().await.unwrap();
+    assert_eq!(me.pending_invitations, count);
+}
+
+#[then(expr = "{user} is a member of team {word} owned by {user}")]
+async fn user_is_team_member(w: &mut World, user: UserParam, team: String, owner: UserParam) {
+    w.assert_result_ok();
+
+    let team = w.team(&owner, &team).await.unwrap();
+    let user = w.auth_user(&user).await.unwrap();
+    let me = user.lock().await.whoami().await.unwrap();
+    assert!(me.teams.iter().any(|t| t.team.id == team.id));
 }
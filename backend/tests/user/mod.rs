@@ -3,10 +3,10 @@ use backend::routes::v1;
 use rand::distributions::Alphanumeric;
 use rand::Rng;
 use retronomicon_dto as dto;
-use retronomicon_dto::types::IdOrSlug;
+use retronomicon_dto::types::{IdOrSlug, Slug};
 use retronomicon_dto::user::UserIdOrUsername;
 use rocket::http::uri::Origin;
-use rocket::http::{Cookie, Method, Status};
+use rocket::http::{Cookie, Header, Method, Status};
 use rocket::local::asynchronous::Client;
 use rocket::uri;
 use std::collections::BTreeMap;
@@ -14,6 +14,18 @@ use std::io::Cursor;
 use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
 
+/// The optional fields of a core release created by
+/// [`User::create_core_release`].
+#[derive(Default)]
+pub struct CoreReleaseOptions<'a> {
+    pub date_released: Option<i64>,
+    pub notes: &'a str,
+    pub metadata: Option<BTreeMap<&'a str, serde_json::Value>>,
+    pub required_system_release: Option<i32>,
+    /// Sent in the `Idempotency-Key` header, as a retried upload would.
+    pub idempotency_key: Option<&'a str>,
+}
+
 fn create_image(text: String) -> Vec<u8> {
     use embedded_graphics::framebuffer::{buffer_size, Framebuffer};
     use embedded_graphics::mono_font::{ascii, MonoTextStyle};
@@ -47,6 +59,39 @@ fn create_image(text: String) -> Vec<u8> {
     bytes
 }
 
+/// Create a Logiqx XML DAT with `count` games of two ROMs each. ROM contents are
+/// random so checksums are unique.
+pub fn create_dat(name: &str, count: usize) -> String {
+    use sha1::Digest;
+
+    let games = (0..count)
+        .map(|i| {
+            let roms = (0..2)
+                .map(|j| {
+                    let data = rand::thread_rng()
+                        .sample_iter(rand::distributions::Standard)
+                        .take(64 + j)
+                        .collect::<Vec<u8>>();
+                    format!(
+                        r#"<rom name="{name}-{i}-{j}.bin" size="{}" crc="{}" md5="{}" sha1="{}" />"#,
+                        data.len(),
+                        hex::encode(retronomicon_db::models::artifact::crc32(&data)),
+                        hex::encode(md5::compute(&data).0),
+                        hex::encode(sha1::Sha1::digest(&data)),
+                    )
+                })
+                .collect::<String>();
+            format!(
+                r#"<game name="{name} Game {i}"><description>{name} Game {i}</description><year>1990</year>{roms}</game>"#
+            )
+        })
+        .collect::<String>();
+
+    format!(
+        r#"<?xml version="1.0"?><datafile><header><name>{name}</name><description>{name}</description><version>1</version><author>cucumber</author></header>{games}</datafile>"#
+    )
+}
+
 static mut COUNTER: AtomicUsize = AtomicUsize::new(0);
 
 fn unique_id() -> usize {
@@ -67,18 +112,30 @@ fn slugify(s: &str) -> String {
     )
 }
 
+/// The status and caching headers of a download, which is not followed.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct DownloadResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub location: Option<String>,
+    pub cache_control: Option<String>,
+    pub generated_at: Option<i64>,
+}
+
 #[derive(Debug, Clone)]
 pub enum User {
     NoAuth {
         client: Arc<Client>,
         cookie: Cookie<'static>,
         name: String,
+        email: String,
         id: i32,
     },
     Auth {
         client: Arc<Client>,
         cookie: Cookie<'static>,
         name: String,
+        email: String,
         id: i32,
     },
     Anonymous {
@@ -94,19 +151,34 @@ impl User {
         }
     }
 
+    fn client(&self) -> &Client {
+        match self {
+            User::NoAuth { client, .. }
+            | User::Auth { client, .. }
+            | User::Anonymous { client } => client,
+        }
+    }
+
+    pub fn email(&self) -> &str {
+        match self {
+            User::NoAuth { email, .. } | User::Auth { email, .. } => email,
+            User::Anonymous { .. } => "",
+        }
+    }
+
     async fn req_<R: serde::de::DeserializeOwned>(
         client: &Client,
         method: Method,
         uri: Origin<'_>,
         cookie: &mut Cookie<'static>,
+        headers: &[Header<'static>],
         body: &impl serde::Serialize,
     ) -> Result<R, Error> {
-        let response = client
-            .req(method, uri)
-            .cookie(cookie.clone())
-            .json(body)
-            .dispatch()
-            .await;
+        let mut request = client.req(method, uri).cookie(cookie.clone()).json(body);
+        for header in headers {
+            request.add_header(header.clone());
+        }
+        let response = request.dispatch().await;
 
         if response.status() != Status::Ok {
             let status = response.status();
@@ -134,14 +206,16 @@ impl User {
         &mut self,
         method: Method,
         uri: Origin<'_>,
+        headers: &[Header<'static>],
         body: &impl serde::Serialize,
     ) -> Result<R, Error> {
         match self {
             User::NoAuth { client, cookie, .. } | User::Auth { client, cookie, .. } => {
-                Self::req_(client, method, uri, cookie, body).await
+                Self::req_(client, method, uri, cookie, headers, body).await
             }
             User::Anonymous { client } => {
-                Self::req_(client, method, uri, &mut Cookie::new("empty", ""), body).await
+                let mut cookie = Cookie::new("empty", "");
+                Self::req_(client, method, uri, &mut cookie, headers, body).await
             }
         }
     }
@@ -151,7 +225,7 @@ impl User {
         uri: Origin<'_>,
         body: &impl serde::Serialize,
     ) -> Result<R, Error> {
-        self.req(Method::Get, uri, body).await
+        self.req(Method::Get, uri, &[], body).await
     }
 
     async fn post<R: serde::de::DeserializeOwned>(
@@ -159,7 +233,7 @@ impl User {
         uri: Origin<'_>,
         body: &impl serde::Serialize,
     ) -> Result<R, Error> {
-        self.req(Method::Post, uri, body).await
+        self.req(Method::Post, uri, &[], body).await
     }
 
     async fn put<R: serde::de::DeserializeOwned>(
@@ -167,7 +241,15 @@ impl User {
         uri: Origin<'_>,
         body: &impl serde::Serialize,
     ) -> Result<R, Error> {
-        self.req(Method::Put, uri, body).await
+        self.req(Method::Put, uri, &[], body).await
+    }
+
+    async fn delete<R: serde::de::DeserializeOwned>(
+        &mut self,
+        uri: Origin<'_>,
+        body: &impl serde::Serialize,
+    ) -> Result<R, Error> {
+        self.req(Method::Delete, uri, &[], body).await
     }
 
     fn gen_string(len: usize) -> String {
@@ -182,6 +264,10 @@ impl User {
         format!("{}@{}", Self::gen_string(10), domain)
     }
 
+    fn password_for(email: &str) -> String {
+        format!("password-{email}")
+    }
+
     fn create_username(prefix: &str) -> String {
         format!("{}_{}", prefix, Self::gen_string(5)).to_lowercase()
     }
@@ -205,15 +291,15 @@ impl User {
     }
 
     async fn create(client: Arc<Client>, name: &str) -> Result<Self, Error> {
+        let email = Self::create_email(name);
         let (cookie, id) = {
-            let email = Self::create_email(name);
-            let password = email.as_str();
+            let password = Self::password_for(&email);
             let user = client
                 .post(uri!(v1::auth::signup()))
                 .json(&dto::auth::SignupRequest {
                     username: None,
                     email: &email,
-                    password,
+                    password: &password,
                 })
                 .dispatch()
                 .await;
@@ -240,6 +326,7 @@ impl User {
             cookie,
             id,
             name: name.to_string(),
+            email,
         })
     }
 
@@ -252,6 +339,7 @@ impl User {
                 cookie,
                 id,
                 name,
+                email,
             } => {
                 // `self` is already borrowed, so can't borrow twice.
                 Self::req_::<dto::Ok>(
@@ -259,6 +347,7 @@ impl User {
                     Method::Put,
                     uri!(v1::me::me_update()),
                     cookie,
+                    &[],
                     &dto::user::UserUpdate {
                         username: Some(&Self::create_username(name)),
                         ..Default::default()
@@ -270,6 +359,7 @@ impl User {
                     client: client.clone(),
                     cookie: cookie.clone(),
                     name: name.clone(),
+                    email: email.clone(),
                     id: *id,
                 };
                 Ok(())
@@ -277,27 +367,180 @@ impl User {
         }
     }
 
-    pub async fn whoami(&mut self) -> Result<dto::user::UserDetails, Error> {
+    /// The password set at signup.
+    pub fn initial_password(&self) -> String {
+        Self::password_for(self.email())
+    }
+
+    pub async fn login(&mut self, password: &str) -> Result<(), Error> {
+        let email = self.email().to_string();
+        self.post::<dto::Ok>(
+            uri!(v1::auth::login()),
+            &dto::auth::LoginRequest {
+                email: &email,
+                password,
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn signup(&mut self, password: &str) -> Result<dto::auth::SignupResponse, Error> {
+        let email = Self::create_email("cucumber-signup");
+        self.post(
+            uri!(v1::auth::signup()),
+            &dto::auth::SignupRequest {
+                username: None,
+                email: &email,
+                password,
+            },
+        )
+        .await
+    }
+
+    pub async fn email_login_request(&mut self) -> Result<(), Error> {
+        let email = self.email().to_string();
+        self.post::<dto::Ok>(
+            uri!(v1::auth::email_login_request()),
+            &dto::auth::EmailLoginRequest { email: &email },
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn email_login_confirm(
+        &mut self,
+        token: &str,
+    ) -> Result<dto::auth::TokenResponse, Error> {
+        let email = self.email().to_string();
+        self.post(
+            uri!(v1::auth::email_login_confirm()),
+            &dto::auth::EmailLoginConfirmRequest {
+                email: &email,
+                token,
+            },
+        )
+        .await
+    }
+
+    pub async fn change_password(
+        &mut self,
+        current_password: &str,
+        new_password: &str,
+    ) -> Result<(), Error> {
+        self.put::<dto::Ok>(
+            uri!(v1::me::me_change_password()),
+            &dto::auth::PasswordChangeRequest {
+                current_password,
+                new_password,
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn providers(&mut self) -> Result<Vec<dto::auth::LinkedAuthProvider>, Error> {
+        self.get(uri!(v1::me::me_providers()), &()).await
+    }
+
+    pub async fn unlink_provider(&mut self, provider: &str) -> Result<(), Error> {
+        self.delete::<dto::Ok>(uri!(v1::me::me_providers_unlink(provider)), &())
+            .await?;
+        Ok(())
+    }
+
+    pub async fn forgot_password(&mut self, email: &str) -> Result<(), Error> {
+        self.post::<dto::Ok>(
+            uri!(v1::auth::forgot()),
+            &dto::auth::PasswordForgotRequest { email },
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn reset_password(&mut self, token: &str, password: &str) -> Result<(), Error> {
+        self.post::<dto::Ok>(
+            uri!(v1::auth::reset()),
+            &dto::auth::PasswordResetRequest { token, password },
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn whoami(&mut self) -> Result<dto::user::Me, Error> {
         self.get(uri!(v1::me::me()), &()).await
     }
 
+    pub async fn users_search(&mut self, q: &str) -> Result<Vec<dto::user::UserRef>, Error> {
+        let uri = format!(
+            "/users/search?q={}",
+            rocket::http::RawStr::new(q).percent_encode()
+        );
+        self.get(Origin::parse_owned(uri).map_err(|e| anyhow!("{e}"))?, &())
+            .await
+    }
+
     pub async fn create_team(
         &mut self,
         name: &str,
+    ) -> Result<dto::teams::TeamCreateResponse, Error> {
+        self.create_team_with_slug(name, &Self::create_slug(name), false)
+            .await
+    }
+
+    pub async fn create_team_with_slug(
+        &mut self,
+        name: &str,
+        slug: &str,
+        upsert: bool,
     ) -> Result<dto::teams::TeamCreateResponse, Error> {
         self.post(
             uri!(v1::teams::teams_create()),
             &dto::teams::TeamCreateRequest {
                 name,
-                slug: &Self::create_slug(name),
+                // Skip the validation so invalid slugs can be sent to the server.
+                slug: serde_json::from_value(serde_json::json!(slug))?,
                 description: "",
                 links: None,
                 metadata: None,
+                upsert,
             },
         )
         .await
     }
 
+    pub async fn health_detailed(&mut self) -> Result<dto::health::HealthDetailedResponse, Error> {
+        self.get(uri!(v1::health::health_detailed()), &()).await
+    }
+
+    pub async fn require_password_reset(&mut self, user_id: i32) -> Result<(), Error> {
+        self.post::<dto::Ok>(
+            uri!(v1::admin::admin_users_require_password_reset(user_id)),
+            &(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn expire_tokens(&mut self) -> Result<dto::auth::ExpireTokensResponse, Error> {
+        self.post(uri!(v1::admin::admin_maintenance_expire_tokens()), &())
+            .await
+    }
+
+    pub async fn backfill_sha1(
+        &mut self,
+        limit: Option<i64>,
+    ) -> Result<dto::artifact::ArtifactBackfillResponse, Error> {
+        self.post(
+            uri!(v1::admin::admin_maintenance_backfill_sha1(
+                None::<u32>,
+                limit
+            )),
+            &(),
+        )
+        .await
+    }
+
     pub async fn invite_to_team(
         &mut self,
         team: i32,
@@ -315,6 +558,18 @@ impl User {
         Ok(())
     }
 
+    pub async fn invite_bulk_to_team(
+        &mut self,
+        team: i32,
+        invites: Vec<dto::teams::TeamInvite<'_>>,
+    ) -> Result<Vec<dto::teams::TeamInviteBulkResult>, Error> {
+        self.post(
+            uri!(v1::teams::invite_bulk(team)),
+            &dto::teams::TeamInviteBulkRequest { invites },
+        )
+        .await
+    }
+
     pub async fn accept_team_invitation(&mut self, team: i32) -> Result<(), Error> {
         self.post::<dto::Ok>(uri!(v1::teams::invite_accept(team)), &())
             .await?;
@@ -335,6 +590,41 @@ impl User {
         Ok(())
     }
 
+    pub async fn update_team(
+        &mut self,
+        team: i32,
+        update: &dto::teams::TeamUpdateRequest<'_>,
+    ) -> Result<(), Error> {
+        self.put::<dto::Ok>(uri!(v1::teams::teams_update(team)), update)
+            .await?;
+        Ok(())
+    }
+
+    /// Get a team by its slug without following redirects. Returns the
+    /// `Location` header if the team was moved.
+    pub async fn team_redirect(&mut self, slug: &str) -> Result<Option<String>, Error> {
+        let response = self
+            .client()
+            .get(uri!(v1::teams::teams_details(slug)))
+            .dispatch()
+            .await;
+
+        let status = response.status();
+        if status == Status::Ok {
+            Ok(None)
+        } else if status == Status::MovedPermanently {
+            Ok(response.headers().get_one("Location").map(String::from))
+        } else {
+            Err(anyhow!("Server returned status: {}", status))
+        }
+    }
+
+    pub async fn delete_team(&mut self, team: i32) -> Result<(), Error> {
+        self.delete::<dto::Ok>(uri!(v1::teams::teams_delete(team)), &())
+            .await?;
+        Ok(())
+    }
+
     pub async fn team_details(&mut self, team: i32) -> Result<dto::teams::TeamDetails, Error> {
         self.get(uri!(v1::teams::teams_details(team)), &()).await
     }
@@ -343,6 +633,7 @@ impl User {
         &mut self,
         team: i32,
         name: &str,
+        manufacturer: &str,
     ) -> Result<dto::systems::SystemCreateResponse, Error> {
         let owner_team = self.team_details(team).await?.team.into();
         let name = Self::create_username(name);
@@ -350,57 +641,83 @@ impl User {
             uri!(v1::systems::systems_create()),
             &dto::systems::SystemCreateRequest {
                 name: &name,
-                slug: &Self::create_slug(&name),
+                slug: Slug::new(Self::create_slug(&name)).map_err(Error::msg)?,
                 description: "",
-                manufacturer: "cucumber-manufacturer",
+                manufacturer,
                 links: None,
                 metadata: None,
                 owner_team,
+                upsert: false,
             },
         )
         .await
     }
 
-    pub async fn create_game(
+    pub async fn systems_list(
+        &mut self,
+        team: i32,
+        name: Option<&str>,
+        manufacturer: Option<&str>,
+    ) -> Result<Vec<dto::systems::SystemListItem>, Error> {
+        let mut uri = format!("/systems?team={team}");
+        if let Some(name) = name {
+            uri += &format!("&name={}", rocket::http::RawStr::new(name).percent_encode());
+        }
+        if let Some(manufacturer) = manufacturer {
+            uri += &format!(
+                "&manufacturer={}",
+                rocket::http::RawStr::new(manufacturer).percent_encode()
+            );
+        }
+        self.get(Origin::parse_owned(uri).map_err(|e| anyhow!("{e}"))?, &())
+            .await
+    }
+
+    pub async fn platforms_list(
         &mut self,
-        system_id: i32,
         name: &str,
-    ) -> Result<dto::games::GameCreateResponse, Error> {
-        let system = self
-            .get::<dto::systems::SystemDetails>(uri!(v1::systems::systems_details(system_id)), &())
-            .await?
-            .id;
-        let system = system.into();
+    ) -> Result<Vec<dto::platforms::Platform>, Error> {
+        let uri = format!(
+            "/platforms?limit=100&name={}",
+            rocket::http::RawStr::new(name).percent_encode()
+        );
+        self.get(Origin::parse_owned(uri).map_err(|e| anyhow!("{e}"))?, &())
+            .await
+    }
 
-        self.post(
-            uri!(v1::games::games_create()),
-            &dto::games::GameCreateRequest {
-                name,
-                description: "",
-                short_description: "",
-                year: 1234,
-                publisher: "cucumber-publisher",
-                developer: "cucumber-developer",
-                links: BTreeMap::new(),
-                system,
-                system_unique_id: unique_id() as i32,
+    /// Create a tag, returning its slug.
+    pub async fn create_tag(&mut self, name: &str) -> Result<String, Error> {
+        let slug = Self::create_slug(name);
+        self.post::<dto::Ok>(
+            uri!(v1::tags::tags_create()),
+            &dto::tags::TagCreate {
+                slug: slug.clone(),
+                description: String::new(),
+                color: 0x808080,
             },
         )
-        .await
+        .await?;
+        Ok(slug)
     }
 
-    pub async fn get_game_by_id(&mut self, game_id: i32) -> Result<dto::games::GameDetails, Error> {
-        self.get(uri!(v1::games::games_details(game_id as u32)), &())
-            .await
+    pub async fn tags_cores(&mut self, tag: &str) -> Result<Vec<dto::cores::CoreListItem>, Error> {
+        self.get(
+            uri!(v1::tags::tags_cores(
+                tag,
+                dto::params::PagingParams::default()
+            )),
+            &(),
+        )
+        .await
     }
 
-    pub async fn get_game_images(
+    pub async fn tags_games(
         &mut self,
-        game_id: i32,
-    ) -> Result<dto::Paginated<dto::images::Image>, Error> {
+        tag: &str,
+    ) -> Result<Vec<dto::games::GameListItemResponse>, Error> {
         self.get(
-            uri!(v1::games::games_images(
-                game_id as u32,
+            uri!(v1::tags::tags_games(
+                tag,
                 dto::params::PagingParams::default()
             )),
             &(),
@@ -408,60 +725,995 @@ impl User {
         .await
     }
 
-    pub async fn get_user_details(
+    pub async fn create_platform(
         &mut self,
-        user: Option<UserIdOrUsername<'_>>,
-    ) -> Result<dto::user::UserDetails, Error> {
-        match user {
-            Some(user) => self.get(uri!(v1::users::users_details(user)), &()).await,
-            None => self.whoami().await,
-        }
+        team: i32,
+        name: &str,
+        metadata_schema: Option<dto::platforms::PlatformMetadataSchema>,
+    ) -> Result<dto::platforms::PlatformCreateResponse, Error> {
+        let name = Self::create_username(name);
+        self.post(
+            uri!(v1::platforms::platforms_create()),
+            &dto::platforms::PlatformCreateRequest {
+                slug: Slug::new(Self::create_slug(&name)).map_err(Error::msg)?,
+                name: &name,
+                description: "",
+                links: None,
+                metadata: None,
+                metadata_schema,
+                owner_team: IdOrSlug::Id(team),
+                upsert: false,
+            },
+        )
+        .await
     }
 
-    pub async fn upload_image(&mut self, game_id: i32, image_name: &str) -> Result<(), Error> {
-        let bytes = create_image(format!("{game_id} / {image_name}.png"));
-        let cookie = match self {
-            User::NoAuth { cookie, .. } | User::Auth { cookie, .. } => cookie.clone(),
-            User::Anonymous { .. } => Cookie::new("empty", ""),
-        };
-        let mut request = match self {
-            User::NoAuth { client, .. } | User::Auth { client, .. } => client,
-            User::Anonymous { client } => client,
-        }
-        .req(Method::Post, uri!(v1::games::games_images_upload(game_id)))
-        .cookie(cookie);
-
-        // Build the form manually. This is very cobbersome but Rocket doesn't provide a better
-        // API just yet. See https://github.com/rwf2/Rocket/issues/1591.
-        let form = [
-            b"-----testboundary\r\n".to_vec(),
-            format!(
-                "Content-Disposition: form-data; name=\"image\"; filename=\"{image_name}.png\"\r\n"
-            )
-            .as_bytes()
-            .to_vec(),
-            b"Content-Type: image/png\r\n".to_vec(),
-            b"\r\n".to_vec(),
-            bytes,
-            b"\r\n".to_vec(),
-            b"-----testboundary--\r\n".to_vec(),
-        ]
-        .concat();
-        request.add_header(rocket::http::Header::new(
-            "Content-Type",
-            "multipart/form-data; boundary=---testboundary",
-        ));
-        request.set_body(form);
+    pub async fn rename_platform(&mut self, platform: i32, name: &str) -> Result<dto::Ok, Error> {
+        let name = Self::create_username(name);
+        self.put(
+            uri!(v1::platforms::platforms_update(platform)),
+            &dto::platforms::PlatformUpdateRequest {
+                slug: None,
+                name: Some(&name),
+                description: None,
+                links: None,
+                metadata: None,
+                metadata_schema: None,
+                team_id: None,
+                add_metadata: None,
+                remove_metadata: None,
+            },
+        )
+        .await
+    }
 
-        let response = request.dispatch().await;
-        if response.status() != Status::Ok {
-            return Err(anyhow!(
-                "Server returned status: {} body: {:?}",
-                response.status(),
-                response.into_string().await
-            ));
-        }
+    pub async fn create_core(
+        &mut self,
+        team: i32,
+        system: i32,
+        name: &str,
+    ) -> Result<dto::cores::CoreCreateResponse, Error> {
+        let name = Self::create_username(name);
+        self.post(
+            uri!(v1::cores::cores_create()),
+            &dto::cores::CoreCreateRequest {
+                slug: Slug::new(Self::create_slug(&name)).map_err(Error::msg)?,
+                name: &name,
+                description: "",
+                links: BTreeMap::new(),
+                metadata: None,
+                system: IdOrSlug::Id(system),
+                owner_team: IdOrSlug::Id(team),
+            },
+        )
+        .await
+    }
+
+    pub async fn cores_list_by_name(
+        &mut self,
+        name_contains: &str,
+    ) -> Result<dto::Paginated<dto::cores::CoreListItem>, Error> {
+        // Newest first, so the cores of the scenario are on the first page
+        // regardless of the cores left by previous runs.
+        let uri = format!(
+            "/cores?name_contains={}&sort=-created",
+            rocket::http::RawStr::new(name_contains).percent_encode()
+        );
+        self.get(Origin::parse_owned(uri).map_err(|e| anyhow!("{e}"))?, &())
+            .await
+    }
+
+    pub async fn cores_list_matching(
+        &mut self,
+        q: &str,
+    ) -> Result<dto::Paginated<dto::cores::CoreListItem>, Error> {
+        // Newest first, see `cores_list_by_name`.
+        let uri = format!(
+            "/cores?q={}&sort=-created",
+            rocket::http::RawStr::new(q).percent_encode()
+        );
+        self.get(Origin::parse_owned(uri).map_err(|e| anyhow!("{e}"))?, &())
+            .await
+    }
+
+    pub async fn cores_for_system(
+        &mut self,
+        system: i32,
+        sort: &str,
+    ) -> Result<dto::Paginated<dto::cores::CoreListItem>, Error> {
+        let uri = format!("/cores?system={system}&sort={sort}");
+        self.get(Origin::parse_owned(uri).map_err(|e| anyhow!("{e}"))?, &())
+            .await
+    }
+
+    pub async fn cores_for_system_with_platforms(
+        &mut self,
+        system: i32,
+    ) -> Result<dto::Paginated<dto::cores::CoreListItem>, Error> {
+        let uri = format!("/cores?system={system}&with_platforms=true");
+        self.get(Origin::parse_owned(uri).map_err(|e| anyhow!("{e}"))?, &())
+            .await
+    }
+
+    pub async fn cores_recently_released(
+        &mut self,
+        since: i64,
+    ) -> Result<Vec<dto::cores::CoreListItem>, Error> {
+        let uri = format!("/cores/recently-released?since={since}&limit=100");
+        self.get(Origin::parse_owned(uri).map_err(|e| anyhow!("{e}"))?, &())
+            .await
+    }
+
+    pub async fn search_cores(
+        &mut self,
+        q: &str,
+    ) -> Result<Vec<dto::cores::CoreSearchItem>, Error> {
+        self.get(uri!(v1::cores::cores_search(q, _)), &()).await
+    }
+
+    pub async fn search(&mut self, q: &str) -> Result<dto::search::SearchResults, Error> {
+        self.get(uri!(v1::search::search(q, _)), &()).await
+    }
+
+    pub async fn resolve(
+        &mut self,
+        request: &dto::search::ResolveRequest,
+    ) -> Result<dto::search::ResolveResponse, Error> {
+        self.post(uri!(v1::search::resolve()), request).await
+    }
+
+    pub async fn create_core_release(
+        &mut self,
+        core: i32,
+        platform: i32,
+        version: &str,
+        options: CoreReleaseOptions<'_>,
+    ) -> Result<dto::cores::releases::CoreReleaseCreateResponse, Error> {
+        let CoreReleaseOptions {
+            date_released,
+            notes,
+            metadata,
+            required_system_release,
+            idempotency_key,
+        } = options;
+        let headers = idempotency_key
+            .map(|key| Header::new("Idempotency-Key", key.to_string()))
+            .into_iter()
+            .collect::<Vec<_>>();
+        self.req(
+            Method::Post,
+            uri!(v1::cores::releases::cores_releases_create(core)),
+            &headers,
+            &dto::cores::releases::CoreReleaseCreateRequest {
+                version,
+                notes: notes.into(),
+                date_released,
+                prerelease: false,
+                links: BTreeMap::new(),
+                metadata: metadata.map(Into::into),
+                platform: IdOrSlug::Id(platform),
+                required_system_release: required_system_release.map(IdOrSlug::Id),
+            },
+        )
+        .await
+    }
+
+    pub async fn core_release_notes_html(
+        &mut self,
+        core: i32,
+        release: i32,
+    ) -> Result<String, Error> {
+        let response = self
+            .client()
+            .get(uri!(v1::cores::releases::cores_releases_notes_html(
+                core,
+                release as u32
+            )))
+            .dispatch()
+            .await;
+
+        if response.status() != Status::Ok {
+            return Err(anyhow!("Server returned status: {}", response.status()));
+        }
+        response
+            .into_string()
+            .await
+            .ok_or_else(|| anyhow!("Empty response."))
+    }
+
+    pub async fn core_changelog(
+        &mut self,
+        core: i32,
+        platform: Option<i32>,
+    ) -> Result<Vec<dto::cores::releases::CoreChangelogEntry>, Error> {
+        let mut uri = format!("/cores/{core}/changelog");
+        if let Some(platform) = platform {
+            uri += &format!("?platform={platform}");
+        }
+        self.get(Origin::parse_owned(uri).map_err(|e| anyhow!("{e}"))?, &())
+            .await
+    }
+
+    pub async fn core_changelog_markdown(&mut self, core: i32) -> Result<String, Error> {
+        let response = self
+            .client()
+            .get(format!("/cores/{core}/changelog"))
+            .header(rocket::http::Header::new("Accept", "text/markdown"))
+            .dispatch()
+            .await;
+
+        if response.status() != Status::Ok {
+            return Err(anyhow!("Server returned status: {}", response.status()));
+        }
+        response
+            .into_string()
+            .await
+            .ok_or_else(|| anyhow!("Empty response."))
+    }
+
+    pub async fn releases_for_system(
+        &mut self,
+        system: i32,
+        sort: Option<&str>,
+    ) -> Result<dto::Paginated<dto::cores::releases::CoreReleaseListItem>, Error> {
+        let mut uri = format!("/releases?system={system}");
+        if let Some(sort) = sort {
+            uri += &format!("&sort={sort}");
+        }
+        self.get(Origin::parse_owned(uri).map_err(|e| anyhow!("{e}"))?, &())
+            .await
+    }
+
+    pub async fn releases_for_system_with_core_slug(
+        &mut self,
+        system: i32,
+        core_slug: &str,
+    ) -> Result<dto::Paginated<dto::cores::releases::CoreReleaseListItem>, Error> {
+        let uri = format!(
+            "/releases?system={system}&core_slug={}",
+            rocket::http::RawStr::new(core_slug).percent_encode()
+        );
+        self.get(Origin::parse_owned(uri).map_err(|e| anyhow!("{e}"))?, &())
+            .await
+    }
+
+    pub async fn core_details(
+        &mut self,
+        core: i32,
+    ) -> Result<dto::cores::CoreDetailsResponse, Error> {
+        self.get(uri!(v1::cores::cores_details(core)), &()).await
+    }
+
+    pub async fn core_redirect(&mut self, slug: &str) -> Result<Option<String>, Error> {
+        let response = self
+            .client()
+            .get(uri!(v1::cores::cores_details(slug)))
+            .dispatch()
+            .await;
+
+        let status = response.status();
+        if status == Status::Ok {
+            Ok(None)
+        } else if status == Status::MovedPermanently {
+            Ok(response.headers().get_one("Location").map(String::from))
+        } else {
+            Err(anyhow!("Server returned status: {}", status))
+        }
+    }
+
+    pub async fn create_game(
+        &mut self,
+        system_id: i32,
+        name: &str,
+    ) -> Result<dto::games::GameCreateResponse, Error> {
+        self.create_game_with_unique_id(system_id, name, Some(unique_id() as i32))
+            .await
+    }
+
+    pub async fn create_game_with_unique_id(
+        &mut self,
+        system_id: i32,
+        name: &str,
+        system_unique_id: Option<i32>,
+    ) -> Result<dto::games::GameCreateResponse, Error> {
+        self.create_game_with(system_id, name, system_unique_id, None, &[])
+            .await
+    }
+
+    pub async fn create_game_in_region(
+        &mut self,
+        system_id: i32,
+        name: &str,
+        region: &str,
+        languages: &[&str],
+    ) -> Result<dto::games::GameCreateResponse, Error> {
+        self.create_game_with(
+            system_id,
+            name,
+            Some(unique_id() as i32),
+            Some(region),
+            languages,
+        )
+        .await
+    }
+
+    async fn create_game_with(
+        &mut self,
+        system_id: i32,
+        name: &str,
+        system_unique_id: Option<i32>,
+        region: Option<&str>,
+        languages: &[&str],
+    ) -> Result<dto::games::GameCreateResponse, Error> {
+        let system = self
+            .get::<dto::systems::SystemDetails>(uri!(v1::systems::systems_details(system_id)), &())
+            .await?
+            .id;
+        let system = system.into();
+
+        self.post(
+            uri!(v1::games::games_create()),
+            &dto::games::GameCreateRequest {
+                name,
+                description: "",
+                short_description: "",
+                year: 1234,
+                publisher: "cucumber-publisher",
+                developer: "cucumber-developer",
+                links: BTreeMap::new(),
+                system,
+                region,
+                languages: languages.to_vec(),
+                system_unique_id,
+            },
+        )
+        .await
+    }
+
+    /// Add an artifact with a random md5 to a game.
+    pub async fn add_game_artifact(&mut self, game_id: i32) -> Result<dto::Ok, Error> {
+        let md5 = md5::compute(Self::gen_string(16)).0;
+        self.post(
+            uri!(v1::games::games_add_artifact(game_id as u32)),
+            &vec![dto::games::GameAddArtifactRequest {
+                mime_type: "application/octet-stream",
+                size: 16,
+                md5: Some(md5.to_vec().into()),
+                sha1: None,
+                sha256: None,
+                crc32: None,
+            }],
+        )
+        .await
+    }
+
+    pub async fn merge_games(&mut self, keep_id: i32, drop_id: i32) -> Result<dto::Ok, Error> {
+        self.post(
+            uri!(v1::games::games_merge(keep_id as u32)),
+            &dto::games::GameMergeRequest { game_id: drop_id },
+        )
+        .await
+    }
+
+    pub async fn artifact_details(
+        &mut self,
+        artifact_id: i32,
+    ) -> Result<dto::artifact::ArtifactDetails, Error> {
+        self.get(
+            uri!(v1::artifacts::artifacts_details(artifact_id as u32)),
+            &(),
+        )
+        .await
+    }
+
+    pub async fn get_game_by_id(&mut self, game_id: i32) -> Result<dto::games::GameDetails, Error> {
+        self.get(uri!(v1::games::games_details(game_id as u32)), &())
+            .await
+    }
+
+    pub async fn get_game_images(
+        &mut self,
+        game_id: i32,
+    ) -> Result<dto::Paginated<dto::images::Image>, Error> {
+        self.get(
+            uri!(v1::games::games_images(
+                game_id as u32,
+                dto::params::PagingParams::default()
+            )),
+            &(),
+        )
+        .await
+    }
+
+    pub async fn set_game_primary_image(
+        &mut self,
+        game_id: i32,
+        image_name: &str,
+    ) -> Result<dto::Ok, Error> {
+        self.put(
+            uri!(v1::games::games_images_set_primary(
+                game_id as u32,
+                image_name
+            )),
+            &(),
+        )
+        .await
+    }
+
+    pub async fn get_user_details(
+        &mut self,
+        user: Option<UserIdOrUsername<'_>>,
+    ) -> Result<dto::user::UserDetails, Error> {
+        match user {
+            Some(user) => self.get(uri!(v1::users::users_details(user)), &()).await,
+            None => self.whoami().await.map(|me| dto::user::UserDetails {
+                user: me.user,
+                teams: me.teams,
+            }),
+        }
+    }
+
+    pub async fn import_dat(
+        &mut self,
+        system_id: i32,
+        dat: &str,
+    ) -> Result<dto::systems::SystemDatImportResponse, Error> {
+        let cookie = match self {
+            User::NoAuth { cookie, .. } | User::Auth { cookie, .. } => cookie.clone(),
+            User::Anonymous { .. } => Cookie::new("empty", ""),
+        };
+        let response = match self {
+            User::NoAuth { client, .. } | User::Auth { client, .. } => client,
+            User::Anonymous { client } => client,
+        }
+        .post(uri!(v1::systems::systems_import_dat(system_id)))
+        .header(rocket::http::ContentType::XML)
+        .cookie(cookie)
+        .body(dat)
+        .dispatch()
+        .await;
+
+        if response.status() != Status::Ok {
+            return Err(anyhow!(
+                "Server returned status: {} body: {:?}",
+                response.status(),
+                response.into_string().await
+            ));
+        }
+
+        response
+            .into_json()
+            .await
+            .ok_or_else(|| anyhow!("Invalid JSON response"))
+    }
+
+    /// Search games of a system by exact name and md5 checksums.
+    pub async fn search_games(
+        &mut self,
+        system_id: i32,
+        exact_name: &str,
+        md5: Vec<dto::encodings::HexString>,
+    ) -> Result<dto::Paginated<dto::games::GameListItemResponse>, Error> {
+        let uri = format!(
+            "/games?system={system_id}&exact_name={}",
+            rocket::http::RawStr::new(exact_name).percent_encode()
+        );
+        self.post(
+            Origin::parse_owned(uri).map_err(|e| anyhow!("{e}"))?,
+            &dto::games::GameListBody {
+                md5: Some(md5),
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    pub async fn games_for_system(
+        &mut self,
+        system_id: i32,
+        sort: &str,
+    ) -> Result<dto::Paginated<dto::games::GameListItemResponse>, Error> {
+        let uri = format!("/games?system={system_id}&sort={sort}");
+        self.get(Origin::parse_owned(uri).map_err(|e| anyhow!("{e}"))?, &())
+            .await
+    }
+
+    /// List games with a raw `system` filter, which may not be a valid id or
+    /// slug.
+    pub async fn games_for_raw_system(
+        &mut self,
+        system: &str,
+    ) -> Result<dto::Paginated<dto::games::GameListItemResponse>, Error> {
+        let uri = format!(
+            "/games?system={}",
+            rocket::http::RawStr::new(system).percent_encode()
+        );
+        self.get(Origin::parse_owned(uri).map_err(|e| anyhow!("{e}"))?, &())
+            .await
+    }
+
+    pub async fn games_for_system_in_region(
+        &mut self,
+        system_id: i32,
+        region: &str,
+    ) -> Result<dto::Paginated<dto::games::GameListItemResponse>, Error> {
+        let uri = format!(
+            "/games?system={system_id}&region={}",
+            rocket::http::RawStr::new(region).percent_encode()
+        );
+        self.get(Origin::parse_owned(uri).map_err(|e| anyhow!("{e}"))?, &())
+            .await
+    }
+
+    /// Search games of a system by md5 and sha1 checksums.
+    pub async fn search_games_by_checksums(
+        &mut self,
+        system_id: i32,
+        md5: Vec<dto::encodings::HexString>,
+        sha1: Vec<dto::encodings::HexString>,
+        checksum_match_mode: dto::games::ChecksumMatchMode,
+    ) -> Result<dto::Paginated<dto::games::GameListItemResponse>, Error> {
+        let uri = format!("/games?system={system_id}");
+        self.post(
+            Origin::parse_owned(uri).map_err(|e| anyhow!("{e}"))?,
+            &dto::games::GameListBody {
+                md5: Some(md5),
+                sha1: Some(sha1),
+                checksum_match_mode: Some(checksum_match_mode),
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    pub async fn games_page(
+        &mut self,
+        page: i64,
+        limit: i64,
+    ) -> Result<dto::Paginated<dto::games::GameListItemResponse>, Error> {
+        let uri = format!("/games?page={page}&limit={limit}");
+        self.get(Origin::parse_owned(uri).map_err(|e| anyhow!("{e}"))?, &())
+            .await
+    }
+
+    /// List games, counting the total with the given mode (`exact` or
+    /// `estimate`).
+    pub async fn games_counted(
+        &mut self,
+        system_id: Option<i32>,
+        count: &str,
+    ) -> Result<dto::Paginated<dto::games::GameListItemResponse>, Error> {
+        let uri = match system_id {
+            Some(system_id) => format!("/games?system={system_id}&count={count}"),
+            None => format!("/games?count={count}"),
+        };
+        self.get(Origin::parse_owned(uri).map_err(|e| anyhow!("{e}"))?, &())
+            .await
+    }
+
+    /// Search games of a system with checksum filters.
+    pub async fn search_games_by_body(
+        &mut self,
+        system_id: i32,
+        body: &dto::games::GameListBody,
+    ) -> Result<dto::Paginated<dto::games::GameListItemResponse>, Error> {
+        let uri = format!("/games?system={system_id}");
+        self.post(Origin::parse_owned(uri).map_err(|e| anyhow!("{e}"))?, body)
+            .await
+    }
+
+    pub async fn games_dat_json(
+        &mut self,
+        system_id: i32,
+    ) -> Result<Vec<dto::systems::SystemDatGame>, Error> {
+        self.get(uri!(v1::systems::systems_games_dat_json(system_id)), &())
+            .await
+    }
+
+    /// Download the Logiqx XML DAT of a system.
+    pub async fn games_dat(&mut self, system_id: i32) -> Result<String, Error> {
+        let response = match self {
+            User::NoAuth { client, .. } | User::Auth { client, .. } => client,
+            User::Anonymous { client } => client,
+        }
+        .get(uri!(v1::systems::systems_games_dat(system_id)))
+        .dispatch()
+        .await;
+
+        if response.status() != Status::Ok {
+            return Err(anyhow!(
+                "Server returned status: {} body: {:?}",
+                response.status(),
+                response.into_string().await
+            ));
+        }
+
+        response
+            .into_string()
+            .await
+            .ok_or_else(|| anyhow!("Empty DAT response"))
+    }
+
+    /// Fetch the Prometheus metrics of the server, as text.
+    pub async fn metrics(&mut self) -> Result<String, Error> {
+        let response = match self {
+            User::NoAuth { client, .. } | User::Auth { client, .. } => client,
+            User::Anonymous { client } => client,
+        }
+        .get("/metrics")
+        .dispatch()
+        .await;
+
+        if response.status() != Status::Ok {
+            return Err(anyhow!("Server returned status: {}", response.status()));
+        }
+
+        response
+            .into_string()
+            .await
+            .ok_or_else(|| anyhow!("Empty metrics response"))
+    }
+
+    /// Send a request, optionally with a request ID, and return the request ID
+    /// of the response.
+    pub async fn request_id(&mut self, request_id: Option<&str>) -> Result<String, Error> {
+        let client = match self {
+            User::NoAuth { client, .. } | User::Auth { client, .. } => client,
+            User::Anonymous { client } => client,
+        };
+        let mut request = client.get("/metrics");
+        if let Some(request_id) = request_id {
+            request = request.header(Header::new("X-Request-Id", request_id.to_string()));
+        }
+        let response = request.dispatch().await;
+
+        response
+            .headers()
+            .get_one("X-Request-Id")
+            .map(ToString::to_string)
+            .ok_or_else(|| anyhow!("No request ID in the response"))
+    }
+
+    /// Send a request and return the value of a header of its response.
+    pub async fn response_header(&mut self, name: &str) -> Result<String, Error> {
+        self.response_header_of("/metrics", name).await
+    }
+
+    /// Get a path and return the value of a header of its response.
+    pub async fn response_header_of(&mut self, path: &str, name: &str) -> Result<String, Error> {
+        let client = match self {
+            User::NoAuth { client, .. } | User::Auth { client, .. } => client,
+            User::Anonymous { client } => client,
+        };
+        let response = client.get(path.to_string()).dispatch().await;
+
+        response
+            .headers()
+            .get_one(name)
+            .map(ToString::to_string)
+            .ok_or_else(|| anyhow!("No {name} header in the response"))
+    }
+
+    pub async fn upload_image(&mut self, game_id: i32, image_name: &str) -> Result<(), Error> {
+        let bytes = create_image(format!("{game_id} / {image_name}.png"));
+        self.upload::<Vec<dto::images::Image>>(
+            uri!(v1::games::games_images_upload(game_id)),
+            "image",
+            &format!("{image_name}.png"),
+            "image/png",
+            bytes,
+        )
+        .await?;
 
         Ok(())
     }
+
+    pub async fn upload_core_artifact(
+        &mut self,
+        core: i32,
+        release: i32,
+        platform: Option<i32>,
+        filename: &str,
+        data: Vec<u8>,
+    ) -> Result<Vec<dto::artifact::ArtifactCreateResponse>, Error> {
+        self.upload(
+            uri!(v1::cores::releases::cores_releases_artifacts_upload(
+                core,
+                release as u32,
+                platform,
+                _
+            )),
+            "artifact",
+            filename,
+            "application/octet-stream",
+            data,
+        )
+        .await
+    }
+
+    pub async fn attach_core_artifact(
+        &mut self,
+        core: i32,
+        release: i32,
+        artifact: i32,
+    ) -> Result<dto::artifact::ArtifactCreateResponse, Error> {
+        self.post(
+            uri!(v1::cores::releases::cores_releases_artifacts_attach(
+                core,
+                release as u32,
+                artifact as u32
+            )),
+            &(),
+        )
+        .await
+    }
+
+    pub async fn upload_core_artifacts(
+        &mut self,
+        core: i32,
+        release: i32,
+        on_conflict: &str,
+        files: Vec<(&str, Vec<u8>)>,
+    ) -> Result<Vec<dto::artifact::ArtifactCreateResponse>, Error> {
+        let uri =
+            format!("/cores/{core}/releases/{release}/artifacts/new?on_conflict={on_conflict}");
+        self.upload_files(
+            Origin::parse_owned(uri).map_err(|e| anyhow!("{e}"))?,
+            "artifact",
+            "application/octet-stream",
+            files,
+        )
+        .await
+    }
+
+    pub async fn core_artifact_filenames(&mut self, core: i32) -> Result<Vec<String>, Error> {
+        self.get(uri!(v1::cores::cores_artifact_filenames(core)), &())
+            .await
+    }
+
+    pub async fn core_releases_full(
+        &mut self,
+        core: i32,
+    ) -> Result<dto::Paginated<dto::cores::releases::CoreReleaseFullItem>, Error> {
+        let paging = dto::params::PagingParams::default();
+        self.get(
+            uri!(v1::cores::releases::cores_releases_full(core, paging)),
+            &(),
+        )
+        .await
+    }
+
+    pub async fn core_release_artifacts(
+        &mut self,
+        core: i32,
+        release: i32,
+        platform: Option<i32>,
+        mime_type: Option<&str>,
+    ) -> Result<dto::Paginated<dto::artifact::CoreReleaseArtifactListItem>, Error> {
+        let paging = dto::params::PagingParams::default();
+        self.get(
+            uri!(v1::cores::releases::cores_releases_artifacts_list(
+                core,
+                release as u32,
+                platform,
+                mime_type,
+                paging
+            )),
+            &(),
+        )
+        .await
+    }
+
+    pub async fn core_release_artifact(
+        &mut self,
+        core: i32,
+        release: i32,
+        artifact: i32,
+    ) -> Result<dto::artifact::CoreReleaseArtifactListItem, Error> {
+        self.get(
+            uri!(v1::cores::releases::cores_releases_artifacts_details(
+                core,
+                release as u32,
+                artifact as u32
+            )),
+            &(),
+        )
+        .await
+    }
+
+    pub async fn artifact_by_sha256(
+        &mut self,
+        sha256: dto::encodings::HexString,
+    ) -> Result<dto::artifact::ArtifactExistsResponse, Error> {
+        self.get(
+            uri!(v1::artifacts::artifacts_by_sha256(sha256.to_string())),
+            &(),
+        )
+        .await
+    }
+
+    pub async fn download_core_release_artifact(
+        &mut self,
+        core: i32,
+        release: i32,
+        artifact: i32,
+    ) -> Result<DownloadResponse, Error> {
+        let response = self
+            .client()
+            .get(uri!(
+                v1::cores::releases::cores_releases_artifacts_download(
+                    core,
+                    release as u32,
+                    artifact as u32
+                )
+            ))
+            .dispatch()
+            .await;
+
+        let header = |name: &str| response.headers().get_one(name).map(str::to_string);
+        Ok(DownloadResponse {
+            status: response.status().code,
+            content_type: header("Content-Type"),
+            location: header("Location"),
+            cache_control: header("Cache-Control"),
+            generated_at: header("X-Generated-At").map(|v| v.parse()).transpose()?,
+        })
+    }
+
+    /// Download all the artifacts of a release as a zip archive, and return
+    /// the names of its entries, read from its central directory.
+    pub async fn download_core_release_zip(
+        &mut self,
+        core: i32,
+        release: i32,
+    ) -> Result<Vec<String>, Error> {
+        let response = self
+            .client()
+            .get(uri!(
+                v1::cores::releases::cores_releases_artifacts_download_zip(core, release as u32)
+            ))
+            .dispatch()
+            .await;
+        if response.status() != Status::Ok {
+            return Err(anyhow!("Server returned status: {}", response.status()));
+        }
+        let zip = response
+            .into_bytes()
+            .await
+            .ok_or_else(|| anyhow!("Empty response"))?;
+
+        let u16_at = |i: usize| u16::from_le_bytes([zip[i], zip[i + 1]]) as usize;
+        let u32_at = |i: usize| u32::from_le_bytes(zip[i..i + 4].try_into().unwrap()) as usize;
+
+        // The archive ends with a 22 bytes record pointing to the directory.
+        let end = zip
+            .len()
+            .checked_sub(22)
+            .ok_or_else(|| anyhow!("Not a zip"))?;
+        let mut offset = u32_at(end + 16);
+        let mut names = Vec::new();
+        for _ in 0..u16_at(end + 10) {
+            let name_len = u16_at(offset + 28);
+            names.push(String::from_utf8(
+                zip[offset + 46..offset + 46 + name_len].to_vec(),
+            )?);
+            offset += 46 + name_len + u16_at(offset + 30) + u16_at(offset + 32);
+        }
+        Ok(names)
+    }
+
+    pub async fn verify_core_release_artifact(
+        &mut self,
+        core: i32,
+        release: i32,
+        artifact: i32,
+    ) -> Result<dto::artifact::ArtifactVerifyResponse, Error> {
+        self.post(
+            uri!(v1::cores::releases::cores_releases_artifacts_verify(
+                core,
+                release as u32,
+                artifact as u32
+            )),
+            &(),
+        )
+        .await
+    }
+
+    pub async fn verify_core_release(
+        &mut self,
+        core: i32,
+        release: i32,
+    ) -> Result<dto::artifact::ReleaseVerifyResponse, Error> {
+        self.post(
+            uri!(v1::admin::admin_cores_releases_verify(core, release as u32)),
+            &(),
+        )
+        .await
+    }
+
+    pub async fn merge_cores(
+        &mut self,
+        source: i32,
+        target: i32,
+    ) -> Result<dto::cores::CoreMergeResponse, Error> {
+        self.post(
+            uri!(v1::admin::admin_cores_merge()),
+            &dto::cores::CoreMergeRequest {
+                source: IdOrSlug::Id(source),
+                target: IdOrSlug::Id(target),
+            },
+        )
+        .await
+    }
+
+    /// Upload a single file as a multipart form field.
+    async fn upload<R: serde::de::DeserializeOwned>(
+        &mut self,
+        uri: Origin<'_>,
+        field: &str,
+        filename: &str,
+        content_type: &str,
+        bytes: Vec<u8>,
+    ) -> Result<R, Error> {
+        self.upload_files(uri, field, content_type, vec![(filename, bytes)])
+            .await
+    }
+
+    /// Upload many files in the same form field of a single request.
+    async fn upload_files<R: serde::de::DeserializeOwned>(
+        &mut self,
+        uri: Origin<'_>,
+        field: &str,
+        content_type: &str,
+        files: Vec<(&str, Vec<u8>)>,
+    ) -> Result<R, Error> {
+        let cookie = match self {
+            User::NoAuth { cookie, .. } | User::Auth { cookie, .. } => cookie.clone(),
+            User::Anonymous { .. } => Cookie::new("empty", ""),
+        };
+        let mut request = match self {
+            User::NoAuth { client, .. } | User::Auth { client, .. } => client,
+            User::Anonymous { client } => client,
+        }
+        .req(Method::Post, uri)
+        .cookie(cookie);
+
+        // Build the form manually. This is very cobbersome but Rocket doesn't provide a better
+        // API just yet. See https://github.com/rwf2/Rocket/issues/1591.
+        let mut form = Vec::new();
+        for (filename, bytes) in files {
+            form.extend_from_slice(b"-----testboundary\r\n");
+            form.extend_from_slice(
+                format!(
+                    "Content-Disposition: form-data; name=\"{field}\"; filename=\"{filename}\"\r\n"
+                )
+                .as_bytes(),
+            );
+            form.extend_from_slice(format!("Content-Type: {content_type}\r\n").as_bytes());
+            form.extend_from_slice(b"\r\n");
+            form.extend_from_slice(&bytes);
+            form.extend_from_slice(b"\r\n");
+        }
+        form.extend_from_slice(b"-----testboundary--\r\n");
+        request.add_header(rocket::http::Header::new(
+            "Content-Type",
+            "multipart/form-data; boundary=---testboundary",
+        ));
+        request.set_body(form);
+
+        let response = request.dispatch().await;
+        if response.status() != Status::Ok {
+            return Err(anyhow!(
+                "Server returned status: {} body: {:?}",
+                response.status(),
+                response.into_string().await
+            ));
+        }
+
+        let content = response
+            .into_string()
+            .await
+            .ok_or_else(|| anyhow!("Could not deserialize from JSON: empty response."))?;
+        serde_json::from_str(&content).map_err(|e| anyhow!(e))
+    }
 }
@@ -4,6 +4,7 @@ use std::path::Path;
 
 pub mod dat;
 pub mod error;
+pub mod naming;
 pub mod optimize;
 
 pub use dat::*;
@@ -0,0 +1,107 @@
+//! Helpers to extract information from game names following the No-Intro
+//! naming convention, e.g. `Title (USA, Europe) (En,Fr,De) (Rev 1)`.
+
+/// Regions used in No-Intro names.
+const REGIONS: &[&str] = &[
+    "Asia",
+    "Australia",
+    "Brazil",
+    "Canada",
+    "China",
+    "Denmark",
+    "Europe",
+    "Finland",
+    "France",
+    "Germany",
+    "Greece",
+    "Hong Kong",
+    "Italy",
+    "Japan",
+    "Korea",
+    "Netherlands",
+    "Norway",
+    "Poland",
+    "Portugal",
+    "Russia",
+    "Scandinavia",
+    "Spain",
+    "Sweden",
+    "Taiwan",
+    "UK",
+    "USA",
+    "World",
+];
+
+/// Information found in the tags of a game name.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct NameTags {
+    /// The region(s) of the game, as written in the name (e.g. `USA, Europe`).
+    pub region: Option<String>,
+
+    /// The language codes of the game (e.g. `En`, `Fr`).
+    pub languages: Vec<String>,
+}
+
+/// Iterate over the content of the parenthesized tags of a name.
+fn tags(name: &str) -> impl Iterator<Item = &str> {
+    name.split('(')
+        .skip(1)
+        .filter_map(|tag| tag.split_once(')').map(|(tag, _)| tag.trim()))
+}
+
+fn is_region_tag(tag: &str) -> bool {
+    tag.split(',').all(|r| REGIONS.contains(&r.trim()))
+}
+
+fn is_language(code: &str) -> bool {
+    let mut chars = code.chars();
+    matches!(
+        (chars.next(), chars.next(), chars.next()),
+        (Some(a), Some(b), None) if a.is_ascii_uppercase() && b.is_ascii_lowercase()
+    )
+}
+
+fn is_languages_tag(tag: &str) -> bool {
+    tag.split(',').all(is_language)
+}
+
+/// Parse the region and languages tags of a game name. Tags that are not
+/// recognized are ignored.
+pub fn parse_name_tags(name: &str) -> NameTags {
+    NameTags {
+        region: tags(name).find(|t| is_region_tag(t)).map(str::to_string),
+        languages: tags(name)
+            .find(|t| is_languages_tag(t))
+            .map(|t| t.split(',').map(str::to_string).collect())
+            .unwrap_or_default(),
+    }
+}
+
+#[test]
+fn region_and_languages() {
+    assert_eq!(
+        parse_name_tags("Game (USA, Europe) (En,Fr,De) (Rev 1)"),
+        NameTags {
+            region: Some("USA, Europe".to_string()),
+            languages: vec!["En".to_string(), "Fr".to_string(), "De".to_string()],
+        }
+    );
+}
+
+#[test]
+fn region_only() {
+    assert_eq!(
+        parse_name_tags("Game (Japan)"),
+        NameTags {
+            region: Some("Japan".to_string()),
+            languages: vec![],
+        }
+    );
+}
+
+#[test]
+fn no_tags() {
+    assert_eq!(parse_name_tags("Game"), NameTags::default());
+    assert_eq!(parse_name_tags("Game (Beta) (Proto)"), NameTags::default());
+    assert_eq!(parse_name_tags("Game (USA"), NameTags::default());
+}
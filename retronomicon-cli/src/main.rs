@@ -7,14 +7,15 @@ use retronomicon_dto as dto;
 use retronomicon_dto::client::ClientConfig;
 use retronomicon_dto::encodings::HexString;
 use retronomicon_dto::params::RangeParams;
-use retronomicon_dto::types::IdOrSlug;
+use retronomicon_dto::types::{IdOrSlug, Metadata, Slug};
 use retronomicon_dto::user::UserIdOrUsername;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use sha2::Digest;
 use std::collections::BTreeMap;
 use std::io::Write;
 use std::path::PathBuf;
-use tracing::{debug, info, Level};
+use tracing::{debug, info, warn, Level};
 use tracing_subscriber::fmt::Subscriber;
 use url::Url;
 
@@ -71,6 +72,9 @@ struct Opts {
 
 #[derive(Debug, Parser)]
 enum Command {
+    /// Administrative commands. These require a root user.
+    Admin(AdminOpts),
+
     /// Core commands.
     Cores(CoreOpts),
 
@@ -83,6 +87,9 @@ enum Command {
     /// Games commands.
     Games(GamesOpts),
 
+    /// Tag commands.
+    Tags(TagOpts),
+
     /// Team commands.
     Teams(TeamOpts),
 
@@ -97,6 +104,32 @@ enum Command {
 
     /// Returns the authentication information.
     Whoami,
+
+    /// Plugin commands.
+    Plugins(PluginOpts),
+
+    /// Any other command is dispatched to a `retronomicon-<name>` executable
+    /// found on the `PATH`, similar to git.
+    #[command(external_subcommand)]
+    External(Vec<String>),
+}
+
+#[derive(Debug, Parser)]
+pub struct PluginOpts {
+    #[command(subcommand)]
+    pub command: PluginCommand,
+}
+
+#[derive(Debug, Parser)]
+pub enum PluginCommand {
+    /// List the plugins found on the `PATH`.
+    List,
+}
+
+#[derive(Debug, Serialize)]
+struct PluginInfo {
+    name: String,
+    path: PathBuf,
 }
 
 #[derive(Debug, Parser)]
@@ -133,6 +166,9 @@ pub enum ReleaseCommand {
     /// Download an artifact.
     Download(ReleaseDownloadOpts),
 
+    /// Download all the artifacts of a release as a zip archive.
+    DownloadAll(ReleaseDownloadAllOpts),
+
     /// List artifacts.
     Artifacts(ReleaseArtifactsOpts),
 }
@@ -142,6 +178,14 @@ pub struct ReleaseArtifactsOpts {
     /// The release's id.
     release_id: String,
 
+    /// Only list artifacts for this platform.
+    #[clap(long)]
+    platform: Option<IdOrSlug<'static>>,
+
+    /// Only list artifacts of this MIME type (e.g. application/x-bitstream).
+    #[clap(long)]
+    mime_type: Option<String>,
+
     #[clap(flatten)]
     paging: dto::params::PagingParams,
 }
@@ -155,6 +199,12 @@ pub struct ReleaseDownloadOpts {
     artifact: u32,
 }
 
+#[derive(Debug, Parser)]
+pub struct ReleaseDownloadAllOpts {
+    /// The release's id.
+    release_id: i32,
+}
+
 #[derive(Debug, Parser)]
 pub struct ReleaseListOpts {
     #[clap(flatten)]
@@ -183,6 +233,11 @@ pub struct ReleaseCreateOpts {
     #[clap(long)]
     prerelease: bool,
 
+    /// The release of the core's system (e.g. firmware) this release requires,
+    /// by id or version.
+    #[clap(long)]
+    required_system_release: Option<String>,
+
     /// Release's links. This is a key-value pair, separated by an equal sign.
     #[clap(long)]
     links: Vec<String>,
@@ -194,6 +249,12 @@ pub struct ReleaseCreateOpts {
     /// Release's files. These are going to be uploaded along with the release.
     #[clap(long)]
     files: Vec<PathBuf>,
+
+    /// Do not upload files the server already has an artifact for (with the
+    /// same SHA256). The existing artifacts are attached to the new release
+    /// instead.
+    #[clap(long)]
+    skip_existing: bool,
 }
 
 #[derive(Debug, Parser)]
@@ -224,12 +285,48 @@ pub enum CoreCommand {
 
     /// Update a core.
     Update(CoreUpdateOpts),
+
+    /// Show the changelog of a core.
+    Changelog(CoreChangelogOpts),
+
+    /// List the cores released since a date, from the most recent.
+    RecentlyReleased(CoresRecentlyReleasedOpts),
+
+    /// List the filenames of all the artifacts of a core, across releases.
+    ArtifactFilenames(CoreArtifactFilenamesOpts),
 }
 
 #[derive(Debug, Parser)]
 pub struct CoresListOpts {
     #[clap(flatten)]
     paging: dto::params::PagingParams,
+
+    /// Filter by name, case insensitive substring.
+    #[clap(long)]
+    name: Option<String>,
+
+    /// Search the name, slug and description, case insensitive substring.
+    #[clap(long)]
+    q: Option<String>,
+
+    /// Sort by `name`, `created` or `updated`. Prefix with `-` for a
+    /// descending order.
+    #[clap(long, allow_hyphen_values = true)]
+    sort: Option<dto::params::SortParams>,
+
+    /// Include the platforms each core has releases for.
+    #[clap(long)]
+    with_platforms: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct CoresRecentlyReleasedOpts {
+    #[clap(flatten)]
+    paging: dto::params::PagingParams,
+
+    /// The date to list releases from, either in RFC3339 or YYYY-MM-DD format.
+    #[clap(long)]
+    since: String,
 }
 
 #[derive(Debug, Parser)]
@@ -239,7 +336,7 @@ pub struct CoreCreateOpts {
 
     /// The slug for the URL.
     #[clap(long)]
-    slug: String,
+    slug: Slug<'static>,
 
     #[clap(long)]
     description: String,
@@ -270,6 +367,22 @@ pub struct CoreGetOpts {
 #[derive(Debug, Parser)]
 pub struct CoreUpdateOpts {}
 
+#[derive(Debug, Parser)]
+pub struct CoreChangelogOpts {
+    /// The core's slug or numerical id.
+    id: IdOrSlug<'static>,
+
+    /// Only show the releases of this platform (slug or numerical id).
+    #[clap(long)]
+    platform: Option<IdOrSlug<'static>>,
+}
+
+#[derive(Debug, Parser)]
+pub struct CoreArtifactFilenamesOpts {
+    /// The core's slug or numerical id.
+    id: IdOrSlug<'static>,
+}
+
 #[derive(Debug, Parser)]
 pub struct GamesOpts {
     #[command(subcommand)]
@@ -285,6 +398,25 @@ pub enum GamesCommand {
     AddArtifact(GameAddArtifactOpts),
     UpdateFromDat(GameUpdateFromDatOpts),
     AddImage(GameAddImageOpts),
+
+    /// Export the games of a system as a Logiqx XML DAT.
+    ExportDat(GameExportDatOpts),
+
+    /// Merge a duplicate game into another one. The duplicate is deleted.
+    Merge(GameMergeOpts),
+
+    /// Game image commands.
+    Images(GameImagesOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct GameMergeOpts {
+    /// The id of the game to keep.
+    id: i32,
+
+    /// The id of the duplicate game to merge into it.
+    #[clap(long)]
+    from: i32,
 }
 
 #[derive(Debug, Parser)]
@@ -308,6 +440,10 @@ pub struct GamesListOpts {
     #[clap(long)]
     exact_name: Option<String>,
 
+    /// Filter by region, case insensitive substring.
+    #[clap(long)]
+    region: Option<String>,
+
     /// Filter by md5, exact substring.
     #[clap(long)]
     md5: Vec<HexString>,
@@ -319,6 +455,31 @@ pub struct GamesListOpts {
     /// Filter by sha256, exact substring.
     #[clap(long)]
     sha256: Vec<HexString>,
+
+    /// Filter by crc32, exact substring.
+    #[clap(long)]
+    crc32: Vec<HexString>,
+
+    /// How to combine the checksum filters. With `all`, games must have a
+    /// matching artifact for every kind of checksum given.
+    #[clap(long, value_enum)]
+    checksum_match_mode: Option<dto::games::ChecksumMatchMode>,
+
+    /// Sort by `name`, `created` or `updated`. Prefix with `-` for a
+    /// descending order.
+    #[clap(long, allow_hyphen_values = true)]
+    sort: Option<dto::params::SortParams>,
+
+    /// How to count the total of games. `estimate` is faster on large
+    /// databases, but only applies when no filter is given.
+    #[clap(long, value_enum)]
+    count: Option<dto::params::CountMode>,
+
+    /// List all the games from the requested page onward, one page at a
+    /// time, printing each game as a line of JSON (NDJSON) as soon as its
+    /// page is received.
+    #[clap(long)]
+    stream: bool,
 }
 
 impl GamesListOpts {
@@ -328,6 +489,9 @@ impl GamesListOpts {
             year: self.year,
             name: self.name.clone(),
             exact_name: self.exact_name.clone(),
+            region: self.region.clone(),
+            sort: self.sort.map(|s| s.to_string()),
+            count: self.count,
         }
     }
     pub fn as_body_dto(&self) -> dto::games::GameListBody {
@@ -347,6 +511,12 @@ impl GamesListOpts {
             } else {
                 Some(self.sha256.clone())
             },
+            crc32: if self.crc32.is_empty() {
+                None
+            } else {
+                Some(self.crc32.clone())
+            },
+            checksum_match_mode: self.checksum_match_mode,
         }
     }
 }
@@ -380,9 +550,18 @@ pub struct GameCreateOpts {
     #[clap(long)]
     system: IdOrSlug<'static>,
 
-    /// The unique identifier for the game in the system.
+    /// The unique identifier for the game in the system. If omitted, the server
+    /// picks the next available one.
     #[clap(long)]
-    system_unique_id: u32,
+    system_unique_id: Option<u32>,
+
+    /// The region(s) the game was released in (e.g. `USA, Europe`).
+    #[clap(long)]
+    region: Option<String>,
+
+    /// A language code supported by the game (e.g. `En`). Can be repeated.
+    #[clap(long = "language")]
+    languages: Vec<String>,
 }
 
 impl GameCreateOpts {
@@ -396,7 +575,9 @@ impl GameCreateOpts {
             developer: &self.developer,
             links: links_dictionary_from_arg(&self.links).unwrap_or_default(),
             system: self.system.clone(),
-            system_unique_id: self.system_unique_id as i32,
+            region: self.region.as_deref(),
+            languages: self.languages.iter().map(String::as_str).collect(),
+            system_unique_id: self.system_unique_id.map(|i| i as i32),
         }
     }
 }
@@ -425,6 +606,10 @@ pub struct GameAddArtifactOpts {
     /// SHA256 checksum of the file, in hexadecimal.
     #[clap(long)]
     sha256: Option<HexString>,
+
+    /// CRC32 checksum of the file, in hexadecimal.
+    #[clap(long)]
+    crc32: Option<HexString>,
 }
 
 impl GameAddArtifactOpts {
@@ -435,6 +620,7 @@ impl GameAddArtifactOpts {
             md5: self.md5.clone(),
             sha1: self.sha1.clone(),
             sha256: self.sha256.clone(),
+            crc32: self.crc32.clone(),
         }
     }
 }
@@ -448,6 +634,39 @@ pub struct GameAddImageOpts {
     path: Vec<PathBuf>,
 }
 
+#[derive(Debug, Parser)]
+pub struct GameImagesOpts {
+    #[command(subcommand)]
+    pub command: GameImagesCommand,
+}
+
+#[derive(Debug, Parser)]
+pub enum GameImagesCommand {
+    /// List the images of a game.
+    List(GameImagesListOpts),
+
+    /// Set the primary image of a game.
+    SetPrimary(GameImagesSetPrimaryOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct GameImagesListOpts {
+    /// The game's unique id.
+    game: i32,
+
+    #[clap(flatten)]
+    paging: dto::params::PagingParams,
+}
+
+#[derive(Debug, Parser)]
+pub struct GameImagesSetPrimaryOpts {
+    /// The game's unique id.
+    game: i32,
+
+    /// The name of an image already uploaded to the game.
+    image: String,
+}
+
 #[derive(Debug, Parser)]
 pub struct GameUpdateFromDatOpts {
     /// The path to the DAT file.
@@ -457,6 +676,50 @@ pub struct GameUpdateFromDatOpts {
     system: IdOrSlug<'static>,
 }
 
+#[derive(Debug, Parser)]
+pub struct GameExportDatOpts {
+    /// The system's slug or numerical id.
+    #[clap(long)]
+    system: IdOrSlug<'static>,
+
+    /// The path of the DAT file to write.
+    #[clap(long)]
+    output: PathBuf,
+}
+
+#[derive(Debug, Parser)]
+pub struct AdminOpts {
+    #[command(subcommand)]
+    pub command: AdminCommand,
+}
+
+#[derive(Debug, Parser)]
+pub enum AdminCommand {
+    /// Core administration.
+    Cores(AdminCoreOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct AdminCoreOpts {
+    #[command(subcommand)]
+    pub command: AdminCoreCommand,
+}
+
+#[derive(Debug, Parser)]
+pub enum AdminCoreCommand {
+    /// Merge a duplicate core into another one of the same system.
+    Merge(AdminCoreMergeOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct AdminCoreMergeOpts {
+    /// The duplicate core's slug or numerical id. It is deleted after merging.
+    source: IdOrSlug<'static>,
+
+    /// The slug or numerical id of the core to merge into.
+    target: IdOrSlug<'static>,
+}
+
 #[derive(Debug, Parser)]
 pub struct TeamOpts {
     #[command(subcommand)]
@@ -494,7 +757,7 @@ pub struct TeamCreateOpts {
 
     /// The slug for the URL.
     #[clap(long)]
-    slug: String,
+    slug: Slug<'static>,
 
     #[clap(long, default_value = "")]
     description: String,
@@ -506,6 +769,10 @@ pub struct TeamCreateOpts {
     /// Team's metadata. This is a key-value pair, separated by an equal sign.
     #[clap(long)]
     metadata: Vec<String>,
+
+    /// Succeed without changes if a team with this slug already exists.
+    #[clap(long)]
+    upsert: bool,
 }
 
 #[derive(Debug, Parser)]
@@ -524,6 +791,12 @@ pub enum UserCommand {
 
     /// Get a user's details.
     Get(UserGet),
+
+    /// Search users by the start of their username.
+    Search(UsersSearch),
+
+    /// Change the password of the current user. This requires a token.
+    ChangePassword,
 }
 
 #[derive(Debug, Parser)]
@@ -545,6 +818,14 @@ pub enum PlatformCommand {
 pub struct PlatformsListOpts {
     #[clap(flatten)]
     paging: dto::params::PagingParams,
+
+    /// Only list platforms with cores for this system. Can be a slug or a numerical id.
+    #[clap(long)]
+    system: Option<IdOrSlug<'static>>,
+
+    /// Filter by name or slug, case insensitive substring.
+    #[clap(long)]
+    name: Option<String>,
 }
 
 #[derive(Debug, Parser)]
@@ -554,7 +835,7 @@ pub struct PlatformCreateOpts {
 
     /// The slug for the URL.
     #[clap(long)]
-    slug: String,
+    slug: Slug<'static>,
 
     /// The team to own the new platform.
     #[clap(long)]
@@ -570,6 +851,52 @@ pub struct PlatformCreateOpts {
     /// Platform's metadata. This is a key-value pair, separated by an equal sign.
     #[clap(long)]
     metadata: Vec<String>,
+
+    /// The schema core release metadata must follow on this platform, as JSON.
+    /// e.g. `{"required": ["storage"], "properties": {"storage": "string"}}`.
+    #[clap(long)]
+    metadata_schema: Option<String>,
+
+    /// Succeed without changes if a platform with this slug already exists.
+    #[clap(long)]
+    upsert: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct TagOpts {
+    #[command(subcommand)]
+    pub command: TagCommand,
+}
+
+#[derive(Debug, Parser)]
+pub enum TagCommand {
+    /// List tags.
+    List(TagsListOpts),
+
+    /// List the cores tagged with a tag.
+    Cores(TagItemsOpts),
+
+    /// List the games tagged with a tag.
+    Games(TagItemsOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct TagItemsOpts {
+    /// The tag's slug or numerical id.
+    id: IdOrSlug<'static>,
+
+    #[clap(flatten)]
+    paging: dto::params::PagingParams,
+}
+
+#[derive(Debug, Parser)]
+pub struct TagsListOpts {
+    #[clap(flatten)]
+    paging: dto::params::PagingParams,
+
+    /// Output the tag slugs, one per line, in their colors instead of JSON.
+    #[clap(long)]
+    color: bool,
 }
 
 #[derive(Debug, Parser)]
@@ -600,6 +927,18 @@ pub struct SystemGetOpts {
 pub struct SystemsListOpts {
     #[clap(flatten)]
     paging: dto::params::PagingParams,
+
+    /// Filter by name or slug, case insensitive substring.
+    #[clap(long)]
+    name: Option<String>,
+
+    /// Filter by manufacturer, case insensitive substring.
+    #[clap(long)]
+    manufacturer: Option<String>,
+
+    /// Only list systems owned by this team. Can be a slug or a numerical id.
+    #[clap(long)]
+    team: Option<IdOrSlug<'static>>,
 }
 
 #[derive(Debug, Parser)]
@@ -609,7 +948,7 @@ pub struct SystemCreateOpts {
 
     /// The slug for the URL.
     #[clap(long)]
-    slug: String,
+    slug: Slug<'static>,
 
     #[clap(long)]
     description: String,
@@ -629,6 +968,10 @@ pub struct SystemCreateOpts {
     /// Can be a slug or a numerical id.
     #[clap(long)]
     team: String,
+
+    /// Succeed without changes if a system with this slug already exists.
+    #[clap(long)]
+    upsert: bool,
 }
 
 #[derive(Debug, Parser)]
@@ -640,6 +983,10 @@ pub struct UpdateUser {
     #[clap(long)]
     pub username: Option<String>,
 
+    /// The new display name.
+    #[clap(long)]
+    pub display_name: Option<String>,
+
     /// The new user description.
     #[clap(long)]
     pub description: Option<String>,
@@ -652,6 +999,15 @@ pub struct UpdateUser {
     /// Remove a link to the user's links. This is the key to be removed. Can be repeated.
     #[clap(long)]
     pub remove_link: Vec<String>,
+
+    /// Add or replace a key of the user's metadata. This is a key-value pair, separated by
+    /// an equal sign, where the value is JSON. Can be repeated.
+    #[clap(long)]
+    pub metadata: Vec<String>,
+
+    /// Remove a key from the user's metadata. Can be repeated.
+    #[clap(long)]
+    pub remove_metadata: Vec<String>,
 }
 
 #[derive(Debug, Parser)]
@@ -666,6 +1022,16 @@ pub struct UserGet {
     id: UserIdOrUsername<'static>,
 }
 
+#[derive(Debug, Parser)]
+pub struct UsersSearch {
+    /// The start of the username, case insensitive.
+    prefix: String,
+
+    /// The maximum number of users to return.
+    #[clap(long)]
+    limit: Option<i64>,
+}
+
 fn output_json<J: Serialize>(value: J, opts: &Opts) -> Result<(), Error> {
     println!(
         "{}",
@@ -706,7 +1072,7 @@ fn links_dictionary_from_arg(arg: &Vec<String>) -> Option<BTreeMap<&str, &str>>
     }
 }
 
-fn metadata_dictionary_from_arg(arg: &Vec<String>) -> Result<Option<BTreeMap<&str, Value>>, Error> {
+fn metadata_dictionary_from_arg(arg: &[String]) -> Result<Option<Metadata>, Error> {
     if arg.is_empty() {
         Ok(None)
     } else {
@@ -715,17 +1081,43 @@ fn metadata_dictionary_from_arg(arg: &Vec<String>) -> Result<Option<BTreeMap<&st
                 let (key, value) = x.split_once('=').unwrap_or((x.as_str(), "null"));
                 Ok((key, serde_json::from_str(value)?))
             })
-            .collect::<Result<BTreeMap<_, _>, _>>()
-            .map(Some)
+            .collect::<Result<BTreeMap<&str, Value>, _>>()
+            .map(|m| Some(m.into()))
     }
 }
 
+/// Parse a date in RFC3339 or YYYY-MM-DD format, the latter at midnight UTC.
+fn parse_date(date: &str) -> Result<chrono::NaiveDateTime, chrono::ParseError> {
+    chrono::DateTime::parse_from_rfc3339(date)
+        .map(|d| d.naive_utc())
+        .or_else(|_| {
+            chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map(|d| d.and_time(chrono::NaiveTime::default()))
+        })
+}
+
 fn client(opts: &Opts) -> dto::client::V1Client {
     let mut config = ClientConfig::default().with_url(&opts.server).unwrap();
     if let Some(token) = &opts.token {
         config = config.with_token(token);
     }
-    dto::client::V1Client::new(config).unwrap()
+    dto::client::V1Client::new(config)
+        .unwrap()
+        .with_version_check(warn_if_server_is_newer)
+}
+
+/// Warn (once) if the server uses a newer API than this client was built with.
+fn warn_if_server_is_newer(server_version: &str) {
+    static WARNED: std::sync::Once = std::sync::Once::new();
+    if dto::client::is_newer_version(server_version, dto::VERSION) {
+        WARNED.call_once(|| {
+            warn!(
+                "The server API version ({server_version}) is newer than this client's ({}), \
+                 some commands may not work. Consider updating.",
+                dto::VERSION
+            );
+        });
+    }
 }
 
 async fn send_<Q>(
@@ -788,7 +1180,7 @@ where
 }
 
 async fn whoami(opts: &Opts) -> Result<(), Error> {
-    let response: dto::user::UserDetails = get("/api/v1/me", opts).await?;
+    let response: dto::user::Me = get("/api/v1/me", opts).await?;
     output_json(response, opts)
 }
 
@@ -814,28 +1206,23 @@ async fn release(opts: &Opts, release_opts: &CoreReleaseOpts) -> Result<(), Erro
             info!("Creating the release...");
             let date_released = match create_opts.date_released.as_ref() {
                 None => None,
-                Some(x) => Some(
-                    chrono::DateTime::parse_from_rfc3339(x)
-                        .map(|d| d.naive_utc())
-                        .or_else(|_| {
-                            chrono::NaiveDate::parse_from_str(x, "%Y-%m-%d")
-                                .map(|d| d.and_time(chrono::NaiveTime::default()))
-                        })?
-                        .timestamp(),
-                ),
+                Some(x) => Some(parse_date(x)?.timestamp()),
             };
             let response: dto::cores::releases::CoreReleaseCreateResponse = client
                 .cores_releases_create(
                     &core,
                     &dto::cores::releases::CoreReleaseCreateRequest {
                         version: &create_opts.version,
-                        notes: &create_opts.notes,
+                        notes: create_opts.notes.as_str().into(),
                         date_released,
                         prerelease: create_opts.prerelease,
                         links: links_dictionary_from_arg(&create_opts.links).unwrap_or_default(),
-                        metadata: metadata_dictionary_from_arg(&create_opts.metadata)?
-                            .unwrap_or_default(),
+                        metadata: metadata_dictionary_from_arg(&create_opts.metadata)?,
                         platform: IdOrSlug::parse(&create_opts.platform),
+                        required_system_release: create_opts
+                            .required_system_release
+                            .as_deref()
+                            .map(IdOrSlug::parse),
                     },
                 )
                 .await?;
@@ -844,10 +1231,34 @@ async fn release(opts: &Opts, release_opts: &CoreReleaseOpts) -> Result<(), Erro
 
             let release_id = response.id;
             for path in &create_opts.files {
+                if create_opts.skip_existing {
+                    let sha256 =
+                        HexString::from(sha2::Sha256::digest(std::fs::read(path)?).to_vec());
+                    match client.artifacts_by_sha256(&sha256).await {
+                        Ok(existing) => {
+                            info!(?path, id = existing.id, "Already uploaded, attaching");
+                            output_json(
+                                client
+                                    .cores_releases_artifacts_attach(&core, release_id, existing.id)
+                                    .await?,
+                                opts,
+                            )?;
+                            continue;
+                        }
+                        Err(dto::client::Error::ServerError(StatusCode::NOT_FOUND, _)) => {}
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+
                 info!(?path, "Uploading");
                 output_json(
                     client
-                        .cores_releases_artifacts_upload(&core, release_id, path)
+                        .cores_releases_artifacts_upload(
+                            &core,
+                            release_id,
+                            &Default::default(),
+                            path,
+                        )
                         .await?,
                     opts,
                 )?;
@@ -877,9 +1288,29 @@ async fn release(opts: &Opts, release_opts: &CoreReleaseOpts) -> Result<(), Erro
             std::io::stdout().write_all(&response)?;
             Ok(())
         }
-        ReleaseCommand::Artifacts(ReleaseArtifactsOpts { release_id, paging }) => output_json(
+        ReleaseCommand::DownloadAll(ReleaseDownloadAllOpts { release_id }) => {
+            let zip = client(opts)
+                .cores_releases_artifacts_download_zip(&core, *release_id)
+                .await?;
+            std::io::stdout().write_all(&zip)?;
+            Ok(())
+        }
+        ReleaseCommand::Artifacts(ReleaseArtifactsOpts {
+            release_id,
+            platform,
+            mime_type,
+            paging,
+        }) => output_json(
             client(opts)
-                .cores_releases_artifacts(&core, release_id.parse::<i32>().unwrap(), paging)
+                .cores_releases_artifacts(
+                    &core,
+                    release_id.parse::<i32>().unwrap(),
+                    paging,
+                    &dto::cores::releases::CoreReleaseArtifactFilterParams {
+                        platform: platform.clone(),
+                        mime_type: mime_type.clone(),
+                    },
+                )
                 .await?,
             opts,
         ),
@@ -891,17 +1322,26 @@ async fn core(opts: &Opts, core_opts: &CoreOpts) -> Result<(), Error> {
         CoreCommand::Releases(release_opts) => release(opts, release_opts).await,
 
         CoreCommand::List(list_opts) => {
-            output_json(client(opts).cores(&list_opts.paging).await?, opts)
+            let filter = dto::cores::CoreListQueryParams {
+                platform: None,
+                system: None,
+                owner_team: None,
+                release_date_ge: None,
+                name_contains: list_opts.name.clone(),
+                q: list_opts.q.clone(),
+                sort: list_opts.sort.map(|s| s.to_string()),
+                with_platforms: list_opts.with_platforms.then_some(true),
+            };
+            output_json(client(opts).cores(&filter, &list_opts.paging).await?, opts)
         }
         CoreCommand::Create(create_opts) => output_json(
             client(opts)
                 .cores_create(&dto::cores::CoreCreateRequest {
                     name: &create_opts.name,
-                    slug: &create_opts.slug,
+                    slug: create_opts.slug.clone(),
                     description: &create_opts.description,
                     links: links_dictionary_from_arg(&create_opts.links).unwrap_or_default(),
-                    metadata: metadata_dictionary_from_arg(&create_opts.metadata)?
-                        .unwrap_or_default(),
+                    metadata: metadata_dictionary_from_arg(&create_opts.metadata)?,
                     system: IdOrSlug::parse(&create_opts.system),
                     owner_team: IdOrSlug::parse(&create_opts.team),
                 })
@@ -914,22 +1354,50 @@ async fn core(opts: &Opts, core_opts: &CoreOpts) -> Result<(), Error> {
         CoreCommand::Update(CoreUpdateOpts {}) => {
             todo!()
         }
+        CoreCommand::Changelog(CoreChangelogOpts { id, platform }) => output_json(
+            client(opts)
+                .cores_changelog(
+                    id,
+                    &dto::cores::releases::CoreChangelogParams {
+                        platform: platform.clone(),
+                    },
+                )
+                .await?,
+            opts,
+        ),
+        CoreCommand::RecentlyReleased(CoresRecentlyReleasedOpts { paging, since }) => {
+            let query = dto::cores::CoreRecentlyReleasedQueryParams {
+                since: parse_date(since)?.timestamp(),
+            };
+            output_json(
+                client(opts).cores_recently_released(&query, paging).await?,
+                opts,
+            )
+        }
+        CoreCommand::ArtifactFilenames(CoreArtifactFilenamesOpts { id }) => {
+            output_json(client(opts).cores_artifact_filenames(id).await?, opts)
+        }
     }
 }
 
-impl<'v> From<&'v UpdateUser> for dto::user::UserUpdate<'v> {
-    fn from(
+impl<'v> TryFrom<&'v UpdateUser> for dto::user::UserUpdate<'v> {
+    type Error = Error;
+
+    fn try_from(
         UpdateUser {
             username,
+            display_name,
             description,
             add_link,
             remove_link,
+            metadata,
+            remove_metadata,
             ..
         }: &'v UpdateUser,
-    ) -> Self {
-        dto::user::UserUpdate {
+    ) -> Result<Self, Error> {
+        Ok(dto::user::UserUpdate {
             username: username.as_deref(),
-            display_name: None,
+            display_name: display_name.as_deref(),
             description: description.as_deref(),
             add_links: links_dictionary_from_arg(add_link),
             remove_links: if remove_link.is_empty() {
@@ -937,15 +1405,21 @@ impl<'v> From<&'v UpdateUser> for dto::user::UserUpdate<'v> {
             } else {
                 Some(remove_link.iter().map(|x| x.as_str()).collect())
             },
+            add_metadata: metadata_dictionary_from_arg(metadata)?,
+            remove_metadata: if remove_metadata.is_empty() {
+                None
+            } else {
+                Some(remove_metadata.iter().map(|x| x.as_str()).collect())
+            },
             ..Default::default()
-        }
+        })
     }
 }
 
 async fn user(opts: &Opts, user_opts: &UserOpts) -> Result<(), Error> {
     match &user_opts.command {
         UserCommand::Update(update_opts) => {
-            let update = update_opts.into();
+            let update = update_opts.try_into()?;
             match &update_opts.user {
                 None => output_json(client(opts).me_update(&update).await?, opts),
                 Some(user_id) => {
@@ -959,13 +1433,51 @@ async fn user(opts: &Opts, user_opts: &UserOpts) -> Result<(), Error> {
         UserCommand::Get(UserGet { id }) => {
             output_json(client(opts).users_details(id).await?, opts)
         }
+        UserCommand::Search(UsersSearch { prefix, limit }) => {
+            let query = dto::user::UserSearchQueryParams {
+                q: prefix.clone(),
+                limit: *limit,
+            };
+            output_json(client(opts).users_search(&query).await?, opts)
+        }
+        UserCommand::ChangePassword => {
+            if opts.token.is_none() {
+                return Err(Error::msg(
+                    "A token is required to change the password (see `login`).",
+                ));
+            }
+
+            let current_password = rpassword::prompt_password("Current password: ")?;
+            let new_password = rpassword::prompt_password("New password: ")?;
+            let confirm_password = rpassword::prompt_password("Confirm new password: ")?;
+            if new_password != confirm_password {
+                return Err(Error::msg("Passwords do not match."));
+            }
+
+            output_json(
+                client(opts)
+                    .me_change_password(&dto::auth::PasswordChangeRequest {
+                        current_password: &current_password,
+                        new_password: &new_password,
+                    })
+                    .await?,
+                opts,
+            )
+        }
     }
 }
 
 async fn platform(opts: &Opts, platform_opts: &PlatformOpts) -> Result<(), Error> {
     match &platform_opts.command {
         PlatformCommand::List(list_opts) => {
-            let query = format!("/api/v1/platforms?{}", to_query(&list_opts.paging));
+            let mut query = url::form_urlencoded::Serializer::new(to_query(&list_opts.paging));
+            if let Some(system) = &list_opts.system {
+                query.append_pair("system", &system.to_string());
+            }
+            if let Some(name) = &list_opts.name {
+                query.append_pair("name", name);
+            }
+            let query = format!("/api/v1/platforms?{}", query.finish());
 
             let response: Vec<dto::platforms::Platform> = get(&query, opts).await?;
             output_json(response, opts)
@@ -976,11 +1488,17 @@ async fn platform(opts: &Opts, platform_opts: &PlatformOpts) -> Result<(), Error
                 opts,
                 dto::platforms::PlatformCreateRequest {
                     name: &create_opts.name,
-                    slug: &create_opts.slug,
+                    slug: create_opts.slug.clone(),
                     description: &create_opts.description,
                     links: links_dictionary_from_arg(&create_opts.links),
                     metadata: metadata_dictionary_from_arg(&create_opts.metadata)?,
+                    metadata_schema: create_opts
+                        .metadata_schema
+                        .as_deref()
+                        .map(serde_json::from_str)
+                        .transpose()?,
                     owner_team: IdOrSlug::parse(&create_opts.team),
+                    upsert: create_opts.upsert,
                 },
             )
             .await?;
@@ -989,10 +1507,50 @@ async fn platform(opts: &Opts, platform_opts: &PlatformOpts) -> Result<(), Error
     }
 }
 
+async fn tag(opts: &Opts, tag_opts: &TagOpts) -> Result<(), Error> {
+    match &tag_opts.command {
+        TagCommand::List(list_opts) => {
+            let query = format!("/api/v1/tags?{}", to_query(&list_opts.paging));
+            let response: Vec<dto::tags::Tag> = get(&query, opts).await?;
+
+            if list_opts.color {
+                for tag in response {
+                    println!("{}{}\x1b[0m", tag.color_ansi(), tag.slug);
+                }
+                Ok(())
+            } else {
+                output_json(response, opts)
+            }
+        }
+        TagCommand::Cores(items_opts) => output_json(
+            client(opts)
+                .tags_cores(&items_opts.id, &items_opts.paging)
+                .await?,
+            opts,
+        ),
+        TagCommand::Games(items_opts) => output_json(
+            client(opts)
+                .tags_games(&items_opts.id, &items_opts.paging)
+                .await?,
+            opts,
+        ),
+    }
+}
+
 async fn system(opts: &Opts, system_opts: &SystemOpts) -> Result<(), Error> {
     match &system_opts.command {
         SystemCommand::List(list_opts) => {
-            let query = format!("/api/v1/systems?{}", to_query(&list_opts.paging));
+            let mut query = url::form_urlencoded::Serializer::new(to_query(&list_opts.paging));
+            if let Some(name) = &list_opts.name {
+                query.append_pair("name", name);
+            }
+            if let Some(manufacturer) = &list_opts.manufacturer {
+                query.append_pair("manufacturer", manufacturer);
+            }
+            if let Some(team) = &list_opts.team {
+                query.append_pair("team", &team.to_string());
+            }
+            let query = format!("/api/v1/systems?{}", query.finish());
 
             let response: Vec<dto::systems::SystemListItem> = get(&query, opts).await?;
             output_json(response, opts)
@@ -1006,18 +1564,20 @@ async fn system(opts: &Opts, system_opts: &SystemOpts) -> Result<(), Error> {
             links,
             metadata,
             team,
+            upsert,
         }) => {
             let response: dto::systems::SystemCreateResponse = post(
                 "/api/v1/systems",
                 opts,
                 dto::systems::SystemCreateRequest {
                     name,
-                    slug,
+                    slug: slug.clone(),
                     description,
                     manufacturer,
                     links: links_dictionary_from_arg(links),
                     metadata: metadata_dictionary_from_arg(metadata)?,
                     owner_team: IdOrSlug::parse(team),
+                    upsert: *upsert,
                 },
             )
             .await?;
@@ -1032,6 +1592,22 @@ async fn system(opts: &Opts, system_opts: &SystemOpts) -> Result<(), Error> {
     }
 }
 
+async fn admin(opts: &Opts, admin_opts: &AdminOpts) -> Result<(), Error> {
+    match &admin_opts.command {
+        AdminCommand::Cores(AdminCoreOpts {
+            command: AdminCoreCommand::Merge(AdminCoreMergeOpts { source, target }),
+        }) => output_json(
+            client(opts)
+                .admin_cores_merge(&dto::cores::CoreMergeRequest {
+                    source: source.clone(),
+                    target: target.clone(),
+                })
+                .await?,
+            opts,
+        ),
+    }
+}
+
 async fn team(opts: &Opts, team_opts: &TeamOpts) -> Result<(), Error> {
     match &team_opts.command {
         TeamCommand::List(list_opts) => {
@@ -1050,16 +1626,18 @@ async fn team(opts: &Opts, team_opts: &TeamOpts) -> Result<(), Error> {
             description,
             links,
             metadata,
+            upsert,
         }) => {
             let response: dto::teams::TeamCreateResponse = post(
                 "/api/v1/teams",
                 opts,
                 dto::teams::TeamCreateRequest {
                     name,
-                    slug,
+                    slug: slug.clone(),
                     description,
                     links: links_dictionary_from_arg(links),
                     metadata: metadata_dictionary_from_arg(metadata)?,
+                    upsert: *upsert,
                 },
             )
             .await?;
@@ -1070,6 +1648,27 @@ async fn team(opts: &Opts, team_opts: &TeamOpts) -> Result<(), Error> {
 
 async fn game(opts: &Opts, game_opts: &GamesOpts) -> Result<(), Error> {
     match &game_opts.command {
+        GamesCommand::List(list_opts) if list_opts.stream => {
+            let client = client(opts);
+            let query = list_opts.as_dto();
+            let body = list_opts.as_body_dto();
+            let limit = list_opts.paging.limit.unwrap_or(dto::params::LIMIT_MAX);
+            let mut page = list_opts.paging.page.unwrap_or(0);
+            loop {
+                let paging = dto::params::PagingParams {
+                    page: Some(page),
+                    limit: Some(limit),
+                };
+                let games = client.games(&query, &paging, &body).await?;
+                for game in games.iter() {
+                    println!("{}", serde_json::to_string(game)?);
+                }
+                if (games.items.len() as i64) < limit {
+                    return Ok(());
+                }
+                page += 1;
+            }
+        }
         GamesCommand::List(list_opts) => output_json(
             client(opts)
                 .games(
@@ -1084,6 +1683,17 @@ async fn game(opts: &Opts, game_opts: &GamesOpts) -> Result<(), Error> {
             client(opts).games_create(&create_opts.as_dto()).await?,
             opts,
         ),
+        GamesCommand::Merge(merge_opts) => output_json(
+            client(opts)
+                .games_merge(
+                    merge_opts.id,
+                    &dto::games::GameMergeRequest {
+                        game_id: merge_opts.from,
+                    },
+                )
+                .await?,
+            opts,
+        ),
         GamesCommand::AddArtifact(artifact_opts) => output_json(
             client(opts)
                 .games_add_artifact(artifact_opts.game, &vec![artifact_opts.as_dto()])
@@ -1092,7 +1702,6 @@ async fn game(opts: &Opts, game_opts: &GamesOpts) -> Result<(), Error> {
         ),
         GamesCommand::UpdateFromDat(update_opts) => {
             let dat = datary::read_file(&update_opts.dat).unwrap();
-            let mut i = i32::MAX;
             let client = client(opts);
             for game in dat.games {
                 let game_id = if let Some(g) = client
@@ -1102,6 +1711,9 @@ async fn game(opts: &Opts, game_opts: &GamesOpts) -> Result<(), Error> {
                             year: None,
                             name: None,
                             exact_name: Some(game.name.clone()),
+                            region: None,
+                            sort: None,
+                            count: None,
                         },
                         &Default::default(),
                         &Default::default(),
@@ -1113,6 +1725,7 @@ async fn game(opts: &Opts, game_opts: &GamesOpts) -> Result<(), Error> {
                     g.id
                 } else {
                     info!(?game, "Creating game");
+                    let tags = datary::naming::parse_name_tags(&game.name);
                     let game = dto::games::GameCreateRequest {
                         name: &game.name,
                         description: &game.description,
@@ -1125,10 +1738,9 @@ async fn game(opts: &Opts, game_opts: &GamesOpts) -> Result<(), Error> {
                         developer: "",
                         links: Default::default(),
                         system: update_opts.system.clone(),
-                        system_unique_id: game.id.map(|i| i as i32).unwrap_or_else(|| {
-                            i -= 1;
-                            i
-                        }),
+                        region: tags.region.as_deref(),
+                        languages: tags.languages.iter().map(String::as_str).collect(),
+                        system_unique_id: game.id.map(|i| i as i32),
                     };
                     let json = client.games_create(&game).await?;
                     output_json(&json, opts)?;
@@ -1154,6 +1766,7 @@ async fn game(opts: &Opts, game_opts: &GamesOpts) -> Result<(), Error> {
                                 md5: r.md5.map(|x| hex::decode(&x).unwrap().into()),
                                 sha1: r.sha1.map(|x| hex::decode(&x).unwrap().into()),
                                 sha256: Some(vec![].into()),
+                                crc32: r.crc.map(|x| hex::decode(&x).unwrap().into()),
                             }],
                         )
                         .await?;
@@ -1161,6 +1774,31 @@ async fn game(opts: &Opts, game_opts: &GamesOpts) -> Result<(), Error> {
             }
             Ok(())
         }
+        GamesCommand::ExportDat(GameExportDatOpts { system, output }) => {
+            let mut response = send_(
+                &Client::new(),
+                reqwest::Method::GET,
+                &format!("/api/v1/systems/{system}/games.dat"),
+                opts,
+                (),
+            )
+            .await?;
+            if response.status() != StatusCode::OK {
+                return Err(Error::msg(format!(
+                    "Status code: {}\n{}",
+                    response.status(),
+                    response.text().await?
+                )));
+            }
+
+            // Write the DAT as it is received, systems can have a lot of games.
+            let mut file = std::fs::File::create(output)?;
+            while let Some(chunk) = response.chunk().await? {
+                file.write_all(&chunk)?;
+            }
+            info!("DAT written to {}", output.display());
+            Ok(())
+        }
         GamesCommand::AddImage(GameAddImageOpts { game, path }) => {
             let client = client(opts);
 
@@ -1173,6 +1811,15 @@ async fn game(opts: &Opts, game_opts: &GamesOpts) -> Result<(), Error> {
             }
             Ok(())
         }
+        GamesCommand::Images(GameImagesOpts {
+            command: GameImagesCommand::List(GameImagesListOpts { game, paging }),
+        }) => output_json(client(opts).games_images(*game, paging).await?, opts),
+        GamesCommand::Images(GameImagesOpts {
+            command: GameImagesCommand::SetPrimary(GameImagesSetPrimaryOpts { game, image }),
+        }) => output_json(
+            client(opts).games_images_set_primary(*game, image).await?,
+            opts,
+        ),
     }
 }
 
@@ -1220,6 +1867,103 @@ async fn signup(opts: &Opts, signup_opts: &LoginOpts) -> Result<(), Error> {
     output_json(response, opts)
 }
 
+/// Prefix of the executables that are plugins.
+const PLUGIN_PREFIX: &str = "retronomicon-";
+
+#[cfg(unix)]
+fn is_executable(path: &std::path::Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &std::path::Path) -> bool {
+    path.is_file()
+}
+
+/// Find all plugins on the `PATH`. If the same plugin exists in multiple
+/// directories, the first one wins, the same way the shell would resolve it.
+fn find_plugins() -> BTreeMap<String, PathBuf> {
+    let mut plugins = BTreeMap::new();
+    let Some(path) = std::env::var_os("PATH") else {
+        return plugins;
+    };
+
+    for dir in std::env::split_paths(&path) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let file_name = entry.file_name();
+            let Some(name) = file_name
+                .to_str()
+                .and_then(|n| n.strip_prefix(PLUGIN_PREFIX))
+                .and_then(|n| n.strip_suffix(std::env::consts::EXE_SUFFIX))
+            else {
+                continue;
+            };
+            let path = entry.path();
+            if !name.is_empty() && is_executable(&path) {
+                plugins.entry(name.to_string()).or_insert(path);
+            }
+        }
+    }
+
+    plugins
+}
+
+fn find_plugin(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    let file_name = format!("{PLUGIN_PREFIX}{name}{}", std::env::consts::EXE_SUFFIX);
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(&file_name))
+        .find(|path| is_executable(path))
+}
+
+async fn plugin(opts: &Opts, plugin_opts: &PluginOpts) -> Result<(), Error> {
+    match plugin_opts.command {
+        PluginCommand::List => output_json(
+            find_plugins()
+                .into_iter()
+                .map(|(name, path)| PluginInfo { name, path })
+                .collect::<Vec<_>>(),
+            opts,
+        ),
+    }
+}
+
+/// Run a plugin, passing it the remaining arguments and the server of this
+/// invocation. The token is passed in the `RETRONOMICON_TOKEN` environment
+/// variable so it does not show up in the process list. Exits with the
+/// plugin's exit code if it fails.
+async fn external(opts: &Opts, args: &[String]) -> Result<(), Error> {
+    let (name, args) = args
+        .split_first()
+        .ok_or_else(|| anyhow::anyhow!("No command specified."))?;
+    let name = name.strip_prefix(PLUGIN_PREFIX).unwrap_or(name);
+    if name.is_empty() || name.contains(std::path::is_separator) {
+        return Err(anyhow::anyhow!("Invalid plugin name: {name}"));
+    }
+    let path =
+        find_plugin(name).ok_or_else(|| anyhow::anyhow!("Unknown command or plugin: {name}"))?;
+    debug!(?path, ?args, "Running plugin");
+
+    let mut command = tokio::process::Command::new(path);
+    command.args(args).arg("--server").arg(opts.server.as_str());
+    command.env_remove("RETRONOMICON_TOKEN");
+    if let Some(token) = &opts.token {
+        command.env("RETRONOMICON_TOKEN", token);
+    }
+
+    let status = command.status().await?;
+    if !status.success() {
+        std::process::exit(status.code().unwrap_or(1));
+    }
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() {
     let opts = Opts::parse();
@@ -1240,6 +1984,7 @@ async fn main() {
         .init();
 
     let result = match &opts.command {
+        Command::Admin(admin_opts) => admin(&opts, admin_opts).await,
         Command::Platforms(platform_opts) => platform(&opts, platform_opts).await,
         Command::Systems(system_opts) => system(&opts, system_opts).await,
         Command::Teams(team_opts) => team(&opts, team_opts).await,
@@ -1247,8 +1992,11 @@ async fn main() {
         Command::Whoami => whoami(&opts).await,
         Command::Cores(core_opts) => core(&opts, core_opts).await,
         Command::Games(games_opts) => game(&opts, games_opts).await,
+        Command::Tags(tag_opts) => tag(&opts, tag_opts).await,
         Command::Login(login_opts) => login(&opts, login_opts).await,
         Command::Signup(login_opts) => signup(&opts, login_opts).await,
+        Command::Plugins(plugin_opts) => plugin(&opts, plugin_opts).await,
+        Command::External(args) => external(&opts, args).await,
     };
 
     match result {
@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX user_passwords_reset_token_idx;
+
+ALTER TABLE user_passwords
+    DROP COLUMN reset_token,
+    DROP COLUMN reset_requested_at;
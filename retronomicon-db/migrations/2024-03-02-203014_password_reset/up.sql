@@ -0,0 +1,7 @@
+-- Your SQL goes here
+
+ALTER TABLE user_passwords
+    ADD COLUMN reset_token        VARCHAR(255),
+    ADD COLUMN reset_requested_at TIMESTAMP;
+
+CREATE UNIQUE INDEX user_passwords_reset_token_idx ON user_passwords (reset_token);
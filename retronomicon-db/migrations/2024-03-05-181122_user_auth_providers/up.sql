@@ -0,0 +1,17 @@
+-- Your SQL goes here
+
+CREATE TABLE user_auth_providers
+(
+    user_id     INT REFERENCES users (id) ON DELETE CASCADE ON UPDATE CASCADE NOT NULL,
+    provider    VARCHAR(255)                                                  NOT NULL,
+    external_id VARCHAR(255)                                                  NOT NULL,
+    created_at  TIMESTAMP                                                     NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (provider, external_id)
+);
+
+CREATE INDEX user_auth_providers_user_id_idx ON user_auth_providers (user_id);
+
+COMMENT ON TABLE user_auth_providers IS 'OAuth providers linked to a user. A user can login with any of them.';
+
+-- Existing OAuth users are not linked here, as only the provider knows their
+-- account id. They are matched by email and linked on their next login.
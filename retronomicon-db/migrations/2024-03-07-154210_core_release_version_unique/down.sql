@@ -0,0 +1,10 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE core_releases
+    DROP CONSTRAINT core_releases_core_id_platform_id_version_key;
+
+CREATE UNIQUE INDEX core_releases_core_id_platform_id_system_id_version_idx ON
+    core_releases (
+                   core_id,
+                   platform_id,
+                   "version" DESC
+        );
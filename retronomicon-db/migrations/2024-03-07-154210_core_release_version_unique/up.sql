@@ -0,0 +1,6 @@
+-- Replace the unique index on releases by a named unique constraint.
+DROP INDEX core_releases_core_id_platform_id_system_id_version_idx;
+
+ALTER TABLE core_releases
+    ADD CONSTRAINT core_releases_core_id_platform_id_version_key
+        UNIQUE (core_id, platform_id, "version");
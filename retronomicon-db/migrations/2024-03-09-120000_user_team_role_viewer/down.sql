@@ -0,0 +1,11 @@
+-- This file should undo anything in `up.sql`
+-- Postgres cannot drop an enum value, so the type is recreated without it.
+DELETE FROM user_teams WHERE "role" = 'viewer';
+
+ALTER TYPE user_team_role RENAME TO user_team_role_old;
+CREATE TYPE user_team_role AS ENUM('owner', 'admin', 'member');
+
+ALTER TABLE user_teams
+    ALTER COLUMN "role" TYPE user_team_role USING "role"::text::user_team_role;
+
+DROP TYPE user_team_role_old;
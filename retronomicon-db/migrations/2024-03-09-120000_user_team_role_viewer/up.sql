@@ -0,0 +1,2 @@
+-- Add a read-only role below members.
+ALTER TYPE user_team_role ADD VALUE 'viewer' BEFORE 'member';
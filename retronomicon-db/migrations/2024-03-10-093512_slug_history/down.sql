@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE core_slug_history;
+DROP TABLE team_slug_history;
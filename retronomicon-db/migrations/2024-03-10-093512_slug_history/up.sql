@@ -0,0 +1,19 @@
+-- Your SQL goes here
+
+CREATE TABLE team_slug_history
+(
+    old_slug   VARCHAR(255) PRIMARY KEY                                      NOT NULL,
+    team_id    INT REFERENCES teams (id) ON DELETE CASCADE ON UPDATE CASCADE NOT NULL,
+    created_at TIMESTAMP                                                     NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+COMMENT ON TABLE team_slug_history IS 'Slugs a team used to have, so old links can be redirected.';
+
+CREATE TABLE core_slug_history
+(
+    old_slug   VARCHAR(255) PRIMARY KEY                                      NOT NULL,
+    core_id    INT REFERENCES cores (id) ON DELETE CASCADE ON UPDATE CASCADE NOT NULL,
+    created_at TIMESTAMP                                                     NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+COMMENT ON TABLE core_slug_history IS 'Slugs a core used to have, so old links can be redirected.';
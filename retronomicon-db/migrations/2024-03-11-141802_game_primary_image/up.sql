@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE games
+    ADD COLUMN primary_image_id INT REFERENCES game_images (id) ON DELETE SET NULL;
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE platforms
+    DROP COLUMN metadata_schema;
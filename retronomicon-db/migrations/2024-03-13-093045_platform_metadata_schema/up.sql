@@ -0,0 +1,5 @@
+-- Your SQL goes here
+ALTER TABLE platforms
+    ADD COLUMN metadata_schema JSONB;
+
+COMMENT ON COLUMN platforms.metadata_schema IS 'Required keys and value types of the metadata of core releases on this platform.';
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX cores_name_trgm_idx;
+DROP EXTENSION IF EXISTS pg_trgm;
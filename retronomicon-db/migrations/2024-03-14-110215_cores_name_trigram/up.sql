@@ -0,0 +1,5 @@
+-- Your SQL goes here
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX cores_name_trgm_idx ON cores USING GIN (name gin_trgm_ops);
+COMMENT ON INDEX cores_name_trgm_idx IS 'Fuzzy and substring search of core names.';
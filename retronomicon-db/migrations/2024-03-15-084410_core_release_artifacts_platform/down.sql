@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE core_release_artifacts
+    DROP COLUMN platform_id;
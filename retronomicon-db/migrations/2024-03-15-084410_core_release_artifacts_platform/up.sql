@@ -0,0 +1,14 @@
+-- Your SQL goes here
+ALTER TABLE core_release_artifacts
+    ADD COLUMN platform_id INT REFERENCES platforms (id);
+
+-- Existing artifacts are for the platform of their release.
+UPDATE core_release_artifacts
+SET platform_id = core_releases.platform_id
+FROM core_releases
+WHERE core_releases.id = core_release_artifacts.core_release_id;
+
+ALTER TABLE core_release_artifacts
+    ALTER COLUMN platform_id SET NOT NULL;
+
+COMMENT ON COLUMN core_release_artifacts.platform_id IS 'The platform this artifact is for. A release can package artifacts for other platforms.';
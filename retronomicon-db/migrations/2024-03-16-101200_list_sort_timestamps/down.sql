@@ -0,0 +1,15 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER IF EXISTS set_updated_at ON core_releases;
+ALTER TABLE core_releases
+    DROP COLUMN updated_at,
+    DROP COLUMN created_at;
+
+DROP TRIGGER IF EXISTS set_updated_at ON games;
+ALTER TABLE games
+    DROP COLUMN updated_at,
+    DROP COLUMN created_at;
+
+DROP TRIGGER IF EXISTS set_updated_at ON cores;
+ALTER TABLE cores
+    DROP COLUMN updated_at,
+    DROP COLUMN created_at;
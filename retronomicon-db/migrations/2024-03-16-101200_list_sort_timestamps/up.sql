@@ -0,0 +1,17 @@
+-- Your SQL goes here
+ALTER TABLE cores
+    ADD COLUMN created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    ADD COLUMN updated_at TIMESTAMP NOT NULL DEFAULT NOW();
+SELECT diesel_manage_updated_at('cores');
+
+ALTER TABLE games
+    ADD COLUMN created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    ADD COLUMN updated_at TIMESTAMP NOT NULL DEFAULT NOW();
+SELECT diesel_manage_updated_at('games');
+
+ALTER TABLE core_releases
+    ADD COLUMN created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    ADD COLUMN updated_at TIMESTAMP NOT NULL DEFAULT NOW();
+SELECT diesel_manage_updated_at('core_releases');
+
+COMMENT ON COLUMN core_releases.created_at IS 'When the release was uploaded. Unlike date_released, this cannot be set by the uploader.';
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX platforms_name_trgm_idx;
+DROP INDEX systems_manufacturer_trgm_idx;
+DROP INDEX systems_name_trgm_idx;
@@ -0,0 +1,11 @@
+-- Your SQL goes here
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX systems_name_trgm_idx ON systems USING GIN (name gin_trgm_ops);
+COMMENT ON INDEX systems_name_trgm_idx IS 'Substring search of system names.';
+
+CREATE INDEX systems_manufacturer_trgm_idx ON systems USING GIN (manufacturer gin_trgm_ops);
+COMMENT ON INDEX systems_manufacturer_trgm_idx IS 'Substring search of system manufacturers.';
+
+CREATE INDEX platforms_name_trgm_idx ON platforms USING GIN (name gin_trgm_ops);
+COMMENT ON INDEX platforms_name_trgm_idx IS 'Substring search of platform names.';
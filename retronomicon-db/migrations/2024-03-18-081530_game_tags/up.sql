@@ -0,0 +1,7 @@
+-- Your SQL goes here
+CREATE TABLE game_tags
+(
+    game_id integer NOT NULL REFERENCES games ON DELETE CASCADE,
+    tag_id  integer NOT NULL REFERENCES tags ON DELETE CASCADE,
+    CONSTRAINT game_tags_pkey PRIMARY KEY (tag_id, game_id)
+);
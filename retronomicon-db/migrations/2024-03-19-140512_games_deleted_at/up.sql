@@ -0,0 +1,5 @@
+-- Your SQL goes here
+ALTER TABLE games
+    ADD COLUMN deleted_at TIMESTAMP;
+
+COMMENT ON COLUMN games.deleted_at IS 'When the game was deleted, e.g. merged into another game. Deleted games are hidden.';
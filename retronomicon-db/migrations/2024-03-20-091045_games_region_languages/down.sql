@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE games
+    DROP COLUMN languages,
+    DROP COLUMN region;
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+ALTER TABLE games
+    ADD COLUMN region    VARCHAR(255),
+    ADD COLUMN languages TEXT[] NOT NULL DEFAULT '{}';
+
+COMMENT ON COLUMN games.region IS 'The region(s) the game was released in, e.g. "USA, Europe".';
+COMMENT ON COLUMN games.languages IS 'The language codes supported by the game, e.g. {"En", "Fr"}.';
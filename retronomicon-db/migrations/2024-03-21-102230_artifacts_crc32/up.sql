@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE artifacts ADD COLUMN crc32 bytea NOT NULL DEFAULT E''::bytea;
@@ -0,0 +1,12 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER IF EXISTS set_updated_at ON platforms;
+ALTER TABLE platforms
+    DROP COLUMN updated_at;
+
+DROP TRIGGER IF EXISTS set_updated_at ON systems;
+ALTER TABLE systems
+    DROP COLUMN updated_at;
+
+DROP TRIGGER IF EXISTS set_updated_at ON teams;
+ALTER TABLE teams
+    DROP COLUMN updated_at;
@@ -0,0 +1,12 @@
+-- Your SQL goes here
+ALTER TABLE teams
+    ADD COLUMN updated_at TIMESTAMP NOT NULL DEFAULT NOW();
+SELECT diesel_manage_updated_at('teams');
+
+ALTER TABLE systems
+    ADD COLUMN updated_at TIMESTAMP NOT NULL DEFAULT NOW();
+SELECT diesel_manage_updated_at('systems');
+
+ALTER TABLE platforms
+    ADD COLUMN updated_at TIMESTAMP NOT NULL DEFAULT NOW();
+SELECT diesel_manage_updated_at('platforms');
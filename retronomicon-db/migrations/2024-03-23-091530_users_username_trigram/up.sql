@@ -0,0 +1,3 @@
+-- Your SQL goes here
+CREATE INDEX users_username_trgm_idx ON users USING GIN (username gin_trgm_ops);
+COMMENT ON INDEX users_username_trgm_idx IS 'Prefix search of usernames.';
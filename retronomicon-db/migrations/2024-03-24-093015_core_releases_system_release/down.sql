@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE core_releases
+    DROP COLUMN system_release_id;
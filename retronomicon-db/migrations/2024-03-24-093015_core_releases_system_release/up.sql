@@ -0,0 +1,5 @@
+-- Your SQL goes here
+ALTER TABLE core_releases
+    ADD COLUMN system_release_id INT REFERENCES system_releases (id);
+
+COMMENT ON COLUMN core_releases.system_release_id IS 'The system release (e.g. firmware) this release requires, if any.';
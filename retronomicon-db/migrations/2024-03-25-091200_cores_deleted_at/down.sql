@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE cores
+    DROP COLUMN deleted_by_id,
+    DROP COLUMN deleted_at;
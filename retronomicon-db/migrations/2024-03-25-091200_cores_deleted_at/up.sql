@@ -0,0 +1,7 @@
+-- Your SQL goes here
+ALTER TABLE cores
+    ADD COLUMN deleted_at TIMESTAMP,
+    ADD COLUMN deleted_by_id INTEGER REFERENCES users;
+
+COMMENT ON COLUMN cores.deleted_at IS 'When the core was deleted, e.g. merged into another core. Deleted cores are hidden, but keep their slug, which redirects to the core they were merged into.';
+COMMENT ON COLUMN cores.deleted_by_id IS 'The user who deleted the core.';
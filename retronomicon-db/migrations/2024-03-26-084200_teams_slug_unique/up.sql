@@ -0,0 +1,3 @@
+-- Your SQL goes here
+
+ALTER TABLE teams ADD CONSTRAINT teams_slug_unique UNIQUE (slug);
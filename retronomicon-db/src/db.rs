@@ -1,5 +1,8 @@
 use diesel::PgConnection;
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use rocket::http::Status;
+use rocket_db_pools::diesel::scoped_futures::ScopedBoxFuture;
+use rocket_db_pools::diesel::AsyncConnection;
 use rocket_db_pools::{Connection, Database, Initializer};
 use tracing::info;
 
@@ -14,10 +17,94 @@ impl RetronomiconDbPool {
         info!("Initializing database");
         Database::init()
     }
+
+    /// A handle to open connections outside of requests (e.g. in background
+    /// tasks), which can outlive the server.
+    pub fn detached(&self) -> ssl_pool::Pool {
+        self.0.clone()
+    }
 }
 
 pub type Db = Connection<RetronomiconDbPool>;
 
+/// A database connection that is not tied to a request. [`Db`] dereferences to
+/// it.
+pub type DbConnection = rocket_db_pools::diesel::AsyncPgConnection;
+
+pub use diesel::result::Error as DbError;
+
+/// Classify a database error that a client can cause, with a message that is
+/// safe to return to it. Returns `None` for unexpected errors, whose message
+/// may contain queries or constraint names and should only be logged.
+pub fn classify_error(error: &DbError) -> Option<(Status, &'static str)> {
+    use diesel::result::DatabaseErrorKind;
+
+    match error {
+        DbError::NotFound => Some((Status::NotFound, "Not found")),
+        DbError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+            Some((Status::Conflict, "Resource already exists"))
+        }
+        DbError::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => {
+            Some((Status::Conflict, "Conflicts with a related resource"))
+        }
+        DbError::DatabaseError(
+            DatabaseErrorKind::NotNullViolation | DatabaseErrorKind::CheckViolation,
+            _,
+        ) => Some((Status::BadRequest, "Invalid value")),
+        _ => None,
+    }
+}
+
+/// An error returned from within a [`transaction`]. Database errors are
+/// classified with [`classify_error`], or reported as internal server errors,
+/// and route errors are kept as is.
+#[derive(Debug)]
+pub struct TransactionError(pub Status, pub String);
+
+impl From<DbError> for TransactionError {
+    fn from(value: DbError) -> Self {
+        match classify_error(&value) {
+            Some((status, message)) => Self(status, message.to_string()),
+            None => Self(Status::InternalServerError, value.to_string()),
+        }
+    }
+}
+
+impl From<(Status, String)> for TransactionError {
+    fn from((status, message): (Status, String)) -> Self {
+        Self(status, message)
+    }
+}
+
+impl From<TransactionError> for (Status, String) {
+    fn from(value: TransactionError) -> Self {
+        (value.0, value.1)
+    }
+}
+
+/// Re-exported so callers of [`transaction`] can box their callback with
+/// `ScopedFutureExt::scope_boxed`.
+pub use rocket_db_pools::diesel::scoped_futures;
+
+/// Run multiple writes of a route handler in a single transaction. If the
+/// callback returns an error, everything it wrote is rolled back.
+pub async fn transaction<'a, R, F>(db: &mut Db, callback: F) -> Result<R, (Status, String)>
+where
+    F: for<'r> FnOnce(&'r mut Db) -> ScopedBoxFuture<'a, 'r, Result<R, TransactionError>>
+        + Send
+        + 'a,
+    R: Send + 'a,
+{
+    db.transaction(callback).await.map_err(Into::into)
+}
+
+/// Run a trivial query to check that the database answers.
+pub async fn ping(db: &mut Db) -> Result<(), diesel::result::Error> {
+    use rocket_db_pools::diesel::RunQueryDsl;
+
+    diesel::sql_query("SELECT 1").execute(db).await.map(|_| ())
+}
+
 const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/");
 
 pub fn run_migrations(database_url: &str) {
@@ -3,13 +3,75 @@ use rocket::figment::Figment;
 use rocket::futures::future::BoxFuture;
 use rocket::futures::FutureExt;
 use rocket::tokio;
+use rocket::tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use rocket_db_pools::diesel::AsyncPgConnection;
 use rustls::Certificate;
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The number of connections that can be open at the same time, if the
+/// `pool_size` of the database is not configured.
+pub const DEFAULT_POOL_SIZE: usize = 16;
+
+/// The number of connections request guards can open at the same time, if
+/// the `guard_pool_size` of the database is not configured.
+pub const DEFAULT_GUARD_POOL_SIZE: usize = 4;
+
+/// How long to wait for a connection when the pool is full, if the
+/// `connect_timeout` of the database is not configured.
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Counters of the connections of a pool, for metrics.
+#[derive(Debug, Default)]
+struct PoolStats {
+    in_use: AtomicUsize,
+    waiters: AtomicUsize,
+}
+
+/// Counts a task waiting for a connection while it exists.
+struct Waiter(Arc<PoolStats>);
+
+impl Waiter {
+    fn new(stats: &Arc<PoolStats>) -> Self {
+        stats.waiters.fetch_add(1, Ordering::Relaxed);
+        Self(stats.clone())
+    }
+}
+
+impl Drop for Waiter {
+    fn drop(&mut self) {
+        self.0.waiters.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// A slot of the pool, held for as long as its connection is open.
+struct Lease {
+    _permit: OwnedSemaphorePermit,
+    stats: Arc<PoolStats>,
+}
+
+impl Lease {
+    fn new(permit: OwnedSemaphorePermit, stats: &Arc<PoolStats>) -> Self {
+        stats.in_use.fetch_add(1, Ordering::Relaxed);
+        Self {
+            _permit: permit,
+            stats: stats.clone(),
+        }
+    }
+}
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        self.stats.in_use.fetch_sub(1, Ordering::Relaxed);
+    }
+}
 
 fn establish_connection<'a>(
     url: &'a str,
     additional_certs: &'a [Certificate],
+    lease: Option<Lease>,
 ) -> BoxFuture<'a, ConnectionResult<AsyncPgConnection>> {
     let fut = async {
         // We first set up the way we want rustls to work.
@@ -21,10 +83,13 @@ fn establish_connection<'a>(
         let (client, conn) = tokio_postgres::connect(url, tls)
             .await
             .map_err(|e| ConnectionError::BadConnection(e.to_string()))?;
+        // The connection ends when the client is dropped, which frees its
+        // slot in the pool.
         tokio::spawn(async move {
             if let Err(e) = conn.await {
                 rocket::error!("Database connection error: {e}");
             }
+            drop(lease);
         });
         AsyncPgConnection::try_from(client).await
     };
@@ -56,10 +121,74 @@ fn root_certs(additional_certs: &[Certificate]) -> rustls::RootCertStore {
 /// The default diesel-async (and by extension, rocket_db_pool::PgPool) does not support
 /// SSL by default (it uses NoTls when connecting). This is not a problem locally but
 /// when using the databases in production we want to be able to use a proper SSL connection.
+///
+/// Connections are not reused, but at most `pool_size` of them can be open at
+/// the same time. Others wait for a connection to close.
+///
+/// Request guards that need the database (e.g. to check the user's team) run
+/// after the route's own connection may have been opened. They get their
+/// connections from `guard_pool_size` separate slots, see
+/// [`Pool::connect_for_guard`].
+#[derive(Clone)]
 pub struct Pool {
     url: String,
     /// A list of additional certificates to trust when connecting to the database.
     additional_certs: Vec<Certificate>,
+    size: usize,
+    connect_timeout: Duration,
+    semaphore: Arc<Semaphore>,
+    guard_semaphore: Arc<Semaphore>,
+    stats: Arc<PoolStats>,
+}
+
+impl Pool {
+    /// Open a new connection to the database, waiting for one to close if
+    /// the pool is full.
+    pub async fn connect(&self) -> ConnectionResult<AsyncPgConnection> {
+        self.connect_with(&self.semaphore).await
+    }
+
+    /// Open a short-lived connection for a request guard. The route holding
+    /// the request may already use a connection; if guards waited for the
+    /// same slots, enough concurrent requests would each hold a slot while
+    /// waiting for another one, and none would make progress.
+    pub async fn connect_for_guard(&self) -> ConnectionResult<AsyncPgConnection> {
+        self.connect_with(&self.guard_semaphore).await
+    }
+
+    async fn connect_with(
+        &self,
+        semaphore: &Arc<Semaphore>,
+    ) -> ConnectionResult<AsyncPgConnection> {
+        let waiter = Waiter::new(&self.stats);
+        let acquire = semaphore.clone().acquire_owned();
+        let permit = tokio::time::timeout(self.connect_timeout, acquire)
+            .await
+            .map_err(|_| {
+                ConnectionError::BadConnection("Timed out waiting for a connection".into())
+            })?
+            .map_err(|_| ConnectionError::BadConnection("The pool is closed".into()))?;
+        drop(waiter);
+
+        let lease = Lease::new(permit, &self.stats);
+        establish_connection(&self.url, &self.additional_certs, Some(lease)).await
+    }
+
+    /// The maximum number of connections open at the same time, not counting
+    /// the connections of request guards.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The number of connections currently open.
+    pub fn in_use(&self) -> usize {
+        self.stats.in_use.load(Ordering::Relaxed)
+    }
+
+    /// The number of tasks waiting for a connection because the pool is full.
+    pub fn waiters(&self) -> usize {
+        self.stats.waiters.load(Ordering::Relaxed)
+    }
 }
 
 #[rocket::async_trait]
@@ -80,18 +209,36 @@ impl rocket_db_pools::Pool for Pool {
             .into_iter()
             .flatten()
             .collect();
+        let size = figment
+            .extract_inner::<usize>("pool_size")
+            .unwrap_or(DEFAULT_POOL_SIZE);
+        let guard_size = figment
+            .extract_inner::<usize>("guard_pool_size")
+            .unwrap_or(DEFAULT_GUARD_POOL_SIZE);
+        let connect_timeout = figment
+            .extract_inner::<u64>("connect_timeout")
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_CONNECT_TIMEOUT);
 
         // Establish the connection once (to check).
-        establish_connection(&url, &certs).await?;
+        establish_connection(&url, &certs, None).await?;
         Ok(Self {
             url,
             additional_certs: certs,
+            size,
+            connect_timeout,
+            semaphore: Arc::new(Semaphore::new(size)),
+            guard_semaphore: Arc::new(Semaphore::new(guard_size)),
+            stats: Arc::default(),
         })
     }
 
     async fn get(&self) -> Result<Self::Connection, Self::Error> {
-        establish_connection(&self.url, &self.additional_certs).await
+        self.connect().await
     }
 
-    async fn close(&self) {}
+    async fn close(&self) {
+        self.semaphore.close();
+        self.guard_semaphore.close();
+    }
 }
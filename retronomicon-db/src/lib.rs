@@ -5,3 +5,4 @@ pub mod models;
 pub mod pages;
 pub mod schema;
 pub mod types;
+pub mod utils;
@@ -1,6 +1,8 @@
 use crate::models::{Core, CoreRelease, Platform, System, User};
 use crate::pages::Paginate;
 use crate::schema;
+use crate::types::FromIdOrSlug;
+use crate::utils::escape_like;
 use crate::Db;
 use chrono::NaiveDateTime;
 use diesel::deserialize::FromSql;
@@ -17,10 +19,12 @@ use sha2::Digest;
 #[diesel(primary_key(core_release_id, artifact_id))]
 #[diesel(belongs_to(models::CoreRelease))]
 #[diesel(belongs_to(models::Artifact))]
+#[diesel(belongs_to(models::Platform))]
 #[diesel(table_name = schema::core_release_artifacts)]
 pub struct CoreReleaseArtifact {
     pub core_release_id: i32,
     pub artifact_id: i32,
+    pub platform_id: i32,
 }
 
 impl CoreReleaseArtifact {
@@ -28,11 +32,13 @@ impl CoreReleaseArtifact {
         db: &mut Db,
         core_release: &CoreRelease,
         artifact: &Artifact,
+        platform_id: i32,
     ) -> Result<Self, diesel::result::Error> {
         diesel::insert_into(schema::core_release_artifacts::table)
             .values((
                 schema::core_release_artifacts::core_release_id.eq(core_release.id),
                 schema::core_release_artifacts::artifact_id.eq(artifact.id),
+                schema::core_release_artifacts::platform_id.eq(platform_id),
             ))
             .returning(schema::core_release_artifacts::all_columns)
             .get_result::<Self>(db)
@@ -68,15 +74,81 @@ impl CoreReleaseArtifact {
             .map(|c| c == 0)
     }
 
+    /// All the filenames used by the artifacts of a core, across all its
+    /// releases, without duplicates and sorted.
+    pub async fn list_all_filenames_for_core(
+        db: &mut Db,
+        core_id: i32,
+    ) -> Result<Vec<String>, diesel::result::Error> {
+        schema::core_release_artifacts::table
+            .inner_join(schema::artifacts::table)
+            .inner_join(schema::core_releases::table)
+            .filter(schema::core_releases::core_id.eq(core_id))
+            .select(schema::artifacts::filename)
+            .distinct()
+            .order(schema::artifacts::filename.asc())
+            .load::<String>(db)
+            .await
+    }
+
+    /// The `n`th alternative of a filename, used when it is already taken.
+    /// The number goes before the extension: `foo.rom` becomes `foo (2).rom`.
+    pub fn numbered_filename(filename: &str, n: u32) -> String {
+        match filename.split_once('.') {
+            Some((stem, extension)) => format!("{stem} ({n}).{extension}"),
+            None => format!("{filename} ({n})"),
+        }
+    }
+
+    /// Normalize a filename before it is validated and used. Surrounding
+    /// whitespace is dropped.
+    pub fn normalize_filename(filename: &str) -> String {
+        filename.trim().to_string()
+    }
+
+    /// Check that a filename is safe to use as part of a storage path. Path
+    /// separators and `..` are refused, as well as names that are empty,
+    /// longer than 255 bytes, start or end with a dot, or are reserved on
+    /// Windows.
     pub async fn is_filename_conform(
         _db: &mut Db,
         _core_release: &CoreRelease,
         filename: &str,
     ) -> Result<bool, diesel::result::Error> {
-        Ok(filename
-            .chars()
-            .all(|c| c.is_alphanumeric() || "()[]{}-_+=!@#$%^&*~,. ".contains(c)))
+        Ok(is_filename_valid(filename))
+    }
+}
+
+const WINDOWS_RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+fn is_filename_valid(filename: &str) -> bool {
+    if filename.is_empty() || filename.len() > 255 {
+        return false;
+    }
+    if filename.contains("..") || filename.starts_with('.') || filename.ends_with('.') {
+        return false;
+    }
+    if !filename
+        .chars()
+        .all(|c| c.is_alphanumeric() || "()[]{}-_+=!@#$%^&*~,. ".contains(c))
+    {
+        return false;
     }
+
+    // Windows reserves these names regardless of the extension.
+    let stem = filename.split('.').next().unwrap_or_default().trim_end();
+    !WINDOWS_RESERVED_NAMES
+        .iter()
+        .any(|r| r.eq_ignore_ascii_case(stem))
+}
+
+/// The CRC32 of some data, big endian so its hexadecimal form matches the
+/// one used in DAT files.
+pub fn crc32(data: &[u8]) -> Vec<u8> {
+    crc32fast::hash(data).to_be_bytes().to_vec()
 }
 
 #[derive(Queryable, Debug, Selectable, Identifiable)]
@@ -99,6 +171,7 @@ pub struct Artifact {
     pub size: i32,
     pub download_url: Option<String>,
     pub sha1: Vec<u8>,
+    pub crc32: Vec<u8>,
 }
 
 impl From<Artifact> for dto::artifact::ArtifactRef {
@@ -109,6 +182,7 @@ impl From<Artifact> for dto::artifact::ArtifactRef {
             size,
             download_url,
             sha1,
+            crc32,
             ..
         }: Artifact,
     ) -> Self {
@@ -134,11 +208,83 @@ impl From<Artifact> for dto::artifact::ArtifactRef {
             } else {
                 Some(sha256.into())
             },
+            crc32: if crc32.is_empty() {
+                None
+            } else {
+                Some(crc32.into())
+            },
         }
     }
 }
 
+#[rocket::async_trait]
+impl FromIdOrSlug for Artifact {
+    const NAME: &'static str = "Artifact";
+
+    async fn from_id(db: &mut Db, id: i32) -> Result<Option<Self>, diesel::result::Error> {
+        schema::artifacts::table
+            .filter(schema::artifacts::id.eq(id))
+            .first::<Self>(db)
+            .await
+            .optional()
+    }
+
+    /// Artifacts do not have slugs.
+    async fn from_slug(_db: &mut Db, _slug: &str) -> Result<Option<Self>, diesel::result::Error> {
+        Ok(None)
+    }
+}
+
 impl Artifact {
+    /// Find an artifact by its SHA256 checksum. If many artifacts share the
+    /// same checksum, the oldest one is returned.
+    pub async fn from_sha256(
+        db: &mut Db,
+        sha256: &[u8],
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        schema::artifacts::table
+            .filter(schema::artifacts::sha256.eq(sha256))
+            .order(schema::artifacts::id.asc())
+            .first::<Self>(db)
+            .await
+            .optional()
+    }
+
+    /// Find a non-yanked core release containing this artifact, along with
+    /// its core.
+    pub async fn public_core_release(
+        &self,
+        db: &mut Db,
+    ) -> Result<Option<(CoreRelease, Core)>, diesel::result::Error> {
+        self.latest_core_release(db, false).await
+    }
+
+    /// Find the latest core release containing this artifact, along with its
+    /// core. Yanked releases are only considered if `include_yanked` is set.
+    pub async fn latest_core_release(
+        &self,
+        db: &mut Db,
+        include_yanked: bool,
+    ) -> Result<Option<(CoreRelease, Core)>, diesel::result::Error> {
+        let mut query = schema::core_release_artifacts::table
+            .inner_join(schema::core_releases::table.inner_join(schema::cores::table))
+            .filter(schema::core_release_artifacts::artifact_id.eq(self.id))
+            .into_boxed();
+        if !include_yanked {
+            query = query.filter(schema::core_releases::yanked.eq(false));
+        }
+
+        query
+            .order(schema::core_releases::date_released.desc())
+            .select((
+                schema::core_releases::all_columns,
+                schema::cores::all_columns,
+            ))
+            .first::<(CoreRelease, Core)>(db)
+            .await
+            .optional()
+    }
+
     pub async fn create_with_data(
         db: &mut Db,
         filename: &str,
@@ -148,6 +294,7 @@ impl Artifact {
         let md5 = md5::compute(data).to_vec();
         let sha1 = sha1::Sha1::digest(data).to_vec();
         let sha256 = sha2::Sha256::digest(data).to_vec();
+        let crc32 = crc32(data);
 
         let artifact = diesel::insert_into(schema::artifacts::table)
             .values((
@@ -158,6 +305,7 @@ impl Artifact {
                 schema::artifacts::sha256.eq(sha256),
                 schema::artifacts::size.eq(data.len() as i32),
                 schema::artifacts::sha1.eq(sha1),
+                schema::artifacts::crc32.eq(crc32),
             ))
             .returning(schema::artifacts::all_columns)
             .get_result::<Self>(db)
@@ -180,6 +328,7 @@ impl Artifact {
         md5: Option<&[u8]>,
         sha1: Option<&[u8]>,
         sha256: Option<&[u8]>,
+        crc32: Option<&[u8]>,
         download_url: Option<&str>,
         size: i32,
     ) -> Result<Self, diesel::result::Error> {
@@ -192,6 +341,7 @@ impl Artifact {
                 schema::artifacts::md5.eq(md5.unwrap_or(&[])),
                 schema::artifacts::sha1.eq(sha1.unwrap_or(&[])),
                 schema::artifacts::sha256.eq(sha256.unwrap_or(&[])),
+                schema::artifacts::crc32.eq(crc32.unwrap_or(&[])),
                 schema::artifacts::size.eq(size),
             ))
             .returning(schema::artifacts::all_columns)
@@ -199,15 +349,62 @@ impl Artifact {
             .await
     }
 
+    /// List the artifacts that have a download URL but no SHA1, because they
+    /// were created before it was recorded. Only artifacts with an id greater
+    /// than `after` are listed, in order, so callers can go through them in
+    /// batches.
+    pub async fn list_missing_sha1(
+        db: &mut Db,
+        after: i32,
+        limit: i64,
+    ) -> Result<Vec<Self>, diesel::result::Error> {
+        schema::artifacts::table
+            .filter(schema::artifacts::sha1.eq(Vec::<u8>::new()))
+            .filter(schema::artifacts::download_url.is_not_null())
+            .filter(schema::artifacts::id.gt(after))
+            .order(schema::artifacts::id.asc())
+            .limit(limit)
+            .load::<Self>(db)
+            .await
+    }
+
+    /// Set the SHA1 of an artifact that was created without one.
+    pub async fn set_sha1(
+        &mut self,
+        db: &mut Db,
+        sha1: Vec<u8>,
+    ) -> Result<(), diesel::result::Error> {
+        diesel::update(schema::artifacts::table)
+            .filter(schema::artifacts::id.eq(self.id))
+            .set(schema::artifacts::sha1.eq(&sha1))
+            .execute(db)
+            .await?;
+        self.sha1 = sha1;
+        Ok(())
+    }
+
     pub async fn list(
         db: &mut Db,
         release: &CoreRelease,
+        platform_id: Option<i32>,
+        mime_type: Option<&str>,
         page: i64,
         limit: i64,
     ) -> Result<(Vec<Self>, i64), diesel::result::Error> {
-        schema::artifacts::table
+        let mut query = schema::artifacts::table
             .inner_join(schema::core_release_artifacts::table)
             .filter(schema::core_release_artifacts::core_release_id.eq(release.id))
+            .into_boxed();
+
+        if let Some(platform_id) = platform_id {
+            query = query.filter(schema::core_release_artifacts::platform_id.eq(platform_id));
+        }
+        if let Some(mime_type) = mime_type {
+            // MIME types are case insensitive.
+            query = query.filter(schema::artifacts::mime_type.ilike(escape_like(mime_type)));
+        }
+
+        query
             .select(schema::artifacts::all_columns)
             .paginate(page)
             .per_page(limit)
@@ -215,6 +412,56 @@ impl Artifact {
             .await
     }
 
+    /// List the artifacts of many releases at once, as pairs of release id and
+    /// artifact, ordered by release and artifact.
+    pub async fn list_for_releases(
+        db: &mut Db,
+        release_ids: &[i32],
+    ) -> Result<Vec<(i32, Self)>, diesel::result::Error> {
+        schema::artifacts::table
+            .inner_join(schema::core_release_artifacts::table)
+            .filter(schema::core_release_artifacts::core_release_id.eq_any(release_ids))
+            .select((
+                schema::core_release_artifacts::core_release_id,
+                schema::artifacts::all_columns,
+            ))
+            .order((
+                schema::core_release_artifacts::core_release_id.asc(),
+                schema::artifacts::id.asc(),
+            ))
+            .load::<(i32, Self)>(db)
+            .await
+    }
+
+    /// Get the data of this artifact, if it is stored in the database.
+    pub async fn file(
+        &self,
+        db: &mut crate::DbConnection,
+    ) -> Result<Option<File>, diesel::result::Error> {
+        schema::files::table
+            .filter(schema::files::id.eq(self.id))
+            .first::<File>(db)
+            .await
+            .optional()
+    }
+
+    /// Get an artifact of a release. Returns `None` if the artifact does not
+    /// exist or does not belong to this release.
+    pub async fn from_release(
+        db: &mut Db,
+        release: &CoreRelease,
+        artifact_id: i32,
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        schema::artifacts::table
+            .inner_join(schema::core_release_artifacts::table)
+            .filter(schema::core_release_artifacts::core_release_id.eq(release.id))
+            .filter(schema::artifacts::id.eq(artifact_id))
+            .select(schema::artifacts::all_columns)
+            .first::<Self>(db)
+            .await
+            .optional()
+    }
+
     pub async fn get_file(
         db: &mut Db,
         core_id: dto::types::IdOrSlug<'_>,
@@ -223,7 +470,6 @@ impl Artifact {
     ) -> Result<(Self, Option<File>), diesel::result::Error> {
         let mut query =
             schema::artifacts::table
-                .inner_join(schema::files::table)
                 .inner_join(schema::core_release_artifacts::table)
                 .inner_join(schema::core_releases::table.on(
                     schema::core_releases::id.eq(schema::core_release_artifacts::core_release_id),
@@ -264,7 +510,6 @@ impl Artifact {
     ) -> Result<(Self, Option<File>), diesel::result::Error> {
         let artifact =
             schema::artifacts::table
-                .inner_join(schema::files::table)
                 .inner_join(schema::core_release_artifacts::table)
                 .inner_join(schema::core_releases::table.on(
                     schema::core_releases::id.eq(schema::core_release_artifacts::core_release_id),
@@ -288,3 +533,65 @@ impl Artifact {
         Ok((artifact, file))
     }
 }
+
+#[test]
+fn filename_valid() {
+    assert!(is_filename_valid("core.rbf"));
+    assert!(is_filename_valid("Core (v1.2) [beta].bin"));
+    assert!(is_filename_valid("console.rbf"));
+}
+
+#[test]
+fn filename_path_traversal() {
+    assert!(!is_filename_valid("../evil"));
+    assert!(!is_filename_valid(".."));
+    assert!(!is_filename_valid("a/b.bin"));
+    assert!(!is_filename_valid("a\\b.bin"));
+    assert!(!is_filename_valid("core..rbf"));
+}
+
+#[test]
+fn filename_dots() {
+    assert!(!is_filename_valid(".hidden"));
+    assert!(!is_filename_valid("core."));
+}
+
+#[test]
+fn filename_reserved_windows_names() {
+    assert!(!is_filename_valid("con"));
+    assert!(!is_filename_valid("CON.rbf"));
+    assert!(!is_filename_valid("lpt1.bin"));
+    assert!(!is_filename_valid("nul .txt"));
+}
+
+#[test]
+fn filename_length() {
+    assert!(!is_filename_valid(""));
+    assert!(is_filename_valid(&"a".repeat(255)));
+    assert!(!is_filename_valid(&"a".repeat(300)));
+}
+
+#[test]
+fn crc32_known_values() {
+    assert_eq!(crc32(b""), [0, 0, 0, 0]);
+    assert_eq!(
+        hex::encode(crc32(b"The quick brown fox jumps over the lazy dog")),
+        "414fa339"
+    );
+}
+
+#[test]
+fn numbered_filename() {
+    assert_eq!(
+        CoreReleaseArtifact::numbered_filename("foo.rom", 2),
+        "foo (2).rom"
+    );
+    assert_eq!(
+        CoreReleaseArtifact::numbered_filename("core.tar.gz", 3),
+        "core (3).tar.gz"
+    );
+    assert_eq!(
+        CoreReleaseArtifact::numbered_filename("core", 2),
+        "core (2)"
+    );
+}
@@ -1,10 +1,14 @@
 use crate::types::FromIdOrSlug;
-use crate::Db;
+use crate::utils::escape_like;
 use crate::{models, schema};
+use crate::{Db, TransactionError};
 use diesel::dsl::count_star;
 use diesel::prelude::*;
 use diesel::query_builder::BoxedSelectStatement;
 use diesel::{AsExpression, FromSqlRow, Identifiable, Queryable};
+use retronomicon_dto::params::{SortField, SortParams};
+use rocket::http::Status;
+use rocket_db_pools::diesel::scoped_futures::ScopedFutureExt;
 use rocket_db_pools::diesel::{AsyncConnection, RunQueryDsl};
 use serde_json::Value as Json;
 
@@ -12,6 +16,14 @@ mod releases;
 use crate::pages::{Paginate, Paginated};
 pub use releases::*;
 
+diesel::sql_function! {
+    /// Trigram similarity between two strings, from the `pg_trgm` extension.
+    fn similarity(x: diesel::sql_types::Text, y: diesel::sql_types::Text) -> diesel::sql_types::Float4;
+}
+
+/// Minimum trigram similarity for a core to match a search.
+const SEARCH_SIMILARITY_THRESHOLD: f32 = 0.3;
+
 #[derive(Queryable, Debug, Identifiable)]
 #[diesel(table_name = schema::cores)]
 pub struct Core {
@@ -23,16 +35,23 @@ pub struct Core {
     pub links: Json,
     pub system_id: i32,
     pub owner_team_id: i32,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+    pub deleted_by_id: Option<i32>,
 }
 
 #[rocket::async_trait]
 impl FromIdOrSlug for Core {
+    const NAME: &'static str = "Core";
+
     async fn from_id(db: &mut Db, id: i32) -> Result<Option<Self>, diesel::result::Error>
     where
         Self: Sized,
     {
         schema::cores::table
             .filter(schema::cores::id.eq(id))
+            .filter(schema::cores::deleted_at.is_null())
             .first::<Self>(db)
             .await
             .optional()
@@ -44,6 +63,21 @@ impl FromIdOrSlug for Core {
     {
         schema::cores::table
             .filter(schema::cores::slug.eq(slug))
+            .filter(schema::cores::deleted_at.is_null())
+            .first::<Self>(db)
+            .await
+            .optional()
+    }
+
+    async fn from_previous_slug(
+        db: &mut Db,
+        slug: &str,
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        schema::core_slug_history::table
+            .inner_join(schema::cores::table)
+            .filter(schema::core_slug_history::old_slug.eq(slug))
+            .filter(schema::cores::deleted_at.is_null())
+            .select(schema::cores::all_columns)
             .first::<Self>(db)
             .await
             .optional()
@@ -57,6 +91,7 @@ impl Core {
         limit: i64,
     ) -> Result<Vec<Self>, diesel::result::Error> {
         schema::cores::table
+            .filter(schema::cores::deleted_at.is_null())
             .offset(page * limit)
             .limit(limit)
             .load::<Self>(db)
@@ -70,6 +105,7 @@ impl Core {
     ) -> Result<Vec<(Self, models::Team)>, diesel::result::Error> {
         schema::cores::table
             .inner_join(schema::teams::table)
+            .filter(schema::cores::deleted_at.is_null())
             .offset(page * limit)
             .limit(limit)
             .load::<(Self, models::Team)>(db)
@@ -84,6 +120,9 @@ impl Core {
         system: Option<i32>,
         team: Option<i32>,
         release_date_ge: Option<chrono::NaiveDateTime>,
+        name_contains: Option<&str>,
+        q: Option<&str>,
+        sort: Option<SortParams>,
     ) -> Result<
         (
             Vec<(
@@ -125,6 +164,7 @@ impl Core {
                 schema::core_releases::all_columns.nullable(),
                 schema::platforms::all_columns,
             ))
+            .filter(schema::cores::deleted_at.is_null())
             .into_boxed();
 
         if let Some(platform) = platform {
@@ -139,6 +179,30 @@ impl Core {
         if let Some(release_date_ge) = release_date_ge {
             query = query.filter(schema::core_releases::date_released.ge(release_date_ge));
         }
+        if let Some(name) = name_contains {
+            query = query.filter(schema::cores::name.ilike(format!("%{}%", escape_like(name))));
+        }
+        if let Some(q) = q {
+            let pattern = format!("%{}%", escape_like(q));
+            query = query.filter(
+                schema::cores::name
+                    .ilike(pattern.clone())
+                    .or(schema::cores::slug.ilike(pattern.clone()))
+                    .or(schema::cores::description.ilike(pattern)),
+            );
+        }
+
+        query = match sort.map(|s| (s.field, s.descending)) {
+            Some((SortField::Name, false)) => query.order(schema::cores::name.asc()),
+            Some((SortField::Name, true)) => query.order(schema::cores::name.desc()),
+            Some((SortField::Created, false)) => query.order(schema::cores::created_at.asc()),
+            Some((SortField::Created, true)) => query.order(schema::cores::created_at.desc()),
+            Some((SortField::Updated, false)) => query.order(schema::cores::updated_at.asc()),
+            Some((SortField::Updated, true)) => query.order(schema::cores::updated_at.desc()),
+            None if q.is_some() => query.order(schema::cores::name.asc()),
+            None => query,
+        };
+        query = query.then_order_by(schema::cores::id.asc());
 
         query
             .paginate(page)
@@ -154,6 +218,164 @@ impl Core {
             .map_err(|e| e.to_string())
     }
 
+    /// List the cores tagged with a tag, with their latest release if any.
+    pub async fn list_by_tag(
+        db: &mut Db,
+        tag_id: i32,
+        page: i64,
+        limit: i64,
+    ) -> Result<
+        Vec<(
+            Self,
+            models::System,
+            models::Team,
+            Option<CoreRelease>,
+            Option<models::Platform>,
+        )>,
+        diesel::result::Error,
+    > {
+        schema::cores::table
+            .inner_join(schema::core_tags::table)
+            .inner_join(schema::teams::table)
+            .inner_join(schema::systems::table)
+            .left_join(
+                schema::core_releases::table.on(schema::core_releases::id.eq(
+                    // See `list_with_teams_and_releases`.
+                    diesel::dsl::sql(
+                        r#"(
+                        SELECT id FROM core_releases
+                            WHERE cores.id = core_releases.core_id
+                            ORDER BY date_released DESC, id DESC
+                            LIMIT 1
+                        )"#,
+                    ),
+                )),
+            )
+            .left_join(
+                schema::platforms::table
+                    .on(schema::platforms::id.eq(schema::core_releases::platform_id)),
+            )
+            .filter(schema::core_tags::tag_id.eq(tag_id))
+            .filter(schema::cores::deleted_at.is_null())
+            .select((
+                schema::cores::all_columns,
+                schema::systems::all_columns,
+                schema::teams::all_columns,
+                schema::core_releases::all_columns.nullable(),
+                schema::platforms::all_columns.nullable(),
+            ))
+            .order(schema::cores::id.asc())
+            .offset(page * limit)
+            .limit(limit)
+            .load(db)
+            .await
+    }
+
+    /// List the cores whose latest release is dated on or after `since`, with
+    /// that release, from the most recently released.
+    pub async fn list_recently_released(
+        db: &mut Db,
+        since: chrono::NaiveDateTime,
+        page: i64,
+        limit: i64,
+    ) -> Result<
+        Vec<(
+            Self,
+            models::System,
+            models::Team,
+            CoreRelease,
+            models::Platform,
+        )>,
+        diesel::result::Error,
+    > {
+        schema::cores::table
+            .inner_join(schema::teams::table)
+            .inner_join(schema::systems::table)
+            .inner_join(
+                schema::core_releases::table.on(schema::core_releases::id.eq(
+                    // See `list_with_teams_and_releases`.
+                    diesel::dsl::sql(
+                        r#"(
+                        SELECT id FROM core_releases
+                            WHERE cores.id = core_releases.core_id
+                            ORDER BY date_released DESC, id DESC
+                            LIMIT 1
+                        )"#,
+                    ),
+                )),
+            )
+            .inner_join(
+                schema::platforms::table
+                    .on(schema::platforms::id.eq(schema::core_releases::platform_id)),
+            )
+            .filter(schema::core_releases::date_released.ge(since))
+            .filter(schema::cores::deleted_at.is_null())
+            .select((
+                schema::cores::all_columns,
+                schema::systems::all_columns,
+                schema::teams::all_columns,
+                schema::core_releases::all_columns,
+                schema::platforms::all_columns,
+            ))
+            .order((
+                schema::core_releases::date_released.desc(),
+                schema::cores::id.asc(),
+            ))
+            .offset(page * limit)
+            .limit(limit)
+            .load(db)
+            .await
+    }
+
+    /// Search cores by name using trigram similarity, so misspelled or partial
+    /// names still match. Results are ordered from the most similar.
+    pub async fn search_by_name(
+        db: &mut Db,
+        q: &str,
+        limit: i64,
+    ) -> Result<Vec<(Self, models::System, models::Team, f32)>, diesel::result::Error> {
+        schema::cores::table
+            .inner_join(schema::systems::table)
+            .inner_join(schema::teams::table)
+            .filter(similarity(schema::cores::name, q).gt(SEARCH_SIMILARITY_THRESHOLD))
+            .filter(schema::cores::deleted_at.is_null())
+            .order((
+                similarity(schema::cores::name, q).desc(),
+                schema::cores::id.asc(),
+            ))
+            .select((
+                schema::cores::all_columns,
+                schema::systems::all_columns,
+                schema::teams::all_columns,
+                similarity(schema::cores::name, q),
+            ))
+            .limit(limit)
+            .load(db)
+            .await
+    }
+
+    pub async fn slug_exists(db: &mut Db, slug: &str) -> Result<bool, diesel::result::Error> {
+        diesel::select(diesel::dsl::exists(
+            schema::cores::table.filter(schema::cores::slug.eq(slug)),
+        ))
+        .get_result(db)
+        .await
+    }
+
+    /// The ids of the cores with these slugs, as `(slug, id)` pairs. Unknown
+    /// slugs are ignored.
+    pub async fn ids_by_slugs(
+        db: &mut Db,
+        slugs: &[String],
+    ) -> Result<Vec<(String, i32)>, diesel::result::Error> {
+        schema::cores::table
+            .filter(schema::cores::slug.eq_any(slugs))
+            .filter(schema::cores::deleted_at.is_null())
+            .select((schema::cores::slug, schema::cores::id))
+            .load(db)
+            .await
+    }
+
     pub async fn create(
         db: &mut Db,
         slug: &str,
@@ -179,6 +401,129 @@ impl Core {
             .await
     }
 
+    /// Merge a duplicate core into another core of the same system. The
+    /// releases and tags of the source core are moved to the target, except
+    /// tags it already has, and the slugs of the source redirect to the
+    /// target. The source core is then marked as deleted by the requesting
+    /// user. Returns the number of releases and tags moved.
+    ///
+    /// The deleted core keeps its slug, so it cannot be used by a new core
+    /// and keeps redirecting to the target.
+    pub async fn merge(
+        db: &mut Db,
+        source_id: i32,
+        target_id: i32,
+        requesting_user_id: i32,
+    ) -> Result<(usize, usize), TransactionError> {
+        db.transaction(|db| {
+            async move {
+                use schema::cores::dsl;
+
+                let source = schema::cores::table
+                    .filter(dsl::id.eq(source_id))
+                    .filter(dsl::deleted_at.is_null())
+                    .first::<Self>(db)
+                    .await?;
+                let target = schema::cores::table
+                    .filter(dsl::id.eq(target_id))
+                    .filter(dsl::deleted_at.is_null())
+                    .first::<Self>(db)
+                    .await?;
+                if source.system_id != target.system_id {
+                    return Err((
+                        Status::BadRequest,
+                        "Cannot merge cores of different systems".to_string(),
+                    )
+                        .into());
+                }
+
+                // Releases are unique per core, platform and version.
+                let target_versions = schema::core_releases::table
+                    .filter(schema::core_releases::core_id.eq(target_id))
+                    .select((
+                        schema::core_releases::platform_id,
+                        schema::core_releases::version,
+                    ))
+                    .load::<(i32, String)>(db)
+                    .await?;
+                let source_versions = schema::core_releases::table
+                    .filter(schema::core_releases::core_id.eq(source_id))
+                    .select((
+                        schema::core_releases::platform_id,
+                        schema::core_releases::version,
+                    ))
+                    .load::<(i32, String)>(db)
+                    .await?;
+                if let Some((_, version)) =
+                    source_versions.iter().find(|v| target_versions.contains(v))
+                {
+                    return Err((
+                        Status::Conflict,
+                        format!("Both cores have a release {version} for the same platform"),
+                    )
+                        .into());
+                }
+
+                let releases = diesel::update(
+                    schema::core_releases::table
+                        .filter(schema::core_releases::core_id.eq(source_id)),
+                )
+                .set(schema::core_releases::core_id.eq(target_id))
+                .execute(db)
+                .await?;
+
+                let tag_ids = schema::core_tags::table
+                    .filter(schema::core_tags::core_id.eq(target_id))
+                    .select(schema::core_tags::tag_id)
+                    .load::<i32>(db)
+                    .await?;
+                let tags = diesel::update(
+                    schema::core_tags::table
+                        .filter(schema::core_tags::core_id.eq(source_id))
+                        .filter(schema::core_tags::tag_id.ne_all(tag_ids)),
+                )
+                .set(schema::core_tags::core_id.eq(target_id))
+                .execute(db)
+                .await?;
+                diesel::delete(
+                    schema::core_tags::table.filter(schema::core_tags::core_id.eq(source_id)),
+                )
+                .execute(db)
+                .await?;
+
+                diesel::update(
+                    schema::core_slug_history::table
+                        .filter(schema::core_slug_history::core_id.eq(source_id)),
+                )
+                .set(schema::core_slug_history::core_id.eq(target_id))
+                .execute(db)
+                .await?;
+                diesel::insert_into(schema::core_slug_history::table)
+                    .values((
+                        schema::core_slug_history::old_slug.eq(&source.slug),
+                        schema::core_slug_history::core_id.eq(target_id),
+                    ))
+                    .on_conflict(schema::core_slug_history::old_slug)
+                    .do_update()
+                    .set(schema::core_slug_history::core_id.eq(target_id))
+                    .execute(db)
+                    .await?;
+
+                diesel::update(schema::cores::table.filter(dsl::id.eq(source_id)))
+                    .set((
+                        dsl::deleted_at.eq(diesel::dsl::now.nullable()),
+                        dsl::deleted_by_id.eq(requesting_user_id),
+                    ))
+                    .execute(db)
+                    .await?;
+
+                Ok((releases, tags))
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+
     pub async fn get_with_owner_and_system(
         db: &mut Db,
         id: i32,
@@ -187,6 +532,7 @@ impl Core {
             .inner_join(schema::teams::table)
             .inner_join(schema::systems::table)
             .filter(schema::cores::id.eq(id))
+            .filter(schema::cores::deleted_at.is_null())
             .first::<(Self, models::Team, models::System)>(db)
             .await
             .optional()
@@ -200,8 +546,55 @@ impl Core {
             .inner_join(schema::teams::table)
             .inner_join(schema::systems::table)
             .filter(schema::cores::slug.eq(slug))
+            .filter(schema::cores::deleted_at.is_null())
             .first::<(Self, models::Team, models::System)>(db)
             .await
             .optional()
     }
+
+    /// The latest release of a core for each platform it was released on,
+    /// ignoring yanked releases. Ordered by platform.
+    pub async fn latest_release_per_platform(
+        db: &mut Db,
+        core_id: i32,
+    ) -> Result<Vec<(CoreRelease, models::Platform)>, diesel::result::Error> {
+        use schema::core_releases::dsl;
+
+        schema::core_releases::table
+            .inner_join(schema::platforms::table)
+            .filter(dsl::core_id.eq(core_id))
+            .filter(dsl::yanked.eq(false))
+            .distinct_on(dsl::platform_id)
+            .order_by((
+                dsl::platform_id.asc(),
+                dsl::date_released.desc(),
+                dsl::id.desc(),
+            ))
+            .select((
+                schema::core_releases::all_columns,
+                schema::platforms::all_columns,
+            ))
+            .load::<(CoreRelease, models::Platform)>(db)
+            .await
+    }
+
+    /// The platforms each of the cores has non-yanked releases for, in a
+    /// single query. Returns pairs of core id and platform, ordered by core
+    /// and platform.
+    pub async fn platforms_for_cores(
+        db: &mut Db,
+        core_ids: &[i32],
+    ) -> Result<Vec<(i32, models::Platform)>, diesel::result::Error> {
+        use schema::core_releases::dsl;
+
+        schema::core_releases::table
+            .inner_join(schema::platforms::table)
+            .filter(dsl::core_id.eq_any(core_ids))
+            .filter(dsl::yanked.eq(false))
+            .distinct()
+            .order_by((dsl::core_id.asc(), schema::platforms::id.asc()))
+            .select((dsl::core_id, schema::platforms::all_columns))
+            .load::<(i32, models::Platform)>(db)
+            .await
+    }
 }
@@ -1,6 +1,8 @@
-use crate::models::{Core, Platform, System, User};
+use crate::models::{Core, Platform, System, SystemReleaseRef, User};
 use crate::pages::Paginate;
 use crate::schema;
+use crate::types::FromIdOrSlug;
+use crate::utils::escape_like;
 use crate::Db;
 use chrono::NaiveDateTime;
 use diesel::deserialize::FromSql;
@@ -10,6 +12,7 @@ use diesel::serialize::{IsNull, Output, ToSql};
 use diesel::upsert::on_constraint;
 use diesel::{AsExpression, FromSqlRow};
 use retronomicon_dto as dto;
+use retronomicon_dto::params::SortField;
 use rocket_db_pools::diesel::{AsyncConnection, RunQueryDsl};
 use serde_json::Value as Json;
 
@@ -27,8 +30,15 @@ pub struct CoreRelease {
     pub uploader_id: i32,
     pub core_id: i32,
     pub platform_id: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub system_release_id: Option<i32>,
 }
 
+/// A row of a list of releases: the release with its platform, core,
+/// uploader and the system release it requires.
+pub type CoreReleaseListRow = (CoreRelease, Platform, Core, User, Option<SystemReleaseRef>);
+
 impl CoreRelease {
     pub fn into_ref(self, platform: Platform) -> dto::cores::releases::CoreReleaseRef {
         dto::cores::releases::CoreReleaseRef {
@@ -42,15 +52,56 @@ impl CoreRelease {
     }
 }
 
+#[rocket::async_trait]
+impl FromIdOrSlug for CoreRelease {
+    const NAME: &'static str = "Release";
+
+    async fn from_id(db: &mut Db, id: i32) -> Result<Option<Self>, diesel::result::Error> {
+        schema::core_releases::table
+            .filter(schema::core_releases::id.eq(id))
+            .first::<Self>(db)
+            .await
+            .optional()
+    }
+
+    /// Releases do not have slugs.
+    async fn from_slug(_db: &mut Db, _slug: &str) -> Result<Option<Self>, diesel::result::Error> {
+        Ok(None)
+    }
+}
+
 impl CoreRelease {
-    pub async fn from_id(db: &mut Db, id: i32) -> Result<Option<Self>, diesel::result::Error> {
+    /// Find a release of a core by its id. Returns `None` if the release
+    /// does not exist or belongs to another core.
+    pub async fn from_core_and_id(
+        db: &mut Db,
+        core_id: i32,
+        id: i32,
+    ) -> Result<Option<Self>, diesel::result::Error> {
         schema::core_releases::table
+            .filter(schema::core_releases::core_id.eq(core_id))
             .filter(schema::core_releases::id.eq(id))
             .first::<Self>(db)
             .await
             .optional()
     }
 
+    /// Find the release of a core for a platform with the given version.
+    pub async fn find_by_version(
+        db: &mut Db,
+        core_id: i32,
+        platform_id: i32,
+        version: &str,
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        schema::core_releases::table
+            .filter(schema::core_releases::core_id.eq(core_id))
+            .filter(schema::core_releases::platform_id.eq(platform_id))
+            .filter(schema::core_releases::version.eq(version))
+            .first::<Self>(db)
+            .await
+            .optional()
+    }
+
     pub async fn create(
         db: &mut Db,
         version: &str,
@@ -62,6 +113,7 @@ impl CoreRelease {
         uploader_id: &User,
         core_id: &Core,
         platform_id: &Platform,
+        system_release_id: Option<i32>,
     ) -> Result<Self, diesel::result::Error> {
         diesel::insert_into(schema::core_releases::table)
             .values((
@@ -75,6 +127,7 @@ impl CoreRelease {
                 schema::core_releases::uploader_id.eq(uploader_id.id),
                 schema::core_releases::core_id.eq(core_id.id),
                 schema::core_releases::platform_id.eq(platform_id.id),
+                schema::core_releases::system_release_id.eq(system_release_id),
             ))
             .returning(schema::core_releases::all_columns)
             .get_result::<Self>(db)
@@ -87,18 +140,24 @@ impl CoreRelease {
         page: i64,
         limit: i64,
         _filter: dto::cores::releases::CoreReleaseFilterParams<'_>,
-    ) -> Result<(Vec<(Self, Platform, Core, User)>, i64), diesel::result::Error> {
+    ) -> Result<(Vec<CoreReleaseListRow>, i64), diesel::result::Error> {
         let mut query = schema::core_releases::table
             .inner_join(schema::platforms::table)
             .inner_join(schema::cores::table)
             .inner_join(
                 schema::users::table.on(schema::users::id.eq(schema::core_releases::uploader_id)),
             )
+            .left_join(schema::system_releases::table)
             .select((
                 schema::core_releases::all_columns,
                 schema::platforms::all_columns,
                 schema::cores::all_columns,
                 schema::users::all_columns,
+                (
+                    schema::system_releases::id,
+                    schema::system_releases::version,
+                )
+                    .nullable(),
             ))
             .into_boxed();
 
@@ -113,7 +172,141 @@ impl CoreRelease {
         query
             .paginate(page)
             .per_page(limit)
-            .load_and_count_total::<(Self, Platform, Core, User)>(db)
+            .load_and_count_total::<CoreReleaseListRow>(db)
+            .await
+    }
+
+    /// List all releases of a core, most recent first, with their platform.
+    pub async fn list_for_core(
+        db: &mut Db,
+        core_id: i32,
+        page: i64,
+        limit: i64,
+    ) -> Result<(Vec<(Self, Platform)>, i64), diesel::result::Error> {
+        schema::core_releases::table
+            .inner_join(schema::platforms::table)
+            .filter(schema::core_releases::core_id.eq(core_id))
+            .select((
+                schema::core_releases::all_columns,
+                schema::platforms::all_columns,
+            ))
+            .order((
+                schema::core_releases::date_released.desc(),
+                schema::core_releases::id.desc(),
+            ))
+            .paginate(page)
+            .per_page(limit)
+            .load_and_count_total::<(Self, Platform)>(db)
+            .await
+    }
+
+    /// The changelog of a core: its non-yanked releases, most recent first,
+    /// optionally for a single platform.
+    pub async fn changelog(
+        db: &mut Db,
+        core_id: i32,
+        platform: Option<dto::types::IdOrSlug<'_>>,
+    ) -> Result<Vec<(Self, Platform)>, diesel::result::Error> {
+        let mut query = schema::core_releases::table
+            .inner_join(schema::platforms::table)
+            .filter(schema::core_releases::core_id.eq(core_id))
+            .filter(schema::core_releases::yanked.eq(false))
+            .select((
+                schema::core_releases::all_columns,
+                schema::platforms::all_columns,
+            ))
+            .into_boxed();
+
+        match platform {
+            Some(dto::types::IdOrSlug::Id(id)) => {
+                query = query.filter(schema::core_releases::platform_id.eq(id));
+            }
+            Some(dto::types::IdOrSlug::Slug(slug)) => {
+                query = query.filter(schema::platforms::slug.eq(slug.to_string()));
+            }
+            None => {}
+        }
+
+        query
+            .order((
+                schema::core_releases::date_released.desc(),
+                schema::core_releases::id.desc(),
+            ))
+            .load::<(Self, Platform)>(db)
+            .await
+    }
+
+    /// List releases of all cores, most recent first unless sorted otherwise.
+    pub async fn list_all(
+        db: &mut Db,
+        page: i64,
+        limit: i64,
+        platform_id: Option<i32>,
+        system_id: Option<i32>,
+        team_id: Option<i32>,
+        core_slug_prefix: Option<&str>,
+        sort: Option<dto::params::SortParams>,
+    ) -> Result<(Vec<CoreReleaseListRow>, i64), diesel::result::Error> {
+        let mut query = schema::core_releases::table
+            .inner_join(schema::platforms::table)
+            .inner_join(schema::cores::table)
+            .inner_join(
+                schema::users::table.on(schema::users::id.eq(schema::core_releases::uploader_id)),
+            )
+            .left_join(schema::system_releases::table)
+            .select((
+                schema::core_releases::all_columns,
+                schema::platforms::all_columns,
+                schema::cores::all_columns,
+                schema::users::all_columns,
+                (
+                    schema::system_releases::id,
+                    schema::system_releases::version,
+                )
+                    .nullable(),
+            ))
+            .into_boxed();
+
+        query = match sort.map(|s| (s.field, s.descending)) {
+            Some((SortField::Name, false)) => query
+                .order(schema::cores::name.asc())
+                .then_order_by(schema::core_releases::version.asc()),
+            Some((SortField::Name, true)) => query
+                .order(schema::cores::name.desc())
+                .then_order_by(schema::core_releases::version.desc()),
+            Some((SortField::Created, false)) => {
+                query.order(schema::core_releases::created_at.asc())
+            }
+            Some((SortField::Created, true)) => {
+                query.order(schema::core_releases::created_at.desc())
+            }
+            Some((SortField::Updated, false)) => {
+                query.order(schema::core_releases::updated_at.asc())
+            }
+            Some((SortField::Updated, true)) => {
+                query.order(schema::core_releases::updated_at.desc())
+            }
+            None => query.order(schema::core_releases::date_released.desc()),
+        };
+        query = query.then_order_by(schema::core_releases::id.desc());
+
+        if let Some(platform_id) = platform_id {
+            query = query.filter(schema::core_releases::platform_id.eq(platform_id));
+        }
+        if let Some(system_id) = system_id {
+            query = query.filter(schema::cores::system_id.eq(system_id));
+        }
+        if let Some(team_id) = team_id {
+            query = query.filter(schema::cores::owner_team_id.eq(team_id));
+        }
+        if let Some(prefix) = core_slug_prefix {
+            query = query.filter(schema::cores::slug.like(format!("{}%", escape_like(prefix))));
+        }
+
+        query
+            .paginate(page)
+            .per_page(limit)
+            .load_and_count_total::<CoreReleaseListRow>(db)
             .await
     }
 }
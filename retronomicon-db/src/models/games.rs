@@ -1,15 +1,19 @@
 use crate::models::{Artifact, System};
 use crate::pages::Paginate;
+use crate::types::FromIdOrSlug;
+use crate::utils::{escape_like, merge_json_links};
 use crate::Db;
 use crate::{models, schema};
 use diesel::deserialize::FromSql;
 use diesel::dsl::sql;
 use diesel::prelude::*;
 use diesel::query_builder::BoxedSelectStatement;
-use diesel::sql_types::Bool;
+use diesel::sql_types::{Array, BigInt, Bool, Bytea, Integer};
 use diesel::{AsExpression, FromSqlRow};
 use retronomicon_dto as dto;
 use retronomicon_dto::artifact::ArtifactRef;
+use retronomicon_dto::games::ChecksumMatchMode;
+use retronomicon_dto::params::{CountMode, SortField};
 use retronomicon_dto::types::IdOrSlug;
 use rocket::http::Status;
 use rocket_db_pools::diesel::{AsyncConnection, RunQueryDsl};
@@ -30,6 +34,8 @@ struct UpdateChangeset<'a> {
     developer: Option<&'a str>,
     links: Option<Json>,
     system_unique_id: Option<i32>,
+    region: Option<&'a str>,
+    languages: Option<Vec<&'a str>>,
 }
 
 #[derive(Queryable, Debug, Identifiable)]
@@ -45,6 +51,12 @@ pub struct Game {
     pub links: Json,
     pub system_id: i32,
     pub system_unique_id: i32,
+    pub primary_image_id: Option<i32>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+    pub region: Option<String>,
+    pub languages: Vec<String>,
 }
 
 #[derive(Queryable, Debug, Identifiable)]
@@ -72,6 +84,37 @@ impl GameArtifact {
             .get_result::<Self>(db)
             .await
     }
+
+    /// List all the artifacts linked to a game.
+    pub async fn list_artifacts(
+        db: &mut Db,
+        game_id: i32,
+    ) -> Result<Vec<Artifact>, diesel::result::Error> {
+        schema::artifacts::table
+            .inner_join(
+                schema::game_artifacts::table
+                    .on(schema::artifacts::id.eq(schema::game_artifacts::artifact_id)),
+            )
+            .filter(schema::game_artifacts::game_id.eq(game_id))
+            .order_by(schema::artifacts::id.asc())
+            .select(schema::artifacts::all_columns)
+            .load::<Artifact>(db)
+            .await
+    }
+}
+
+#[rocket::async_trait]
+impl FromIdOrSlug for Game {
+    const NAME: &'static str = "Game";
+
+    async fn from_id(db: &mut Db, id: i32) -> Result<Option<Self>, diesel::result::Error> {
+        Self::get(db, id).await
+    }
+
+    /// Games do not have slugs.
+    async fn from_slug(_db: &mut Db, _slug: &str) -> Result<Option<Self>, diesel::result::Error> {
+        Ok(None)
+    }
 }
 
 impl Game {
@@ -86,6 +129,8 @@ impl Game {
         links: Json,
         system_id: i32,
         system_unique_id: i32,
+        region: Option<&str>,
+        languages: &[&str],
     ) -> Result<Self, diesel::result::Error> {
         diesel::insert_into(schema::games::table)
             .values((
@@ -98,12 +143,24 @@ impl Game {
                 schema::games::links.eq(links),
                 schema::games::system_id.eq(system_id),
                 schema::games::system_unique_id.eq(system_unique_id),
+                schema::games::region.eq(region),
+                schema::games::languages.eq(languages),
             ))
             .returning(schema::games::all_columns)
             .get_result::<Self>(db)
             .await
     }
 
+    /// List games with their system and artifacts, one row per artifact.
+    /// All filters are combined in a single query, with AND semantics, except
+    /// for checksums. An artifact matches if it matches any of the checksums,
+    /// and only the matching artifacts are returned when any is specified.
+    /// With [`ChecksumMatchMode::All`], a game must also have a matching
+    /// artifact for every kind of checksum given.
+    ///
+    /// With [`CountMode::Estimate`] and no filter, the total is estimated
+    /// (see [`Game::estimate_count`]) instead of counted. The returned flag
+    /// is true if the total is an estimate.
     pub async fn list<'a>(
         db: &mut Db,
         page: i64,
@@ -112,12 +169,27 @@ impl Game {
         year: (Bound<i32>, Bound<i32>),
         name: Option<&'a str>,
         exact_name: Option<&'a str>,
+        region: Option<&'a str>,
         md5: Vec<Vec<u8>>,
         sha1: Vec<Vec<u8>>,
         sha256: Vec<Vec<u8>>,
-    ) -> Result<(Vec<(Self, System, Option<Artifact>)>, i64), diesel::result::Error> {
+        crc32: Vec<Vec<u8>>,
+        checksum_match_mode: ChecksumMatchMode,
+        sort: Option<dto::params::SortParams>,
+        count: CountMode,
+    ) -> Result<(Vec<(Self, System, Option<Artifact>)>, i64, bool), diesel::result::Error> {
         use schema::games::dsl;
 
+        let unfiltered = system.is_none()
+            && year == (Bound::Unbounded, Bound::Unbounded)
+            && name.is_none()
+            && exact_name.is_none()
+            && region.is_none()
+            && md5.is_empty()
+            && sha1.is_empty()
+            && sha256.is_empty()
+            && crc32.is_empty();
+
         let mut query: BoxedSelectStatement<'a, _, _, _, _> = schema::games::table
             .inner_join(schema::systems::table)
             .left_join(
@@ -131,6 +203,7 @@ impl Game {
                 schema::systems::all_columns,
                 schema::artifacts::all_columns.nullable(),
             ))
+            .filter(dsl::deleted_at.is_null())
             .into_boxed();
 
         match system {
@@ -166,107 +239,292 @@ impl Game {
             query = query.filter(dsl::name.eq(name.to_string()));
         }
 
-        if !md5.is_empty() {
-            query = query.filter((schema::artifacts::dsl::md5).eq_any(md5));
+        if let Some(region) = region {
+            query = query.filter(dsl::region.ilike(format!("%{}%", escape_like(region))));
         }
-        if !sha1.is_empty() {
-            query = query.filter((schema::artifacts::dsl::sha1).eq_any(sha1));
+
+        let kinds = [&md5, &sha1, &sha256, &crc32]
+            .iter()
+            .filter(|checksums| !checksums.is_empty())
+            .count() as i32;
+
+        if checksum_match_mode == ChecksumMatchMode::All && kinds > 1 {
+            // Count the kinds of checksum each game has a match for. Lists
+            // that are empty never match, so they are not counted.
+            query = query.filter(
+                sql::<Bool>(
+                    r#"games.id IN (
+                    SELECT game_id FROM (
+                        SELECT game_artifacts.game_id, 'md5' AS kind FROM game_artifacts
+                            INNER JOIN artifacts ON artifacts.id = game_artifacts.artifact_id
+                            WHERE artifacts.md5 = ANY("#,
+                )
+                .bind::<Array<Bytea>, _>(md5.clone())
+                .sql(
+                    r#")
+                        UNION ALL
+                        SELECT game_artifacts.game_id, 'sha1' AS kind FROM game_artifacts
+                            INNER JOIN artifacts ON artifacts.id = game_artifacts.artifact_id
+                            WHERE artifacts.sha1 = ANY("#,
+                )
+                .bind::<Array<Bytea>, _>(sha1.clone())
+                .sql(
+                    r#")
+                        UNION ALL
+                        SELECT game_artifacts.game_id, 'sha256' AS kind FROM game_artifacts
+                            INNER JOIN artifacts ON artifacts.id = game_artifacts.artifact_id
+                            WHERE artifacts.sha256 = ANY("#,
+                )
+                .bind::<Array<Bytea>, _>(sha256.clone())
+                .sql(
+                    r#")
+                        UNION ALL
+                        SELECT game_artifacts.game_id, 'crc32' AS kind FROM game_artifacts
+                            INNER JOIN artifacts ON artifacts.id = game_artifacts.artifact_id
+                            WHERE artifacts.crc32 = ANY("#,
+                )
+                .bind::<Array<Bytea>, _>(crc32.clone())
+                .sql(
+                    r#")
+                    ) AS matches
+                    GROUP BY game_id
+                    HAVING COUNT(DISTINCT kind) = "#,
+                )
+                .bind::<Integer, _>(kinds)
+                .sql(")"),
+            );
         }
-        if !sha256.is_empty() {
-            query = query.filter((schema::artifacts::dsl::sha256).eq_any(sha256));
+
+        if kinds > 0 {
+            query = query.filter(
+                schema::artifacts::dsl::md5
+                    .eq_any(md5)
+                    .or(schema::artifacts::dsl::sha1.eq_any(sha1))
+                    .or(schema::artifacts::dsl::sha256.eq_any(sha256))
+                    .or(schema::artifacts::dsl::crc32.eq_any(crc32)),
+            );
         }
 
-        query
+        query = match sort.map(|s| (s.field, s.descending)) {
+            Some((SortField::Name, false)) => query.order(dsl::name.asc()),
+            Some((SortField::Name, true)) => query.order(dsl::name.desc()),
+            Some((SortField::Created, false)) => query.order(dsl::created_at.asc()),
+            Some((SortField::Created, true)) => query.order(dsl::created_at.desc()),
+            Some((SortField::Updated, false)) => query.order(dsl::updated_at.asc()),
+            Some((SortField::Updated, true)) => query.order(dsl::updated_at.desc()),
+            None => query,
+        };
+        // Keep the rows of a game together, so they can be grouped in order.
+        query = query
+            .then_order_by(dsl::id.asc())
+            .then_order_by(schema::artifacts::id.asc());
+
+        if count == CountMode::Estimate && unfiltered {
+            if let Some(total) = Self::estimate_count(db).await? {
+                let rows = query.limit(limit).offset(page * limit).load(db).await?;
+                return Ok((rows, total, true));
+            }
+        }
+
+        let (rows, total) = query
             .paginate(page)
             .per_page(limit)
             .load_and_count_total(db)
-            .await
+            .await?;
+        Ok((rows, total, false))
+    }
+
+    /// An estimate of the number of games, from the statistics Postgres keeps
+    /// on the table. This is much faster than counting on a large table, but
+    /// includes deleted games. Returns `None` if there are no statistics yet
+    /// (e.g. the table was never analyzed).
+    pub async fn estimate_count(db: &mut Db) -> Result<Option<i64>, diesel::result::Error> {
+        #[derive(QueryableByName)]
+        struct Estimate {
+            #[diesel(sql_type = BigInt)]
+            estimate: i64,
+        }
+
+        let Estimate { estimate } = diesel::sql_query(
+            "SELECT reltuples::bigint AS estimate FROM pg_class WHERE oid = 'games'::regclass",
+        )
+        .get_result(db)
+        .await?;
+        Ok((estimate > 0).then_some(estimate))
     }
 
-    pub async fn find_by_sha256(
+    /// List the games tagged with a tag, with their system and artifacts, one
+    /// row per artifact. Rows of the same game are consecutive.
+    pub async fn list_by_tag(
         db: &mut Db,
+        tag_id: i32,
         page: i64,
         limit: i64,
-        sha256: Vec<Vec<u8>>,
-        system: Option<IdOrSlug<'_>>,
-    ) -> Result<Vec<Self>, diesel::result::Error> {
-        use schema::games::dsl;
-
-        let mut query = schema::games::table
+    ) -> Result<Vec<(Self, System, Option<Artifact>)>, diesel::result::Error> {
+        schema::games::table
+            .inner_join(schema::game_tags::table)
             .inner_join(schema::systems::table)
-            .inner_join(schema::game_artifacts::table)
-            .inner_join(
-                schema::artifacts::table
-                    .on(schema::artifacts::id.eq(schema::game_artifacts::artifact_id)),
+            .left_join(
+                schema::game_artifacts::table.inner_join(
+                    schema::artifacts::table
+                        .on(schema::artifacts::id.eq(schema::game_artifacts::artifact_id)),
+                ),
             )
-            .filter(schema::artifacts::dsl::sha256.eq_any(sha256))
-            .order_by(dsl::name.asc())
+            .filter(schema::game_tags::tag_id.eq(tag_id))
+            .filter(schema::games::deleted_at.is_null())
+            .select((
+                schema::games::all_columns,
+                schema::systems::all_columns,
+                schema::artifacts::all_columns.nullable(),
+            ))
+            .order(schema::games::id.asc())
+            .then_order_by(schema::artifacts::id.asc())
             .offset(page * limit)
             .limit(limit)
-            .select(schema::games::all_columns)
-            .into_boxed();
+            .load(db)
+            .await
+    }
 
-        if let Some(system) = system {
-            if let Some(system_id) = system.as_id() {
-                query = query.filter(dsl::system_id.eq(system_id));
-            } else if let Some(system_slug) = system.as_slug() {
-                query = query.filter(schema::systems::dsl::slug.eq(system_slug.to_string()));
-            };
-        }
+    /// Find a game in a system by its exact name.
+    pub async fn find_by_exact_name(
+        db: &mut Db,
+        system_id: i32,
+        name: &str,
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        use schema::games::dsl;
 
-        return query.load::<Self>(db).await;
+        schema::games::table
+            .filter(dsl::system_id.eq(system_id))
+            .filter(dsl::name.eq(name))
+            .filter(dsl::deleted_at.is_null())
+            .first(db)
+            .await
+            .optional()
     }
 
-    pub async fn find_by_sha1(
+    /// Whether a game of the system already uses this `system_unique_id`.
+    pub async fn system_unique_id_exists(
         db: &mut Db,
-        page: i64,
-        limit: i64,
-        sha1: Vec<Vec<u8>>,
-        system: Option<IdOrSlug<'_>>,
-    ) -> Result<Vec<Self>, diesel::result::Error> {
+        system_id: i32,
+        system_unique_id: i32,
+    ) -> Result<bool, diesel::result::Error> {
         use schema::games::dsl;
 
-        let mut query = schema::games::table
-            .inner_join(schema::systems::table)
-            .inner_join(schema::game_artifacts::table)
-            .inner_join(
-                schema::artifacts::table
-                    .on(schema::artifacts::id.eq(schema::game_artifacts::artifact_id)),
+        diesel::select(diesel::dsl::exists(
+            schema::games::table
+                .filter(dsl::system_id.eq(system_id))
+                .filter(dsl::system_unique_id.eq(system_unique_id)),
+        ))
+        .get_result(db)
+        .await
+    }
+
+    /// Returns the highest `system_unique_id` used in a system, if any game exists.
+    pub async fn max_system_unique_id(
+        db: &mut Db,
+        system_id: i32,
+    ) -> Result<Option<i32>, diesel::result::Error> {
+        use schema::games::dsl;
+
+        schema::games::table
+            .filter(dsl::system_id.eq(system_id))
+            .select(diesel::dsl::max(dsl::system_unique_id))
+            .first(db)
+            .await
+    }
+
+    /// List all games of a system with their artifacts, ordered by name. Games
+    /// without any artifact are returned once with `None`.
+    pub async fn list_with_artifacts(
+        db: &mut Db,
+        system_id: i32,
+    ) -> Result<Vec<(Self, Option<Artifact>)>, diesel::result::Error> {
+        use schema::games::dsl;
+
+        schema::games::table
+            .left_join(
+                schema::game_artifacts::table.inner_join(
+                    schema::artifacts::table
+                        .on(schema::artifacts::id.eq(schema::game_artifacts::artifact_id)),
+                ),
             )
-            .filter(schema::artifacts::dsl::sha1.eq_any(sha1))
-            .order_by(dsl::name.asc())
-            .offset(page * limit)
-            .limit(limit)
-            .select(schema::games::all_columns)
-            .into_boxed();
+            .filter(dsl::system_id.eq(system_id))
+            .filter(dsl::deleted_at.is_null())
+            .order_by((dsl::name.asc(), dsl::id.asc(), schema::artifacts::id.asc()))
+            .select((
+                schema::games::all_columns,
+                schema::artifacts::all_columns.nullable(),
+            ))
+            .load::<(Self, Option<Artifact>)>(db)
+            .await
+    }
 
-        if let Some(system) = system {
-            if let Some(system_id) = system.as_id() {
-                query = query.filter(dsl::system_id.eq(system_id));
-            } else if let Some(system_slug) = system.as_slug() {
-                query = query.filter(schema::systems::dsl::slug.eq(system_slug.to_string()));
-            };
-        }
+    /// Search games by name, case insensitive substring. Games are ordered by
+    /// name.
+    pub async fn search_by_name(
+        db: &mut Db,
+        q: &str,
+        limit: i64,
+    ) -> Result<Vec<(Self, System)>, diesel::result::Error> {
+        use schema::games::dsl;
 
-        return query.load::<Self>(db).await;
+        schema::games::table
+            .inner_join(schema::systems::table)
+            .filter(dsl::name.ilike(format!("%{}%", escape_like(q))))
+            .filter(dsl::deleted_at.is_null())
+            .order_by((dsl::name.asc(), dsl::id.asc()))
+            .select((schema::games::all_columns, schema::systems::all_columns))
+            .limit(limit)
+            .load::<(Self, System)>(db)
+            .await
     }
 
-    pub async fn details(db: &mut Db, id: i32) -> Result<(Self, System), diesel::result::Error> {
+    /// Get a game with its system and primary image (if any).
+    pub async fn details(
+        db: &mut Db,
+        id: i32,
+    ) -> Result<(Self, System, Option<models::GameImage>), diesel::result::Error> {
         use schema::games::dsl;
 
         schema::games::table
             .inner_join(schema::systems::table)
+            .left_join(
+                schema::game_images::table
+                    .on(dsl::primary_image_id.eq(schema::game_images::id.nullable())),
+            )
             .filter(dsl::id.eq(id))
-            .select((schema::games::all_columns, schema::systems::all_columns))
-            .first::<(Self, System)>(db)
+            .filter(dsl::deleted_at.is_null())
+            .select((
+                schema::games::all_columns,
+                schema::systems::all_columns,
+                schema::game_images::all_columns.nullable(),
+            ))
+            .first::<(Self, System, Option<models::GameImage>)>(db)
             .await
     }
 
+    /// Set the primary image of a game. The caller must make sure the image
+    /// belongs to this game.
+    pub async fn set_primary_image(
+        db: &mut Db,
+        id: i32,
+        image_id: i32,
+    ) -> Result<(), diesel::result::Error> {
+        use schema::games::dsl;
+
+        diesel::update(schema::games::table.filter(dsl::id.eq(id)))
+            .set(dsl::primary_image_id.eq(image_id))
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
     pub async fn get(db: &mut Db, id: i32) -> Result<Option<Self>, diesel::result::Error> {
         use schema::games::dsl;
 
         schema::games::table
             .filter(dsl::id.eq(id))
+            .filter(dsl::deleted_at.is_null())
             .first(db)
             .await
             .optional()
@@ -284,6 +542,8 @@ impl Game {
         add_links: Option<BTreeMap<&'_ str, &'_ str>>,
         remove_links: Option<Vec<&'_ str>>,
         system_unique_id: Option<i32>,
+        region: Option<&'_ str>,
+        languages: Option<Vec<&'_ str>>,
     ) -> Result<(), diesel::result::Error> {
         db.transaction(|db| {
             async move {
@@ -296,30 +556,18 @@ impl Game {
                     developer,
                     links: None,
                     system_unique_id,
+                    region,
+                    languages,
                 };
 
                 if add_links.is_some() || remove_links.is_some() {
-                    let mut links = serde_json::from_value::<BTreeMap<String, String>>(
-                        schema::games::table
-                            .filter(schema::games::dsl::id.eq(id))
-                            .select(schema::games::dsl::links)
-                            .first::<Json>(db)
-                            .await?,
-                    )
-                    .map_err(|e| diesel::result::Error::DeserializationError(Box::new(e)))?;
-
-                    if let Some(add_links) = add_links {
-                        for (k, v) in add_links {
-                            links.insert(k.to_string(), v.to_string());
-                        }
-                    }
-                    if let Some(remove_links) = remove_links {
-                        for k in remove_links {
-                            links.remove(k);
-                        }
-                    }
-
-                    changeset.links = Some(serde_json::to_value(links).unwrap());
+                    let mut links = schema::games::table
+                        .filter(schema::games::dsl::id.eq(id))
+                        .select(schema::games::dsl::links)
+                        .first::<Json>(db)
+                        .await?;
+                    merge_json_links(&mut links, add_links, remove_links);
+                    changeset.links = Some(links);
                 }
 
                 diesel::update(schema::games::table.filter(schema::games::dsl::id.eq(id)))
@@ -333,4 +581,96 @@ impl Game {
         })
         .await
     }
+
+    /// Merge a duplicate game into another one. The artifacts, images and tags
+    /// of the dropped game are moved to the kept game, except those it already
+    /// has, and links it does not have yet are copied. The dropped game is then
+    /// marked as deleted.
+    pub async fn merge(
+        db: &mut Db,
+        keep_id: i32,
+        drop_id: i32,
+    ) -> Result<(), diesel::result::Error> {
+        db.transaction(|db| {
+            async move {
+                use schema::games::dsl;
+
+                let keep = schema::games::table
+                    .filter(dsl::id.eq(keep_id))
+                    .filter(dsl::deleted_at.is_null())
+                    .first::<Self>(db)
+                    .await?;
+                let dropped = schema::games::table
+                    .filter(dsl::id.eq(drop_id))
+                    .filter(dsl::deleted_at.is_null())
+                    .first::<Self>(db)
+                    .await?;
+
+                let artifact_ids = schema::game_artifacts::table
+                    .filter(schema::game_artifacts::game_id.eq(keep_id))
+                    .select(schema::game_artifacts::artifact_id)
+                    .load::<i32>(db)
+                    .await?;
+                diesel::update(
+                    schema::game_artifacts::table
+                        .filter(schema::game_artifacts::game_id.eq(drop_id))
+                        .filter(schema::game_artifacts::artifact_id.ne_all(artifact_ids)),
+                )
+                .set(schema::game_artifacts::game_id.eq(keep_id))
+                .execute(db)
+                .await?;
+
+                let image_names = schema::game_images::table
+                    .filter(schema::game_images::game_id.eq(keep_id))
+                    .select(schema::game_images::image_name)
+                    .load::<String>(db)
+                    .await?;
+                diesel::update(
+                    schema::game_images::table
+                        .filter(schema::game_images::game_id.eq(drop_id))
+                        .filter(schema::game_images::image_name.ne_all(image_names)),
+                )
+                .set(schema::game_images::game_id.eq(keep_id))
+                .execute(db)
+                .await?;
+
+                let tag_ids = schema::game_tags::table
+                    .filter(schema::game_tags::game_id.eq(keep_id))
+                    .select(schema::game_tags::tag_id)
+                    .load::<i32>(db)
+                    .await?;
+                diesel::update(
+                    schema::game_tags::table
+                        .filter(schema::game_tags::game_id.eq(drop_id))
+                        .filter(schema::game_tags::tag_id.ne_all(tag_ids)),
+                )
+                .set(schema::game_tags::game_id.eq(keep_id))
+                .execute(db)
+                .await?;
+
+                let mut links = match keep.links {
+                    Value::Object(links) => links,
+                    _ => Default::default(),
+                };
+                if let Value::Object(drop_links) = dropped.links {
+                    for (name, url) in drop_links {
+                        links.entry(name).or_insert(url);
+                    }
+                }
+                diesel::update(schema::games::table.filter(dsl::id.eq(keep_id)))
+                    .set(dsl::links.eq(Value::Object(links)))
+                    .execute(db)
+                    .await?;
+
+                diesel::update(schema::games::table.filter(dsl::id.eq(drop_id)))
+                    .set(dsl::deleted_at.eq(diesel::dsl::now.nullable()))
+                    .execute(db)
+                    .await?;
+
+                Ok(())
+            }
+            .scope_boxed()
+        })
+        .await
+    }
 }
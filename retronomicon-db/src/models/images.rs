@@ -61,6 +61,29 @@ impl GameImage {
             .await
     }
 
+    /// Count the images of a game.
+    pub async fn count_for_game(db: &mut Db, game_id: i32) -> Result<i64, diesel::result::Error> {
+        schema::game_images::table
+            .filter(schema::game_images::game_id.eq(game_id))
+            .count()
+            .get_result::<i64>(db)
+            .await
+    }
+
+    /// Find an image of a game by its name.
+    pub async fn find_by_name(
+        db: &mut Db,
+        game_id: i32,
+        image_name: &str,
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        schema::game_images::table
+            .filter(schema::game_images::game_id.eq(game_id))
+            .filter(schema::game_images::image_name.eq(image_name))
+            .first::<Self>(db)
+            .await
+            .optional()
+    }
+
     pub async fn list(
         db: &mut Db,
         page: i64,
@@ -1,4 +1,5 @@
 use crate::types::FromIdOrSlug;
+use crate::utils::escape_like;
 use crate::Db;
 use crate::{models, schema};
 use diesel::prelude::*;
@@ -17,6 +18,7 @@ struct Update<'a> {
     links: Option<Json>,
     metadata: Option<Json>,
     owner_team_id: Option<i32>,
+    metadata_schema: Option<Json>,
 }
 
 #[derive(Queryable, Debug, Identifiable)]
@@ -29,6 +31,8 @@ pub struct Platform {
     pub links: Json,
     pub metadata: Json,
     pub owner_team_id: i32,
+    pub metadata_schema: Option<Json>,
+    pub updated_at: chrono::NaiveDateTime,
 }
 
 impl From<Platform> for dto::platforms::Platform {
@@ -37,12 +41,15 @@ impl From<Platform> for dto::platforms::Platform {
             id: value.id,
             slug: value.slug,
             name: value.name,
+            updated_at: value.updated_at.timestamp(),
         }
     }
 }
 
 #[rocket::async_trait]
 impl FromIdOrSlug for Platform {
+    const NAME: &'static str = "Platform";
+
     async fn from_id(db: &mut Db, id: i32) -> Result<Option<Self>, diesel::result::Error> {
         schema::platforms::table
             .filter(schema::platforms::id.eq(id))
@@ -71,6 +78,39 @@ impl From<Platform> for dto::platforms::PlatformRef {
 }
 
 impl Platform {
+    /// The schema of core release metadata on this platform, if any.
+    pub fn metadata_schema(
+        &self,
+    ) -> Result<Option<dto::platforms::PlatformMetadataSchema>, serde_json::Error> {
+        self.metadata_schema
+            .clone()
+            .map(serde_json::from_value)
+            .transpose()
+    }
+
+    pub async fn slug_exists(db: &mut Db, slug: &str) -> Result<bool, diesel::result::Error> {
+        diesel::select(diesel::dsl::exists(
+            schema::platforms::table.filter(schema::platforms::slug.eq(slug)),
+        ))
+        .get_result(db)
+        .await
+    }
+
+    /// The ids of the platforms with these slugs, as `(slug, id)` pairs. Unknown
+    /// slugs are ignored.
+    pub async fn ids_by_slugs(
+        db: &mut Db,
+        slugs: &[String],
+    ) -> Result<Vec<(String, i32)>, diesel::result::Error> {
+        schema::platforms::table
+            .filter(schema::platforms::slug.eq_any(slugs))
+            .select((schema::platforms::slug, schema::platforms::id))
+            .load(db)
+            .await
+    }
+
+    /// Create a platform. With `upsert`, nothing is inserted if a platform
+    /// with the same slug exists, and `None` is returned.
     pub async fn create(
         db: &mut Db,
         slug: &str,
@@ -78,59 +118,79 @@ impl Platform {
         description: &str,
         links: Json,
         metadata: Json,
+        metadata_schema: Option<Json>,
         owner: &models::Team,
-    ) -> Result<Self, diesel::result::Error> {
-        diesel::insert_into(schema::platforms::table)
-            .values((
-                schema::platforms::slug.eq(slug),
-                schema::platforms::name.eq(name),
-                schema::platforms::description.eq(description),
-                schema::platforms::links.eq(links),
-                schema::platforms::metadata.eq(metadata),
-                schema::platforms::owner_team_id.eq(owner.id),
-            ))
-            .returning(schema::platforms::all_columns)
-            .get_result::<Self>(db)
-            .await
+        upsert: bool,
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        let insert = diesel::insert_into(schema::platforms::table).values((
+            schema::platforms::slug.eq(slug),
+            schema::platforms::name.eq(name),
+            schema::platforms::description.eq(description),
+            schema::platforms::links.eq(links),
+            schema::platforms::metadata.eq(metadata),
+            schema::platforms::owner_team_id.eq(owner.id),
+            schema::platforms::metadata_schema.eq(metadata_schema),
+        ));
+
+        if upsert {
+            insert
+                .on_conflict(schema::platforms::slug)
+                .do_nothing()
+                .returning(schema::platforms::all_columns)
+                .get_result::<Self>(db)
+                .await
+                .optional()
+        } else {
+            insert
+                .returning(schema::platforms::all_columns)
+                .get_result::<Self>(db)
+                .await
+                .map(Some)
+        }
     }
 
+    /// List platforms. If `system` is set, only platforms with at least one core
+    /// release of a core targeting this system are returned.
     pub async fn list(
         db: &mut Db,
         page: i64,
         limit: i64,
+        name: Option<&str>,
+        system: Option<IdOrSlug<'_>>,
     ) -> Result<Vec<Self>, diesel::result::Error> {
-        schema::platforms::table
+        let mut query = schema::platforms::table.into_boxed();
+
+        if let Some(name) = name {
+            let pattern = format!("%{}%", escape_like(name));
+            query = query.filter(
+                schema::platforms::name
+                    .ilike(pattern.clone())
+                    .or(schema::platforms::slug.ilike(pattern)),
+            );
+        }
+
+        if let Some(system) = system {
+            let mut platform_ids = schema::core_releases::table
+                .inner_join(schema::cores::table.inner_join(schema::systems::table))
+                .select(schema::core_releases::platform_id)
+                .into_boxed();
+
+            if let Some(id) = system.as_id() {
+                platform_ids = platform_ids.filter(schema::systems::id.eq(id));
+            } else if let Some(slug) = system.as_slug() {
+                platform_ids = platform_ids.filter(schema::systems::slug.eq(slug.to_string()));
+            }
+
+            query = query.filter(schema::platforms::id.eq_any(platform_ids));
+        }
+
+        query
             .offset(page * limit)
             .limit(limit)
             .load::<Self>(db)
             .await
     }
 
-    pub async fn get_with_owner(
-        db: &mut Db,
-        id: IdOrSlug<'_>,
-    ) -> Result<Option<(Self, models::Team)>, diesel::result::Error> {
-        if let Some(id) = id.as_id() {
-            schema::platforms::table
-                .filter(schema::platforms::id.eq(id))
-                .inner_join(schema::teams::table)
-                .select((schema::platforms::all_columns, schema::teams::all_columns))
-                .first::<(Self, models::Team)>(db)
-                .await
-                .optional()
-        } else if let Some(slug) = id.as_slug() {
-            schema::platforms::table
-                .filter(schema::platforms::slug.eq(slug))
-                .inner_join(schema::teams::table)
-                .select((schema::platforms::all_columns, schema::teams::all_columns))
-                .first::<(Self, models::Team)>(db)
-                .await
-                .optional()
-        } else {
-            return Err(diesel::result::Error::NotFound);
-        }
-    }
-
     pub async fn update(
         db: &mut Db,
         id: i32,
@@ -140,6 +200,7 @@ impl Platform {
         links: Option<Json>,
         metadata: Option<Json>,
         owner_team_id: Option<i32>,
+        metadata_schema: Option<Json>,
     ) -> Result<(), diesel::result::Error> {
         diesel::update(schema::platforms::table)
             .filter(schema::platforms::id.eq(id))
@@ -150,6 +211,7 @@ impl Platform {
                 links,
                 metadata,
                 owner_team_id,
+                metadata_schema,
             })
             .execute(db)
             .await?;
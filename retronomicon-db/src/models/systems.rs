@@ -1,4 +1,5 @@
 use crate::types::FromIdOrSlug;
+use crate::utils::escape_like;
 use crate::Db;
 use crate::{models, schema};
 use diesel::prelude::*;
@@ -21,10 +22,13 @@ pub struct System {
     pub links: Json,
     pub metadata: Json,
     pub owner_team_id: i32,
+    pub updated_at: chrono::NaiveDateTime,
 }
 
 #[rocket::async_trait]
 impl FromIdOrSlug for System {
+    const NAME: &'static str = "System";
+
     async fn from_id(db: &mut Db, id: i32) -> Result<Option<Self>, diesel::result::Error> {
         schema::systems::table
             .filter(schema::systems::id.eq(id))
@@ -64,19 +68,74 @@ impl System {
             .await
     }
 
+    /// List systems with their owner team. The `name` filter matches both the
+    /// name and the slug of a system. All filters are case insensitive
+    /// substrings, except for the team.
     pub async fn list_with_team(
         db: &mut Db,
         page: i64,
         limit: i64,
+        name: Option<&str>,
+        manufacturer: Option<&str>,
+        team: Option<IdOrSlug<'_>>,
     ) -> Result<Vec<(Self, models::Team)>, diesel::result::Error> {
-        schema::systems::table
+        let mut query = schema::systems::table
+            .inner_join(schema::teams::table)
+            .select((schema::systems::all_columns, schema::teams::all_columns))
+            .into_boxed();
+
+        if let Some(name) = name {
+            let pattern = format!("%{}%", escape_like(name));
+            query = query.filter(
+                schema::systems::name
+                    .ilike(pattern.clone())
+                    .or(schema::systems::slug.ilike(pattern)),
+            );
+        }
+        if let Some(manufacturer) = manufacturer {
+            query = query.filter(
+                schema::systems::manufacturer.ilike(format!("%{}%", escape_like(manufacturer))),
+            );
+        }
+        if let Some(team) = team {
+            if let Some(id) = team.as_id() {
+                query = query.filter(schema::teams::id.eq(id));
+            } else if let Some(slug) = team.as_slug() {
+                query = query.filter(schema::teams::slug.eq(slug.to_string()));
+            }
+        }
+
+        query
+            .order(schema::systems::id.asc())
             .offset(page * limit)
             .limit(limit)
-            .inner_join(schema::teams::table)
             .load::<(Self, models::Team)>(db)
             .await
     }
 
+    pub async fn slug_exists(db: &mut Db, slug: &str) -> Result<bool, diesel::result::Error> {
+        diesel::select(diesel::dsl::exists(
+            schema::systems::table.filter(schema::systems::slug.eq(slug)),
+        ))
+        .get_result(db)
+        .await
+    }
+
+    /// The ids of the systems with these slugs, as `(slug, id)` pairs. Unknown
+    /// slugs are ignored.
+    pub async fn ids_by_slugs(
+        db: &mut Db,
+        slugs: &[String],
+    ) -> Result<Vec<(String, i32)>, diesel::result::Error> {
+        schema::systems::table
+            .filter(schema::systems::slug.eq_any(slugs))
+            .select((schema::systems::slug, schema::systems::id))
+            .load(db)
+            .await
+    }
+
+    /// Create a system. With `upsert`, nothing is inserted if a system with
+    /// the same slug exists, and `None` is returned.
     pub async fn create(
         db: &mut Db,
         slug: &str,
@@ -86,19 +145,28 @@ impl System {
         links: Json,
         metadata: Json,
         owner_team_id: i32,
-    ) -> Result<Self, diesel::result::Error> {
-        diesel::insert_into(schema::systems::table)
-            .values((
-                schema::systems::slug.eq(slug),
-                schema::systems::name.eq(name),
-                schema::systems::description.eq(description),
-                schema::systems::manufacturer.eq(manufacturer),
-                schema::systems::links.eq(links),
-                schema::systems::metadata.eq(metadata),
-                schema::systems::owner_team_id.eq(owner_team_id),
-            ))
-            .get_result(db)
-            .await
+        upsert: bool,
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        let insert = diesel::insert_into(schema::systems::table).values((
+            schema::systems::slug.eq(slug),
+            schema::systems::name.eq(name),
+            schema::systems::description.eq(description),
+            schema::systems::manufacturer.eq(manufacturer),
+            schema::systems::links.eq(links),
+            schema::systems::metadata.eq(metadata),
+            schema::systems::owner_team_id.eq(owner_team_id),
+        ));
+
+        if upsert {
+            insert
+                .on_conflict(schema::systems::slug)
+                .do_nothing()
+                .get_result(db)
+                .await
+                .optional()
+        } else {
+            insert.get_result(db).await.map(Some)
+        }
     }
 
     pub async fn get(db: &mut Db, id: IdOrSlug<'_>) -> Result<Option<Self>, diesel::result::Error> {
@@ -112,3 +180,44 @@ impl System {
         query.first::<Self>(db).await.optional()
     }
 }
+
+/// The id and version of a system release.
+#[derive(Queryable, Debug)]
+pub struct SystemReleaseRef {
+    pub id: i32,
+    pub version: String,
+}
+
+impl From<SystemReleaseRef> for dto::systems::SystemReleaseRef {
+    fn from(value: SystemReleaseRef) -> Self {
+        Self {
+            id: value.id,
+            version: value.version,
+        }
+    }
+}
+
+impl SystemReleaseRef {
+    /// Find a release of a system by its id or its version.
+    pub async fn find(
+        db: &mut Db,
+        system_id: i32,
+        release: IdOrSlug<'_>,
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        let mut query = schema::system_releases::table
+            .filter(schema::system_releases::system_id.eq(system_id))
+            .select((
+                schema::system_releases::id,
+                schema::system_releases::version,
+            ))
+            .into_boxed();
+        query = match release {
+            IdOrSlug::Id(id) => query.filter(schema::system_releases::id.eq(id)),
+            IdOrSlug::Slug(version) => {
+                query.filter(schema::system_releases::version.eq(version.into_owned()))
+            }
+        };
+
+        query.first::<Self>(db).await.optional()
+    }
+}
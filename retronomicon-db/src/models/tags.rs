@@ -27,6 +27,8 @@ impl From<Tag> for dto::tags::Tag {
 
 #[rocket::async_trait]
 impl FromIdOrSlug for Tag {
+    const NAME: &'static str = "Tag";
+
     async fn from_id(db: &mut crate::Db, id: i32) -> Result<Option<Self>, diesel::result::Error>
     where
         Self: Sized,
@@ -12,6 +12,7 @@ use diesel::{AsExpression, FromSqlRow};
 use retronomicon_dto as dto;
 use retronomicon_dto::types::IdOrSlug;
 use rocket::http::Status;
+use rocket_db_pools::diesel::scoped_futures::ScopedFutureExt;
 use rocket_db_pools::diesel::{AsyncConnection, RunQueryDsl};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value as Json};
@@ -37,6 +38,7 @@ pub struct Team {
     pub description: String,
     pub links: Json,
     pub metadata: Json,
+    pub updated_at: NaiveDateTime,
 }
 
 impl From<Team> for dto::teams::TeamRef {
@@ -61,12 +63,15 @@ impl From<Team> for dto::teams::Team {
             description: value.description,
             links: value.links,
             metadata: value.metadata,
+            updated_at: value.updated_at.timestamp(),
         }
     }
 }
 
 #[rocket::async_trait]
 impl FromIdOrSlug for Team {
+    const NAME: &'static str = "Team";
+
     async fn from_id(db: &mut Db, id: i32) -> Result<Option<Self>, diesel::result::Error> {
         schema::teams::table
             .filter(schema::teams::id.eq(id))
@@ -82,6 +87,19 @@ impl FromIdOrSlug for Team {
             .await
             .optional()
     }
+
+    async fn from_previous_slug(
+        db: &mut Db,
+        slug: &str,
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        schema::team_slug_history::table
+            .inner_join(schema::teams::table)
+            .filter(schema::team_slug_history::old_slug.eq(slug))
+            .select(schema::teams::all_columns)
+            .first::<Team>(db)
+            .await
+            .optional()
+    }
 }
 
 impl Team {
@@ -97,6 +115,16 @@ impl Team {
             .optional()
     }
 
+    pub async fn slug_exists(db: &mut Db, slug: &str) -> Result<bool, diesel::result::Error> {
+        diesel::select(diesel::dsl::exists(
+            schema::teams::table.filter(schema::teams::slug.eq(slug)),
+        ))
+        .get_result(db)
+        .await
+    }
+
+    /// Create a team. With `upsert`, nothing is inserted if a team with the
+    /// same slug exists, and `None` is returned.
     pub async fn create(
         db: &mut Db,
         slug: &str,
@@ -104,20 +132,35 @@ impl Team {
         description: &str,
         links: Json,
         metadata: Json,
-    ) -> Result<Self, diesel::result::Error> {
-        diesel::insert_into(schema::teams::table)
-            .values((
-                schema::teams::slug.eq(slug),
-                schema::teams::name.eq(name),
-                schema::teams::description.eq(description),
-                schema::teams::links.eq(links),
-                schema::teams::metadata.eq(metadata),
-            ))
-            .returning(schema::teams::all_columns)
-            .get_result::<Self>(db)
-            .await
+        upsert: bool,
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        let insert = diesel::insert_into(schema::teams::table).values((
+            schema::teams::slug.eq(slug),
+            schema::teams::name.eq(name),
+            schema::teams::description.eq(description),
+            schema::teams::links.eq(links),
+            schema::teams::metadata.eq(metadata),
+        ));
+
+        if upsert {
+            insert
+                .on_conflict(schema::teams::slug)
+                .do_nothing()
+                .returning(schema::teams::all_columns)
+                .get_result::<Self>(db)
+                .await
+                .optional()
+        } else {
+            insert
+                .returning(schema::teams::all_columns)
+                .get_result::<Self>(db)
+                .await
+                .map(Some)
+        }
     }
 
+    /// Update a team. If the slug changes, the old one is kept in the slug
+    /// history so it can still be used to find the team.
     pub async fn update(
         db: &mut Db,
         id: i32,
@@ -127,26 +170,87 @@ impl Team {
         links: Option<Json>,
         metadata: Option<Json>,
     ) -> Result<(), diesel::result::Error> {
-        diesel::update(schema::teams::table)
-            .filter(schema::teams::id.eq(id))
-            .set(&TeamUpdate {
-                slug,
-                name,
-                description,
-                links,
-                metadata,
-            })
-            .execute(db)
+        db.transaction(|db| {
+            async move {
+                if let Some(slug) = slug {
+                    let old_slug = schema::teams::table
+                        .filter(schema::teams::id.eq(id))
+                        .select(schema::teams::slug)
+                        .first::<String>(db)
+                        .await?;
+
+                    if old_slug != slug {
+                        diesel::insert_into(schema::team_slug_history::table)
+                            .values((
+                                schema::team_slug_history::old_slug.eq(&old_slug),
+                                schema::team_slug_history::team_id.eq(id),
+                            ))
+                            .on_conflict(schema::team_slug_history::old_slug)
+                            .do_update()
+                            .set(schema::team_slug_history::team_id.eq(id))
+                            .execute(db)
+                            .await?;
+                    }
+                }
+
+                diesel::update(schema::teams::table)
+                    .filter(schema::teams::id.eq(id))
+                    .set(&TeamUpdate {
+                        slug,
+                        name,
+                        description,
+                        links,
+                        metadata,
+                    })
+                    .execute(db)
+                    .await?;
+                Ok(())
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+
+    /// Count the cores, systems and platforms owned by a team, in that order.
+    pub async fn owned_resources_count(
+        db: &mut Db,
+        id: i32,
+    ) -> Result<(i64, i64, i64), diesel::result::Error> {
+        let cores = schema::cores::table
+            .filter(schema::cores::owner_team_id.eq(id))
+            .count()
+            .get_result::<i64>(db)
+            .await?;
+        let systems = schema::systems::table
+            .filter(schema::systems::owner_team_id.eq(id))
+            .count()
+            .get_result::<i64>(db)
+            .await?;
+        let platforms = schema::platforms::table
+            .filter(schema::platforms::owner_team_id.eq(id))
+            .count()
+            .get_result::<i64>(db)
             .await?;
-        Ok(())
+        Ok((cores, systems, platforms))
     }
 
+    /// Delete the team along with its memberships and pending invitations.
     pub async fn delete(db: &mut Db, id: i32) -> Result<(), diesel::result::Error> {
-        diesel::delete(schema::teams::table)
-            .filter(schema::teams::id.eq(id))
-            .execute(db)
-            .await
-            .map(|_| ())
+        db.transaction(|db| {
+            async move {
+                diesel::delete(schema::user_teams::table)
+                    .filter(schema::user_teams::team_id.eq(id))
+                    .execute(db)
+                    .await?;
+                diesel::delete(schema::teams::table)
+                    .filter(schema::teams::id.eq(id))
+                    .execute(db)
+                    .await
+                    .map(|_| ())
+            }
+            .scope_boxed()
+        })
+        .await
     }
 
     pub async fn list(
@@ -161,6 +265,25 @@ impl Team {
             .await
     }
 
+    /// Invite multiple users to this team, all in a single transaction.
+    pub async fn invite_users(
+        &self,
+        db: &mut Db,
+        from_id: i32,
+        invites: &[(User, models::UserTeamRole)],
+    ) -> Result<(), diesel::result::Error> {
+        db.transaction(|db| {
+            async move {
+                for (user, role) in invites {
+                    user.invite_to(db, from_id, self.id, *role).await?;
+                }
+                Ok(())
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+
     pub async fn users_ref(
         &self,
         db: &mut Db,
@@ -2,6 +2,7 @@ use crate::models;
 use crate::models::Team;
 use crate::schema;
 use crate::schema::sql_types;
+use crate::utils::{escape_like, merge_json_links, merge_json_metadata};
 use crate::Db;
 use chrono::NaiveDateTime;
 use diesel::deserialize::FromSql;
@@ -18,14 +19,51 @@ use rocket::request;
 use rocket_db_pools::diesel::scoped_futures::ScopedFutureExt;
 use rocket_db_pools::diesel::{AsyncConnection, RunQueryDsl};
 use serde_json::{Value as Json, Value};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::{Debug, Formatter};
 use std::io::Write;
 
+mod auth_provider;
 mod password;
 use crate::pages::Paginate;
+pub use auth_provider::*;
 pub use password::*;
 
+/// The error returned by [`User::from_email`] when the password is correct but
+/// the user must reset it before logging in.
+#[derive(Debug)]
+pub struct PasswordNeedsReset;
+
+impl std::fmt::Display for PasswordNeedsReset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Password needs reset")
+    }
+}
+
+impl std::error::Error for PasswordNeedsReset {}
+
+/// Maximum number of usernames tried (including the desired one) when looking
+/// for an available username.
+const USERNAME_MAX_ATTEMPTS: usize = 100;
+
+/// Returns the usernames to try for a desired username, in order: `foo`, `foo2`,
+/// `foo3`, etc. The base is truncated so the suffixed username is never longer
+/// than the maximum username length.
+fn username_candidates(desired: &str, attempts: usize) -> Vec<String> {
+    (1..=attempts)
+        .map(|i| {
+            let suffix = if i == 1 { String::new() } else { i.to_string() };
+            let mut end = desired
+                .len()
+                .min(dto::user::Username::MAX_LENGTH - suffix.len());
+            while !desired.is_char_boundary(end) {
+                end -= 1;
+            }
+            format!("{}{suffix}", &desired[..end])
+        })
+        .collect()
+}
+
 #[derive(AsChangeset)]
 #[diesel(table_name = schema::users)]
 struct UserSignupChangeset<'a> {
@@ -117,7 +155,7 @@ impl User {
             .ok_or(anyhow::Error::msg("Invalid password"))?;
 
         if user_password.needs_reset {
-            return Err(anyhow::Error::msg("Password needs reset"));
+            return Err(PasswordNeedsReset.into());
         }
         if user_password.validation_token.is_some() {
             return Err(anyhow::Error::msg("Email not validated"));
@@ -126,6 +164,17 @@ impl User {
         Ok(user)
     }
 
+    pub async fn find_by_email(
+        db: &mut Db,
+        email: &str,
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        schema::users::table
+            .filter(schema::users::email.eq(email))
+            .first::<User>(db)
+            .await
+            .optional()
+    }
+
     pub async fn from_auth(
         db: &mut Db,
         email: &str,
@@ -156,6 +205,7 @@ impl User {
         let mut query = schema::user_teams::table
             .inner_join(schema::users::table.on(schema::users::id.eq(schema::user_teams::user_id)))
             .inner_join(schema::teams::table)
+            .filter(schema::user_teams::invite_from.is_null())
             .select((
                 schema::users::all_columns,
                 schema::teams::all_columns,
@@ -163,21 +213,19 @@ impl User {
             ))
             .into_boxed();
 
-        if let Some(id) = user_id.as_id() {
-            query = query.filter(schema::users::dsl::id.eq(id));
-        } else if let Some(username) = user_id.as_username() {
-            query = query.filter(schema::users::dsl::username.eq(username));
-        } else {
-            return Ok(None);
-        }
+        query = match user_id {
+            dto::user::UserIdOrUsername::Id(id) => query.filter(schema::users::dsl::id.eq(id)),
+            dto::user::UserIdOrUsername::Username(username) => {
+                query.filter(schema::users::dsl::username.eq(username.into_inner().into_owned()))
+            }
+        };
 
-        if let Some(id) = team_id.as_id() {
-            query = query.filter(schema::user_teams::dsl::team_id.eq(id));
-        } else if let Some(slug) = team_id.as_slug() {
-            query = query.filter(schema::teams::dsl::slug.eq(slug));
-        } else {
-            return Ok(None);
-        }
+        query = match team_id {
+            dto::types::IdOrSlug::Id(id) => query.filter(schema::user_teams::dsl::team_id.eq(id)),
+            dto::types::IdOrSlug::Slug(slug) => {
+                query.filter(schema::teams::dsl::slug.eq(slug.into_owned()))
+            }
+        };
 
         query
             .first::<(models::User, models::Team, models::UserTeamRole)>(db)
@@ -198,6 +246,22 @@ impl User {
             .await
     }
 
+    /// Users whose username starts with `prefix` (case insensitive), sorted by
+    /// username. Deleted users and users without a username are excluded.
+    pub async fn search_by_username_prefix(
+        db: &mut Db,
+        prefix: &str,
+        limit: i64,
+    ) -> Result<Vec<Self>, diesel::result::Error> {
+        schema::users::table
+            .filter(schema::users::username.ilike(format!("{}%", escape_like(prefix))))
+            .filter(schema::users::deleted.eq(false))
+            .order(schema::users::username.asc())
+            .limit(limit)
+            .load(db)
+            .await
+    }
+
     pub async fn get_user_with_teams(
         db: &mut Db,
         user_id: dto::user::UserIdOrUsername<'_>,
@@ -228,6 +292,63 @@ impl User {
         Ok(Some((user, teams)))
     }
 
+    /// Returns the user, the teams they are a member of and the number of
+    /// pending team invitations, all read in a single transaction.
+    pub async fn get_me(
+        db: &mut Db,
+        user_id: i32,
+    ) -> Result<(Self, Vec<(i32, String, String, models::UserTeamRole)>, i64), diesel::result::Error>
+    {
+        db.transaction(|db| {
+            async move {
+                let user = Self::from_id(db, user_id).await?;
+
+                let teams = models::UserTeam::belonging_to(&user)
+                    .inner_join(schema::teams::table)
+                    .filter(schema::user_teams::invite_from.is_null())
+                    .select((
+                        schema::teams::id,
+                        schema::teams::name,
+                        schema::teams::slug,
+                        schema::user_teams::role,
+                    ))
+                    .load::<(i32, String, String, models::UserTeamRole)>(db)
+                    .await?;
+
+                let pending_invitations = models::UserTeam::belonging_to(&user)
+                    .filter(schema::user_teams::invite_from.is_not_null())
+                    .count()
+                    .get_result::<i64>(db)
+                    .await?;
+
+                Ok((user, teams, pending_invitations))
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+
+    /// Returns the desired username if it is not taken, or the first available
+    /// username with a numeric suffix (`foo2`, `foo3`, ...). Returns `None` if
+    /// no username could be found within a bounded number of attempts.
+    pub async fn available_username(
+        db: &mut Db,
+        desired: &str,
+    ) -> Result<Option<String>, diesel::result::Error> {
+        use schema::users::dsl;
+
+        let candidates = username_candidates(desired, USERNAME_MAX_ATTEMPTS);
+        let taken = dsl::users
+            .filter(dsl::username.eq_any(&candidates))
+            .select(dsl::username)
+            .load::<Option<String>>(db)
+            .await?;
+
+        Ok(candidates
+            .into_iter()
+            .find(|c| !taken.iter().any(|t| t.as_deref() == Some(c.as_str()))))
+    }
+
     pub async fn create(
         db: &mut Db,
         username: Option<&str>,
@@ -292,25 +413,19 @@ impl User {
                 if let Some(links) = form.links.as_ref() {
                     changeset.links = Some(serde_json::to_value(links).unwrap());
                 } else if form.add_links.is_some() || form.remove_links.is_some() {
-                    let mut links = BTreeMap::new();
                     let user: models::User = schema::users::table.find(self.id).first(db).await?;
+                    let mut links = user.links;
+                    merge_json_links(&mut links, form.add_links, form.remove_links);
+                    changeset.links = Some(links);
+                }
 
-                    if let Value::Object(user_links) = user.links {
-                        links.extend(user_links.into_iter());
-                    }
-
-                    if let Some(user_links) = form.add_links {
-                        for (k, v) in user_links.into_iter() {
-                            links.insert(k.to_string(), v.into());
-                        }
-                    }
-                    if let Some(user_links) = form.remove_links {
-                        for k in user_links.into_iter() {
-                            links.remove(&k.to_string());
-                        }
-                    }
-
-                    changeset.links = Some(serde_json::to_value(links).unwrap());
+                if let Some(metadata) = form.metadata.as_ref() {
+                    changeset.metadata = Some(serde_json::to_value(metadata).unwrap());
+                } else if form.add_metadata.is_some() || form.remove_metadata.is_some() {
+                    let user: models::User = schema::users::table.find(self.id).first(db).await?;
+                    let mut metadata = user.metadata;
+                    merge_json_metadata(&mut metadata, form.add_metadata, form.remove_metadata);
+                    changeset.metadata = Some(metadata);
                 }
 
                 diesel::update(schema::users::table)
@@ -435,6 +550,7 @@ pub enum UserTeamRole {
     Owner = 2,
     Admin = 1,
     Member = 0,
+    Viewer = -1,
 }
 
 impl UserTeamRole {
@@ -447,6 +563,11 @@ impl UserTeamRole {
         // Admins and owners can create cores.
         *self >= Self::Admin
     }
+
+    pub fn can_read_private_resources(&self) -> bool {
+        // Any role, including viewers, can see the team's private resources.
+        *self >= Self::Viewer
+    }
 }
 
 impl ToSql<sql_types::UserTeamRole, Pg> for UserTeamRole {
@@ -455,6 +576,7 @@ impl ToSql<sql_types::UserTeamRole, Pg> for UserTeamRole {
             Self::Owner => out.write_all(b"owner")?,
             Self::Admin => out.write_all(b"admin")?,
             Self::Member => out.write_all(b"member")?,
+            Self::Viewer => out.write_all(b"viewer")?,
         }
         Ok(IsNull::No)
     }
@@ -466,6 +588,7 @@ impl FromSql<sql_types::UserTeamRole, Pg> for UserTeamRole {
             b"owner" => Ok(Self::Owner),
             b"admin" => Ok(Self::Admin),
             b"member" => Ok(Self::Member),
+            b"viewer" => Ok(Self::Viewer),
             _ => Err("Unrecognized enum variant".into()),
         }
     }
@@ -477,6 +600,7 @@ impl From<UserTeamRole> for dto::types::UserTeamRole {
             UserTeamRole::Owner => Self::Owner,
             UserTeamRole::Admin => Self::Admin,
             UserTeamRole::Member => Self::Member,
+            UserTeamRole::Viewer => Self::Viewer,
         }
     }
 }
@@ -487,6 +611,7 @@ impl From<dto::types::UserTeamRole> for UserTeamRole {
             dto::types::UserTeamRole::Owner => Self::Owner,
             dto::types::UserTeamRole::Admin => Self::Admin,
             dto::types::UserTeamRole::Member => Self::Member,
+            dto::types::UserTeamRole::Viewer => Self::Viewer,
         }
     }
 }
@@ -504,8 +629,10 @@ pub struct UserTeam {
 }
 
 impl UserTeam {
-    pub async fn user_is_in_team(
-        db: &mut crate::Db,
+    /// Whether the user is a member of the team with at least the member
+    /// role. Viewers and pending invitations do not count.
+    pub async fn user_is_member_of_team(
+        db: &mut crate::DbConnection,
         user_id: i32,
         team_id: i32,
     ) -> Result<bool, diesel::result::Error> {
@@ -514,9 +641,53 @@ impl UserTeam {
         Ok(user_teams::table
             .filter(user_teams::user_id.eq(user_id))
             .filter(user_teams::team_id.eq(team_id))
-            .first::<Self>(db)
+            .filter(user_teams::invite_from.is_null())
+            .select(user_teams::role)
+            .first::<UserTeamRole>(db)
             .await
             .optional()?
-            .is_some())
+            .is_some_and(|role| role >= UserTeamRole::Member))
+    }
+
+    /// Returns the roles of a user in multiple teams, in a single query. Teams
+    /// the user is not part of (or only invited to) are not in the map.
+    pub async fn roles_in_teams(
+        db: &mut crate::Db,
+        user_id: i32,
+        team_ids: &[i32],
+    ) -> Result<HashMap<i32, UserTeamRole>, diesel::result::Error> {
+        use schema::user_teams;
+
+        Ok(user_teams::table
+            .filter(user_teams::user_id.eq(user_id))
+            .filter(user_teams::team_id.eq_any(team_ids))
+            .filter(user_teams::invite_from.is_null())
+            .select((user_teams::team_id, user_teams::role))
+            .load::<(i32, UserTeamRole)>(db)
+            .await?
+            .into_iter()
+            .collect())
+    }
+}
+
+#[test]
+fn username_candidates_suffixes() {
+    assert_eq!(
+        username_candidates("foo", 4),
+        vec!["foo", "foo2", "foo3", "foo4"]
+    );
+}
+
+#[test]
+fn username_candidates_max_length() {
+    let long = "a".repeat(dto::user::Username::MAX_LENGTH);
+    let candidates = username_candidates(&long, 12);
+
+    assert_eq!(candidates[0], long);
+    for c in &candidates {
+        assert!(c.len() <= dto::user::Username::MAX_LENGTH);
+        assert!(dto::user::Username::new(c.as_str()).is_ok());
     }
+    assert_eq!(candidates[1], format!("{}2", &long[..31]));
+    assert_eq!(candidates[11], format!("{}12", &long[..30]));
 }
@@ -0,0 +1,75 @@
+use crate::models::User;
+use crate::schema::user_auth_providers::dsl;
+use crate::{schema, Db};
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::{OptionalExtension, QueryDsl};
+use rocket_db_pools::diesel::RunQueryDsl;
+
+/// An OAuth provider linked to a user account.
+#[derive(Clone, Debug, Queryable, Selectable, Identifiable)]
+#[diesel(primary_key(provider, external_id))]
+#[diesel(belongs_to(User))]
+#[diesel(table_name = schema::user_auth_providers)]
+pub struct UserAuthProvider {
+    pub user_id: i32,
+    pub provider: String,
+    pub external_id: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl UserAuthProvider {
+    pub async fn find(
+        db: &mut Db,
+        provider: &str,
+        external_id: &str,
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        dsl::user_auth_providers
+            .filter(dsl::provider.eq(provider))
+            .filter(dsl::external_id.eq(external_id))
+            .first::<Self>(db)
+            .await
+            .optional()
+    }
+
+    pub async fn list(db: &mut Db, user: &User) -> Result<Vec<Self>, diesel::result::Error> {
+        dsl::user_auth_providers
+            .filter(dsl::user_id.eq(user.id))
+            .order(dsl::created_at.asc())
+            .load::<Self>(db)
+            .await
+    }
+
+    pub async fn link(
+        db: &mut Db,
+        user: &User,
+        provider: &str,
+        external_id: &str,
+    ) -> Result<Self, diesel::result::Error> {
+        diesel::insert_into(schema::user_auth_providers::table)
+            .values((
+                dsl::user_id.eq(user.id),
+                dsl::provider.eq(provider),
+                dsl::external_id.eq(external_id),
+            ))
+            .returning(schema::user_auth_providers::all_columns)
+            .get_result(db)
+            .await
+    }
+
+    /// Unlink all the entries of a provider from the user. Returns the number of
+    /// entries removed.
+    pub async fn unlink(
+        db: &mut Db,
+        user: &User,
+        provider: &str,
+    ) -> Result<usize, diesel::result::Error> {
+        diesel::delete(
+            dsl::user_auth_providers
+                .filter(dsl::user_id.eq(user.id))
+                .filter(dsl::provider.eq(provider)),
+        )
+        .execute(db)
+        .await
+    }
+}
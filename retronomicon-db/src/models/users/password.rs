@@ -1,6 +1,6 @@
 use crate::models::User;
 use crate::schema::user_passwords::dsl;
-use crate::{schema, Db};
+use crate::{schema, Db, DbConnection};
 use argon2::password_hash::SaltString;
 use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version};
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
@@ -11,6 +11,65 @@ use rand::Rng;
 use rocket::error;
 use rocket_db_pools::diesel::RunQueryDsl;
 
+/// How long a password reset token is valid for, in seconds.
+pub const RESET_TOKEN_VALIDITY_SECS: i64 = 60 * 60;
+
+/// Minimum delay between two password reset requests for the same user, in seconds.
+pub const RESET_TOKEN_COOLDOWN_SECS: i64 = 60;
+
+/// How long an email validation (or login) token is valid for, in seconds.
+pub const VALIDATION_TOKEN_VALIDITY_SECS: i64 = 24 * 60 * 60;
+
+/// Minimum delay between two email login requests for the same user while
+/// the previous token is still valid, in seconds.
+pub const VALIDATION_TOKEN_COOLDOWN_SECS: i64 = 5 * 60;
+
+/// Minimum length of a user chosen password.
+pub const PASSWORD_MIN_LENGTH: usize = 8;
+
+/// Minimum number of distinct characters in a user chosen password.
+const PASSWORD_MIN_DISTINCT_CHARS: usize = 5;
+
+/// Validate a password chosen by a user before hashing it. The email and username
+/// of the user are used to reject passwords that are trivially guessable.
+pub fn validate_password(
+    password: &str,
+    email: &str,
+    username: Option<&str>,
+) -> Result<(), &'static str> {
+    if password.is_empty() {
+        return Err("Password cannot be empty");
+    }
+    if password.chars().count() < PASSWORD_MIN_LENGTH {
+        return Err("Password is too short");
+    }
+
+    let lowercase = password.to_lowercase();
+    let local_part = email.split('@').next().unwrap_or_default();
+    if lowercase == email.to_lowercase() || lowercase == local_part.to_lowercase() {
+        return Err("Password cannot be the same as the email");
+    }
+    if username.is_some_and(|username| lowercase == username.to_lowercase()) {
+        return Err("Password cannot be the same as the username");
+    }
+
+    let distinct = password
+        .chars()
+        .collect::<std::collections::BTreeSet<_>>()
+        .len();
+    if distinct < PASSWORD_MIN_DISTINCT_CHARS {
+        return Err("Password is too simple");
+    }
+
+    Ok(())
+}
+
+fn generate_token() -> String {
+    let mut buffer = [0; 32];
+    rand::thread_rng().fill(&mut buffer);
+    URL_SAFE_NO_PAD.encode(buffer)
+}
+
 pub struct DbPassword(String);
 
 impl From<&UserPassword> for DbPassword {
@@ -65,10 +124,15 @@ pub struct UserPassword {
     pub updated_at: NaiveDateTime,
     pub needs_reset: bool,
     pub validation_token: Option<String>,
+    pub reset_token: Option<String>,
+    pub reset_requested_at: Option<NaiveDateTime>,
 }
 
 impl UserPassword {
-    async fn from_user(db: &mut Db, user: &User) -> Result<Option<Self>, diesel::result::Error> {
+    pub async fn from_user(
+        db: &mut Db,
+        user: &User,
+    ) -> Result<Option<Self>, diesel::result::Error> {
         schema::user_passwords::table
             .filter(schema::user_passwords::user_id.eq(user.id))
             .first::<Self>(db)
@@ -76,22 +140,42 @@ impl UserPassword {
             .optional()
     }
 
-    pub async fn from_validation_token(
+    /// Clear the validation token of the user with this email if it matches
+    /// `token` and is not older than [`VALIDATION_TOKEN_VALIDITY_SECS`], and
+    /// return the user. This is done in a single query so the same token can
+    /// never be used twice, nor used after it expired.
+    pub async fn consume_validation_token(
         db: &mut Db,
         email: &str,
         token: &str,
     ) -> Result<Option<(User, Self)>, diesel::result::Error> {
-        schema::user_passwords::table
-            .inner_join(schema::users::table)
+        let now = chrono::Utc::now().naive_utc();
+        let user_ids = schema::users::table
             .filter(schema::users::email.eq(email))
-            .filter(schema::user_passwords::validation_token.eq(token))
-            .select((
-                schema::users::all_columns,
-                schema::user_passwords::all_columns,
-            ))
-            .first(db)
-            .await
-            .optional()
+            .select(schema::users::id);
+
+        let user_password = diesel::update(
+            dsl::user_passwords
+                .filter(dsl::user_id.eq_any(user_ids))
+                .filter(dsl::validation_token.eq(token))
+                .filter(
+                    dsl::updated_at
+                        .ge(now - chrono::Duration::seconds(VALIDATION_TOKEN_VALIDITY_SECS)),
+                ),
+        )
+        .set(dsl::validation_token.eq::<Option<String>>(None))
+        .returning(schema::user_passwords::all_columns)
+        .get_result::<Self>(db)
+        .await
+        .optional()?;
+
+        match user_password {
+            Some(user_password) => {
+                let user = User::from_id(db, user_password.user_id).await?;
+                Ok(Some((user, user_password)))
+            }
+            None => Ok(None),
+        }
     }
 
     pub async fn create(
@@ -109,9 +193,7 @@ impl UserPassword {
         };
 
         let validation_token = if create_token {
-            let mut buffer = [0; 32];
-            rand::thread_rng().fill(&mut buffer);
-            Some(URL_SAFE_NO_PAD.encode(&buffer))
+            Some(generate_token())
         } else {
             None
         };
@@ -154,11 +236,167 @@ impl UserPassword {
         }
     }
 
-    pub async fn validated(&self, db: &mut Db) -> Result<(), diesel::result::Error> {
+    /// Require the user to reset their password before they can login with it
+    /// again. Returns false if the user has no password.
+    pub async fn require_reset(db: &mut Db, user_id: i32) -> Result<bool, diesel::result::Error> {
+        diesel::update(dsl::user_passwords.filter(dsl::user_id.eq(user_id)))
+            .set(dsl::needs_reset.eq(true))
+            .execute(db)
+            .await
+            .map(|count| count > 0)
+    }
+
+    /// Create a new validation token for the user, which can be used to login by
+    /// email. This creates an empty password entry if the user does not have one.
+    /// Returns `None` if the user already has a token issued within the last
+    /// [`VALIDATION_TOKEN_COOLDOWN_SECS`].
+    pub async fn create_validation_token(
+        db: &mut Db,
+        user: &User,
+        pepper: &[u8],
+    ) -> Result<Option<String>, anyhow::Error> {
+        let user_password = match Self::from_user(db, user).await? {
+            Some(user_password) => user_password,
+            None => Self::create(db, user, None, pepper, false).await?,
+        };
+
+        let now = chrono::Utc::now().naive_utc();
+        if user_password.validation_token.is_some()
+            && user_password.updated_at + chrono::Duration::seconds(VALIDATION_TOKEN_COOLDOWN_SECS)
+                > now
+        {
+            return Ok(None);
+        }
+
+        // Bump `updated_at` so the new token is not expired with older ones.
+        let token = generate_token();
+        diesel::update(dsl::user_passwords.filter(dsl::user_id.eq(user_password.user_id)))
+            .set((
+                dsl::validation_token.eq(Some(&token)),
+                dsl::updated_at.eq(now),
+            ))
+            .execute(db)
+            .await?;
+
+        Ok(Some(token))
+    }
+
+    /// Clear the validation tokens of password entries last updated before
+    /// `older_than`, so links that were never clicked cannot be used anymore.
+    /// Returns the number of tokens cleared.
+    ///
+    /// This takes a bare connection so it can run outside of a request.
+    pub async fn expire_old_tokens(
+        db: &mut DbConnection,
+        older_than: NaiveDateTime,
+    ) -> Result<usize, diesel::result::Error> {
+        diesel::update(
+            dsl::user_passwords
+                .filter(dsl::validation_token.is_not_null())
+                .filter(dsl::updated_at.lt(older_than)),
+        )
+        .set(dsl::validation_token.eq::<Option<String>>(None))
+        .execute(db)
+        .await
+    }
+
+    /// Create a new password reset token for the user, creating an empty password
+    /// entry if the user does not have one (e.g. they signed up using OAuth).
+    /// Returns `None` if a token was already requested within the last
+    /// [`RESET_TOKEN_COOLDOWN_SECS`].
+    pub async fn create_reset_token(
+        db: &mut Db,
+        user: &User,
+        pepper: &[u8],
+    ) -> Result<Option<String>, anyhow::Error> {
+        let now = chrono::Utc::now().naive_utc();
+        let user_password = match Self::from_user(db, user).await? {
+            Some(user_password) => user_password,
+            None => Self::create(db, user, None, pepper, false).await?,
+        };
+
+        if let Some(requested_at) = user_password.reset_requested_at {
+            if requested_at + chrono::Duration::seconds(RESET_TOKEN_COOLDOWN_SECS) > now {
+                return Ok(None);
+            }
+        }
+
+        let token = generate_token();
+        diesel::update(dsl::user_passwords.filter(dsl::user_id.eq(user_password.user_id)))
+            .set((
+                dsl::reset_token.eq(Some(&token)),
+                dsl::reset_requested_at.eq(Some(now)),
+            ))
+            .execute(db)
+            .await?;
+
+        Ok(Some(token))
+    }
+
+    /// Replace the password of the user.
+    pub async fn update_password(
+        &self,
+        db: &mut Db,
+        password: &str,
+        pepper: &[u8],
+    ) -> Result<(), anyhow::Error> {
+        let password_hash: String = DbPassword::create(pepper, password)?.into();
         diesel::update(dsl::user_passwords.filter(dsl::user_id.eq(self.user_id)))
-            .set((dsl::validation_token.eq::<Option<String>>(None),))
+            .set((
+                dsl::password.eq(password_hash),
+                dsl::updated_at.eq(chrono::Utc::now().naive_utc()),
+            ))
             .execute(db)
             .await?;
+
+        Ok(())
+    }
+
+    /// Find the user and password entry owning a reset token.
+    /// Returns `None` if the token does not exist or has expired.
+    pub async fn from_reset_token(
+        db: &mut Db,
+        token: &str,
+    ) -> Result<Option<(User, Self)>, diesel::result::Error> {
+        let now = chrono::Utc::now().naive_utc();
+        schema::user_passwords::table
+            .inner_join(schema::users::table)
+            .filter(schema::user_passwords::reset_token.eq(token))
+            .filter(
+                schema::user_passwords::reset_requested_at
+                    .gt(now - chrono::Duration::seconds(RESET_TOKEN_VALIDITY_SECS)),
+            )
+            .select((
+                schema::users::all_columns,
+                schema::user_passwords::all_columns,
+            ))
+            .first(db)
+            .await
+            .optional()
+    }
+
+    /// Set a new password after a reset. This also clears the `needs_reset` flag
+    /// and the email validation token, since receiving the reset token proves
+    /// ownership of the email.
+    pub async fn reset(
+        &self,
+        db: &mut Db,
+        password: &str,
+        pepper: &[u8],
+    ) -> Result<(), anyhow::Error> {
+        let password_hash: String = DbPassword::create(pepper, password)?.into();
+        diesel::update(dsl::user_passwords.filter(dsl::user_id.eq(self.user_id)))
+            .set((
+                dsl::password.eq(password_hash),
+                dsl::updated_at.eq(chrono::Utc::now().naive_utc()),
+                dsl::needs_reset.eq(false),
+                dsl::validation_token.eq::<Option<String>>(None),
+                dsl::reset_token.eq::<Option<String>>(None),
+                dsl::reset_requested_at.eq::<Option<NaiveDateTime>>(None),
+            ))
+            .execute(db)
+            .await?;
+
         Ok(())
     }
 
@@ -178,3 +416,30 @@ fn can_hash_password() {
     assert!(password_hash.verify(pepper.as_bytes(), password).unwrap());
     assert!(!password_hash.verify(pepper.as_bytes(), "wrong").unwrap());
 }
+
+#[test]
+fn validate_password_rejects_empty() {
+    assert!(validate_password("", "user@example.com", None).is_err());
+}
+
+#[test]
+fn validate_password_rejects_weak() {
+    let email = "user@example.com";
+    // Too short.
+    assert!(validate_password("aB3$", email, None).is_err());
+    // Not enough distinct characters.
+    assert!(validate_password("aaaaaaaaaaaa", email, None).is_err());
+    assert!(validate_password("abababababab", email, None).is_err());
+    // Same as the email, its local part or the username.
+    assert!(validate_password("user@example.com", email, None).is_err());
+    assert!(validate_password("USER@example.com", email, None).is_err());
+    assert!(validate_password("userlong", "userlong@example.com", None).is_err());
+    assert!(validate_password("some_username", email, Some("some_username")).is_err());
+}
+
+#[test]
+fn validate_password_accepts_strong() {
+    let email = "user@example.com";
+    assert!(validate_password("correct horse battery staple", email, None).is_ok());
+    assert!(validate_password("Tr0ub4dor&3", email, Some("user")).is_ok());
+}
@@ -20,6 +20,7 @@ diesel::table! {
         #[max_length = 255]
         download_url -> Nullable<Varchar>,
         sha1 -> Bytea,
+        crc32 -> Bytea,
     }
 }
 
@@ -27,6 +28,7 @@ diesel::table! {
     core_release_artifacts (core_release_id, artifact_id) {
         core_release_id -> Int4,
         artifact_id -> Int4,
+        platform_id -> Int4,
     }
 }
 
@@ -44,6 +46,18 @@ diesel::table! {
         uploader_id -> Int4,
         core_id -> Int4,
         platform_id -> Int4,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        system_release_id -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    core_slug_history (old_slug) {
+        #[max_length = 255]
+        old_slug -> Varchar,
+        core_id -> Int4,
+        created_at -> Timestamp,
     }
 }
 
@@ -65,6 +79,10 @@ diesel::table! {
         links -> Jsonb,
         system_id -> Int4,
         owner_team_id -> Int4,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
+        deleted_by_id -> Nullable<Int4>,
     }
 }
 
@@ -82,6 +100,13 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    game_tags (tag_id, game_id) {
+        game_id -> Int4,
+        tag_id -> Int4,
+    }
+}
+
 diesel::table! {
     game_image_tags (game_image_id, tag_id) {
         game_image_id -> Int4,
@@ -117,6 +142,13 @@ diesel::table! {
         links -> Jsonb,
         system_id -> Int4,
         system_unique_id -> Int4,
+        primary_image_id -> Nullable<Int4>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
+        #[max_length = 255]
+        region -> Nullable<Varchar>,
+        languages -> Array<Text>,
     }
 }
 
@@ -138,6 +170,8 @@ diesel::table! {
         links -> Jsonb,
         metadata -> Jsonb,
         owner_team_id -> Int4,
+        metadata_schema -> Nullable<Jsonb>,
+        updated_at -> Timestamp,
     }
 }
 
@@ -181,6 +215,7 @@ diesel::table! {
         links -> Jsonb,
         metadata -> Jsonb,
         owner_team_id -> Int4,
+        updated_at -> Timestamp,
     }
 }
 
@@ -194,6 +229,15 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    team_slug_history (old_slug) {
+        #[max_length = 255]
+        old_slug -> Varchar,
+        team_id -> Int4,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     teams (id) {
         id -> Int4,
@@ -203,6 +247,18 @@ diesel::table! {
         description -> Text,
         links -> Jsonb,
         metadata -> Jsonb,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    user_auth_providers (provider, external_id) {
+        user_id -> Int4,
+        #[max_length = 255]
+        provider -> Varchar,
+        #[max_length = 255]
+        external_id -> Varchar,
+        created_at -> Timestamp,
     }
 }
 
@@ -215,6 +271,9 @@ diesel::table! {
         needs_reset -> Bool,
         #[max_length = 255]
         validation_token -> Nullable<Varchar>,
+        #[max_length = 255]
+        reset_token -> Nullable<Varchar>,
+        reset_requested_at -> Nullable<Timestamp>,
     }
 }
 
@@ -252,19 +311,25 @@ diesel::table! {
 
 diesel::joinable!(core_release_artifacts -> artifacts (artifact_id));
 diesel::joinable!(core_release_artifacts -> core_releases (core_release_id));
+diesel::joinable!(core_release_artifacts -> platforms (platform_id));
 diesel::joinable!(core_releases -> cores (core_id));
 diesel::joinable!(core_releases -> platforms (platform_id));
+diesel::joinable!(core_releases -> system_releases (system_release_id));
 diesel::joinable!(core_releases -> users (uploader_id));
+diesel::joinable!(core_slug_history -> cores (core_id));
 diesel::joinable!(core_tags -> cores (core_id));
 diesel::joinable!(core_tags -> tags (tag_id));
 diesel::joinable!(cores -> systems (system_id));
 diesel::joinable!(cores -> teams (owner_team_id));
+diesel::joinable!(cores -> users (deleted_by_id));
 diesel::joinable!(files -> artifacts (id));
 diesel::joinable!(game_artifacts -> artifacts (artifact_id));
 diesel::joinable!(game_artifacts -> games (game_id));
 diesel::joinable!(game_image_tags -> game_images (game_image_id));
 diesel::joinable!(game_image_tags -> tags (tag_id));
 diesel::joinable!(game_images -> games (game_id));
+diesel::joinable!(game_tags -> games (game_id));
+diesel::joinable!(game_tags -> tags (tag_id));
 diesel::joinable!(games -> systems (system_id));
 diesel::joinable!(platform_tags -> platforms (platform_id));
 diesel::joinable!(platform_tags -> tags (tag_id));
@@ -274,6 +339,8 @@ diesel::joinable!(system_release_artifacts -> system_releases (system_release_id
 diesel::joinable!(system_releases -> systems (system_id));
 diesel::joinable!(system_releases -> users (uploader_id));
 diesel::joinable!(systems -> teams (owner_team_id));
+diesel::joinable!(team_slug_history -> teams (team_id));
+diesel::joinable!(user_auth_providers -> users (user_id));
 diesel::joinable!(user_passwords -> users (user_id));
 diesel::joinable!(user_teams -> teams (team_id));
 
@@ -281,12 +348,14 @@ diesel::allow_tables_to_appear_in_same_query!(
     artifacts,
     core_release_artifacts,
     core_releases,
+    core_slug_history,
     core_tags,
     cores,
     files,
     game_artifacts,
     game_image_tags,
     game_images,
+    game_tags,
     games,
     platform_tags,
     platforms,
@@ -295,8 +364,10 @@ diesel::allow_tables_to_appear_in_same_query!(
     system_tags,
     systems,
     tags,
+    team_slug_history,
     teams,
     user_passwords,
+    user_auth_providers,
     user_teams,
     users,
 );
@@ -4,12 +4,27 @@ use rocket::http::Status;
 
 #[rocket::async_trait]
 pub trait FromIdOrSlug {
+    /// The name of the model, used in error messages.
+    const NAME: &'static str = "Model";
+
     async fn from_id(db: &mut Db, id: i32) -> Result<Option<Self>, diesel::result::Error>
     where
         Self: Sized;
     async fn from_slug(db: &mut Db, slug: &str) -> Result<Option<Self>, diesel::result::Error>
     where
         Self: Sized;
+
+    /// Find a model by a slug it had before being renamed. Models that keep
+    /// no slug history never match.
+    async fn from_previous_slug(
+        _db: &mut Db,
+        _slug: &str,
+    ) -> Result<Option<Self>, diesel::result::Error>
+    where
+        Self: Sized,
+    {
+        Ok(None)
+    }
 }
 
 #[rocket::async_trait]
@@ -26,23 +41,119 @@ pub trait FetchModel<T: FromIdOrSlug> {
             Err((Status::BadRequest, "Invalid id or slug".to_string()))
         }
     }
-    async fn from_id(db: &mut Db, id: i32) -> Result<T, (Status, String)>;
-    async fn from_slug(db: &mut Db, slug: &str) -> Result<T, (Status, String)>;
+    async fn from_id(db: &mut Db, id: i32) -> Result<T, (Status, String)> {
+        Self::fetch_or_404(db, id).await
+    }
+    async fn from_slug(db: &mut Db, slug: &str) -> Result<T, (Status, String)> {
+        Self::fetch_or_404_slug(db, slug).await
+    }
+
+    /// Fetch a model by its id. Returns a 404 error if it does not exist, and
+    /// a 500 error if the database failed.
+    async fn fetch_or_404(db: &mut Db, id: i32) -> Result<T, (Status, String)>;
+
+    /// Same as `fetch_or_404`, but by slug.
+    async fn fetch_or_404_slug(db: &mut Db, slug: &str) -> Result<T, (Status, String)>;
+
+    /// Same as `from_id_or_slug`, but also matches slugs the model used to
+    /// have. The boolean is true if the model was found by an old slug, in
+    /// which case callers should redirect to its current slug.
+    async fn from_id_or_slug_or_previous(
+        db: &mut Db,
+        id: dto::types::IdOrSlug<'_>,
+    ) -> Result<(T, bool), (Status, String)>;
 }
 
 #[rocket::async_trait]
-impl<T: FromIdOrSlug> FetchModel<T> for T {
-    async fn from_id(db: &mut Db, id: i32) -> Result<T, (Status, String)> {
-        Ok(T::from_id(db, id)
-            .await
-            .map_err(|e| (Status::InternalServerError, e.to_string()))?
-            .ok_or((Status::NotFound, "Not found".to_string()))?)
+impl<T: FromIdOrSlug + Send> FetchModel<T> for T {
+    async fn fetch_or_404(db: &mut Db, id: i32) -> Result<T, (Status, String)> {
+        found_or_404::<T>(T::from_id(db, id).await)
     }
 
-    async fn from_slug(db: &mut Db, slug: &str) -> Result<T, (Status, String)> {
-        Ok(T::from_slug(db, slug)
+    async fn fetch_or_404_slug(db: &mut Db, slug: &str) -> Result<T, (Status, String)> {
+        found_or_404::<T>(T::from_slug(db, slug).await)
+    }
+
+    async fn from_id_or_slug_or_previous(
+        db: &mut Db,
+        id: dto::types::IdOrSlug<'_>,
+    ) -> Result<(T, bool), (Status, String)> {
+        let Some(slug) = id.as_slug() else {
+            return <T as FetchModel<T>>::from_id_or_slug(db, id)
+                .await
+                .map(|t| (t, false));
+        };
+
+        if let Some(t) = T::from_slug(db, slug)
             .await
             .map_err(|e| (Status::InternalServerError, e.to_string()))?
-            .ok_or((Status::NotFound, "Not found".to_string()))?)
+        {
+            return Ok((t, false));
+        }
+
+        found_or_404::<T>(T::from_previous_slug(db, slug).await).map(|t| (t, true))
     }
 }
+
+/// Map the result of a lookup to a route error: database errors are 500s and
+/// a missing model is a 404.
+fn found_or_404<T: FromIdOrSlug>(
+    result: Result<Option<T>, diesel::result::Error>,
+) -> Result<T, (Status, String)> {
+    found_or_404_named(T::NAME, result)
+}
+
+/// Same as `found_or_404`, for lookups that are not by id or slug (e.g. a
+/// release of a given core). `name` is used in the error message.
+pub fn found_or_404_named<T>(
+    name: &str,
+    result: Result<Option<T>, diesel::result::Error>,
+) -> Result<T, (Status, String)> {
+    result
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or_else(|| (Status::NotFound, format!("{name} not found")))
+}
+
+#[cfg(test)]
+struct TestModel;
+
+#[cfg(test)]
+#[rocket::async_trait]
+impl FromIdOrSlug for TestModel {
+    const NAME: &'static str = "Test";
+
+    async fn from_id(_db: &mut Db, _id: i32) -> Result<Option<Self>, diesel::result::Error> {
+        Ok(None)
+    }
+    async fn from_slug(_db: &mut Db, _slug: &str) -> Result<Option<Self>, diesel::result::Error> {
+        Ok(None)
+    }
+}
+
+#[test]
+fn found_or_404_found() {
+    assert!(found_or_404(Ok(Some(TestModel))).is_ok());
+}
+
+#[test]
+fn found_or_404_not_found() {
+    let (status, message) = found_or_404::<TestModel>(Ok(None)).err().unwrap();
+    assert_eq!(status, Status::NotFound);
+    assert_eq!(message, "Test not found");
+}
+
+#[test]
+fn found_or_404_db_error() {
+    let (status, _) =
+        found_or_404::<TestModel>(Err(diesel::result::Error::BrokenTransactionManager))
+            .err()
+            .unwrap();
+    assert_eq!(status, Status::InternalServerError);
+}
+
+#[test]
+fn found_or_404_named_not_found() {
+    let (status, message) = found_or_404_named::<()>("Release", Ok(None)).err().unwrap();
+    assert_eq!(status, Status::NotFound);
+    assert_eq!(message, "Release not found");
+}
@@ -0,0 +1,125 @@
+use retronomicon_dto::types::Metadata;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Apply add/remove semantics to a JSON object of metadata. Keys in `add` are
+/// inserted (replacing existing values), then keys in `remove` are removed.
+/// If `metadata` is not an object (e.g. `null`), it is treated as empty.
+pub fn merge_json_metadata(metadata: &mut Value, add: Option<Metadata>, remove: Option<Vec<&str>>) {
+    if !metadata.is_object() {
+        *metadata = Value::Object(Map::new());
+    }
+    let object = metadata
+        .as_object_mut()
+        .expect("Just checked it's an object");
+
+    if let Some(add) = add {
+        for (k, v) in add {
+            object.insert(k, v);
+        }
+    }
+    if let Some(remove) = remove {
+        for k in remove {
+            object.remove(k);
+        }
+    }
+}
+
+/// Apply add/remove semantics to a JSON object of links (name to URL). This
+/// follows the same rules as [`merge_json_metadata`].
+pub fn merge_json_links(
+    links: &mut Value,
+    add: Option<BTreeMap<&str, &str>>,
+    remove: Option<Vec<&str>>,
+) {
+    let add = add.map(|add| {
+        add.into_iter()
+            .map(|(k, v)| (k.to_string(), Value::String(v.to_string())))
+            .collect::<BTreeMap<_, _>>()
+            .into()
+    });
+    merge_json_metadata(links, add, remove);
+}
+
+/// Escape the wildcards of a `LIKE` pattern, so the string only matches
+/// itself.
+pub fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[test]
+fn escape_like_wildcards() {
+    assert_eq!(escape_like("core-1"), "core-1");
+    assert_eq!(escape_like("100%_a\\b"), "100\\%\\_a\\\\b");
+}
+
+#[test]
+fn merge_json_metadata_add() {
+    let mut metadata = serde_json::json!({ "a": 1, "b": 2 });
+    merge_json_metadata(
+        &mut metadata,
+        Some(BTreeMap::from([("b", Value::from("two")), ("c", Value::from(3))]).into()),
+        None,
+    );
+    assert_eq!(metadata, serde_json::json!({ "a": 1, "b": "two", "c": 3 }));
+}
+
+#[test]
+fn merge_json_metadata_remove() {
+    let mut metadata = serde_json::json!({ "a": 1, "b": 2 });
+    merge_json_metadata(&mut metadata, None, Some(vec!["a", "missing"]));
+    assert_eq!(metadata, serde_json::json!({ "b": 2 }));
+}
+
+#[test]
+fn merge_json_metadata_add_and_remove_same_key() {
+    let mut metadata = serde_json::json!({});
+    merge_json_metadata(
+        &mut metadata,
+        Some(BTreeMap::from([("a", Value::from(1))]).into()),
+        Some(vec!["a"]),
+    );
+    assert_eq!(metadata, serde_json::json!({}));
+}
+
+#[test]
+fn merge_json_metadata_not_an_object() {
+    let mut metadata = Value::Null;
+    merge_json_metadata(
+        &mut metadata,
+        Some(BTreeMap::from([("a", Value::from(1))]).into()),
+        None,
+    );
+    assert_eq!(metadata, serde_json::json!({ "a": 1 }));
+
+    let mut metadata = serde_json::json!([1, 2]);
+    merge_json_metadata(&mut metadata, None, None);
+    assert_eq!(metadata, serde_json::json!({}));
+}
+
+#[test]
+fn merge_json_links_add_and_remove() {
+    let mut links = serde_json::json!({ "a": "https://a", "b": "https://b" });
+    merge_json_links(
+        &mut links,
+        Some([("b", "https://b2"), ("c", "https://c")].into()),
+        Some(vec!["a"]),
+    );
+    assert_eq!(
+        links,
+        serde_json::json!({ "b": "https://b2", "c": "https://c" })
+    );
+}
+
+#[test]
+fn merge_json_links_null() {
+    let mut links = Value::Null;
+    merge_json_links(&mut links, Some([("a", "https://a")].into()), None);
+    assert_eq!(links, serde_json::json!({ "a": "https://a" }));
+
+    let mut links = Value::Null;
+    merge_json_links(&mut links, None, Some(vec!["a"]));
+    assert_eq!(links, serde_json::json!({}));
+}
@@ -21,6 +21,9 @@ pub struct ArtifactRef {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sha256: Option<HexString>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crc32: Option<HexString>,
 }
 
 /// Checksum of an artifact. There needs to be at least one checksum.
@@ -45,6 +48,10 @@ pub struct ArtifactChecksum {
     /// SHA256 checksum of the file, in hexadecimal.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sha256: Option<HexString>,
+
+    /// CRC32 checksum of the file, in hexadecimal.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crc32: Option<HexString>,
 }
 
 /// The content being uploaded. Either a file, or the checksums of a file
@@ -61,6 +68,22 @@ pub enum ArtifactData {
     Checksums(ArtifactChecksum),
 }
 
+/// What to do when an uploaded artifact has the same filename as another
+/// artifact of the release.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "rocket", derive(rocket::form::FromFormField))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub enum ArtifactConflictMode {
+    /// Refuse the upload with a conflict error.
+    #[default]
+    Error,
+
+    /// Store the artifact under a new name, numbered like `foo (2).rom`.
+    Rename,
+}
+
 /// The result of creating a new artifact.
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
@@ -68,6 +91,10 @@ pub struct ArtifactCreateResponse {
     /// The ID of the artifact.
     pub id: i32,
 
+    /// The filename the artifact was stored under. Differs from the uploaded
+    /// one if it was renamed to avoid a conflict.
+    pub filename: String,
+
     /// A URL to download it if there was an uploaded file.
     pub url: Option<String>,
 }
@@ -80,6 +107,108 @@ pub struct CoreReleaseArtifactListItem {
     pub download_url: String,
     pub mime_type: String,
     pub created_at: i64,
+
+    /// The size of the artifact, in bytes.
+    pub size: i32,
+
+    pub r#ref: ArtifactRef,
+}
+
+/// An artifact found by its checksum.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct ArtifactExistsResponse {
+    pub id: i32,
+
+    /// Where to download the artifact.
+    pub download_url: String,
+}
+
+/// The result of verifying the integrity of an artifact.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct ArtifactVerifyResponse {
+    /// Whether the checksum of the stored data matches the stored checksum.
+    pub valid: bool,
+
+    /// The SHA256 checksum stored for the artifact.
+    pub stored_sha256: HexString,
+
+    /// The SHA256 checksum of the data, as computed by the server.
+    pub computed_sha256: HexString,
+}
+
+/// The result of verifying one artifact of a release.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct ReleaseArtifactVerifyItem {
+    pub id: i32,
+    pub filename: String,
+
+    /// Whether the checksum of the data matches the stored checksum. False
+    /// if the data could not be read.
+    pub valid: bool,
+
+    /// The SHA256 checksum stored for the artifact.
+    pub expected: HexString,
+
+    /// The SHA256 checksum of the data, as computed by the server. Missing if
+    /// the data could not be read.
+    pub actual: Option<HexString>,
+
+    /// Why the data could not be read.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The result of verifying all the artifacts of a release.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct ReleaseVerifyResponse {
+    pub artifacts: Vec<ReleaseArtifactVerifyItem>,
+}
+
+/// Parameters for computing the missing SHA1 of a batch of artifacts.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct ArtifactBackfillQueryParams {
+    /// Only go through artifacts with an id greater than this one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<u32>,
+
+    /// The maximum number of artifacts to go through. Must be between 1 and
+    /// 100. Defaults to 20.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+}
+
+/// The result of computing the missing SHA1 of a batch of artifacts.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct ArtifactBackfillResponse {
+    /// The number of artifacts whose SHA1 was stored.
+    pub updated: usize,
+    /// The artifacts that could not be downloaded or did not match their
+    /// other checksums.
+    pub failed: Vec<i32>,
+    /// The id of the last artifact of the batch, to pass as `after` for the
+    /// next batch. Missing if there was nothing left to backfill.
+    pub last_id: Option<i32>,
+}
+
+/// Details of a single publicly available artifact.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct ArtifactDetails {
+    pub id: i32,
+    pub filename: String,
+    pub mime_type: String,
+    pub created_at: i64,
+
+    /// Where to download the artifact, either its external URL or the
+    /// download route of a core release it belongs to.
+    pub download_url: String,
+
     pub r#ref: ArtifactRef,
 }
 
@@ -100,6 +229,7 @@ fn artifact_data_2() {
         md5: Some(b"abc".into()),
         sha1: Some(b"def".into()),
         sha256: None,
+        crc32: None,
     });
     let json = serde_json::to_string(&data).unwrap();
     assert_eq!(json, r#"{"size":123,"md5":"616263","sha1":"646566"}"#);
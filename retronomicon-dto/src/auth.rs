@@ -18,6 +18,15 @@ pub struct SignupRequest<'a> {
 pub struct SignupResponse {
     pub id: i32,
     pub email: String,
+
+    /// A JWT for the new user, if they were logged in right away. Users who
+    /// must validate their email first do not get one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+
+    /// When `token` expires, in seconds since UNIX EPOCH.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_expiry: Option<i64>,
 }
 
 /// A login request with an email and password.
@@ -29,6 +38,41 @@ pub struct LoginRequest<'a> {
     pub password: &'a str,
 }
 
+/// A request to receive a login link by email.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct EmailLoginRequest<'a> {
+    pub email: &'a str,
+}
+
+/// A request to login using the token received by email.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct EmailLoginConfirmRequest<'a> {
+    pub email: &'a str,
+    pub token: &'a str,
+}
+
+/// A request to change the password of the current user.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct PasswordChangeRequest<'a> {
+    pub current_password: &'a str,
+    pub new_password: &'a str,
+}
+
+/// An OAuth provider linked to the user account.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct LinkedAuthProvider {
+    /// The name of the provider (e.g. `github`).
+    pub provider: String,
+    /// The identifier of the user with this provider.
+    pub external_id: String,
+    /// When this provider was linked, as a UNIX timestamp.
+    pub created_at: i64,
+}
+
 /// A JWT authentication token.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
@@ -36,3 +80,38 @@ pub struct TokenResponse {
     /// The token itself.
     pub token: String,
 }
+
+/// A request to send a password reset link to an email.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct PasswordForgotRequest<'a> {
+    pub email: &'a str,
+}
+
+/// A request to set a new password using a reset token received by email.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct PasswordResetRequest<'a> {
+    pub token: &'a str,
+    pub password: &'a str,
+}
+
+/// The result of expiring old email validation tokens.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct ExpireTokensResponse {
+    /// The number of tokens that were expired.
+    pub expired: usize,
+}
+
+/// The body of the error returned when logging in with a password that must
+/// be reset first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct PasswordResetRequiredResponse {
+    /// Always `password_reset_required`.
+    pub error: String,
+
+    /// The page where the user can request a password reset.
+    pub reset_url: String,
+}
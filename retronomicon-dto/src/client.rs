@@ -17,6 +17,52 @@ pub enum Error {
 
 pub const DEFAULT_SERVER_URL: &str = "https://retronomicon.land/";
 
+/// The header carrying the ID of a request. The server echoes it back in its
+/// response, and includes it in its logs.
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// The header carrying the API version of the server (see [`crate::VERSION`]).
+pub const API_VERSION_HEADER: &str = "X-Api-Version";
+
+/// The header carrying the minimum API version supported by the server (see
+/// [`crate::API_MIN_VERSION`]).
+pub const API_MIN_VERSION_HEADER: &str = "X-Api-Min-Version";
+
+/// Whether `version` is newer than `than`. Both are `major.minor.patch`
+/// versions, and anything after a `-` or `+` is ignored. Versions that
+/// cannot be parsed are never newer.
+pub fn is_newer_version(version: &str, than: &str) -> bool {
+    fn parse(version: &str) -> Option<Vec<u64>> {
+        version
+            .split(['-', '+'])
+            .next()?
+            .split('.')
+            .map(|part| part.parse().ok())
+            .collect()
+    }
+
+    match (parse(version), parse(than)) {
+        (Some(version), Some(than)) => version > than,
+        _ => false,
+    }
+}
+
+/// Generate a new ID for a request. It is unique within this process, and made
+/// of the process ID and the current time to avoid collisions with other
+/// clients.
+#[cfg(feature = "client")]
+pub fn new_request_id() -> String {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!("{:x}-{nanos:x}-{count:x}", std::process::id())
+}
+
 #[cfg(feature = "client")]
 #[derive(Default, Debug, Clone)]
 pub struct ClientConfig<'a> {
@@ -46,26 +92,187 @@ macro_rules! declare_client_impl {
         declare_client! {
             $async_or_blocking;
 
+            post auth_email_login_request(
+                ("auth/email-login/request"),
+                @body body: &crate::auth::EmailLoginRequest<'_>,
+            ) -> crate::Ok;
+            post auth_email_login_confirm(
+                ("auth/email-login/confirm"),
+                @body body: &crate::auth::EmailLoginConfirmRequest<'_>,
+            ) -> crate::auth::TokenResponse;
+            post auth_forgot(
+                ("auth/forgot"),
+                @body body: &crate::auth::PasswordForgotRequest<'_>,
+            ) -> crate::Ok;
+            post auth_reset(
+                ("auth/reset"),
+                @body body: &crate::auth::PasswordResetRequest<'_>,
+            ) -> crate::Ok;
+            post auth_signup(
+                ("signup"),
+                @body body: &crate::auth::SignupRequest<'_>,
+            ) -> crate::auth::SignupResponse;
+            post auth_login(
+                ("login"),
+                @body body: &crate::auth::LoginRequest<'_>,
+            ) -> crate::Ok;
+
             get users(
                 ("users"),
                 @query paging: &crate::params::PagingParams,
             ) -> crate::Paginated<crate::user::UserRef>;
+            post users_check(
+                ("users/check"),
+                @query query: &crate::user::UserCheckQueryParams<'_>,
+            ) -> crate::user::UserCheckResponse;
             get users_details(
                 ("users/{id}", id: &crate::user::UserIdOrUsername<'_>),
             ) -> crate::user::UserDetails;
+            get users_search(
+                ("users/search"),
+                @query query: &crate::user::UserSearchQueryParams,
+            ) -> Vec<crate::user::UserRef>;
             put users_update(
                 ("users/{id}", id: &crate::user::UserIdOrUsername<'_>),
                 @body body: &crate::user::UserUpdate<'_>,
             ) -> crate::Ok;
+            get me(
+                ("me"),
+            ) -> crate::user::Me;
+            post me_token(
+                ("me/token"),
+            ) -> crate::auth::TokenResponse;
             put me_update(
                 ("me"),
                 @body body: &crate::user::UserUpdate<'_>,
             ) -> crate::Ok;
+            put me_change_password(
+                ("me/password"),
+                @body body: &crate::auth::PasswordChangeRequest<'_>,
+            ) -> crate::Ok;
+            get me_providers(
+                ("me/providers"),
+            ) -> Vec<crate::auth::LinkedAuthProvider>;
+            delete me_providers_unlink(
+                ("me/providers/{provider}", provider: &str),
+            ) -> crate::Ok;
+
+            get artifacts_details(
+                ("artifacts/{id}", id: i32),
+            ) -> crate::artifact::ArtifactDetails;
+            get artifacts_by_sha256(
+                ("artifacts/by-sha256/{sha256}", sha256: &crate::encodings::HexString),
+            ) -> crate::artifact::ArtifactExistsResponse;
+
+            get teams(
+                ("teams"),
+                @query paging: &crate::params::PagingParams,
+            ) -> Vec<crate::teams::Team>;
+            get teams_details(
+                ("teams/{id}", id: &crate::types::IdOrSlug<'_>),
+            ) -> crate::teams::TeamDetails;
+            post teams_create(
+                ("teams/new"),
+                @body body: &crate::teams::TeamCreateRequest<'_>,
+            ) -> crate::teams::TeamCreateResponse;
+            put teams_update(
+                ("teams/{id}", id: &crate::types::IdOrSlug<'_>),
+                @body body: &crate::teams::TeamUpdateRequest<'_>,
+            ) -> crate::Ok;
+            delete teams_delete(
+                ("teams/{id}", id: &crate::types::IdOrSlug<'_>),
+            ) -> crate::Ok;
+            post teams_invite(
+                ("teams/{id}/invite", id: &crate::types::IdOrSlug<'_>),
+                @body body: &crate::teams::TeamInvite<'_>,
+            ) -> crate::Ok;
+            post teams_invite_accept(
+                ("teams/{id}/invitation/accept", id: &crate::types::IdOrSlug<'_>),
+            ) -> crate::Ok;
+            post teams_invite_bulk(
+                ("teams/{id}/invitations/bulk", id: &crate::types::IdOrSlug<'_>),
+                @body body: &crate::teams::TeamInviteBulkRequest<'_>,
+            ) -> Vec<crate::teams::TeamInviteBulkResult>;
+
+            get health_detailed(
+                ("health/detailed"),
+            ) -> crate::health::HealthDetailedResponse;
+
+            get platforms(
+                ("platforms"),
+                @query filter: &crate::platforms::PlatformListQueryParams<'_>,
+                @query paging: &crate::params::PagingParams,
+            ) -> Vec<crate::platforms::Platform>;
+            get platforms_details(
+                ("platforms/{id}", id: &crate::types::IdOrSlug<'_>),
+            ) -> crate::platforms::PlatformDetails;
+            post platforms_create(
+                ("platforms/new"),
+                @body body: &crate::platforms::PlatformCreateRequest<'_>,
+            ) -> crate::platforms::PlatformCreateResponse;
+            put platforms_update(
+                ("platforms/{id}", id: &crate::types::IdOrSlug<'_>),
+                @body body: &crate::platforms::PlatformUpdateRequest<'_>,
+            ) -> crate::Ok;
+
+            get systems(
+                ("systems"),
+                @query filter: &crate::systems::SystemListQueryParams<'_>,
+                @query paging: &crate::params::PagingParams,
+            ) -> Vec<crate::systems::SystemListItem>;
+            get systems_details(
+                ("systems/{id}", id: &crate::types::IdOrSlug<'_>),
+            ) -> crate::systems::SystemDetails;
+            post systems_create(
+                ("systems/new"),
+                @body body: &crate::systems::SystemCreateRequest<'_>,
+            ) -> crate::systems::SystemCreateResponse;
+            get systems_games_dat(
+                ("systems/{id}/games.dat", id: &crate::types::IdOrSlug<'_>),
+            ) -> String as text;
+            get systems_games_dat_json(
+                ("systems/{id}/games.dat.json", id: &crate::types::IdOrSlug<'_>),
+            ) -> Vec<crate::systems::SystemDatGame>;
+
+            post admin_users_require_password_reset(
+                (
+                    "admin/users/{id}/require-password-reset",
+                    id: &crate::user::UserIdOrUsername<'_>,
+                ),
+            ) -> crate::Ok;
+            post admin_maintenance_expire_tokens(
+                ("admin/maintenance/expire-tokens"),
+            ) -> crate::auth::ExpireTokensResponse;
+            post admin_maintenance_backfill_sha1(
+                ("admin/maintenance/backfill-sha1"),
+                @query query: &crate::artifact::ArtifactBackfillQueryParams,
+            ) -> crate::artifact::ArtifactBackfillResponse;
+            post admin_cores_releases_verify(
+                (
+                    "admin/cores/{core_id}/releases/{release_id}/verify",
+                    core_id: &crate::types::IdOrSlug<'_>,
+                    release_id: i32,
+                ),
+            ) -> crate::artifact::ReleaseVerifyResponse;
+            post admin_cores_merge(
+                ("admin/cores/merge"),
+                @body body: &crate::cores::CoreMergeRequest<'_>,
+            ) -> crate::cores::CoreMergeResponse;
 
             get cores(
                 ("cores"),
+                @query filter: &crate::cores::CoreListQueryParams<'_>,
                 @query paging: &crate::params::PagingParams,
             ) -> crate::Paginated<crate::cores::CoreListItem>;
+            get cores_search(
+                ("cores/search"),
+                @query query: &crate::cores::CoreSearchQueryParams,
+            ) -> Vec<crate::cores::CoreSearchItem>;
+            get cores_recently_released(
+                ("cores/recently-released"),
+                @query query: &crate::cores::CoreRecentlyReleasedQueryParams,
+                @query paging: &crate::params::PagingParams,
+            ) -> Vec<crate::cores::CoreListItem>;
             get cores_details(
                 ("cores/{id}", id: &crate::types::IdOrSlug<'_>),
             ) -> crate::cores::CoreDetailsResponse;
@@ -73,11 +280,38 @@ macro_rules! declare_client_impl {
                 ("cores/new"),
                 @body body: &crate::cores::CoreCreateRequest<'_>,
             ) -> crate::cores::CoreCreateResponse;
+            get cores_artifact_filenames(
+                ("cores/{id}/artifact-filenames", id: &crate::types::IdOrSlug<'_>),
+            ) -> Vec<String>;
 
             get cores_releases(
                 ("cores/{id}/releases", id: &crate::types::IdOrSlug<'_>),
                 @query paging: &crate::params::PagingParams,
             ) -> crate::Paginated<crate::cores::releases::CoreReleaseListItem>;
+            get cores_releases_full(
+                ("cores/{id}/releases/full", id: &crate::types::IdOrSlug<'_>),
+                @query paging: &crate::params::PagingParams,
+            ) -> crate::Paginated<crate::cores::releases::CoreReleaseFullItem>;
+            get cores_changelog(
+                ("cores/{id}/changelog", id: &crate::types::IdOrSlug<'_>),
+                @query filter: &crate::cores::releases::CoreChangelogParams<'_>,
+            ) -> Vec<crate::cores::releases::CoreChangelogEntry>;
+            get search(
+                ("search"),
+                @query query: &crate::search::SearchQueryParams,
+            ) -> crate::search::SearchResults;
+            post resolve(
+                ("resolve"),
+                @body body: &crate::search::ResolveRequest,
+            ) -> crate::search::ResolveResponse;
+            get releases(
+                ("releases"),
+                @query filter: &crate::cores::releases::ReleaseListQueryParams<'_>,
+                @query paging: &crate::params::PagingParams,
+            ) -> crate::Paginated<crate::cores::releases::CoreReleaseListItem>;
+            get cores_releases_notes_html(
+                ("cores/{id}/releases/{release_id}/notes.html", id: &crate::types::IdOrSlug<'_>, release_id: u32),
+            ) -> String as text;
             get cores_releases_artifacts(
                 (
                     "cores/{core_id}/releases/{release_id}/artifacts",
@@ -85,7 +319,55 @@ macro_rules! declare_client_impl {
                     release_id: i32,
                 ),
                 @query paging: &crate::params::PagingParams,
+                @query filter: &crate::cores::releases::CoreReleaseArtifactFilterParams<'_>,
             ) -> crate::Paginated<crate::artifact::CoreReleaseArtifactListItem>;
+            get cores_releases_artifact_get(
+                (
+                    "cores/{core_id}/releases/{release_id}/artifacts/{artifact_id}",
+                    core_id: &crate::types::IdOrSlug<'_>,
+                    release_id: i32,
+                    artifact_id: i32,
+                ),
+            ) -> crate::artifact::CoreReleaseArtifactListItem;
+            get cores_releases_artifacts_download(
+                (
+                    "cores/{core_id}/releases/{release_id}/artifacts/{artifact_id}/download",
+                    core_id: &crate::types::IdOrSlug<'_>,
+                    release_id: i32,
+                    artifact_id: i32,
+                ),
+            ) -> Vec<u8> as bytes;
+            get cores_releases_artifacts_download_zip(
+                (
+                    "cores/{core_id}/releases/{release_id}/artifacts/download.zip",
+                    core_id: &crate::types::IdOrSlug<'_>,
+                    release_id: i32,
+                ),
+            ) -> Vec<u8> as bytes;
+            get cores_releases_artifacts_download_filename(
+                (
+                    "cores/{core_id}/releases/{release_id}/artifacts/download/{filename}",
+                    core_id: &crate::types::IdOrSlug<'_>,
+                    release_id: i32,
+                    filename: &str,
+                ),
+            ) -> Vec<u8> as bytes;
+            post cores_releases_artifacts_attach(
+                (
+                    "cores/{core_id}/releases/{release_id}/artifacts/{artifact_id}/attach",
+                    core_id: &crate::types::IdOrSlug<'_>,
+                    release_id: i32,
+                    artifact_id: i32,
+                ),
+            ) -> crate::artifact::ArtifactCreateResponse;
+            post cores_releases_artifacts_verify(
+                (
+                    "cores/{core_id}/releases/{release_id}/artifacts/{artifact_id}/verify",
+                    core_id: &crate::types::IdOrSlug<'_>,
+                    release_id: i32,
+                    artifact_id: i32,
+                ),
+            ) -> crate::artifact::ArtifactVerifyResponse;
             post cores_releases_create(
                 ("cores/{id}/releases/new", id: &crate::types::IdOrSlug<'_>),
                 @body body: &crate::cores::releases::CoreReleaseCreateRequest<'_>,
@@ -96,15 +378,35 @@ macro_rules! declare_client_impl {
                     core_id: &crate::types::IdOrSlug<'_>,
                     release_id: i32,
                 ),
+                @query params: &crate::cores::releases::CoreReleaseArtifactUploadParams<'_>,
                 @file file,
             ) -> Vec<crate::artifact::ArtifactCreateResponse>;
 
+            get tags(
+                ("tags"),
+                @query paging: &crate::params::PagingParams,
+            ) -> Vec<crate::tags::Tag>;
+            get tags_cores(
+                ("tags/{id}/cores", id: &crate::types::IdOrSlug<'_>),
+                @query paging: &crate::params::PagingParams,
+            ) -> Vec<crate::cores::CoreListItem>;
+            get tags_games(
+                ("tags/{id}/games", id: &crate::types::IdOrSlug<'_>),
+                @query paging: &crate::params::PagingParams,
+            ) -> Vec<crate::games::GameListItemResponse>;
+
             get games(
                 ("games"),
                 @query query: &crate::games::GameListQueryParams<'_>,
                 @query page: &crate::params::PagingParams,
                 @body filter: &crate::games::GameListBody,
             ) -> crate::Paginated<crate::games::GameListItemResponse>;
+            post games_list_extended(
+                ("games"),
+                @query query: &crate::games::GameListQueryParams<'_>,
+                @query page: &crate::params::PagingParams,
+                @body filter: &crate::games::GameListBody,
+            ) -> crate::Paginated<crate::games::GameListItemResponse>;
             get games_details(
                 ("games/{id}", id: i32),
             ) -> crate::games::GameDetails;
@@ -116,6 +418,10 @@ macro_rules! declare_client_impl {
                 ("games/{id}", id: i32),
                 @body body: &crate::games::GameUpdateRequest<'_>,
             ) -> crate::Ok;
+            post games_merge(
+                ("games/{id}/merge", id: i32),
+                @body body: &crate::games::GameMergeRequest,
+            ) -> crate::Ok;
             post games_add_artifact(
                 ("games/{id}/artifacts/new", id: i32),
                 @body body: &Vec<crate::games::GameAddArtifactRequest<'_>>,
@@ -128,11 +434,23 @@ macro_rules! declare_client_impl {
                 ("games/{id}/images/new", id: i32),
                 @file file,
             ) -> Vec<crate::images::Image>;
+            put games_images_set_primary(
+                ("games/{id}/images/{name}/set-primary", id: i32, name: &str),
+            ) -> crate::Ok;
         }
     };
 }
 
 macro_rules! declare_client {
+    // Decode the body of a successful response. Routes are JSON by default,
+    // `as text` returns the raw body and `as bytes` the raw binary body.
+    (@decode async $response: ident) => { $response.json().await? };
+    (@decode async $response: ident text) => { $response.text().await? };
+    (@decode async $response: ident bytes) => { $response.bytes().await?.to_vec() };
+    (@decode blocking $response: ident) => { $response.json()? };
+    (@decode blocking $response: ident text) => { $response.text()? };
+    (@decode blocking $response: ident bytes) => { $response.bytes()?.to_vec() };
+
     (
         url;
 
@@ -147,7 +465,7 @@ macro_rules! declare_client {
                 $(@query $query_name: ident: $query_type: ty, )*
                 $(@body $body_name: ident: $body_type: ty, )*
                 $(@file $file_name: ident $(,)? )*
-            ) -> $rtype: ty;
+            ) -> $rtype: ty $(as $decode: ident)?;
         )*
     ) => {
         $(
@@ -172,7 +490,7 @@ macro_rules! declare_client {
                 $(@query $query_name: ident: $query_type: ty, )*
                 $(@body $body_name: ident: $body_type: ty, )*
                 $(@file $file_name: ident $(,)? )*
-            ) -> $rtype: ty;
+            ) -> $rtype: ty $(as $decode: ident)?;
         )*
     ) => {
         $(
@@ -186,6 +504,7 @@ macro_rules! declare_client {
             ) -> Result<$rtype, super::Error> {
                 let request = self.1
                     . $method (crate::routes::v1:: $fname ( &self.0, $( $path_name, )* ))
+                    .header(crate::client::REQUEST_ID_HEADER, crate::client::new_request_id())
                     $(.query( $query_name ))*
                     $(.json( $body_name ))*
                 ;
@@ -216,11 +535,10 @@ macro_rules! declare_client {
                 let response = request
                     .send()
                     .await?;
+                self.check_version(&response);
 
                 if response.status().is_success() {
-                    Ok(response
-                        .json()
-                        .await?)
+                    Ok(declare_client!(@decode async response $($decode)?))
                 } else {
                     let status = response.status();
                     let body = response.text().await?;
@@ -244,7 +562,7 @@ macro_rules! declare_client {
                 $(@query $query_name: ident: $query_type: ty, )*
                 $(@body $body_name: ident: $body_type: ty, )*
                 $(@file $file_name: ident $(,)? )*
-            ) -> $rtype: ty;
+            ) -> $rtype: ty $(as $decode: ident)?;
         )*
     ) => {
         $(
@@ -258,6 +576,7 @@ macro_rules! declare_client {
             ) -> Result<$rtype, super::Error> {
                 let request = self.1
                     . $method (crate::routes::v1:: $fname ( &self.0, $( $path_name, )* ))
+                    .header(crate::client::REQUEST_ID_HEADER, crate::client::new_request_id())
                     $(.query( &$query_name ))*
                     $(.json( $body_name ))*
                 ;
@@ -287,9 +606,10 @@ macro_rules! declare_client {
 
                 let response = request
                     .send()?;
+                self.check_version(&response);
 
                 if response.status().is_success() {
-                    Ok(response.json()?)
+                    Ok(declare_client!(@decode blocking response $($decode)?))
                 } else {
                     let status = response.status();
                     let body = response.text()?;
@@ -307,6 +627,25 @@ pub mod routes {
         pub const BASE: &str = "/api/v1/";
 
         declare_client_impl!(url);
+
+        // Routes that a client does not call directly (e.g. they redirect to
+        // a third party, or take a raw body), but whose URL is still useful.
+        declare_client! {
+            url;
+
+            get auth_github_login(("login/github"),) -> ();
+            get auth_google_login(("login/google"),) -> ();
+            get auth_patreon_login(("login/patreon"),) -> ();
+            post auth_logout(("logout"),) -> ();
+
+            // Same as `cores_changelog`, with an `Accept: text/markdown` header.
+            get cores_changelog_markdown(
+                ("cores/{id}/changelog", id: &crate::types::IdOrSlug<'_>),
+            ) -> String;
+            post systems_import_dat(
+                ("systems/{id}/import-dat", id: &crate::types::IdOrSlug<'_>),
+            ) -> crate::systems::SystemDatImportResponse;
+        }
     }
 }
 
@@ -316,10 +655,20 @@ pub mod v1 {
     use reqwest::header;
     use reqwest::{Client, Url};
 
-    pub struct V1Client(Url, Client);
+    /// A function called with the API version of the server, for every
+    /// response that has one.
+    pub type VersionCheck = fn(&str);
+
+    pub struct V1Client(Url, Client, Option<VersionCheck>);
 
     #[cfg(feature = "blocking")]
-    pub struct BlockingV1Client(Url, reqwest::blocking::Client);
+    pub struct BlockingV1Client(Url, reqwest::blocking::Client, Option<VersionCheck>);
+
+    fn server_version(headers: &header::HeaderMap) -> Option<&str> {
+        headers
+            .get(super::API_VERSION_HEADER)
+            .and_then(|value| value.to_str().ok())
+    }
 
     impl V1Client {
         fn client(auth_token: Option<&str>) -> Result<reqwest::Client, reqwest::Error> {
@@ -340,7 +689,24 @@ pub mod v1 {
 
         pub fn new(ClientConfig { url_base, token }: ClientConfig) -> Result<Self, String> {
             let url = url_base.unwrap_or_else(|| Url::parse(super::DEFAULT_SERVER_URL).unwrap());
-            Ok(Self(url, Self::client(token).map_err(|e| e.to_string())?))
+            Ok(Self(
+                url,
+                Self::client(token).map_err(|e| e.to_string())?,
+                None,
+            ))
+        }
+
+        /// Call `check` with the API version of the server on every response,
+        /// e.g. to warn if it is newer than [`crate::VERSION`].
+        pub fn with_version_check(mut self, check: VersionCheck) -> Self {
+            self.2 = Some(check);
+            self
+        }
+
+        fn check_version(&self, response: &reqwest::Response) {
+            if let (Some(check), Some(version)) = (self.2, server_version(response.headers())) {
+                check(version);
+            }
         }
 
         declare_client_impl!(async);
@@ -366,7 +732,24 @@ pub mod v1 {
 
         pub fn new(ClientConfig { url_base, token }: ClientConfig) -> Result<Self, String> {
             let url = url_base.unwrap_or_else(|| Url::parse(super::DEFAULT_SERVER_URL).unwrap());
-            Ok(Self(url, Self::client(token).map_err(|e| e.to_string())?))
+            Ok(Self(
+                url,
+                Self::client(token).map_err(|e| e.to_string())?,
+                None,
+            ))
+        }
+
+        /// Call `check` with the API version of the server on every response,
+        /// e.g. to warn if it is newer than [`crate::VERSION`].
+        pub fn with_version_check(mut self, check: VersionCheck) -> Self {
+            self.2 = Some(check);
+            self
+        }
+
+        fn check_version(&self, response: &reqwest::blocking::Response) {
+            if let (Some(check), Some(version)) = (self.2, server_version(response.headers())) {
+                check(version);
+            }
         }
 
         declare_client_impl!(blocking);
@@ -376,4 +759,123 @@ pub mod v1 {
 #[cfg(feature = "blocking")]
 pub use v1::BlockingV1Client;
 #[cfg(feature = "client")]
-pub use v1::V1Client;
+pub use v1::{V1Client, VersionCheck};
+
+#[test]
+fn newer_version() {
+    assert!(is_newer_version("0.3.0", "0.2.3"));
+    assert!(is_newer_version("0.2.10", "0.2.3"));
+    assert!(is_newer_version("1.0.0-beta", "0.9.9"));
+    assert!(!is_newer_version("0.2.3", "0.2.3"));
+    assert!(!is_newer_version("0.2.3+build", "0.2.3"));
+    assert!(!is_newer_version("0.1.9", "0.2.3"));
+    assert!(!is_newer_version("invalid", "0.2.3"));
+}
+
+#[test]
+fn routes_are_well_formed() {
+    use routes::v1::*;
+
+    let base = url::Url::parse(DEFAULT_SERVER_URL).unwrap();
+    let id = crate::types::IdOrSlug::from("some-slug");
+    let user = crate::user::UserIdOrUsername::from(1);
+    let sha256 = crate::encodings::HexString::from(&[0xab; 32]);
+
+    let urls = [
+        auth_email_login_request(&base),
+        auth_email_login_confirm(&base),
+        auth_forgot(&base),
+        auth_reset(&base),
+        auth_signup(&base),
+        auth_login(&base),
+        users(&base),
+        users_check(&base),
+        users_details(&base, &user),
+        users_search(&base),
+        users_update(&base, &user),
+        me(&base),
+        me_token(&base),
+        me_update(&base),
+        me_change_password(&base),
+        me_providers(&base),
+        me_providers_unlink(&base, "github"),
+        artifacts_details(&base, 1),
+        artifacts_by_sha256(&base, &sha256),
+        teams(&base),
+        teams_details(&base, &id),
+        teams_create(&base),
+        teams_update(&base, &id),
+        teams_delete(&base, &id),
+        teams_invite(&base, &id),
+        teams_invite_accept(&base, &id),
+        teams_invite_bulk(&base, &id),
+        health_detailed(&base),
+        platforms(&base),
+        platforms_details(&base, &id),
+        platforms_create(&base),
+        platforms_update(&base, &id),
+        systems(&base),
+        systems_details(&base, &id),
+        systems_create(&base),
+        systems_games_dat(&base, &id),
+        systems_games_dat_json(&base, &id),
+        systems_import_dat(&base, &id),
+        admin_users_require_password_reset(&base, &user),
+        admin_maintenance_expire_tokens(&base),
+        admin_maintenance_backfill_sha1(&base),
+        admin_cores_releases_verify(&base, &id, 1),
+        admin_cores_merge(&base),
+        cores(&base),
+        cores_search(&base),
+        cores_recently_released(&base),
+        cores_details(&base, &id),
+        cores_create(&base),
+        cores_artifact_filenames(&base, &id),
+        cores_releases(&base, &id),
+        cores_releases_full(&base, &id),
+        cores_changelog(&base, &id),
+        cores_changelog_markdown(&base, &id),
+        search(&base),
+        resolve(&base),
+        releases(&base),
+        cores_releases_notes_html(&base, &id, 1),
+        cores_releases_artifacts(&base, &id, 1),
+        cores_releases_artifact_get(&base, &id, 1, 2),
+        cores_releases_artifacts_download(&base, &id, 1, 2),
+        cores_releases_artifacts_download_zip(&base, &id, 1),
+        cores_releases_artifacts_download_filename(&base, &id, 1, "core.rbf"),
+        cores_releases_artifacts_verify(&base, &id, 1, 2),
+        cores_releases_artifacts_attach(&base, &id, 1, 2),
+        cores_releases_create(&base, &id),
+        cores_releases_artifacts_upload(&base, &id, 1),
+        tags(&base),
+        tags_cores(&base, &id),
+        tags_games(&base, &id),
+        games(&base),
+        games_list_extended(&base),
+        games_details(&base, 1),
+        games_create(&base),
+        games_update(&base, 1),
+        games_merge(&base, 1),
+        games_add_artifact(&base, 1),
+        games_images(&base, 1),
+        games_add_image(&base, 1),
+        games_images_set_primary(&base, 1, "title"),
+        auth_github_login(&base),
+        auth_google_login(&base),
+        auth_patreon_login(&base),
+        auth_logout(&base),
+    ];
+
+    for url in urls {
+        assert_eq!(url.origin(), base.origin(), "{url}");
+        assert!(url.path().starts_with(BASE), "{url}");
+        assert_eq!(url.query(), None, "{url}");
+        assert!(url.path_segments().unwrap().all(|s| !s.is_empty()), "{url}");
+    }
+
+    assert_eq!(
+        cores_releases_artifacts_download(&base, &id, 1, 2).as_str(),
+        "https://retronomicon.land/api/v1/cores/some-slug/releases/1/artifacts/2/download"
+    );
+}
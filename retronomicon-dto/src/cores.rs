@@ -1,7 +1,8 @@
 use crate::cores::releases::CoreReleaseRef;
+use crate::platforms::PlatformRef;
 use crate::systems::SystemRef;
 use crate::teams::TeamRef;
-use crate::types::IdOrSlug;
+use crate::types::{IdOrSlug, Metadata, Slug};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::BTreeMap;
@@ -27,6 +28,42 @@ pub struct CoreListQueryParams<'v> {
 
     /// Filter by latest release date. By default, include all cores.
     pub release_date_ge: Option<i64>,
+
+    /// Filter by name, case insensitive substring. By default, include all cores.
+    pub name_contains: Option<String>,
+
+    /// Search the name, slug and description of cores, case insensitive
+    /// substring. Unless `sort` is set, matching cores are sorted by name.
+    pub q: Option<String>,
+
+    /// Sort cores by `name`, `created` or `updated`. Prefix with `-` for a
+    /// descending order. By default, cores are sorted by id.
+    pub sort: Option<String>,
+
+    /// Include the platforms each core has releases for. Defaults to false.
+    pub with_platforms: Option<bool>,
+}
+
+/// Parameters for searching cores by name.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct CoreSearchQueryParams {
+    /// The name to search for. Does not need to be exact.
+    pub q: String,
+
+    /// The maximum number of cores to return. Must be between 10 and 100.
+    /// Defaults to 20.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+}
+
+/// Parameters for listing the cores released recently.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct CoreRecentlyReleasedQueryParams {
+    /// Only include cores whose latest release is dated on or after this
+    /// time, in seconds since UNIX EPOCH.
+    pub since: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -53,16 +90,37 @@ pub struct CoreListItem {
     pub system: SystemRef,
     pub owner_team: TeamRef,
     pub latest_release: Option<CoreReleaseRef>,
+
+    /// When the core was last updated, in seconds since UNIX EPOCH.
+    pub updated_at: i64,
+
+    /// The platforms the core has releases for. Only set when requested
+    /// with `with_platforms`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub platforms: Option<Vec<PlatformRef>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct CoreSearchItem {
+    pub id: i32,
+    pub slug: String,
+    pub name: String,
+    pub system: SystemRef,
+    pub owner_team: TeamRef,
+
+    /// How similar the name of the core is to the query, between 0 and 1.
+    pub similarity: f32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct CoreCreateRequest<'v> {
-    pub slug: &'v str,
+    pub slug: Slug<'v>,
     pub name: &'v str,
     pub description: &'v str,
     pub links: BTreeMap<&'v str, &'v str>,
-    pub metadata: BTreeMap<&'v str, Value>,
+    pub metadata: Option<Metadata>,
     pub system: IdOrSlug<'v>,
     pub owner_team: IdOrSlug<'v>,
 }
@@ -86,4 +144,32 @@ pub struct CoreDetailsResponse {
     pub metadata: BTreeMap<String, Value>,
     pub system: SystemRef,
     pub owner_team: TeamRef,
+
+    /// When the core was last updated, in seconds since UNIX EPOCH.
+    pub updated_at: i64,
+
+    /// The latest release for each platform the core was released on.
+    #[serde(default)]
+    pub releases_by_platform: Vec<CoreReleaseRef>,
+}
+
+/// Merge a duplicate core into another one.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct CoreMergeRequest<'v> {
+    /// The duplicate core. Its releases and tags are moved to the target,
+    /// and it is deleted afterward. Its slug redirects to the target.
+    #[serde(borrow)]
+    pub source: IdOrSlug<'v>,
+
+    /// The core to keep. Must be for the same system as the source.
+    #[serde(borrow)]
+    pub target: IdOrSlug<'v>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct CoreMergeResponse {
+    pub moved_releases: i64,
+    pub moved_tags: i64,
 }
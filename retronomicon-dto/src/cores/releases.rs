@@ -1,9 +1,11 @@
+use crate::artifact::{ArtifactConflictMode, CoreReleaseArtifactListItem};
 use crate::cores::CoreRef;
 use crate::platforms::PlatformRef;
-use crate::types::IdOrSlug;
+use crate::systems::SystemReleaseRef;
+use crate::types::{IdOrSlug, Metadata};
 use crate::user::UserRef;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use std::borrow::Cow;
 use std::collections::BTreeMap;
 
 /// Parameters for filtering a list of core releases.
@@ -30,6 +32,65 @@ pub struct CoreReleaseFilterParams<'v> {
     pub platform: Option<IdOrSlug<'v>>,
 }
 
+/// Parameters for filtering the list of releases across all cores.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "rocket", derive(rocket::form::FromForm))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct ReleaseListQueryParams<'v> {
+    /// Filter releases by platform. By default, include all platforms.
+    #[serde(borrow)]
+    pub platform: Option<IdOrSlug<'v>>,
+
+    /// Filter releases by the system of their core. By default, include all
+    /// systems.
+    #[serde(borrow)]
+    pub system: Option<IdOrSlug<'v>>,
+
+    /// Filter releases by the team owning their core. By default, include all
+    /// teams.
+    #[serde(borrow)]
+    pub team: Option<IdOrSlug<'v>>,
+
+    /// Filter releases by the slug of their core, which must start with this
+    /// prefix.
+    #[serde(borrow)]
+    pub core_slug: Option<Cow<'v, str>>,
+
+    /// Sort releases by the `name` of their core, or the date they were
+    /// `created` or `updated`. Prefix with `-` for a descending order. By
+    /// default, releases are sorted by release date, most recent first.
+    pub sort: Option<String>,
+}
+
+/// Parameters for filtering the artifacts of a release.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct CoreReleaseArtifactFilterParams<'v> {
+    /// Filter artifacts by platform. By default, include all platforms.
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub platform: Option<IdOrSlug<'v>>,
+
+    /// Filter artifacts by MIME type (e.g. `application/x-bitstream`), case
+    /// insensitive. By default, include all types.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+}
+
+/// Parameters for uploading artifacts to a release.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct CoreReleaseArtifactUploadParams<'v> {
+    /// The platform the artifacts are for. Defaults to the platform of the
+    /// release.
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub platform: Option<IdOrSlug<'v>>,
+
+    /// What to do with artifacts whose filename is already used in the
+    /// release. Defaults to `error`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_conflict: Option<ArtifactConflictMode>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct CoreReleaseRef {
@@ -49,6 +110,52 @@ pub struct CoreReleaseRef {
     pub platform: PlatformRef,
 }
 
+/// Parameters for the changelog of a core.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "rocket", derive(rocket::form::FromForm))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct CoreChangelogParams<'v> {
+    /// Only include the releases of this platform. By default, include all
+    /// platforms.
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub platform: Option<IdOrSlug<'v>>,
+}
+
+/// A release in the changelog of a core.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct CoreChangelogEntry {
+    pub version: String,
+
+    /// Date the release was uploaded to the server, in seconds since UNIX EPOCH.
+    pub date_released: i64,
+
+    /// Release notes, in Markdown.
+    pub notes: String,
+
+    /// Which platform was this release made for.
+    pub platform: PlatformRef,
+}
+
+/// The maximum number of artifacts listed in each release of
+/// [`CoreReleaseFullItem`].
+pub const MAX_ARTIFACTS_PER_RELEASE: usize = 50;
+
+/// A release of a core with its artifacts.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct CoreReleaseFullItem {
+    #[serde(flatten)]
+    pub release: CoreReleaseRef,
+
+    /// The artifacts of the release. At most [`MAX_ARTIFACTS_PER_RELEASE`]
+    /// are listed; use the artifacts list of the release to get all of them.
+    pub artifacts: Vec<CoreReleaseArtifactListItem>,
+
+    /// The total number of artifacts of the release.
+    pub artifacts_count: usize,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct CoreReleaseListItem {
@@ -56,20 +163,31 @@ pub struct CoreReleaseListItem {
     pub release: CoreReleaseRef,
     pub core: CoreRef,
     pub uploader: UserRef,
+
+    /// The system release (e.g. firmware) this release requires, if any.
+    pub required_system_release: Option<SystemReleaseRef>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct CoreReleaseCreateRequest<'v> {
     pub version: &'v str,
-    pub notes: &'v str,
+    /// Release notes, in Markdown. May contain escaped characters (e.g. new
+    /// lines), so it cannot always be borrowed.
+    #[serde(borrow)]
+    pub notes: Cow<'v, str>,
     pub date_released: Option<i64>,
     pub prerelease: bool,
     pub links: BTreeMap<&'v str, &'v str>,
-    pub metadata: BTreeMap<&'v str, Value>,
+    pub metadata: Option<Metadata>,
 
     #[serde(borrow)]
     pub platform: IdOrSlug<'v>,
+
+    /// The release of the core's system (e.g. firmware) this release
+    /// requires, by id or version.
+    #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+    pub required_system_release: Option<IdOrSlug<'v>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
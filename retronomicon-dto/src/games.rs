@@ -1,6 +1,6 @@
 use crate::artifact::ArtifactRef;
 use crate::encodings::HexString;
-use crate::params::RangeParams;
+use crate::params::{CountMode, RangeParams};
 use crate::systems::SystemRef;
 use crate::types::IdOrSlug;
 use serde::{Deserialize, Serialize};
@@ -30,6 +30,37 @@ pub struct GameListQueryParams<'v> {
     /// they will both try to match and may give no result.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub exact_name: Option<String>,
+
+    /// Filter by region, case insensitive substring (e.g. `usa` matches games
+    /// released in `USA, Europe`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+
+    /// Sort games by `name`, `created` or `updated`. Prefix with `-` for a
+    /// descending order. By default, games are sorted by id.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+
+    /// How to count the total of games. `estimate` is only used when no
+    /// filter is given. Defaults to `exact`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<CountMode>,
+}
+
+/// How the checksum filters of a games list are combined.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "rocket", derive(rocket::form::FromFormField))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub enum ChecksumMatchMode {
+    /// A game matches if any of its artifacts matches any of the checksums.
+    #[default]
+    Any,
+
+    /// A game matches only if, for every kind of checksum given (md5, sha1,
+    /// sha256), one of its artifacts matches it.
+    All,
 }
 
 /// Parameters for filtering the list of games using checksums.
@@ -48,6 +79,14 @@ pub struct GameListBody {
     /// Filter by sha256 checksum.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sha256: Option<Vec<HexString>>,
+
+    /// Filter by crc32 checksum.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crc32: Option<Vec<HexString>>,
+
+    /// How to combine the checksum filters. Defaults to `any`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checksum_match_mode: Option<ChecksumMatchMode>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -72,6 +111,14 @@ pub struct GameListItemResponse {
     /// for all games in this system.
     pub system_unique_id: i32,
 
+    /// The region(s) the game was released in (e.g. `USA, Europe`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+
+    /// The language codes supported by the game (e.g. `En`, `Fr`).
+    #[serde(default)]
+    pub languages: Vec<String>,
+
     /// The checksums and sizes of all artifacts the game.
     pub artifacts: Vec<ArtifactRef>,
 }
@@ -88,7 +135,16 @@ pub struct GameCreateRequest<'a> {
     pub developer: &'a str,
     pub links: BTreeMap<&'a str, &'a str>,
     pub system: IdOrSlug<'a>,
-    pub system_unique_id: i32,
+    /// The region(s) the game was released in (e.g. `USA, Europe`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<&'a str>,
+    /// The language codes supported by the game (e.g. `En`, `Fr`).
+    #[serde(default)]
+    pub languages: Vec<&'a str>,
+    /// The unique identifier of the game in its system, e.g. the ID in a DAT
+    /// file. If omitted, the next ID after the highest one in the system is
+    /// used.
+    pub system_unique_id: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -97,6 +153,14 @@ pub struct GameCreateResponse {
     pub id: i32,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct GameMergeRequest {
+    /// The duplicate game to merge into this one. Its artifacts, images and
+    /// tags are moved, and it is deleted afterward.
+    pub game_id: i32,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct GameDetails {
@@ -110,6 +174,12 @@ pub struct GameDetails {
     pub links: Value,
     pub system_unique_id: i32,
     pub system: SystemRef,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    #[serde(default)]
+    pub languages: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub primary_image: Option<crate::images::Image>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -125,6 +195,8 @@ pub struct GameUpdateRequest<'a> {
     pub add_links: Option<BTreeMap<&'a str, &'a str>>,
     pub remove_links: Option<Vec<&'a str>>,
     pub system_unique_id: Option<i32>,
+    pub region: Option<&'a str>,
+    pub languages: Option<Vec<&'a str>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -148,4 +220,8 @@ pub struct GameAddArtifactRequest<'a> {
     /// SHA256 checksum of the file, in hexadecimal.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sha256: Option<HexString>,
+
+    /// CRC32 checksum of the file, in hexadecimal.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crc32: Option<HexString>,
 }
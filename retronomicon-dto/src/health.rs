@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+/// The status of a single component of the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum ComponentStatus {
+    Ok,
+    Error,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct HealthDetailedResponse {
+    pub database: ComponentStatus,
+    pub storage: ComponentStatus,
+
+    /// Time taken to check each component, in milliseconds.
+    pub db_latency_ms: f64,
+    pub storage_latency_ms: f64,
+
+    /// The version of the backend.
+    pub version: String,
+    pub uptime_seconds: u64,
+}
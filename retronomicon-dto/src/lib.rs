@@ -11,8 +11,10 @@ pub mod artifact;
 pub mod auth;
 pub mod cores;
 pub mod games;
+pub mod health;
 pub mod images;
 pub mod platforms;
+pub mod search;
 pub mod systems;
 pub mod tags;
 pub mod teams;
@@ -22,6 +24,15 @@ pub mod client;
 
 pub use client::routes;
 
+/// The version of this crate, which is the version of the API. The server
+/// sends it in the `X-Api-Version` header of its responses.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The minimum version of this crate that the server supports. Clients built
+/// with an older version may not understand its responses. The server sends
+/// it in the `X-Api-Min-Version` header of its responses.
+pub const API_MIN_VERSION: &str = "0.2.0";
+
 pub mod reexports {
     pub use strum;
 }
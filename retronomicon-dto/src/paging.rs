@@ -23,6 +23,11 @@ pub struct Paginated<T> {
 
     /// The items requested.
     pub items: Vec<T>,
+
+    /// Whether `total` (and `page_count`) is an estimate from the database
+    /// statistics instead of an exact count.
+    #[serde(default)]
+    pub count_is_estimate: bool,
 }
 
 impl<T> Paginated<T> {
@@ -34,9 +39,16 @@ impl<T> Paginated<T> {
             per_page,
             page_count,
             items,
+            count_is_estimate: false,
         }
     }
 
+    /// Mark the total as an estimate (see [`Paginated::count_is_estimate`]).
+    pub fn with_estimated_count(mut self, count_is_estimate: bool) -> Self {
+        self.count_is_estimate = count_is_estimate;
+        self
+    }
+
     pub fn map_items<'a, T2: Serialize + Deserialize<'a>>(
         self,
         f: impl Fn(T) -> T2,
@@ -47,6 +59,7 @@ impl<T> Paginated<T> {
             per_page: self.per_page,
             page_count: self.page_count,
             items: self.items.into_iter().map(f).collect(),
+            count_is_estimate: self.count_is_estimate,
         }
     }
 
@@ -4,3 +4,6 @@ pub use paging::*;
 
 pub mod range;
 pub use range::*;
+
+pub mod sort;
+pub use sort::*;
@@ -26,6 +26,23 @@ pub struct PagingParams {
     pub limit: Option<i64>,
 }
 
+/// How the total number of items of a list is counted.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "rocket", derive(rocket::form::FromFormField))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub enum CountMode {
+    /// Count all matching items.
+    #[default]
+    Exact,
+
+    /// Use the statistics of the database for the total of an unfiltered
+    /// list, which is much faster on large tables. Filtered lists are always
+    /// counted exactly.
+    Estimate,
+}
+
 impl PagingParams {
     pub fn new(page: Option<i64>, limit: Option<i64>) -> Self {
         Self { page, limit }
@@ -56,8 +73,50 @@ impl PagingParams {
             ))
         } else if limit > LIMIT_MAX {
             Err(format!("Limit must be less than or equal to {LIMIT_MAX}"))
+        } else if page.checked_mul(limit).is_none() {
+            Err("Page is too large".to_string())
         } else {
             Ok((page, limit))
         }
     }
+
+    /// Validate the parameters, and also reject pages starting after
+    /// `max_offset` items. Deep pages are expensive to compute on large
+    /// tables; clients should narrow their filters instead.
+    pub fn validate_max_offset(&self, max_offset: i64) -> Result<(i64, i64), String> {
+        let (page, limit) = self.validate()?;
+        if page * limit > max_offset {
+            Err(format!(
+                "Page starts after the maximum of {max_offset} items, use filters to narrow the list"
+            ))
+        } else {
+            Ok((page, limit))
+        }
+    }
+}
+
+#[test]
+fn validate() {
+    assert_eq!(PagingParams::new(None, None).validate(), Ok((0, 20)));
+    assert_eq!(PagingParams::new(Some(3), Some(50)).validate(), Ok((3, 50)));
+    assert!(PagingParams::new(Some(-1), None).validate().is_err());
+    assert!(PagingParams::new(None, Some(5)).validate().is_err());
+    assert!(PagingParams::new(Some(i64::MAX), Some(100))
+        .validate()
+        .is_err());
+}
+
+#[test]
+fn validate_max_offset() {
+    let max_offset = 1000;
+    assert_eq!(
+        PagingParams::new(Some(10), Some(100)).validate_max_offset(max_offset),
+        Ok((10, 100))
+    );
+    assert!(PagingParams::new(Some(11), Some(100))
+        .validate_max_offset(max_offset)
+        .is_err());
+    assert!(PagingParams::new(Some(i64::MAX), Some(10))
+        .validate_max_offset(max_offset)
+        .is_err());
 }
@@ -1,11 +1,15 @@
-use serde::{Deserialize, Serialize};
-use std::fmt::Debug;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{Debug, Display};
 use std::ops::Bound;
 use std::str::FromStr;
 
 /// Parameters for a range of integers.
-#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
-#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+///
+/// The textual form (used for query strings and serialization) is similar to
+/// Rust ranges: `from..to`, `from..=to`, `from..`, `..to`, `..=to` and `..`. An
+/// exclusive lower bound is prefixed with `>` (e.g. `>from..to`). The shorthands
+/// `value`, `>=from`, `>from`, `<=to` and `<to` are also accepted when parsing.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct RangeParams<T> {
     pub from: Bound<T>,
     pub to: Bound<T>,
@@ -59,16 +63,22 @@ where
         const LEFT_ERR: &str = "Invalid left value";
         const RIGHT_ERR: &str = "Invalid right value";
 
-        let (from, to) = if let Some((l, r)) = s.split_once("..=") {
-            (
-                Bound::Included(l.parse::<T>().map_err(|_| LEFT_ERR)?),
-                Bound::Included(r.parse::<T>().map_err(|_| RIGHT_ERR)?),
-            )
-        } else if let Some((l, r)) = s.split_once("..") {
-            (
-                Bound::Included(l.parse::<T>().map_err(|_| LEFT_ERR)?),
-                Bound::Excluded(r.parse::<T>().map_err(|_| RIGHT_ERR)?),
-            )
+        let (from, to) = if let Some((l, r)) = s.split_once("..") {
+            let from = if l.is_empty() {
+                Bound::Unbounded
+            } else if let Some(gt) = l.strip_prefix('>') {
+                Bound::Excluded(gt.parse::<T>().map_err(|_| LEFT_ERR)?)
+            } else {
+                Bound::Included(l.parse::<T>().map_err(|_| LEFT_ERR)?)
+            };
+            let to = if r.is_empty() {
+                Bound::Unbounded
+            } else if let Some(le) = r.strip_prefix('=') {
+                Bound::Included(le.parse::<T>().map_err(|_| RIGHT_ERR)?)
+            } else {
+                Bound::Excluded(r.parse::<T>().map_err(|_| RIGHT_ERR)?)
+            };
+            (from, to)
         } else if let Some(ge) = s.strip_prefix(">=") {
             (
                 Bound::Included(ge.parse::<T>().map_err(|_| LEFT_ERR)?),
@@ -98,3 +108,127 @@ where
         Ok(RangeParams { from, to })
     }
 }
+
+impl<T: Display> Display for RangeParams<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.from {
+            Bound::Included(from) => write!(f, "{from}")?,
+            Bound::Excluded(from) => write!(f, ">{from}")?,
+            Bound::Unbounded => {}
+        }
+        f.write_str("..")?;
+        match &self.to {
+            Bound::Included(to) => write!(f, "={to}"),
+            Bound::Excluded(to) => write!(f, "{to}"),
+            Bound::Unbounded => Ok(()),
+        }
+    }
+}
+
+impl<T: Display> Serialize for RangeParams<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for RangeParams<T>
+where
+    T: Copy + FromStr,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Self::from_str(&String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature = "openapi")]
+impl<T> schemars::JsonSchema for RangeParams<T> {
+    fn schema_name() -> String {
+        "RangeParams".to_owned()
+    }
+
+    fn json_schema(_gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        schemars::schema::Schema::Object(schemars::schema_for_value!("1990..=1999").schema)
+    }
+}
+
+#[test]
+fn parse_shorthands() {
+    let parse = |s: &str| RangeParams::<i32>::from_str(s).unwrap();
+
+    assert_eq!(
+        parse("5"),
+        RangeParams {
+            from: Bound::Included(5),
+            to: Bound::Included(5)
+        }
+    );
+    assert_eq!(
+        parse(">=5"),
+        RangeParams {
+            from: Bound::Included(5),
+            to: Bound::Unbounded
+        }
+    );
+    assert_eq!(
+        parse(">5"),
+        RangeParams {
+            from: Bound::Excluded(5),
+            to: Bound::Unbounded
+        }
+    );
+    assert_eq!(
+        parse("<=5"),
+        RangeParams {
+            from: Bound::Unbounded,
+            to: Bound::Included(5)
+        }
+    );
+    assert_eq!(
+        parse("<5"),
+        RangeParams {
+            from: Bound::Unbounded,
+            to: Bound::Excluded(5)
+        }
+    );
+    assert_eq!(
+        parse("-5..-1"),
+        RangeParams {
+            from: Bound::Included(-5),
+            to: Bound::Excluded(-1)
+        }
+    );
+    assert!(RangeParams::<i32>::from_str("a..").is_err());
+    assert!(RangeParams::<i32>::from_str("1..=").is_err());
+    assert!(RangeParams::<i32>::from_str("").is_err());
+}
+
+#[test]
+fn display_roundtrip() {
+    let values = [i32::MIN, -1000, -1, 0, 1, 42, 1999, i32::MAX];
+    let bounds = |v: i32| [Bound::Included(v), Bound::Excluded(v), Bound::Unbounded];
+
+    for a in values {
+        for b in values {
+            for from in bounds(a) {
+                for to in bounds(b) {
+                    let range = RangeParams { from, to };
+                    let s = range.to_string();
+                    assert_eq!(RangeParams::from_str(&s), Ok(range), "{s}");
+
+                    let json = serde_json::to_string(&range).unwrap();
+                    assert_eq!(json, format!("\"{s}\""));
+                    assert_eq!(
+                        serde_json::from_str::<RangeParams<i32>>(&json).unwrap(),
+                        range
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A field that lists can be sorted by.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SortField {
+    /// Sort by name.
+    Name,
+
+    /// Sort by the date the item was created.
+    Created,
+
+    /// Sort by the date the item was last updated.
+    Updated,
+}
+
+impl SortField {
+    pub const ALL: [SortField; 3] = [SortField::Name, SortField::Created, SortField::Updated];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortField::Name => "name",
+            SortField::Created => "created",
+            SortField::Updated => "updated",
+        }
+    }
+}
+
+/// The order of a list.
+///
+/// The textual form is the name of the field to sort by, in ascending order,
+/// or prefixed with `-` for a descending order (e.g. `name` or `-created`).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SortParams {
+    pub field: SortField,
+    pub descending: bool,
+}
+
+impl SortParams {
+    /// Parse an optional sort key, as received from a query string.
+    pub fn parse_opt(sort: Option<&str>) -> Result<Option<Self>, String> {
+        sort.map(Self::from_str).transpose()
+    }
+}
+
+impl FromStr for SortParams {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, descending) = match s.strip_prefix('-') {
+            Some(name) => (name, true),
+            None => (s, false),
+        };
+
+        SortField::ALL
+            .into_iter()
+            .find(|field| field.as_str() == name)
+            .map(|field| Self { field, descending })
+            .ok_or_else(|| {
+                format!(
+                    "Invalid sort key {s:?}, expected one of: {}",
+                    SortField::ALL.map(|f| f.as_str()).join(", ")
+                )
+            })
+    }
+}
+
+impl Display for SortParams {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.descending {
+            f.write_str("-")?;
+        }
+        f.write_str(self.field.as_str())
+    }
+}
+
+#[test]
+fn parse() {
+    assert_eq!(
+        SortParams::from_str("name"),
+        Ok(SortParams {
+            field: SortField::Name,
+            descending: false
+        })
+    );
+    assert_eq!(
+        SortParams::from_str("-created"),
+        Ok(SortParams {
+            field: SortField::Created,
+            descending: true
+        })
+    );
+    assert!(SortParams::from_str("id").is_err());
+    assert!(SortParams::from_str("--name").is_err());
+    assert!(SortParams::from_str("").is_err());
+}
+
+#[test]
+fn roundtrip() {
+    for s in [
+        "name", "-name", "created", "-created", "updated", "-updated",
+    ] {
+        assert_eq!(SortParams::from_str(s).unwrap().to_string(), s);
+    }
+}
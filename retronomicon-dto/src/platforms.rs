@@ -1,9 +1,26 @@
 use crate::teams::TeamRef;
-use crate::types::IdOrSlug;
+use crate::types::{IdOrSlug, Metadata, Slug};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::BTreeMap;
 
+/// Parameters for filtering the list of platforms.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "rocket", derive(rocket::form::FromForm))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct PlatformListQueryParams<'v> {
+    /// Filter by name or slug, case insensitive substring. By default,
+    /// include all platforms.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Only include platforms that have at least one core release for a core
+    /// targeting this system. By default, include all platforms.
+    #[serde(borrow)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<IdOrSlug<'v>>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct PlatformRef {
@@ -18,6 +35,89 @@ pub struct Platform {
     pub id: i32,
     pub slug: String,
     pub name: String,
+
+    /// When the platform was last updated, in seconds since UNIX EPOCH.
+    pub updated_at: i64,
+}
+
+/// The type of a value in a metadata schema.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataValueType {
+    String,
+    Number,
+    Integer,
+    Boolean,
+    Array,
+    Object,
+}
+
+impl std::fmt::Display for MetadataValueType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            MetadataValueType::String => "string",
+            MetadataValueType::Number => "number",
+            MetadataValueType::Integer => "integer",
+            MetadataValueType::Boolean => "boolean",
+            MetadataValueType::Array => "array",
+            MetadataValueType::Object => "object",
+        })
+    }
+}
+
+impl MetadataValueType {
+    pub fn matches(&self, value: &Value) -> bool {
+        match self {
+            MetadataValueType::String => value.is_string(),
+            MetadataValueType::Number => value.is_number(),
+            MetadataValueType::Integer => value.is_i64() || value.is_u64(),
+            MetadataValueType::Boolean => value.is_boolean(),
+            MetadataValueType::Array => value.is_array(),
+            MetadataValueType::Object => value.is_object(),
+        }
+    }
+}
+
+/// The metadata that core releases on a platform are expected to have.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct PlatformMetadataSchema {
+    /// Keys that must be present in the metadata.
+    #[serde(default)]
+    pub required: Vec<String>,
+
+    /// The type of values, by key. Keys not listed here can have any value.
+    #[serde(default)]
+    pub properties: BTreeMap<String, MetadataValueType>,
+}
+
+impl PlatformMetadataSchema {
+    /// Validate metadata against this schema. Returns a message for every
+    /// missing or invalid key.
+    pub fn validate(&self, metadata: &Value) -> Result<(), Vec<String>> {
+        let empty = serde_json::Map::new();
+        let metadata = metadata.as_object().unwrap_or(&empty);
+
+        let missing = self
+            .required
+            .iter()
+            .filter(|key| !metadata.contains_key(key.as_str()))
+            .map(|key| format!("missing key '{key}'"));
+        let invalid = self.properties.iter().filter_map(|(key, ty)| {
+            metadata
+                .get(key)
+                .filter(|value| !ty.matches(value))
+                .map(|_| format!("key '{key}' must be of type {ty}"))
+        });
+
+        let errors = missing.chain(invalid).collect::<Vec<_>>();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -31,7 +131,14 @@ pub struct PlatformDetails {
     pub links: Value,
     pub metadata: Value,
 
+    /// The schema the metadata of core releases on this platform must follow.
+    #[serde(default)]
+    pub metadata_schema: Option<PlatformMetadataSchema>,
+
     pub owner_team: TeamRef,
+
+    /// When the platform was last updated, in seconds since UNIX EPOCH.
+    pub updated_at: i64,
 }
 
 /// Parameters for creating a new platform.
@@ -39,7 +146,7 @@ pub struct PlatformDetails {
 #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct PlatformCreateRequest<'v> {
     /// A slug for the platform. Must be unique to all platforms.
-    pub slug: &'v str,
+    pub slug: Slug<'v>,
 
     /// The human-readable name of the platform.
     pub name: &'v str,
@@ -51,11 +158,20 @@ pub struct PlatformCreateRequest<'v> {
     pub links: Option<BTreeMap<&'v str, &'v str>>,
 
     /// Metadata for the platform. No schema is enforced.
-    pub metadata: Option<BTreeMap<&'v str, Value>>,
+    pub metadata: Option<Metadata>,
+
+    /// The schema the metadata of core releases on this platform must follow.
+    /// By default, any metadata is accepted.
+    pub metadata_schema: Option<PlatformMetadataSchema>,
 
     /// The team id who will own the platform. The user must be a member of the
     /// team.
     pub owner_team: IdOrSlug<'v>,
+
+    /// If a platform with this slug already exists, return it unchanged
+    /// instead of failing. Defaults to false.
+    #[serde(default)]
+    pub upsert: bool,
 }
 
 /// Parameters for creating a new platform.
@@ -67,6 +183,9 @@ pub struct PlatformCreateResponse {
 
     /// The slug of the platform.
     pub slug: String,
+
+    /// Whether the platform was created, or already existed.
+    pub created: bool,
 }
 
 /// Parameters for updating a platform's information.
@@ -88,7 +207,66 @@ pub struct PlatformUpdateRequest<'v> {
     /// Metadata for the platform. No schema is enforced.
     pub metadata: Option<Value>,
 
+    /// Replace the schema the metadata of core releases on this platform
+    /// must follow. Existing releases are not validated again.
+    pub metadata_schema: Option<PlatformMetadataSchema>,
+
     /// The team id who will own the platform. The user must be a member of the
     /// team.
     pub team_id: Option<i32>,
+
+    /// Add or replace keys in the metadata. If the `metadata` key is also
+    /// passed, this is ignored.
+    pub add_metadata: Option<Metadata>,
+
+    /// Remove keys from the metadata. If the `metadata` key is also passed,
+    /// this is ignored.
+    pub remove_metadata: Option<Vec<&'v str>>,
+}
+
+#[test]
+fn metadata_schema_conforming() {
+    let schema: PlatformMetadataSchema = serde_json::from_value(serde_json::json!({
+        "required": ["video_modes", "storage"],
+        "properties": { "video_modes": "array", "storage": "string", "fps": "integer" }
+    }))
+    .unwrap();
+
+    assert_eq!(
+        schema.validate(&serde_json::json!({
+            "video_modes": ["ntsc", "pal"],
+            "storage": "sd",
+            "other": 1.5
+        })),
+        Ok(())
+    );
+    assert_eq!(
+        PlatformMetadataSchema::default().validate(&serde_json::json!({})),
+        Ok(())
+    );
+}
+
+#[test]
+fn metadata_schema_non_conforming() {
+    let schema: PlatformMetadataSchema = serde_json::from_value(serde_json::json!({
+        "required": ["video_modes", "storage"],
+        "properties": { "video_modes": "array", "fps": "integer" }
+    }))
+    .unwrap();
+
+    assert_eq!(
+        schema.validate(&serde_json::json!({ "video_modes": "ntsc", "fps": 59.94 })),
+        Err(vec![
+            "missing key 'storage'".to_string(),
+            "key 'fps' must be of type integer".to_string(),
+            "key 'video_modes' must be of type array".to_string(),
+        ])
+    );
+    assert_eq!(
+        schema.validate(&serde_json::json!(null)),
+        Err(vec![
+            "missing key 'video_modes'".to_string(),
+            "missing key 'storage'".to_string(),
+        ])
+    );
 }
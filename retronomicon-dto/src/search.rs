@@ -0,0 +1,98 @@
+use crate::cores::CoreSearchItem;
+use crate::systems::{SystemListItem, SystemRef};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use strum::{Display, EnumString};
+
+/// The maximum number of results of each type in a search.
+pub const SEARCH_LIMIT_PER_TYPE: i64 = 10;
+
+/// A type of item to search for.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize, EnumString, Display)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum SearchType {
+    Core,
+    Game,
+    System,
+}
+
+impl SearchType {
+    pub const ALL: [SearchType; 3] = [SearchType::Core, SearchType::Game, SearchType::System];
+
+    /// Parse a comma separated list of types, e.g. `core,system`.
+    pub fn parse_list(types: &str) -> Result<Vec<Self>, String> {
+        types
+            .split(',')
+            .map(|t| Self::from_str(t.trim()).map_err(|_| format!("Invalid search type {t:?}")))
+            .collect()
+    }
+}
+
+/// Parameters for searching cores, games and systems at once.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct SearchQueryParams {
+    /// The text to search for.
+    pub q: String,
+
+    /// A comma separated list of the types to search (`core`, `game` or
+    /// `system`). Defaults to all types.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub types: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct GameSearchItem {
+    pub id: i32,
+    pub name: String,
+    pub year: i32,
+    pub system: SystemRef,
+}
+
+/// The results of a search, by type. Types that were not searched are empty.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct SearchResults {
+    pub cores: Vec<CoreSearchItem>,
+    pub games: Vec<GameSearchItem>,
+    pub systems: Vec<SystemListItem>,
+}
+
+/// The maximum number of slugs of each type that can be resolved at once.
+pub const MAX_RESOLVE_SLUGS_PER_TYPE: usize = 1000;
+
+/// Slugs of cores, systems and platforms to resolve to their ids.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct ResolveRequest {
+    #[serde(default)]
+    pub cores: Vec<String>,
+    #[serde(default)]
+    pub systems: Vec<String>,
+    #[serde(default)]
+    pub platforms: Vec<String>,
+}
+
+/// The ids of resolved slugs, by type. Unknown slugs are omitted.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct ResolveResponse {
+    pub cores: BTreeMap<String, i32>,
+    pub systems: BTreeMap<String, i32>,
+    pub platforms: BTreeMap<String, i32>,
+}
+
+#[test]
+fn parse_list() {
+    assert_eq!(
+        SearchType::parse_list("core,system"),
+        Ok(vec![SearchType::Core, SearchType::System])
+    );
+    assert_eq!(SearchType::parse_list("game"), Ok(vec![SearchType::Game]));
+    assert!(SearchType::parse_list("core,user").is_err());
+    assert!(SearchType::parse_list("").is_err());
+}
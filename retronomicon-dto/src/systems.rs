@@ -1,8 +1,30 @@
+use crate::encodings::HexString;
 use crate::teams::TeamRef;
-use crate::types::IdOrSlug;
+use crate::types::{IdOrSlug, Metadata, Slug};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
+/// Parameters for filtering the list of systems.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "rocket", derive(rocket::form::FromForm))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct SystemListQueryParams<'v> {
+    /// Filter by name or slug, case insensitive substring. By default,
+    /// include all systems.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Filter by manufacturer, case insensitive substring. By default,
+    /// include all systems.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manufacturer: Option<String>,
+
+    /// Filter by owner team. By default, include all teams.
+    #[serde(borrow)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub team: Option<IdOrSlug<'v>>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct SystemRef {
@@ -10,6 +32,14 @@ pub struct SystemRef {
     pub slug: String,
 }
 
+/// A release of a system's files (e.g. a firmware).
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct SystemReleaseRef {
+    pub id: i32,
+    pub version: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct SystemListItem {
@@ -18,6 +48,9 @@ pub struct SystemListItem {
     pub name: String,
     pub manufacturer: String,
     pub owner_team: TeamRef,
+
+    /// When the system was last updated, in seconds since UNIX EPOCH.
+    pub updated_at: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -31,18 +64,26 @@ pub struct SystemDetails {
     pub links: BTreeMap<String, String>,
     pub metadata: BTreeMap<String, serde_json::Value>,
     pub owner_team: TeamRef,
+
+    /// When the system was last updated, in seconds since UNIX EPOCH.
+    pub updated_at: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct SystemCreateRequest<'a> {
-    pub slug: &'a str,
+    pub slug: Slug<'a>,
     pub name: &'a str,
     pub description: &'a str,
     pub manufacturer: &'a str,
     pub links: Option<BTreeMap<&'a str, &'a str>>,
-    pub metadata: Option<BTreeMap<&'a str, serde_json::Value>>,
+    pub metadata: Option<Metadata>,
     pub owner_team: IdOrSlug<'a>,
+
+    /// If a system with this slug already exists, return it unchanged
+    /// instead of failing. Defaults to false.
+    #[serde(default)]
+    pub upsert: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -50,4 +91,53 @@ pub struct SystemCreateRequest<'a> {
 pub struct SystemCreateResponse {
     pub id: i32,
     pub slug: String,
+
+    /// Whether the system was created, or already existed.
+    pub created: bool,
+}
+
+/// Result of importing a DAT file into a system.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct SystemDatImportResponse {
+    /// Number of games that did not exist and were created.
+    pub games_created: u32,
+
+    /// Number of games that already existed in the system.
+    pub games_existing: u32,
+
+    /// Number of ROMs added to games (new or existing).
+    pub roms_added: u32,
+}
+
+/// A game of a system, as exported in the system's DAT.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct SystemDatGame {
+    pub id: i32,
+    pub name: String,
+    pub description: String,
+    pub year: i32,
+    pub system_unique_id: i32,
+    pub roms: Vec<SystemDatRom>,
+}
+
+/// A ROM of a game, as exported in the system's DAT.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct SystemDatRom {
+    pub name: String,
+    pub size: i32,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub md5: Option<HexString>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha1: Option<HexString>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<HexString>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crc32: Option<HexString>,
 }
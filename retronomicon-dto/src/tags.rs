@@ -18,3 +18,41 @@ pub struct TagCreate {
     /// An RGB color. The top 8 bits are ignored.
     pub color: u32,
 }
+
+impl Tag {
+    /// The ANSI escape sequence to set the foreground to this tag's color, on
+    /// terminals that support 24-bit colors.
+    pub fn color_ansi(&self) -> String {
+        let (r, g, b) = color_i64_to_rgb(self.color as i64);
+        format!("\x1b[38;2;{r};{g};{b}m")
+    }
+}
+
+/// Extract the red, green and blue components of a color, as stored in the
+/// lower 24 bits of an integer.
+pub fn color_i64_to_rgb(c: i64) -> (u8, u8, u8) {
+    (
+        ((c >> 16) & 0xFF) as u8,
+        ((c >> 8) & 0xFF) as u8,
+        (c & 0xFF) as u8,
+    )
+}
+
+#[test]
+fn color_to_rgb() {
+    assert_eq!(color_i64_to_rgb(0), (0, 0, 0));
+    assert_eq!(color_i64_to_rgb(0xFFFFFF), (255, 255, 255));
+    assert_eq!(color_i64_to_rgb(0x123456), (0x12, 0x34, 0x56));
+    assert_eq!(color_i64_to_rgb(0x7F_FF0080), (0xFF, 0x00, 0x80));
+    assert_eq!(color_i64_to_rgb(-1), (255, 255, 255));
+}
+
+#[test]
+fn color_ansi() {
+    let tag = Tag {
+        id: 1,
+        slug: "arcade".to_string(),
+        color: 0xFF8000,
+    };
+    assert_eq!(tag.color_ansi(), "\x1b[38;2;255;128;0m");
+}
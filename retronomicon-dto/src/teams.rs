@@ -1,4 +1,4 @@
-use crate::types::{IdOrSlug, UserTeamRole};
+use crate::types::{IdOrSlug, Metadata, Slug, UserTeamRole};
 use crate::user::{UserIdOrUsername, UserRef};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -27,6 +27,9 @@ pub struct Team {
     pub description: String,
     pub links: Value,
     pub metadata: Value,
+
+    /// When the team was last updated, in seconds since UNIX EPOCH.
+    pub updated_at: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -47,6 +50,9 @@ pub struct TeamDetails {
     pub links: BTreeMap<String, String>,
     pub metadata: BTreeMap<String, Value>,
 
+    /// When the team was last updated, in seconds since UNIX EPOCH.
+    pub updated_at: i64,
+
     pub users: Vec<TeamUserRef>,
 }
 
@@ -55,7 +61,7 @@ pub struct TeamDetails {
 #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct TeamCreateRequest<'a> {
     /// A slug for the team.
-    pub slug: &'a str,
+    pub slug: Slug<'a>,
     /// A name for the team.
     pub name: &'a str,
     /// The description of the team.
@@ -65,7 +71,12 @@ pub struct TeamCreateRequest<'a> {
     pub links: Option<BTreeMap<&'a str, &'a str>>,
 
     /// Generic metadata associated with the team.
-    pub metadata: Option<BTreeMap<&'a str, Value>>,
+    pub metadata: Option<Metadata>,
+
+    /// If a team with this slug already exists, return it unchanged instead
+    /// of failing. Defaults to false.
+    #[serde(default)]
+    pub upsert: bool,
 }
 
 /// Response when creating a team.
@@ -74,10 +85,13 @@ pub struct TeamCreateRequest<'a> {
 pub struct TeamCreateResponse {
     pub id: i32,
     pub slug: String,
+
+    /// Whether the team was created, or already existed.
+    pub created: bool,
 }
 
 /// Arguments to create a team.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct TeamUpdateRequest<'a> {
     /// A slug for the team.
@@ -91,7 +105,7 @@ pub struct TeamUpdateRequest<'a> {
     pub links: Option<BTreeMap<&'a str, &'a str>>,
 
     /// Replace all metadata associated with the team.
-    pub metadata: Option<BTreeMap<&'a str, Value>>,
+    pub metadata: Option<Metadata>,
 
     /// Add new links to the list. If the `links` key is also passed,
     /// this is ignored.
@@ -99,6 +113,14 @@ pub struct TeamUpdateRequest<'a> {
 
     /// Remove links from the list.
     pub remove_links: Option<Vec<&'a str>>,
+
+    /// Add or replace keys in the metadata. If the `metadata` key is also
+    /// passed, this is ignored.
+    pub add_metadata: Option<Metadata>,
+
+    /// Remove keys from the metadata. If the `metadata` key is also passed,
+    /// this is ignored.
+    pub remove_metadata: Option<Vec<&'a str>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -110,3 +132,33 @@ pub struct TeamInvite<'a> {
     #[serde(default)]
     pub role: UserTeamRole,
 }
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct TeamInviteBulkRequest<'a> {
+    #[serde(borrow)]
+    pub invites: Vec<TeamInvite<'a>>,
+}
+
+/// The outcome of a single invitation in a bulk invite.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum TeamInviteStatus {
+    /// The user was invited (or their pending role was raised).
+    Invited,
+
+    /// No user matches this ID or username.
+    NotFound,
+
+    /// The inviter cannot invite users with this role.
+    Forbidden,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct TeamInviteBulkResult {
+    /// The user ID or username, as it was passed in the request.
+    pub user: String,
+    pub status: TeamInviteStatus,
+}
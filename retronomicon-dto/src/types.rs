@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::convert::Infallible;
 use std::str::FromStr;
 use strum::{Display, EnumString};
@@ -15,10 +17,116 @@ pub enum UserTeamRole {
     Admin,
     #[default]
     Member,
+    /// Read-only membership. Can see the team's private resources but cannot
+    /// contribute to them.
+    Viewer,
+}
+
+/// The maximum length of a slug, in characters.
+pub const MAX_SLUG_LENGTH: usize = 255;
+
+/// Slugs that are used as keywords in URLs, and that the database refuses.
+pub const RESERVED_SLUGS: &[&str] = &[
+    "new", "edit", "delete", "latest", "popular", "invalid", "all",
+];
+
+/// Validate a slug. It must be between 3 and [`MAX_SLUG_LENGTH`] characters
+/// long, start with a lowercase letter, contain only lowercase letters,
+/// digits and single dashes between them, and not be one of
+/// [`RESERVED_SLUGS`]. This mirrors the `slug` domain of the database.
+pub fn validate_slug(slug: &str) -> Result<(), &'static str> {
+    // Validate against the regex `^[a-z][a-z0-9]*(?:-[a-z0-9]+)*$`
+    if slug.len() < 3 {
+        return Err("Slug cannot be less than 3 characters");
+    }
+    if slug.len() > MAX_SLUG_LENGTH {
+        return Err("Slug cannot be more than 255 characters");
+    }
+    if !slug
+        .chars()
+        .all(|c| matches!(c, 'a'..='z' | '0'..='9' | '-'))
+    {
+        return Err("Slug must contain only lowercase letters, numbers and dashes");
+    }
+    if !slug.starts_with(|c: char| c.is_ascii_lowercase()) {
+        return Err("Slug must start with a letter");
+    }
+    if slug.ends_with('-') || slug.contains("--") {
+        return Err("Slug cannot end with a dash or contain consecutive dashes");
+    }
+    if RESERVED_SLUGS.contains(&slug) {
+        return Err("Slug is a reserved keyword");
+    }
+    Ok(())
+}
+
+/// A valid slug (see [`validate_slug`]).
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct Slug<'v>(Cow<'v, str>);
+
+impl<'v> Slug<'v> {
+    pub fn new(slug: impl Into<Cow<'v, str>>) -> Result<Self, &'static str> {
+        let slug = slug.into();
+        validate_slug(&slug)?;
+        Ok(Self(slug))
+    }
+
+    /// Validate a slug that was deserialized (which does not check it).
+    pub fn validate(&self) -> Result<(), &'static str> {
+        validate_slug(&self.0)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> Cow<'v, str> {
+        self.0
+    }
+}
+
+impl std::ops::Deref for Slug<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for Slug<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl<'v> TryFrom<&'v str> for Slug<'v> {
+    type Error = &'static str;
+
+    fn try_from(value: &'v str) -> Result<Self, Self::Error> {
+        Slug::new(value)
+    }
+}
+
+impl FromStr for Slug<'static> {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Slug::new(s.to_string())
+    }
+}
+
+/// An [`IdOrSlug`] that cannot refer to any resource.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum IdOrSlugError {
+    #[error("Empty id or slug")]
+    Empty,
+    #[error("Invalid id {0}")]
+    InvalidId(i32),
 }
 
 /// Either an ID (integer) or a slug (string).
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(untagged)]
 #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub enum IdOrSlug<'v> {
@@ -106,6 +214,20 @@ impl<'v> IdOrSlug<'v> {
         }
     }
 
+    /// Check that this can refer to a resource, i.e. that it is a positive ID
+    /// or a non-empty slug. Use this on filters, where an invalid value would
+    /// otherwise silently match nothing (or everything).
+    ///
+    /// Slugs are not checked with [`validate_slug`]: older resources can have
+    /// slugs that are too short to be created today, and must still be found.
+    pub fn resolve(self) -> Result<Self, IdOrSlugError> {
+        match &self {
+            IdOrSlug::Id(id) if *id <= 0 => Err(IdOrSlugError::InvalidId(*id)),
+            IdOrSlug::Slug(slug) if slug.is_empty() => Err(IdOrSlugError::Empty),
+            _ => Ok(self),
+        }
+    }
+
     pub fn reduce<R>(self, f_id: impl FnOnce(i32) -> R, f_slug: impl FnOnce(&str) -> R) -> R {
         match self {
             IdOrSlug::Id(id) => f_id(id),
@@ -134,3 +256,106 @@ impl<'v> From<&'v str> for IdOrSlug<'v> {
         IdOrSlug::Slug(slug.into())
     }
 }
+
+/// Generic metadata associated with a resource, as a map of keys to JSON
+/// values. Serializes as a JSON object.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+#[serde(transparent)]
+pub struct Metadata(BTreeMap<String, Value>);
+
+impl Metadata {
+    pub fn into_inner(self) -> BTreeMap<String, Value> {
+        self.0
+    }
+
+    /// Convert the metadata to a JSON object, e.g. to store it in a
+    /// database column.
+    pub fn into_json_value(self) -> Value {
+        Value::Object(self.0.into_iter().collect())
+    }
+}
+
+impl std::ops::Deref for Metadata {
+    type Target = BTreeMap<String, Value>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl IntoIterator for Metadata {
+    type Item = (String, Value);
+    type IntoIter = std::collections::btree_map::IntoIter<String, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl From<BTreeMap<String, Value>> for Metadata {
+    fn from(value: BTreeMap<String, Value>) -> Self {
+        Self(value)
+    }
+}
+
+impl From<BTreeMap<&str, Value>> for Metadata {
+    fn from(value: BTreeMap<&str, Value>) -> Self {
+        Self(value.into_iter().map(|(k, v)| (k.to_string(), v)).collect())
+    }
+}
+
+#[test]
+fn metadata_serde() {
+    let metadata: Metadata = serde_json::from_str(r#"{"b": [1, 2], "a": null}"#).unwrap();
+    assert_eq!(metadata.len(), 2);
+    assert_eq!(
+        metadata.clone().into_json_value(),
+        serde_json::json!({ "a": null, "b": [1, 2] })
+    );
+    assert_eq!(
+        serde_json::to_string(&metadata).unwrap(),
+        r#"{"a":null,"b":[1,2]}"#
+    );
+    assert!(serde_json::from_str::<Metadata>("[1, 2]").is_err());
+}
+
+#[test]
+fn slug_validation() {
+    assert!(validate_slug("abc").is_ok());
+    assert!(validate_slug("my-core-2").is_ok());
+    assert!(validate_slug("a-1").is_ok());
+    assert!(validate_slug("123").is_err());
+    assert!(validate_slug("1-2").is_err());
+    assert!(validate_slug("1ab").is_err());
+    assert!(validate_slug("a--b").is_err());
+    assert!(validate_slug("new").is_err());
+    assert!(validate_slug("latest").is_err());
+    assert!(validate_slug("newest").is_ok());
+    assert!(validate_slug(&"a".repeat(255)).is_ok());
+    assert!(validate_slug(&"a".repeat(256)).is_err());
+    assert!(validate_slug("ab").is_err());
+    assert!(validate_slug("-abc").is_err());
+    assert!(validate_slug("abc-").is_err());
+    assert!(validate_slug("Abc").is_err());
+    assert!(validate_slug("a_b").is_err());
+    assert!(validate_slug("a b").is_err());
+    assert!(Slug::new("valid-slug").is_ok());
+    assert!(Slug::new("Invalid").is_err());
+}
+
+#[test]
+fn id_or_slug_resolve() {
+    assert!(IdOrSlug::parse("12").resolve().is_ok());
+    assert!(IdOrSlug::parse("my-system").resolve().is_ok());
+    assert!(IdOrSlug::parse("ab").resolve().is_ok());
+    assert_eq!(IdOrSlug::parse("").resolve(), Err(IdOrSlugError::Empty));
+    assert_eq!(
+        IdOrSlug::parse("0").resolve(),
+        Err(IdOrSlugError::InvalidId(0))
+    );
+    assert_eq!(
+        IdOrSlug::parse("-3").resolve(),
+        Err(IdOrSlugError::InvalidId(-3))
+    );
+}
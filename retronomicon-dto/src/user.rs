@@ -1,5 +1,5 @@
 use crate::teams::TeamRef;
-use crate::types::UserTeamRole;
+use crate::types::{Metadata, UserTeamRole};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::borrow::Cow;
@@ -14,12 +14,15 @@ use std::str::FromStr;
 pub struct Username<'v>(Cow<'v, str>);
 
 impl<'v> Username<'v> {
+    /// The maximum length of a username, in bytes.
+    pub const MAX_LENGTH: usize = 32;
+
     pub fn new(username: impl Into<Cow<'v, str>>) -> Result<Self, &'static str> {
         let username = username.into();
         if username.len() < 2 {
             return Err("Username cannot be less than 2 characters");
         }
-        if username.len() > 32 {
+        if username.len() > Self::MAX_LENGTH {
             return Err("Username is too long");
         }
 
@@ -47,6 +50,24 @@ impl<'v> Username<'v> {
     }
 }
 
+/// The maximum length of a display name, in characters.
+pub const DISPLAY_NAME_MAX_LENGTH: usize = 64;
+
+/// Validate a display name. It cannot be blank, be longer than
+/// [`DISPLAY_NAME_MAX_LENGTH`] characters or contain control characters.
+pub fn validate_display_name(display_name: &str) -> Result<(), &'static str> {
+    if display_name.trim().is_empty() {
+        return Err("Display name cannot be empty");
+    }
+    if display_name.chars().count() > DISPLAY_NAME_MAX_LENGTH {
+        return Err("Display name is too long");
+    }
+    if display_name.chars().any(char::is_control) {
+        return Err("Display name cannot contain control characters");
+    }
+    Ok(())
+}
+
 impl<'v> TryInto<Username<'v>> for &'v str {
     type Error = &'static str;
 
@@ -208,9 +229,37 @@ pub struct UserUpdate<'a> {
     pub display_name: Option<&'a str>,
     pub description: Option<&'a str>,
     pub links: Option<BTreeMap<&'a str, &'a str>>,
-    pub metadata: Option<BTreeMap<&'a str, Value>>,
+    pub metadata: Option<Metadata>,
     pub add_links: Option<BTreeMap<&'a str, &'a str>>,
     pub remove_links: Option<Vec<&'a str>>,
+    pub add_metadata: Option<Metadata>,
+    pub remove_metadata: Option<Vec<&'a str>>,
+}
+
+/// The number of users returned by a username search, by default.
+pub const USER_SEARCH_LIMIT_DEFAULT: i64 = 10;
+
+/// The maximum number of users returned by a username search.
+pub const USER_SEARCH_LIMIT_MAX: i64 = 50;
+
+/// Parameters for checking whether a username is available.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct UserCheckQueryParams<'v> {
+    #[serde(borrow)]
+    pub username: Cow<'v, str>,
+}
+
+/// Parameters for searching users by the start of their username.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct UserSearchQueryParams {
+    /// The start of the username, case insensitive.
+    pub q: String,
+
+    /// The maximum number of users to return, up to 50. Defaults to 10.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -226,6 +275,8 @@ pub struct UserDetailsInner {
     pub id: i32,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
     pub description: String,
     pub links: Value,
     pub metadata: Value,
@@ -267,4 +318,26 @@ pub struct User {
     pub display_name: Option<String>,
 }
 
-pub type Me = UserDetails;
+/// The current user, with their teams and pending invitations.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct Me {
+    #[serde(flatten)]
+    pub user: UserDetailsInner,
+
+    /// Teams the user is a member of. Pending invitations are not included.
+    pub teams: Vec<UserTeamRef>,
+
+    /// Number of team invitations the user has not accepted yet.
+    pub pending_invitations: i64,
+}
+
+#[test]
+fn display_name_validation() {
+    assert!(validate_display_name("Jane Doe").is_ok());
+    assert!(validate_display_name(&"é".repeat(DISPLAY_NAME_MAX_LENGTH)).is_ok());
+    assert!(validate_display_name("").is_err());
+    assert!(validate_display_name("  ").is_err());
+    assert!(validate_display_name(&"a".repeat(DISPLAY_NAME_MAX_LENGTH + 1)).is_err());
+    assert!(validate_display_name("Jane\nDoe").is_err());
+}